@@ -0,0 +1,69 @@
+//! Mide el rasterizador con una esfera que llena la pantalla.
+//!
+//! ```text
+//! cargo bench --bench raster
+//! cargo bench --bench raster --features simd
+//! ```
+
+use std::cell::Cell;
+use std::time::Instant;
+use nalgebra_glm::Vec3;
+use sr_02_line::pipeline::{create_model_matrix, create_viewport_matrix, Matrices, Uniforms};
+use sr_02_line::primitives;
+use sr_02_line::shaders::{vertex_shader, PlanetShader};
+use sr_02_line::camera::Camera;
+use sr_02_line::triangle::TriangleFragments;
+
+const WIDTH: usize = 800;
+const HEIGHT: usize = 600;
+const FRAMES: u32 = 20;
+const ROUNDS: u32 = 5;
+
+fn main() {
+    let sphere = primitives::uv_sphere(0.5, 64, 32);
+
+    // la esfera ocupa toda la altura de la pantalla
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, 1.2), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+    camera.set_aspect(WIDTH as f32 / HEIGHT as f32);
+    let mut uniforms = Uniforms::new(PlanetShader::Rocky, 1);
+    uniforms.matrices = Matrices {
+        model: create_model_matrix(Vec3::zeros(), 1.0, Vec3::zeros()),
+        view: camera.view_matrix(),
+        projection: camera.projection_matrix(),
+        viewport: create_viewport_matrix(WIDTH as f32, HEIGHT as f32),
+        cull: camera.projection_matrix() * camera.view_matrix(),
+    };
+    let vertices: Vec<_> = sphere.vertices.iter().map(|vertex| vertex_shader(vertex, &uniforms)).collect();
+
+    // solo la cobertura: el shader de vértices ya se aplicó y la prueba de
+    // profundidad rechaza todos los píxeles, así que no se arma ningún fragmento
+    let covered = Cell::new(0usize);
+    let visible = |_, _, _| {
+        covered.set(covered.get() + 1);
+        false
+    };
+    // se queda con la mejor de varias rondas, la menos afectada por otros procesos
+    let elapsed = (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..FRAMES {
+                for face in sphere.indices.chunks_exact(3) {
+                    let [a, b, c] = [face[0], face[1], face[2]].map(|index| &vertices[index as usize]);
+                    TriangleFragments::new(a, b, c, uniforms.material.shading, visible).for_each(drop);
+                }
+            }
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default();
+    let fragments = covered.get() / ROUNDS as usize;
+
+    let mode = if cfg!(feature = "simd") { "simd" } else { "escalar" };
+    println!(
+        "{}: {} triángulos, {} píxeles cubiertos por fotograma, {:.2} ms por fotograma",
+        mode,
+        sphere.indices.len() / 3,
+        fragments / FRAMES as usize,
+        elapsed.as_secs_f64() * 1000.0 / FRAMES as f64,
+    );
+}
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sr_02_line::obj::Obj;
+
+// Any input may be rejected, but a loaded object must never panic later on
+// and must only contain finite geometry with in-range indices.
+fuzz_target!(|data: &[u8]| {
+    let Ok(obj) = Obj::load_from_buf(&mut &data[..]) else { return };
+
+    let model = obj.get_model();
+    assert!(model.indices.iter().all(|&index| (index as usize) < model.vertices.len()));
+    assert!(model.vertices.iter().all(|vertex| vertex.position.iter().all(|c| c.is_finite())));
+
+    let vertex_array = obj.get_vertex_array();
+    assert_eq!(vertex_array.len(), model.indices.len());
+});
@@ -0,0 +1,167 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use crate::events::Event;
+
+/// Archivo donde el visor guarda la línea de tiempo de señales.
+pub const CUE_SHEET_PATH: &str = "assets/cues.csv";
+
+/// Señal de sonido asociada a un evento de la simulación.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundCue {
+    /// Dos cuerpos chocaron.
+    Impact,
+    /// La cámara pasó cerca de un cuerpo.
+    Flyby,
+    /// Empezó un eclipse.
+    Eclipse,
+    /// Una acción de la interfaz: editar la escena o recargar un archivo.
+    UiClick,
+}
+
+impl SoundCue {
+    /// Devuelve el nombre corto de la señal.
+    pub fn name(self) -> &'static str {
+        match self {
+            SoundCue::Impact => "impact",
+            SoundCue::Flyby => "flyby",
+            SoundCue::Eclipse => "eclipse",
+            SoundCue::UiClick => "ui_click",
+        }
+    }
+
+    /// Busca la señal que corresponde a un evento.
+    ///
+    /// # Retorna
+    /// La señal y el nombre de lo que la produjo, o `None` si el evento no suena.
+    pub fn for_event(event: &Event) -> Option<(SoundCue, String)> {
+        match event {
+            Event::Collision { first, second } => Some((SoundCue::Impact, format!("{} / {}", first, second))),
+            Event::Flyby { body } => Some((SoundCue::Flyby, body.clone())),
+            Event::EclipseStarted { occluder, body } => Some((SoundCue::Eclipse, format!("{} / {}", occluder, body))),
+            Event::NodeAdded { name } | Event::NodeRemoved { name } => Some((SoundCue::UiClick, name.clone())),
+            Event::AssetReloaded { path } => Some((SoundCue::UiClick, path.clone())),
+            Event::FrameCompleted { .. } => None,
+        }
+    }
+}
+
+/// Una señal en la línea de tiempo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CueRecord {
+    /// Fotograma en que sonó.
+    pub frame: u32,
+    /// Señal.
+    pub cue: SoundCue,
+    /// Cuerpo, par de cuerpos o archivo que la produjo.
+    pub subject: String,
+}
+
+/// Línea de tiempo de las señales de sonido.
+///
+/// Se alimenta con los eventos del `EventBus` y sirve para sincronizar el sonido
+/// de un video grabado con lo que pasó en la simulación, aunque el visor se haya
+/// ejecutado sin audio.
+#[derive(Debug, Clone, Default)]
+pub struct CueSheet {
+    records: Vec<CueRecord>,
+    /// Fotograma en curso: el siguiente al último `FrameCompleted` recibido.
+    frame: u32,
+}
+
+impl CueSheet {
+    /// Crea una línea de tiempo vacía.
+    pub fn new() -> Self {
+        CueSheet::default()
+    }
+
+    /// Registra la señal de un evento, si tiene una.
+    ///
+    /// # Retorna
+    /// La señal registrada.
+    pub fn observe(&mut self, event: &Event) -> Option<&CueRecord> {
+        if let Event::FrameCompleted { frame } = event {
+            self.frame = frame + 1;
+            return None;
+        }
+        let (cue, subject) = SoundCue::for_event(event)?;
+        self.records.push(CueRecord { frame: self.frame, cue, subject });
+        self.records.last()
+    }
+
+    /// Señales registradas, en orden.
+    pub fn records(&self) -> &[CueRecord] {
+        &self.records
+    }
+
+    /// Escribe la línea de tiempo como CSV (`frame,seconds,cue,subject`).
+    ///
+    /// # Parámetros
+    /// - `path`: Archivo de destino.
+    /// - `frames_per_second`: Velocidad del video, para convertir fotogramas a segundos.
+    pub fn write<P: AsRef<Path>>(&self, path: P, frames_per_second: f32) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "frame,seconds,cue,subject")?;
+        for record in &self.records {
+            let seconds = record.frame as f32 / frames_per_second;
+            let subject = record.subject.replace('"', "\"\"");
+            writeln!(writer, "{},{:.3},{},\"{}\"", record.frame, seconds, record.cue.name(), subject)?;
+        }
+        writer.flush()
+    }
+}
+
+/// Reproduce las señales con tonos sintetizados y un fondo ambiental continuo.
+///
+/// Solo existe con la característica `audio` (rodio).
+#[cfg(feature = "audio")]
+pub struct Player {
+    /// El flujo de salida deja de sonar al soltarlo, así que se guarda aunque no se use.
+    _stream: rodio::OutputStream,
+    handle: rodio::OutputStreamHandle,
+    ambient: rodio::Sink,
+}
+
+#[cfg(feature = "audio")]
+impl Player {
+    /// Abre el dispositivo de audio por defecto y empieza el fondo ambiental.
+    ///
+    /// # Retorna
+    /// `None` si no hay un dispositivo de salida disponible.
+    pub fn new() -> Option<Player> {
+        use rodio::Source;
+        use rodio::source::SineWave;
+
+        let (stream, handle) = rodio::OutputStream::try_default().ok()?;
+        let ambient = rodio::Sink::try_new(&handle).ok()?;
+        // Dos tonos graves en quinta justa; una onda seno no termina nunca.
+        ambient.append(SineWave::new(55.0).amplify(0.04).mix(SineWave::new(82.5).amplify(0.025)));
+        Some(Player { _stream: stream, handle, ambient })
+    }
+
+    /// Cambia el volumen del fondo ambiental (1 es el normal, 0 lo silencia).
+    pub fn set_ambient_volume(&self, volume: f32) {
+        self.ambient.set_volume(volume);
+    }
+
+    /// Toca una señal sin esperar a que termine.
+    pub fn play(&self, cue: SoundCue) {
+        use std::time::Duration;
+        use rodio::Source;
+        use rodio::source::SineWave;
+
+        let (frequency, millis, volume) = match cue {
+            SoundCue::Impact => (70.0, 400, 0.5),
+            SoundCue::Flyby => (330.0, 700, 0.15),
+            SoundCue::Eclipse => (196.0, 900, 0.2),
+            SoundCue::UiClick => (1200.0, 40, 0.15),
+        };
+        let duration = Duration::from_millis(millis);
+        let tone = SineWave::new(frequency)
+            .take_duration(duration)
+            .fade_in(duration / 8)
+            .amplify(volume);
+        // Sin dispositivo (por ejemplo si se desconectó) la señal simplemente no suena.
+        let _ = self.handle.play_raw(tone);
+    }
+}
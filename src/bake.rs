@@ -0,0 +1,210 @@
+use std::f32::consts::PI;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use nalgebra_glm::{Vec2, Vec3};
+use crate::color::Color;
+use crate::elevation::MESH_RADIUS;
+use crate::environment::{EnvironmentError, EnvironmentMap};
+use crate::fragment::Fragment;
+use crate::pipeline::Matrices;
+use crate::shaders::{fragment_shader, vertex_shader};
+use crate::vertex::Vertex;
+use crate::Uniforms;
+
+/// Carpeta donde se guardan las texturas horneadas si no se indica otra con `--out`.
+pub const BAKE_DIR: &str = "assets/baked";
+
+/// Nombres de los archivos de las caras de un cubemap, en el orden de `CUBE_FACES`.
+pub const CUBE_FACE_NAMES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// Dirección hacia la que mira cada cara del cubo y hacia dónde crecen sus columnas
+/// y sus filas, como en los cubemaps de OpenGL: `+X`, `-X`, `+Y`, `-Y`, `+Z`, `-Z`.
+const CUBE_FACES: [(Vec3, Vec3, Vec3); 6] = [
+    (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, -1.0, 0.0)),
+    (Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, -1.0, 0.0)),
+    (Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+    (Vec3::new(0.0, -1.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+    (Vec3::new(0.0, 0.0, 1.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 0.0)),
+    (Vec3::new(0.0, 0.0, -1.0), Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 0.0)),
+];
+
+/// Ángulo, en radianes, entre la normal de cada punto y la dirección desde la que
+/// se lo mira al hornearlo (ver `Baker`).
+const VIEW_TILT: f32 = PI / 3.0;
+
+/// Imagen con la superficie de un planeta ya calculada.
+#[derive(Debug, Clone)]
+pub struct BakedTexture {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl BakedTexture {
+    /// Carga una textura guardada con `save_ppm` (o cualquier PPM o PNG).
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, EnvironmentError> {
+        let image = EnvironmentMap::load(path)?;
+        let (width, height) = image.size();
+        Ok(BakedTexture { width, height, pixels: image.pixels().to_vec() })
+    }
+
+    /// Ancho y alto de la imagen, en píxeles.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Píxeles de la imagen, fila por fila.
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// Devuelve el color en unas coordenadas de textura, interpolando los cuatro
+    /// píxeles vecinos. Como en `Heightmap::sample`, `u` da la vuelta y `v` se
+    /// limita al borde, así una textura equirectangular se lee con las coordenadas
+    /// de `primitives::uv_sphere`.
+    pub fn sample(&self, tex_coords: Vec2) -> Color {
+        let x = tex_coords.x.rem_euclid(1.0) * self.width as f32 - 0.5;
+        let y = tex_coords.y.clamp(0.0, 1.0) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+
+        let pixel = |column: f32, row: f32| {
+            let column = (column as i64).rem_euclid(self.width as i64) as usize;
+            let row = (row as i64).clamp(0, self.height as i64 - 1) as usize;
+            self.pixels[row * self.width + column]
+        };
+
+        let top = pixel(x0, y0).lerp(&pixel(x0 + 1.0, y0), tx);
+        let bottom = pixel(x0, y0 + 1.0).lerp(&pixel(x0 + 1.0, y0 + 1.0), tx);
+        top.lerp(&bottom, ty)
+    }
+
+    /// Guarda la imagen como PPM binario (P6), que `load` y `EnvironmentMap::load` leen.
+    pub fn save_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for &pixel in &self.pixels {
+            writer.write_all(&<[u8; 3]>::from(pixel))?;
+        }
+        writer.flush()
+    }
+}
+
+/// Evalúa el shader de fragmentos de un planeta sobre toda su superficie para
+/// guardarlo como textura, sin rasterizar ninguna malla.
+///
+/// Cada punto se ilumina de frente, con la luz en la dirección de su normal: la
+/// textura queda iluminada de forma pareja, sin lado nocturno, sin halo
+/// atmosférico y sin sombras de otros cuerpos. La cámara se mira desde
+/// `VIEW_TILT` de la normal, porque de frente el brillo especular del agua y del
+/// hielo cubriría toda la textura; ese brillo depende de dónde esté el observador
+/// y no es parte de la superficie.
+pub struct Baker {
+    uniforms: Uniforms,
+}
+
+impl Baker {
+    /// Prepara la evaluación de un shader.
+    ///
+    /// # Parámetros
+    /// - `uniforms`: Shader, ruido, parámetros y tiempo del cuerpo, como para dibujarlo;
+    ///   las matrices, la atmósfera y la sombra se ignoran.
+    pub fn new(mut uniforms: Uniforms) -> Self {
+        uniforms.matrices = Matrices::default();
+        uniforms.lighting.atmosphere = None;
+        uniforms.lighting.occluder_radius = 0.0;
+        uniforms.shadow = None;
+        Baker { uniforms }
+    }
+
+    /// Color de la superficie en un punto de la esfera.
+    ///
+    /// # Parámetros
+    /// - `direction`: Dirección desde el centro del cuerpo, en el espacio del modelo;
+    ///   no hace falta normalizarla.
+    pub fn color(&mut self, direction: Vec3) -> Color {
+        let Some(normal) = direction.try_normalize(1e-6) else { return Color::black() };
+        let position = normal * MESH_RADIUS;
+        let vertex = vertex_shader(&Vertex::new(position, normal, sphere_tex_coords(normal), 0.0), &self.uniforms);
+
+        let mut fragment = Fragment::new(0.0, 0.0, vertex.color, 0.0, normal, 1.0, position);
+        fragment.tex_coords = vertex.tex_coords;
+        fragment.elevation = vertex.elevation;
+        let helper = if normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+        let tangent = normal.cross(&helper).normalize();
+        fragment.view_direction = normal * VIEW_TILT.cos() + tangent * VIEW_TILT.sin();
+        self.uniforms.lighting.light_direction = normal;
+        self.uniforms.lighting.camera_position = position + fragment.view_direction;
+        Color::from(fragment_shader(&fragment, &self.uniforms))
+    }
+
+    /// Calcula una textura equirectangular con las coordenadas de textura de
+    /// `primitives::uv_sphere`: la columna es la longitud y la fila va del polo
+    /// norte (`+Y`) al sur.
+    ///
+    /// # Parámetros
+    /// - `width`, `height`: Tamaño de la imagen; conviene el doble de ancho que de alto.
+    pub fn equirect(&mut self, width: usize, height: usize) -> BakedTexture {
+        let (width, height) = (width.max(1), height.max(1));
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in 0..height {
+            let (sin_theta, cos_theta) = ((row as f32 + 0.5) / height as f32 * PI).sin_cos();
+            for column in 0..width {
+                let (sin_phi, cos_phi) = ((column as f32 + 0.5) / width as f32 * 2.0 * PI).sin_cos();
+                pixels.push(self.color(Vec3::new(sin_theta * cos_phi, cos_theta, sin_theta * sin_phi)));
+            }
+        }
+        BakedTexture { width, height, pixels }
+    }
+
+    /// Calcula las seis caras de un cubemap, en el orden de `CUBE_FACE_NAMES`.
+    ///
+    /// # Parámetros
+    /// - `size`: Lado de cada cara, en píxeles.
+    pub fn cubemap(&mut self, size: usize) -> [BakedTexture; 6] {
+        let size = size.max(1);
+        CUBE_FACES.map(|(forward, right, down)| {
+            let mut pixels = Vec::with_capacity(size * size);
+            for row in 0..size {
+                let t = (row as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+                for column in 0..size {
+                    let s = (column as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+                    pixels.push(self.color(forward + right * s + down * t));
+                }
+            }
+            BakedTexture { width: size, height: size, pixels }
+        })
+    }
+}
+
+/// Devuelve el color de un cubemap en una dirección.
+///
+/// # Parámetros
+/// - `faces`: Las caras en el orden de `CUBE_FACE_NAMES` (ver `Baker::cubemap`).
+/// - `direction`: Dirección desde el centro del cuerpo; no hace falta normalizarla.
+pub fn sample_cubemap(faces: &[BakedTexture; 6], direction: Vec3) -> Color {
+    // La cara es la del eje en que la dirección es más larga.
+    let Some((index, (forward, right, down))) = CUBE_FACES.iter()
+        .enumerate()
+        .max_by(|a, b| a.1.0.dot(&direction).total_cmp(&b.1.0.dot(&direction))) else { return Color::black() };
+    let depth = forward.dot(&direction);
+    if depth <= 0.0 {
+        return Color::black();
+    }
+    let s = right.dot(&direction) / depth;
+    let t = down.dot(&direction) / depth;
+    let face = &faces[index];
+    let (width, height) = face.size();
+    // Las caras no dan la vuelta: se muestrea sin salir del borde.
+    let x = ((s + 1.0) / 2.0 * width as f32).clamp(0.5, width as f32 - 0.5);
+    let y = ((t + 1.0) / 2.0 * height as f32).clamp(0.5, height as f32 - 0.5);
+    face.sample(Vec2::new(x / width as f32, y / height as f32))
+}
+
+/// Coordenadas de textura de un punto de la esfera, como las de `primitives::uv_sphere`.
+fn sphere_tex_coords(normal: Vec3) -> Vec2 {
+    let u = (normal.z.atan2(normal.x) / (2.0 * PI)).rem_euclid(1.0);
+    let v = normal.y.clamp(-1.0, 1.0).acos() / PI;
+    Vec2::new(u, v)
+}
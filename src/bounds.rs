@@ -0,0 +1,137 @@
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+
+/// Caja alineada con los ejes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    /// Esquina con las coordenadas mínimas.
+    pub min: Vec3,
+    /// Esquina con las coordenadas máximas.
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// Calcula la caja más pequeña que contiene todos los puntos.
+    ///
+    /// # Retorna
+    /// `None` si no hay puntos.
+    pub fn from_points<'a, I: IntoIterator<Item = &'a Vec3>>(points: I) -> Option<Aabb> {
+        let mut points = points.into_iter();
+        let first = *points.next()?;
+        Some(points.fold(Aabb { min: first, max: first }, |aabb, point| Aabb {
+            min: aabb.min.inf(point),
+            max: aabb.max.sup(point),
+        }))
+    }
+
+    /// Centro de la caja.
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    /// Largo de la caja en cada eje.
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Indica si el punto está dentro de la caja (o en su borde).
+    pub fn contains(&self, point: &Vec3) -> bool {
+        (0..3).all(|axis| point[axis] >= self.min[axis] && point[axis] <= self.max[axis])
+    }
+
+    /// Calcula la caja alineada con los ejes que envuelve a esta caja transformada.
+    ///
+    /// Usa el método de Arvo: cada columna de la matriz aporta su mínimo y su máximo
+    /// por separado, sin transformar las ocho esquinas.
+    pub fn transformed(&self, matrix: &Mat4) -> Aabb {
+        let translation = Vec3::new(matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]);
+        let mut min = translation;
+        let mut max = translation;
+        for row in 0..3 {
+            for column in 0..3 {
+                let a = matrix[(row, column)] * self.min[column];
+                let b = matrix[(row, column)] * self.max[column];
+                min[row] += a.min(b);
+                max[row] += a.max(b);
+            }
+        }
+        Aabb { min, max }
+    }
+}
+
+/// Esfera que envuelve un conjunto de puntos.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    /// Centro de la esfera.
+    pub center: Vec3,
+    /// Radio de la esfera.
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Calcula una esfera centrada en la caja de los puntos que los contiene a todos.
+    /// No es la mínima posible, pero para mallas casi simétricas queda muy cerca.
+    ///
+    /// # Retorna
+    /// `None` si no hay puntos.
+    pub fn from_points<'a, I>(points: I) -> Option<BoundingSphere>
+    where
+        I: IntoIterator<Item = &'a Vec3>,
+        I::IntoIter: Clone,
+    {
+        let points = points.into_iter();
+        let center = Aabb::from_points(points.clone())?.center();
+        let radius = points.map(|point| (point - center).magnitude()).fold(0.0, f32::max);
+        Some(BoundingSphere { center, radius })
+    }
+
+    /// Indica si el punto está dentro de la esfera (o en su borde).
+    pub fn contains(&self, point: &Vec3) -> bool {
+        (point - self.center).magnitude_squared() <= self.radius * self.radius
+    }
+
+    /// Calcula la esfera transformada. Con escalas distintas en cada eje el radio
+    /// usa la mayor, así que la esfera sigue envolviendo la malla.
+    pub fn transformed(&self, matrix: &Mat4) -> BoundingSphere {
+        let center = matrix * Vec4::new(self.center.x, self.center.y, self.center.z, 1.0);
+        let scale = (0..3)
+            .map(|column| Vec3::new(matrix[(0, column)], matrix[(1, column)], matrix[(2, column)]).magnitude())
+            .fold(0.0, f32::max);
+        BoundingSphere { center: center.xyz(), radius: self.radius * scale }
+    }
+}
+
+/// Volúmenes envolventes de una malla.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    /// Caja alineada con los ejes.
+    pub aabb: Aabb,
+    /// Esfera envolvente.
+    pub sphere: BoundingSphere,
+}
+
+impl Bounds {
+    /// Calcula la caja y la esfera de un conjunto de puntos.
+    ///
+    /// # Retorna
+    /// `None` si no hay puntos.
+    pub fn from_points<'a, I>(points: I) -> Option<Bounds>
+    where
+        I: IntoIterator<Item = &'a Vec3>,
+        I::IntoIter: Clone,
+    {
+        let points = points.into_iter();
+        Some(Bounds {
+            aabb: Aabb::from_points(points.clone())?,
+            sphere: BoundingSphere::from_points(points)?,
+        })
+    }
+
+    /// Transforma ambos volúmenes, por ejemplo con la matriz de modelo de un objeto
+    /// para obtenerlos en el mundo.
+    pub fn transformed(&self, matrix: &Mat4) -> Bounds {
+        Bounds {
+            aabb: self.aabb.transformed(matrix),
+            sphere: self.sphere.transformed(matrix),
+        }
+    }
+}
@@ -1,20 +1,117 @@
-use nalgebra_glm::{Vec3, rotate_vec3};
+use nalgebra_glm::{Vec3, Mat4, Quat, look_at, perspective, quat_angle_axis, quat_identity, quat_rotate_vec3};
 use std::f32::consts::PI;
+use crate::scene::{LAYER_BODIES, LAYER_UI};
+use crate::geometry::intersect::Ray;
+use crate::bounds::BoundingSphere;
+use crate::pipeline::create_orthographic_matrix;
+
+/// Fracción del camino hacia el cuerpo seguido que el punto central recorre en el
+/// primer fotograma después de `Camera::focus_on`.
+const FOCUS_START_BLEND: f32 = 0.05;
+
+/// Cuánto crece esa fracción en cada fotograma; al llegar a 1 la cámara ya acompaña
+/// al cuerpo sin retraso.
+const FOCUS_BLEND_STEP: f32 = 0.02;
+
+/// Fracción del espacio que queda hasta un límite de distancia que puede recorrer un
+/// paso de `Camera::zoom`; al acercarse al límite los pasos se achican y la cámara frena.
+const ZOOM_EASE: f32 = 0.25;
+
+/// Distancia mínima entre el ojo y la superficie de un cuerpo, en múltiplos del plano
+/// cercano, para que la superficie no quede recortada (ver `Camera::collide`).
+const COLLISION_MARGIN: f32 = 2.0;
+
+/// Modo de control de la cámara.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    /// La cámara gira y se acerca alrededor de su punto central.
+    Orbit,
+    /// La cámara se desplaza libremente en primera persona.
+    Fly,
+}
+
+/// Proyección con que la cámara forma la imagen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Projection {
+    /// En perspectiva: lo lejano se ve más chico.
+    #[default]
+    Perspective,
+    /// Ortográfica: todo se ve del mismo tamaño a cualquier distancia, para vistas
+    /// esquemáticas como un mapa del sistema visto desde arriba. El alto visible es
+    /// el que abarca la perspectiva a la distancia del punto central, así que al
+    /// cambiar de proyección el encuadre se conserva y `zoom` sigue acercando.
+    Orthographic,
+}
+
+impl Projection {
+    /// Devuelve el nombre de la proyección.
+    pub fn name(self) -> &'static str {
+        match self {
+            Projection::Perspective => "perspective",
+            Projection::Orthographic => "orthographic",
+        }
+    }
+
+    /// Busca una proyección por su nombre.
+    pub fn from_name(name: &str) -> Option<Projection> {
+        [Projection::Perspective, Projection::Orthographic].into_iter().find(|projection| projection.name() == name)
+    }
+
+    /// Devuelve la otra proyección.
+    pub fn toggled(self) -> Projection {
+        match self {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Perspective,
+        }
+    }
+}
 
 /// Representa una cámara en un espacio 3D.
+#[derive(Debug, Clone)]
 pub struct Camera {
     /// Posición de la cámara (punto de vista).
     pub eye: Vec3,
     /// Punto al que la cámara está mirando.
     pub center: Vec3,
-    /// Vector que representa la dirección "arriba" para la cámara.
+    /// Vector que representa la dirección "arriba" para la cámara; se inclina con el
+    /// alabeo (ver `roll`) y al pasar sobre los polos.
     pub up: Vec3,
+    /// Eje "arriba" del mundo: la órbita horizontal gira alrededor de él y `level`
+    /// endereza la cámara hacia él. Por defecto `+Y`, pero puede ser cualquier dirección.
+    pub world_up: Vec3,
     /// Indica si la cámara ha cambiado de posición o dirección.
     pub has_changed: bool,
+    /// Modo de control actual.
+    pub mode: CameraMode,
+    /// Proyección de la imagen.
+    pub projection: Projection,
+    /// Campo de visión vertical, en radianes.
+    pub fov: f32,
+    /// Corrimiento horizontal de la imagen en coordenadas normalizadas (2 es el ancho
+    /// entero), sin girar la cámara; lo usa el estéreo (ver `stereo::Stereo`).
+    pub lens_shift: f32,
+    /// Relación de aspecto (ancho / alto) de la imagen.
+    pub aspect: f32,
+    /// Distancia al plano de recorte cercano.
+    pub near: f32,
+    /// Distancia al plano de recorte lejano.
+    pub far: f32,
+    /// Capas de la escena que esta cámara dibuja.
+    pub layer_mask: u32,
+    /// Distancia mínima entre el ojo y el punto central al acercarse con `zoom`.
+    pub min_distance: f32,
+    /// Distancia máxima entre el ojo y el punto central al alejarse con `zoom`.
+    pub max_distance: f32,
+    /// Si es `true`, el visor no deja que el ojo entre en los cuerpos (ver `collide`).
+    pub collision: bool,
+    /// Índice del nodo de la escena que la cámara sigue (ver `focus_on`).
+    pub focus: Option<usize>,
+    /// Fracción del camino hacia el cuerpo seguido que se recorre por fotograma.
+    focus_blend: f32,
 }
 
 impl Camera {
-    /// Crea una nueva instancia de `Camera`.
+    /// Crea una nueva instancia de `Camera` con una proyección de 45° y aspecto 1:1.
     ///
     /// # Parámetros
     /// - `eye`: La posición inicial de la cámara.
@@ -28,10 +125,123 @@ impl Camera {
             eye,
             center,
             up,
+            world_up: Vec3::new(0.0, 1.0, 0.0),
             has_changed: true,
+            mode: CameraMode::Orbit,
+            projection: Projection::Perspective,
+            fov: 45.0 * PI / 180.0,
+            lens_shift: 0.0,
+            aspect: 1.0,
+            near: 0.1,
+            far: 1000.0,
+            layer_mask: LAYER_BODIES | LAYER_UI,
+            min_distance: 0.2,
+            max_distance: 500.0,
+            collision: true,
+            focus: None,
+            focus_blend: 1.0,
         }
     }
 
+    /// Indica si la cámara dibuja algo que pertenece a las capas dadas.
+    pub fn sees(&self, layers: u32) -> bool {
+        self.layer_mask & layers != 0
+    }
+
+    /// Muestra u oculta una capa en esta cámara.
+    pub fn toggle_layer(&mut self, layer: u32) {
+        self.layer_mask ^= layer;
+        self.has_changed = true;
+    }
+
+    /// Construye la matriz de vista (look-at) a partir de la posición y orientación actuales.
+    pub fn view_matrix(&self) -> Mat4 {
+        look_at(&self.eye, &self.center, &self.up)
+    }
+
+    /// Construye la matriz de proyección, en perspectiva u ortográfica según `projection`
+    /// y corrida según `lens_shift`.
+    pub fn projection_matrix(&self) -> Mat4 {
+        let projection = match self.projection {
+            Projection::Perspective => perspective(self.aspect, self.fov, self.near, self.far),
+            Projection::Orthographic => {
+                let half_height = self.orthographic_half_height();
+                create_orthographic_matrix(half_height * self.aspect, half_height, self.near, self.far)
+            }
+        };
+        if self.lens_shift == 0.0 {
+            return projection;
+        }
+        let mut shift = Mat4::identity();
+        shift[(0, 3)] = self.lens_shift;
+        shift * projection
+    }
+
+    /// Cambia entre la proyección en perspectiva y la ortográfica.
+    pub fn toggle_projection(&mut self) {
+        self.projection = self.projection.toggled();
+        self.has_changed = true;
+    }
+
+    /// Mitad del alto visible con la proyección ortográfica: lo que la perspectiva
+    /// abarca a la distancia del punto central.
+    fn orthographic_half_height(&self) -> f32 {
+        ((self.center - self.eye).magnitude() * (self.fov / 2.0).tan()).max(1e-3)
+    }
+
+    /// Calcula las esquinas del frustum de la cámara entre dos distancias.
+    ///
+    /// # Parámetros
+    /// - `near`: Distancia de la cara cercana.
+    /// - `far`: Distancia de la cara lejana; puede ser menor que `self.far`
+    ///   para dibujar un frustum recortado.
+    ///
+    /// # Retorna
+    /// Las cuatro esquinas cercanas seguidas de las cuatro lejanas, en el orden
+    /// abajo-izquierda, abajo-derecha, arriba-derecha, arriba-izquierda.
+    pub fn frustum_corners(&self, near: f32, far: f32) -> [Vec3; 8] {
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward);
+        let tan_half_fov = (self.fov / 2.0).tan();
+
+        let mut corners = [Vec3::zeros(); 8];
+        for (face, distance) in [near, far].into_iter().enumerate() {
+            let half_height = match self.projection {
+                Projection::Perspective => distance * tan_half_fov,
+                Projection::Orthographic => self.orthographic_half_height(),
+            };
+            let half_width = half_height * self.aspect;
+            let center = self.eye + forward * distance;
+            let offsets = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+            for (corner, (sx, sy)) in offsets.into_iter().enumerate() {
+                corners[face * 4 + corner] = center + right * (sx * half_width) + up * (sy * half_height);
+            }
+        }
+        corners
+    }
+
+    /// Cambia todos los parámetros de la proyección.
+    ///
+    /// # Parámetros
+    /// - `fov`: Campo de visión vertical, en radianes.
+    /// - `aspect`: Relación de aspecto (ancho / alto).
+    /// - `near`: Distancia al plano cercano.
+    /// - `far`: Distancia al plano lejano.
+    pub fn set_projection(&mut self, fov: f32, aspect: f32, near: f32, far: f32) {
+        self.fov = fov;
+        self.aspect = aspect;
+        self.near = near;
+        self.far = far;
+        self.has_changed = true;
+    }
+
+    /// Ajusta la relación de aspecto, por ejemplo al redimensionar la ventana.
+    pub fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+        self.has_changed = true;
+    }
+
     /// Cambia la base del vector proporcionado según la orientación de la cámara.
     ///
     /// # Parámetros
@@ -52,60 +262,192 @@ impl Camera {
         rotated.normalize()
     }
 
-    /// Realiza una órbita alrededor del punto central de la cámara.
+    /// Calcula el rayo que sale del ojo y pasa por un punto de la imagen, por ejemplo
+    /// para saber qué cuerpo está bajo el cursor (ver `Scene::pick`).
     ///
     /// # Parámetros
-    /// - `delta_yaw`: Cambio en el ángulo de giro alrededor del eje Y.
-    /// - `delta_pitch`: Cambio en el ángulo de elevación alrededor del eje X.
-    pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
-        let radius_vector = self.eye - self.center;
-        let radius = radius_vector.magnitude();
+    /// - `x`: Coordenada x en píxeles, de izquierda a derecha.
+    /// - `y`: Coordenada y en píxeles, de arriba hacia abajo.
+    /// - `width`: Ancho de la imagen en píxeles; su relación con `height` debería ser `aspect`.
+    /// - `height`: Alto de la imagen en píxeles.
+    pub fn ray_through(&self, x: f32, y: f32, width: f32, height: f32) -> Ray {
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward);
+        let tan_half_fov = (self.fov / 2.0).tan();
 
-        let current_yaw = radius_vector.z.atan2(radius_vector.x);
+        let horizontal = (2.0 * x / width - 1.0) * tan_half_fov * self.aspect;
+        let vertical = (1.0 - 2.0 * y / height) * tan_half_fov;
+        match self.projection {
+            Projection::Perspective => Ray::new(self.eye, forward + right * horizontal + up * vertical),
+            // Rayos paralelos, que salen del plano del ojo.
+            Projection::Orthographic => {
+                let distance = (self.center - self.eye).magnitude();
+                Ray::new(self.eye + (right * horizontal + up * vertical) * distance, forward)
+            }
+        }
+    }
 
-        let radius_xz = (radius_vector.x * radius_vector.x + radius_vector.z * radius_vector.z).sqrt();
-        let current_pitch = (-radius_vector.y).atan2(radius_xz);
+    /// Empieza a seguir un cuerpo de la escena: en los fotogramas siguientes el punto
+    /// central se desliza hasta él y después lo acompaña en su órbita (ver `track_focus`).
+    /// La cámara pasa al modo de órbita, para girar alrededor del cuerpo.
+    ///
+    /// # Parámetros
+    /// - `entity`: Índice del nodo de la escena.
+    pub fn focus_on(&mut self, entity: usize) {
+        self.mode = CameraMode::Orbit;
+        self.focus = Some(entity);
+        self.focus_blend = FOCUS_START_BLEND;
+    }
 
-        let new_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
-        let new_pitch = (current_pitch + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+    /// Deja de seguir al cuerpo; la cámara se queda donde está.
+    pub fn release_focus(&mut self) {
+        self.focus = None;
+    }
 
-        let new_eye = self.center + Vec3::new(
-            radius * new_yaw.cos() * new_pitch.cos(),
-            -radius * new_pitch.sin(),
-            radius * new_yaw.sin() * new_pitch.cos()
-        );
+    /// Acerca el punto central al cuerpo seguido, moviendo el ojo lo mismo para
+    /// conservar la distancia y el ángulo de la vista. Se llama una vez por fotograma;
+    /// el paso empieza corto y crece, así el viaje arranca y frena con suavidad.
+    ///
+    /// # Parámetros
+    /// - `target`: Posición actual en el mundo del cuerpo seguido.
+    pub fn track_focus(&mut self, target: Vec3) {
+        if self.focus.is_none() {
+            return;
+        }
+        let offset = (target - self.center) * self.focus_blend;
+        if offset != Vec3::zeros() {
+            self.translate(offset);
+        }
+        self.focus_blend = (self.focus_blend + FOCUS_BLEND_STEP).min(1.0);
+    }
 
-        self.eye = new_eye;
+    /// Realiza una órbita alrededor del punto central de la cámara.
+    ///
+    /// El giro horizontal es alrededor de `world_up` y el vertical alrededor del eje
+    /// derecho de la cámara, con cuaterniones que rotan también el vector `up`: no hay
+    /// límite de elevación y la cámara puede pasar sobre los polos sin trabarse,
+    /// conservando su alabeo.
+    ///
+    /// # Parámetros
+    /// - `delta_yaw`: Cambio en el ángulo de giro alrededor de `world_up`.
+    /// - `delta_pitch`: Cambio en el ángulo de elevación; positivo baja la cámara.
+    pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        let Some((_, right, _)) = self.frame() else { return };
+        let rotation = self.yaw_rotation(-delta_yaw) * quat_angle_axis(delta_pitch, &right);
+        self.eye = self.center + quat_rotate_vec3(&rotation, &(self.eye - self.center));
+        self.up = quat_rotate_vec3(&rotation, &self.up);
         self.has_changed = true;
     }
 
-    /// Acerca o aleja la cámara a lo largo de su dirección de visión.
+    /// Inclina la cámara alrededor de la dirección en la que mira, para tomas con el
+    /// horizonte torcido.
+    ///
+    /// # Parámetros
+    /// - `angle`: Ángulo en radianes; positivo inclina la cámara hacia la derecha.
+    pub fn roll(&mut self, angle: f32) {
+        let Some((forward, _, up)) = self.frame() else { return };
+        self.up = quat_rotate_vec3(&quat_angle_axis(angle, &forward), &up);
+        self.has_changed = true;
+    }
+
+    /// Quita el alabeo: vuelve a alinear `up` con `world_up` sin cambiar hacia dónde mira.
+    /// Si la cámara mira justo a lo largo de `world_up`, no cambia.
+    pub fn level(&mut self) {
+        let rolled = self.up;
+        self.up = self.world_up;
+        if self.frame().is_none() {
+            self.up = rolled;
+        }
+        self.has_changed = true;
+    }
+
+    /// Ejes de la cámara: adelante, derecha y arriba, normalizados y perpendiculares.
+    ///
+    /// # Retorna
+    /// `None` si la cámara no tiene dirección (el ojo está en el punto central) o si
+    /// `up` es paralelo a la dirección de la vista.
+    fn frame(&self) -> Option<(Vec3, Vec3, Vec3)> {
+        let forward = (self.center - self.eye).try_normalize(1e-6)?;
+        let right = forward.cross(&self.up).try_normalize(1e-6)?;
+        Some((forward, right, right.cross(&forward)))
+    }
+
+    /// Rotación alrededor de `world_up`, o ninguna si `world_up` es nulo.
+    fn yaw_rotation(&self, angle: f32) -> Quat {
+        match self.world_up.try_normalize(1e-6) {
+            Some(axis) => quat_angle_axis(angle, &axis),
+            None => quat_identity(),
+        }
+    }
+
+    /// Acerca o aleja la cámara a lo largo de su dirección de visión, sin salir del
+    /// rango entre `min_distance` y `max_distance` al punto central. Cerca de un límite
+    /// cada paso recorre como mucho una fracción de lo que falta, así la cámara frena
+    /// con suavidad en lugar de detenerse de golpe, y nunca pasa del punto central.
     ///
     /// # Parámetros
     /// - `delta`: Cantidad para mover la cámara hacia adelante (positivo) o hacia atrás (negativo).
     pub fn zoom(&mut self, delta: f32) {
-        let direction = (self.center - self.eye).normalize();
-        self.eye += direction * delta;
+        let offset = self.center - self.eye;
+        let distance = offset.magnitude();
+        if distance <= f32::EPSILON {
+            return;
+        }
+        let step = if delta > 0.0 {
+            delta.min((distance - self.min_distance).max(0.0) * ZOOM_EASE)
+        } else {
+            delta.max(-(self.max_distance - distance).max(0.0) * ZOOM_EASE)
+        };
+        self.eye += offset / distance * step;
         self.has_changed = true;
     }
 
+    /// Saca el ojo de una esfera si quedó dentro o demasiado cerca de su superficie,
+    /// para que acercarse a un planeta se detenga justo encima de él. En órbita solo
+    /// se mueve el ojo, así la cámara sigue mirando al mismo punto; en vuelo libre se
+    /// mueve la cámara entera.
+    ///
+    /// # Parámetros
+    /// - `sphere`: Esfera del cuerpo en el mundo.
+    ///
+    /// # Retorna
+    /// `true` si hubo que mover la cámara.
+    pub fn collide(&mut self, sphere: &BoundingSphere) -> bool {
+        let offset = self.eye - sphere.center;
+        let distance = offset.magnitude();
+        let clearance = sphere.radius + self.near * COLLISION_MARGIN;
+        if distance >= clearance {
+            return false;
+        }
+        // Desde el centro exacto no hay dirección: se sale hacia atrás de la vista.
+        let outward = offset.try_normalize(1e-6)
+            .or_else(|| (self.eye - self.center).try_normalize(1e-6))
+            .unwrap_or(self.world_up);
+        let push = outward * (clearance - distance);
+        match self.mode {
+            CameraMode::Orbit => {
+                self.eye += push;
+                self.has_changed = true;
+            }
+            CameraMode::Fly => self.translate(push),
+        }
+        true
+    }
+
     /// Mueve el punto central de la cámara en función de un vector de dirección.
     ///
     /// # Parámetros
     /// - `direction`: Un vector que determina la dirección y la magnitud del movimiento.
     pub fn move_center(&mut self, direction: Vec3) {
-        let radius_vector = self.center - self.eye;
-        let radius = radius_vector.magnitude();
-
+        let Some((_, right, _)) = self.frame() else { return };
         let angle_x = direction.x * 0.05; // Ajusta este factor para controlar la velocidad de rotación
         let angle_y = direction.y * 0.05;
 
-        let rotated = rotate_vec3(&radius_vector, angle_x, &Vec3::new(0.0, 1.0, 0.0));
-
-        let right = rotated.cross(&self.up).normalize();
-        let final_rotated = rotate_vec3(&rotated, angle_y, &right);
-
-        self.center = self.eye + final_rotated.normalize() * radius;
+        // Como en `orbit`, el giro vertical también inclina `up` y no se traba en los polos.
+        let rotation = self.yaw_rotation(angle_x) * quat_angle_axis(angle_y, &right);
+        self.center = self.eye + quat_rotate_vec3(&rotation, &(self.center - self.eye));
+        self.up = quat_rotate_vec3(&rotation, &self.up);
         self.has_changed = true;
     }
 
@@ -121,4 +463,286 @@ impl Camera {
             false
         }
     }
+
+    /// Alterna entre el modo de órbita y el modo de vuelo libre.
+    ///
+    /// Al cambiar de modo la cámara deja de seguir al cuerpo que seguía.
+    pub fn toggle_mode(&mut self) {
+        self.focus = None;
+        self.mode = match self.mode {
+            CameraMode::Orbit => CameraMode::Fly,
+            CameraMode::Fly => CameraMode::Orbit,
+        };
+    }
+
+    /// Avanza o retrocede la cámara en la dirección en la que mira, moviendo
+    /// también el punto central para conservar la orientación.
+    ///
+    /// # Parámetros
+    /// - `delta`: Distancia a recorrer; positiva hacia adelante.
+    pub fn move_forward(&mut self, delta: f32) {
+        let forward = (self.center - self.eye).normalize();
+        self.translate(forward * delta);
+    }
+
+    /// Desplaza la cámara lateralmente.
+    ///
+    /// # Parámetros
+    /// - `delta`: Distancia a recorrer; positiva hacia la derecha.
+    pub fn strafe(&mut self, delta: f32) {
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        self.translate(right * delta);
+    }
+
+    /// Sube o baja la cámara a lo largo de su vector "arriba".
+    ///
+    /// # Parámetros
+    /// - `delta`: Distancia a recorrer; positiva hacia arriba.
+    pub fn rise(&mut self, delta: f32) {
+        let up = self.up.normalize();
+        self.translate(up * delta);
+    }
+
+    /// Traslada el ojo y el punto central a la vez.
+    fn translate(&mut self, offset: Vec3) {
+        self.eye += offset;
+        self.center += offset;
+        self.has_changed = true;
+    }
+
+    /// Coloca la cámara en la posición del recorrido correspondiente al instante `t`.
+    ///
+    /// # Parámetros
+    /// - `path`: El recorrido a seguir.
+    /// - `t`: Instante del recorrido, en las mismas unidades que los puntos de paso.
+    pub fn follow_path(&mut self, path: &CameraPath, t: f32) {
+        if let Some(waypoint) = path.sample(t) {
+            self.eye = waypoint.eye;
+            self.center = waypoint.center;
+            self.up = waypoint.up;
+            self.has_changed = true;
+        }
+    }
+}
+
+/// Velocidad por debajo de la cual la inercia se da por terminada.
+const MOTION_EPSILON: f32 = 1e-5;
+
+/// Controles de cámara con el mouse: arrastrar para girar o mirar alrededor y la
+/// rueda para acercarse, con inercia.
+///
+/// Los arrastres y la rueda fijan una velocidad en lugar de mover la cámara
+/// directamente; `apply` la aplica una vez por fotograma y la reduce con `damping`,
+/// así la cámara sigue deslizándose un poco al soltar el botón y frena con suavidad.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MouseControls {
+    /// Radianes que gira la cámara por píxel arrastrado (ver `Camera::orbit`).
+    pub orbit_sensitivity: f32,
+    /// Cuánto se desplaza la vista por píxel arrastrado (ver `Camera::move_center`).
+    pub pan_sensitivity: f32,
+    /// Fracción de la distancia al punto central que avanza cada paso de la rueda.
+    pub zoom_sensitivity: f32,
+    /// Fracción de la velocidad que queda de un fotograma al siguiente sin arrastrar:
+    /// 0 frena en seco y cerca de 1 deja la cámara deslizándose mucho tiempo.
+    pub damping: f32,
+    /// Giro pendiente (horizontal, vertical) en radianes por fotograma.
+    orbit_velocity: (f32, f32),
+    /// Desplazamiento pendiente de la vista por fotograma.
+    pan_velocity: (f32, f32),
+    /// Acercamiento pendiente, en fracciones de la distancia por fotograma.
+    zoom_velocity: f32,
+}
+
+impl Default for MouseControls {
+    fn default() -> Self {
+        MouseControls {
+            orbit_sensitivity: 0.005,
+            pan_sensitivity: 0.02,
+            zoom_sensitivity: 0.02,
+            damping: 0.85,
+            orbit_velocity: (0.0, 0.0),
+            pan_velocity: (0.0, 0.0),
+            zoom_velocity: 0.0,
+        }
+    }
+}
+
+impl MouseControls {
+    /// Multiplica las tres sensibilidades por el mismo factor.
+    pub fn scale_sensitivity(&mut self, factor: f32) {
+        self.orbit_sensitivity *= factor;
+        self.pan_sensitivity *= factor;
+        self.zoom_sensitivity *= factor;
+    }
+
+    /// Registra un arrastre para girar alrededor del punto central. Mientras el botón
+    /// sigue apretado se llama en cada fotograma, aunque el mouse no se mueva.
+    ///
+    /// # Parámetros
+    /// - `dx`, `dy`: Píxeles que se movió el mouse desde el fotograma anterior.
+    pub fn drag_orbit(&mut self, dx: f32, dy: f32) {
+        // Se arrastra la escena: hacia la derecha la cámara va a la izquierda, y hacia abajo sube.
+        self.orbit_velocity = (dx * self.orbit_sensitivity, -dy * self.orbit_sensitivity);
+    }
+
+    /// Registra un arrastre para mover la vista; como `drag_orbit`, se llama en cada
+    /// fotograma mientras el botón sigue apretado.
+    ///
+    /// # Parámetros
+    /// - `dx`, `dy`: Píxeles que se movió el mouse desde el fotograma anterior.
+    pub fn drag_pan(&mut self, dx: f32, dy: f32) {
+        // También se arrastra la escena: hacia la derecha la vista gira a la izquierda.
+        self.pan_velocity = (dx * self.pan_sensitivity, dy * self.pan_sensitivity);
+    }
+
+    /// Registra un movimiento de la rueda.
+    ///
+    /// # Parámetros
+    /// - `steps`: Pasos de la rueda; positivos para acercarse.
+    pub fn scroll(&mut self, steps: f32) {
+        self.zoom_velocity += steps * self.zoom_sensitivity;
+    }
+
+    /// Detiene cualquier movimiento pendiente.
+    pub fn stop(&mut self) {
+        self.orbit_velocity = (0.0, 0.0);
+        self.pan_velocity = (0.0, 0.0);
+        self.zoom_velocity = 0.0;
+    }
+
+    /// Aplica el movimiento pendiente a la cámara y lo reduce con `damping`.
+    /// Se llama una vez por fotograma, después de registrar los arrastres.
+    ///
+    /// Mover la vista deja de seguir al cuerpo seguido (ver `Camera::focus_on`).
+    pub fn apply(&mut self, camera: &mut Camera) {
+        let (yaw, pitch) = self.orbit_velocity;
+        if yaw != 0.0 || pitch != 0.0 {
+            camera.orbit(yaw, pitch);
+        }
+        let (pan_x, pan_y) = self.pan_velocity;
+        if pan_x != 0.0 || pan_y != 0.0 {
+            camera.release_focus();
+            camera.move_center(Vec3::new(pan_x, pan_y, 0.0));
+        }
+        if self.zoom_velocity != 0.0 {
+            // Cada paso es una fracción de la distancia; `zoom` frena antes de sus límites.
+            let distance = (camera.center - camera.eye).magnitude();
+            camera.zoom(self.zoom_velocity * distance);
+        }
+
+        let damping = self.damping.clamp(0.0, 1.0);
+        let decay = |value: f32| if (value * damping).abs() < MOTION_EPSILON { 0.0 } else { value * damping };
+        self.orbit_velocity = (decay(yaw), decay(pitch));
+        self.pan_velocity = (decay(pan_x), decay(pan_y));
+        self.zoom_velocity = decay(self.zoom_velocity);
+    }
+}
+
+/// Punto de paso de un recorrido de cámara.
+#[derive(Debug, Clone, Copy)]
+pub struct Waypoint {
+    /// Posición de la cámara.
+    pub eye: Vec3,
+    /// Punto al que mira la cámara.
+    pub center: Vec3,
+    /// Dirección "arriba" de la cámara.
+    pub up: Vec3,
+    /// Instante en que la cámara pasa por este punto.
+    pub time: f32,
+}
+
+/// Recorrido de cámara definido por puntos de paso e interpolado con
+/// splines de Catmull-Rom, para grabar sobrevuelos suaves.
+#[derive(Debug, Clone, Default)]
+pub struct CameraPath {
+    /// Puntos de paso ordenados por tiempo.
+    waypoints: Vec<Waypoint>,
+}
+
+impl CameraPath {
+    /// Crea un recorrido vacío.
+    pub fn new() -> Self {
+        CameraPath { waypoints: Vec::new() }
+    }
+
+    /// Agrega un punto de paso manteniendo el orden por tiempo.
+    pub fn add_waypoint(&mut self, waypoint: Waypoint) {
+        let index = self.waypoints.partition_point(|w| w.time <= waypoint.time);
+        self.waypoints.insert(index, waypoint);
+    }
+
+    /// Agrega la pose actual de la cámara como punto de paso en el instante dado.
+    pub fn record(&mut self, camera: &Camera, time: f32) {
+        self.add_waypoint(Waypoint {
+            eye: camera.eye,
+            center: camera.center,
+            up: camera.up,
+            time,
+        });
+    }
+
+    /// Elimina todos los puntos de paso.
+    pub fn clear(&mut self) {
+        self.waypoints.clear();
+    }
+
+    /// Devuelve los puntos de paso del recorrido.
+    pub fn waypoints(&self) -> &[Waypoint] {
+        &self.waypoints
+    }
+
+    /// Instante del último punto de paso, o 0 si el recorrido está vacío.
+    pub fn duration(&self) -> f32 {
+        self.waypoints.last().map_or(0.0, |w| w.time)
+    }
+
+    /// Calcula la pose interpolada en el instante `t`. Fuera del rango del
+    /// recorrido se devuelve el primer o el último punto de paso.
+    ///
+    /// # Retorna
+    /// La pose interpolada, o `None` si el recorrido está vacío.
+    pub fn sample(&self, t: f32) -> Option<Waypoint> {
+        let first = *self.waypoints.first()?;
+        let last = *self.waypoints.last()?;
+        if t <= first.time {
+            return Some(first);
+        }
+        if t >= last.time {
+            return Some(last);
+        }
+
+        // Segmento [i, i + 1] que contiene a t, con sus vecinos para la spline.
+        let i = self.waypoints.partition_point(|w| w.time <= t) - 1;
+        let p1 = &self.waypoints[i];
+        let p2 = &self.waypoints[i + 1];
+        let p0 = &self.waypoints[i.saturating_sub(1)];
+        let p3 = &self.waypoints[(i + 2).min(self.waypoints.len() - 1)];
+
+        let span = (p2.time - p1.time).max(f32::EPSILON);
+        let u = (t - p1.time) / span;
+
+        Some(Waypoint {
+            eye: catmull_rom(&p0.eye, &p1.eye, &p2.eye, &p3.eye, u),
+            center: catmull_rom(&p0.center, &p1.center, &p2.center, &p3.center, u),
+            up: catmull_rom(&p0.up, &p1.up, &p2.up, &p3.up, u).normalize(),
+            time: t,
+        })
+    }
+}
+
+/// Evalúa una spline de Catmull-Rom uniforme entre `p1` y `p2`.
+///
+/// # Parámetros
+/// - `p0`, `p3`: Puntos vecinos que definen las tangentes.
+/// - `p1`, `p2`: Extremos del segmento.
+/// - `u`: Parámetro entre 0 (en `p1`) y 1 (en `p2`).
+fn catmull_rom(p0: &Vec3, p1: &Vec3, p2: &Vec3, p3: &Vec3, u: f32) -> Vec3 {
+    let u2 = u * u;
+    let u3 = u2 * u;
+
+    (p1 * 2.0
+        + (p2 - p0) * u
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * u2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * u3) * 0.5
 }
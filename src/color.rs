@@ -1,4 +1,5 @@
 use std::fmt;
+use nalgebra_glm::Vec3;
 
 /// Representa un color RGB.
 #[derive(Debug, Clone, Copy)]
@@ -19,6 +20,7 @@ impl Color {
     /// # Ejemplo
     ///
     /// ```
+    /// # use sr_02_line::color::Color;
     /// let color = Color::from_hex(0xFF5733);
     /// ```
     pub const fn from_hex(hex: u32) -> Self {
@@ -38,6 +40,16 @@ impl Color {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
 
+    /// Empaqueta el color como un píxel del framebuffer (`0x00RRGGBB`).
+    pub fn to_u32(&self) -> u32 {
+        self.to_hex()
+    }
+
+    /// Desempaqueta un píxel del framebuffer (`0x00RRGGBB`); el byte alto se ignora.
+    pub const fn from_u32(pixel: u32) -> Self {
+        Color::from_hex(pixel)
+    }
+
     /// Realiza la interpolación lineal entre dos colores.
     ///
     /// # Parámetros
@@ -90,6 +102,355 @@ impl Color {
 
         Color::new(r, g, b)
     }
+
+    /// Crea un color a partir de tono, saturación y valor (HSV).
+    ///
+    /// # Parámetros
+    /// - `hue`: Tono en grados (0 rojo, 120 verde, 240 azul); da la vuelta fuera de 0..360.
+    /// - `saturation`: Entre 0 (gris) y 1 (color puro).
+    /// - `value`: Entre 0 (negro) y 1 (el color con todo su brillo).
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+        let chroma = value * saturation;
+        Color::from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    /// Crea un color a partir de tono, saturación y luminosidad (HSL).
+    ///
+    /// # Parámetros
+    /// - `hue`: Tono en grados (0 rojo, 120 verde, 240 azul); da la vuelta fuera de 0..360.
+    /// - `saturation`: Entre 0 (gris) y 1 (color puro).
+    /// - `lightness`: Entre 0 (negro) y 1 (blanco); el color puro está en 0.5.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Color::from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// Arma un color con la croma dada en el tono indicado, más `offset` en cada canal.
+    fn from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Self {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let second = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, second, 0.0),
+            1 => (second, chroma, 0.0),
+            2 => (0.0, chroma, second),
+            3 => (0.0, second, chroma),
+            4 => (second, 0.0, chroma),
+            _ => (chroma, 0.0, second),
+        };
+        let channel = |value: f32| ((value + offset).clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color::new(channel(r), channel(g), channel(b))
+    }
+
+    /// Devuelve el tono (en grados), el máximo y el mínimo de los canales entre 0 y 1,
+    /// lo que comparten las conversiones a HSV y HSL.
+    fn hue_max_min(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let hue = if chroma <= 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+        (hue, max, min)
+    }
+
+    /// Convierte el color a tono (grados), saturación y valor (ver `from_hsv`).
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let saturation = if max <= 0.0 { 0.0 } else { (max - min) / max };
+        (hue, saturation, max)
+    }
+
+    /// Convierte el color a tono (grados), saturación y luminosidad (ver `from_hsl`).
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max - min <= 0.0 { 0.0 } else { (max - min) / (1.0 - (2.0 * lightness - 1.0).abs()) };
+        (hue, saturation, lightness)
+    }
+
+    /// Gira el tono del color, conservando su saturación y luminosidad.
+    ///
+    /// # Parámetros
+    /// - `degrees`: Grados que se suma al tono; negativos giran hacia el otro lado.
+    pub fn hue_shift(&self, degrees: f32) -> Self {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Color::from_hsl(hue + degrees, saturation, lightness)
+    }
+
+    /// Cambia la saturación del color (en HSL).
+    ///
+    /// # Parámetros
+    /// - `amount`: Cantidad que se suma a la saturación, entre -1 y 1; los valores
+    ///   negativos lo llevan hacia el gris.
+    pub fn saturate(&self, amount: f32) -> Self {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Color::from_hsl(hue, saturation + amount, lightness)
+    }
+
+    /// Oscurece el color restando `amount` (entre 0 y 1) a su luminosidad.
+    pub fn darken(&self, amount: f32) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// Aclara el color sumando `amount` (entre 0 y 1) a su luminosidad.
+    pub fn lighten(&self, amount: f32) -> Self {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Color::from_hsl(hue, saturation, lightness + amount)
+    }
+}
+
+/// Degradado entre colores fijos en posiciones crecientes, para colorear una
+/// superficie según su elevación (u otro valor) sin saltos entre franjas.
+///
+/// La cantidad de paradas es parte del tipo, así que una paleta vive en la pila
+/// y evaluarla en cada fragmento no reserva memoria.
+///
+/// # Ejemplo
+///
+/// ```
+/// # use sr_02_line::color::{Color, Palette};
+/// let terrain = Palette::new([
+///     (-0.2, Color::new(0, 60, 120)),
+///     (0.1, Color::new(40, 140, 60)),
+///     (0.6, Color::new(255, 255, 255)),
+/// ]);
+/// let beach = terrain.sample(0.05);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Palette<const N: usize> {
+    stops: [(f32, Color); N],
+}
+
+impl<const N: usize> Palette<N> {
+    /// Crea una paleta a partir de sus paradas `(posición, color)`; se ordenan por posición.
+    pub fn new(mut stops: [(f32, Color); N]) -> Self {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Palette { stops }
+    }
+
+    /// Crea una paleta con los colores repartidos en partes iguales entre 0 y 1.
+    pub fn even(colors: [Color; N]) -> Self {
+        let last = N.saturating_sub(1).max(1) as f32;
+        let mut position = 0.0;
+        Palette {
+            stops: colors.map(|color| {
+                let stop = (position / last, color);
+                position += 1.0;
+                stop
+            }),
+        }
+    }
+
+    /// Evalúa el degradado en `t`, interpolando entre las dos paradas más cercanas.
+    /// Antes de la primera parada y después de la última se mantiene su color; una
+    /// paleta vacía da negro.
+    pub fn sample(&self, t: f32) -> Color {
+        sample_stops(&self.stops, t)
+    }
+}
+
+/// Rampa de colores según la elevación: paradas `(elevación, color)` ordenadas, con
+/// interpolación lineal entre ellas. Dos paradas en la misma elevación dan un corte
+/// duro, como la línea de la costa.
+///
+/// A diferencia de `Palette`, la cantidad de paradas se decide al ejecutar (hasta
+/// `MAX_STOPS`), así que una rampa se puede leer del archivo de parámetros de los
+/// shaders (ver `shader_params::ShaderParams`); igual que ella, no reserva memoria.
+#[derive(Debug, Clone, Copy)]
+pub struct ColorRamp {
+    stops: [(f32, Color); ColorRamp::MAX_STOPS],
+    len: usize,
+}
+
+impl ColorRamp {
+    /// Máximo de paradas de una rampa.
+    pub const MAX_STOPS: usize = 16;
+
+    /// Crea una rampa a partir de sus paradas; se ordenan por elevación.
+    ///
+    /// # Retorna
+    /// `None` si no hay paradas o hay más de `MAX_STOPS`.
+    pub fn new(stops: &[(f32, Color)]) -> Option<Self> {
+        if stops.is_empty() || stops.len() > ColorRamp::MAX_STOPS {
+            return None;
+        }
+        let mut ramp = ColorRamp { stops: [(0.0, Color::black()); ColorRamp::MAX_STOPS], len: stops.len() };
+        ramp.stops[..stops.len()].copy_from_slice(stops);
+        ramp.stops[..stops.len()].sort_by(|a, b| a.0.total_cmp(&b.0));
+        Some(ramp)
+    }
+
+    /// Crea una rampa fija a partir de paradas ya ordenadas por elevación; al ser
+    /// `const` sirve para declarar rampas constantes.
+    pub const fn from_sorted<const N: usize>(stops: [(f32, Color); N]) -> Self {
+        assert!(N > 0 && N <= ColorRamp::MAX_STOPS, "una rampa tiene entre 1 y MAX_STOPS paradas");
+        let mut all = [(0.0, Color::black()); ColorRamp::MAX_STOPS];
+        let mut index = 0;
+        while index < N {
+            all[index] = stops[index];
+            index += 1;
+        }
+        ColorRamp { stops: all, len: N }
+    }
+
+    /// Paradas de la rampa, ordenadas por elevación.
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops[..self.len]
+    }
+
+    /// Evalúa la rampa en una elevación. Antes de la primera parada y después de
+    /// la última se mantiene su color.
+    pub fn sample(&self, elevation: f32) -> Color {
+        sample_stops(self.stops(), elevation)
+    }
+}
+
+/// Interpola entre paradas ordenadas `(posición, color)`; la lógica común de
+/// `Palette` y `ColorRamp`.
+fn sample_stops(stops: &[(f32, Color)], t: f32) -> Color {
+    let (Some(&(first_position, first_color)), Some(&(_, last_color))) = (stops.first(), stops.last()) else {
+        return Color::black();
+    };
+    if t <= first_position {
+        return first_color;
+    }
+    for pair in stops.windows(2) {
+        let ((start, from), (end, to)) = (pair[0], pair[1]);
+        if t <= end {
+            let span = end - start;
+            return if span <= 0.0 { to } else { from.lerp(&to, (t - start) / span) };
+        }
+    }
+    last_color
+}
+
+/// Codificación con la que se escriben los colores en la imagen final.
+///
+/// Los shaders y el post-procesado trabajan con valores lineales; la
+/// codificación se aplica al pasar cada color calculado en punto flotante a los
+/// 8 bits por canal del framebuffer (ver `PixelFormat`).
+///
+/// Las paletas de los shaders se eligieron mirando la pantalla, es decir, ya
+/// en sRGB; por eso la salida por omisión es `Linear`, que las deja como están.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputEncoding {
+    /// Los valores se escriben tal cual, sin corrección.
+    #[default]
+    Linear,
+    /// Corrección gamma simple con el exponente dado (normalmente 2.2).
+    Gamma(f32),
+    /// Curva estándar sRGB, con su tramo lineal cerca del negro.
+    Srgb,
+}
+
+impl OutputEncoding {
+    /// Codifica un valor lineal entre 0 y 1.
+    pub fn encode(self, linear: f32) -> f32 {
+        let linear = linear.clamp(0.0, 1.0);
+        match self {
+            OutputEncoding::Linear => linear,
+            OutputEncoding::Gamma(gamma) => linear.powf(1.0 / gamma),
+            OutputEncoding::Srgb => {
+                if linear <= 0.003_130_8 {
+                    linear * 12.92
+                } else {
+                    1.055 * linear.powf(1.0 / 2.4) - 0.055
+                }
+            }
+        }
+    }
+
+    /// Deshace `encode`: devuelve el valor lineal de un valor codificado entre 0 y 1.
+    pub fn decode(self, encoded: f32) -> f32 {
+        let encoded = encoded.clamp(0.0, 1.0);
+        match self {
+            OutputEncoding::Linear => encoded,
+            OutputEncoding::Gamma(gamma) => encoded.powf(gamma),
+            OutputEncoding::Srgb => {
+                if encoded <= 0.040_45 {
+                    encoded / 12.92
+                } else {
+                    ((encoded + 0.055) / 1.055).powf(2.4)
+                }
+            }
+        }
+    }
+
+    /// Devuelve la siguiente codificación, para alternar entre ellas desde el teclado.
+    pub fn next(self) -> Self {
+        match self {
+            OutputEncoding::Linear => OutputEncoding::Gamma(2.2),
+            OutputEncoding::Gamma(_) => OutputEncoding::Srgb,
+            OutputEncoding::Srgb => OutputEncoding::Linear,
+        }
+    }
+}
+
+/// Cómo guarda el framebuffer los colores que los shaders calculan en punto
+/// flotante.
+///
+/// `pack` codifica y redondea cada color una sola vez, al escribir el píxel, así
+/// las curvas gamma y sRGB no estiran un valor que ya se redondeó a 8 bits en
+/// lineal (lo que deja franjas en las sombras). `unpack` deshace la
+/// codificación, para que las mezclas (transparencias, supermuestreo,
+/// post-procesado) se hagan con valores lineales.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelFormat {
+    /// Codificación con la que se escriben los valores.
+    pub encoding: OutputEncoding,
+    /// Activa el tramado ordenado (Bayer) al redondear a 8 bits por canal, que
+    /// reemplaza las franjas de los degradados suaves por un patrón fino.
+    pub dither: bool,
+}
+
+impl Default for PixelFormat {
+    /// Salida lineal con tramado.
+    fn default() -> Self {
+        PixelFormat { encoding: OutputEncoding::Linear, dither: true }
+    }
+}
+
+/// Matriz de Bayer de 4x4: el umbral de cada píxel de un bloque, de 0 a 15.
+const BAYER_4X4: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5],
+];
+
+impl PixelFormat {
+    /// Empaqueta un color lineal como píxel del framebuffer (`0x00RRGGBB`),
+    /// codificándolo; los valores fuera de 0 a 1 se saturan.
+    ///
+    /// Con `dither`, antes de redondear se suma el desplazamiento de Bayer del
+    /// píxel, entre -0.5 y 0.5 de un paso de 8 bits: un valor que cae entre dos
+    /// niveles se reparte entre ambos según su posición. Un color que ya es
+    /// exacto en 8 bits (una paleta con la salida lineal) no cambia.
+    ///
+    /// # Parámetros
+    /// - `rgb`: Color lineal.
+    /// - `x`, `y`: Píxel donde se escribe, que elige el umbral de la matriz.
+    pub fn pack(self, rgb: Vec3, x: usize, y: usize) -> u32 {
+        let offset = if self.dither { (BAYER_4X4[y % 4][x % 4] as f32 + 0.5) / 16.0 - 0.5 } else { 0.0 };
+        let channel = |value: f32| (self.encoding.encode(value) * 255.0 + offset).round().clamp(0.0, 255.0) as u8;
+        Color::new(channel(rgb.x), channel(rgb.y), channel(rgb.z)).to_u32()
+    }
+
+    /// Desempaqueta un píxel del framebuffer a valores lineales entre 0 y 1.
+    pub fn unpack(self, pixel: u32) -> Vec3 {
+        Vec3::from(Color::from_u32(pixel)).map(|value| self.encoding.decode(value))
+    }
 }
 
 // Implementación de la suma para la estructura Color.
@@ -107,6 +468,30 @@ impl Add for Color {
     }
 }
 
+// Suma acumulada, como en `color += luz`.
+use std::ops::AddAssign;
+
+impl AddAssign for Color {
+    fn add_assign(&mut self, other: Color) {
+        *self = *self + other;
+    }
+}
+
+// Resta por componentes, que se satura en 0.
+use std::ops::Sub;
+
+impl Sub for Color {
+    type Output = Color;
+
+    fn sub(self, other: Color) -> Color {
+        Color {
+            r: self.r.saturating_sub(other.r),
+            g: self.g.saturating_sub(other.g),
+            b: self.b.saturating_sub(other.b),
+        }
+    }
+}
+
 // Implementación de la multiplicación por un escalar para la estructura Color.
 use std::ops::Mul;
 
@@ -122,6 +507,49 @@ impl Mul<f32> for Color {
     }
 }
 
+// Multiplicación por componentes, con los colores entre 0 y 1: filtra un color por
+// otro (la luz de un color sobre una superficie de otro).
+impl Mul<Color> for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        let channel = |a: u8, b: u8| ((a as u16 * b as u16 + 127) / 255) as u8;
+        Color {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+        }
+    }
+}
+
+// Conversiones desde y hacia componentes sueltos.
+impl From<[u8; 3]> for Color {
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        Color { r, g, b }
+    }
+}
+
+impl From<Color> for [u8; 3] {
+    fn from(color: Color) -> Self {
+        [color.r, color.g, color.b]
+    }
+}
+
+// Componentes entre 0 y 1, para hacer la cuenta de la luz en punto flotante; al
+// volver, los valores fuera de rango se saturan.
+impl From<Vec3> for Color {
+    fn from(rgb: Vec3) -> Self {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color { r: channel(rgb.x), g: channel(rgb.y), b: channel(rgb.z) }
+    }
+}
+
+impl From<Color> for Vec3 {
+    fn from(color: Color) -> Self {
+        Vec3::new(color.r as f32, color.g as f32, color.b as f32) / 255.0
+    }
+}
+
 // Implementación de la visualización de la estructura Color.
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
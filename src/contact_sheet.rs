@@ -0,0 +1,147 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::craters;
+use crate::framebuffer::{Framebuffer, Viewport};
+use crate::obj::Model;
+use crate::pipeline::{create_model_matrix, render, Lighting, Matrices, PipelineHooks};
+use crate::shader_params::ShaderParams;
+use crate::shaders::PlanetShader;
+use crate::text::{draw_text, text_width, GLYPH_HEIGHT};
+use crate::turntable::Turntable;
+use crate::Uniforms;
+
+/// Archivo donde se guarda la hoja de contactos si no se indica otro con `--out`.
+pub const CONTACT_SHEET_PATH: &str = "assets/contact_sheet.ppm";
+
+/// Color de fondo de la hoja, oscuro para que se vean los halos y las nubes.
+const BACKGROUND: u32 = 0x1C1C24;
+
+/// Espacio sobre y debajo del nombre de cada celda, en píxeles.
+const LABEL_MARGIN: usize = 4;
+
+/// Una celda de la hoja de contactos: una esfera con su nombre debajo.
+pub struct Swatch {
+    /// Nombre que se escribe debajo de la esfera.
+    pub label: String,
+    /// Shader y parámetros de la esfera; las matrices y la luz las pone la hoja.
+    pub uniforms: Uniforms,
+}
+
+impl Swatch {
+    /// Crea la celda de un shader con sus parámetros del archivo (los que falten
+    /// quedan en los valores de fábrica). Para mostrar una variante del mismo
+    /// shader basta con cambiar `uniforms.surface` y el nombre.
+    ///
+    /// # Parámetros
+    /// - `shader`: Shader de la esfera.
+    /// - `params`: Parámetros de los shaders, como los de `assets/shader_params.toml`.
+    pub fn for_shader(shader: PlanetShader, params: &ShaderParams) -> Self {
+        let mut uniforms = Uniforms::new(shader, 0);
+        uniforms.surface = *params.surface();
+        if shader == PlanetShader::Moon {
+            uniforms.material.craters = craters::generate(uniforms.noise.seed, 1.0);
+        }
+        Swatch { label: shader.name().to_string(), uniforms }
+    }
+}
+
+/// Una celda por cada shader del renderizador, en el orden de `PlanetShader::ALL`.
+pub fn shader_swatches(params: &ShaderParams) -> Vec<Swatch> {
+    PlanetShader::ALL.iter().map(|&shader| Swatch::for_shader(shader, params)).collect()
+}
+
+/// Hoja de contactos: todas las celdas en una sola imagen, en una grilla casi
+/// cuadrada, para comparar de un vistazo cada aspecto que puede dar el renderizador.
+///
+/// Cada esfera se dibuja en su propio viewport con la cámara y la luz de
+/// `Turntable`, y la tijera evita que una celda (o un nombre largo) pise a las vecinas.
+#[derive(Debug, Clone)]
+pub struct ContactSheet {
+    /// Lado de la zona de cada esfera, en píxeles; el nombre va debajo.
+    pub cell_size: usize,
+    /// Tamaño de la fuente de los nombres (1 es el tamaño original).
+    pub label_scale: usize,
+    /// Cámara, luz y ángulo de las esferas.
+    pub turntable: Turntable,
+    /// Ángulo de giro de las esferas alrededor del eje Y, en radianes.
+    pub angle: f32,
+}
+
+impl ContactSheet {
+    /// Crea una hoja con celdas de `cell_size` píxeles de lado.
+    pub fn new(cell_size: usize) -> Self {
+        ContactSheet {
+            cell_size: cell_size.max(1),
+            label_scale: if cell_size >= 200 { 2 } else { 1 },
+            turntable: Turntable::new(1),
+            angle: 0.0,
+        }
+    }
+
+    /// Crea la hoja más grande con `count` celdas que entra en `width` píxeles de ancho.
+    pub fn fit_width(width: usize, count: usize) -> Self {
+        ContactSheet::new(width / columns(count))
+    }
+
+    /// Alto de una celda con su nombre, en píxeles.
+    fn row_height(&self) -> usize {
+        self.cell_size + GLYPH_HEIGHT * self.label_scale + 2 * LABEL_MARGIN
+    }
+
+    /// Ancho y alto de la imagen para `count` celdas.
+    pub fn size(&self, count: usize) -> (usize, usize) {
+        let columns = columns(count);
+        (columns * self.cell_size, count.div_ceil(columns).max(1) * self.row_height())
+    }
+
+    /// Dibuja la hoja.
+    ///
+    /// # Parámetros
+    /// - `swatches`: Celdas, de izquierda a derecha y de arriba abajo.
+    /// - `model`: Malla de las esferas, de radio 0.5 (por ejemplo `primitives::uv_sphere`).
+    ///
+    /// # Retorna
+    /// Un framebuffer del tamaño de `size` con la hoja terminada.
+    pub fn render(&self, swatches: &mut [Swatch], model: &Model) -> Framebuffer {
+        let (width, height) = self.size(swatches.len());
+        let mut framebuffer = Framebuffer::new(width, height);
+        framebuffer.set_background_color(BACKGROUND);
+        framebuffer.clear();
+
+        let camera = self.turntable.camera(1.0);
+        let (view, projection) = (camera.view_matrix(), camera.projection_matrix());
+        let columns = columns(swatches.len());
+        for (index, swatch) in swatches.iter_mut().enumerate() {
+            let (x, y) = (index % columns * self.cell_size, index / columns * self.row_height());
+            let cell = Viewport::new(x, y, self.cell_size, self.cell_size);
+            swatch.uniforms.matrices = Matrices {
+                model: create_model_matrix(Vec3::zeros(), 1.0, Vec3::new(0.0, self.angle, 0.0)),
+                view,
+                projection,
+                viewport: cell.matrix(),
+                cull: projection * view,
+            };
+            swatch.uniforms.lighting = Lighting {
+                light_direction: self.turntable.light_direction,
+                camera_position: camera.eye,
+                atmosphere: swatch.uniforms.material.shader.atmosphere(),
+                ..Lighting::default()
+            };
+            framebuffer.set_viewport(cell);
+            framebuffer.set_scissor(Some(Viewport::new(x, y, self.cell_size, self.row_height())));
+            render(&mut framebuffer, &swatch.uniforms, &model.vertices, &model.indices, &mut PipelineHooks::new());
+
+            // el nombre centrado debajo de la esfera; si no entra, la tijera lo corta en el borde
+            let label_x = x + self.cell_size.saturating_sub(text_width(&swatch.label, self.label_scale)) / 2;
+            draw_text(&mut framebuffer, label_x, y + self.cell_size + LABEL_MARGIN, &swatch.label, Color::new(230, 230, 230), self.label_scale);
+        }
+        framebuffer.set_viewport(Viewport::full(width, height));
+        framebuffer.set_scissor(None);
+        framebuffer
+    }
+}
+
+/// Columnas de la grilla para `count` celdas: la raíz cuadrada, redondeada hacia arriba.
+fn columns(count: usize) -> usize {
+    (1..).find(|columns| columns * columns >= count).unwrap_or(1).max(1)
+}
@@ -0,0 +1,130 @@
+use std::fs;
+use std::io;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::scene::Scene;
+
+/// Carpeta donde el visor guarda el estado si se cae.
+pub const CRASH_DIR: &str = "assets/crash";
+
+/// Último fotograma terminado: lo que se guarda si el visor entra en pánico.
+struct Snapshot {
+    frame: u32,
+    sim_time: f32,
+    framebuffer: Framebuffer,
+    camera: Camera,
+}
+
+/// Estado del último fotograma terminado. El hook de pánico no puede ver las
+/// variables del ciclo principal, así que `record_frame` deja aquí una copia.
+static SNAPSHOT: Mutex<Option<Snapshot>> = Mutex::new(None);
+
+/// Escena del último `record_scene`. Va aparte de `SNAPSHOT` porque cambia mucho
+/// menos que el fotograma.
+static SCENE: Mutex<Option<Scene>> = Mutex::new(None);
+
+/// Instala un hook de pánico que, antes del mensaje habitual, guarda en `directory`
+/// el último fotograma terminado (`framebuffer.ppm`), la escena con sus semillas
+/// (`scene.scene`, que se puede volver a abrir con el visor) y un resumen con el
+/// mensaje del pánico, el fotograma y la pose de la cámara (`state.txt`).
+///
+/// Solo se guarda algo después de la primera llamada a `record_frame`.
+pub fn install_panic_hook<P: Into<PathBuf>>(directory: P) {
+    let directory = directory.into();
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // si el pánico ocurrió mientras se copiaba el estado, el candado está tomado
+        // y no hay un estado coherente que guardar
+        if let (Ok(snapshot), Ok(scene)) = (SNAPSHOT.try_lock(), SCENE.try_lock()) {
+            if let Some(snapshot) = snapshot.as_ref() {
+                match write_dump(&directory, snapshot, scene.as_ref(), info) {
+                    Ok(()) => eprintln!("Estado del fotograma {} guardado en {}", snapshot.frame, directory.display()),
+                    Err(error) => eprintln!("No se pudo guardar el estado en {}: {}", directory.display(), error),
+                }
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+/// Guarda una copia de la escena para el hook de `install_panic_hook`. Copiar la
+/// escena entera en cada fotograma sería caro, así que se llama solo cuando cambia:
+/// al cargarla y después de editarla, deshacer, recargarla o cambiar de sistema.
+///
+/// # Parámetros
+/// - `scene`: Escena que se está dibujando.
+pub fn record_scene(scene: &Scene) {
+    let Ok(mut recorded) = SCENE.lock() else { return };
+    match recorded.as_mut() {
+        Some(recorded) => recorded.clone_from(scene),
+        None => *recorded = Some(scene.clone()),
+    }
+}
+
+/// Guarda una copia del fotograma terminado y de la cámara con que se dibujó, para
+/// el hook de `install_panic_hook`. Se llama una vez por fotograma, justo después
+/// de `Framebuffer::swap_buffers`: copia del búfer frontal solo las regiones que ese
+/// cambio marcó (`Framebuffer::dirty_regions`), y la imagen entera la primera vez
+/// o si cambió el tamaño.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer recién presentado.
+/// - `camera`: Cámara con que se dibujó.
+/// - `frame`: Número del fotograma.
+/// - `sim_time`: Tiempo de simulación, en fotogramas.
+pub fn record_frame(framebuffer: &Framebuffer, camera: &Camera, frame: u32, sim_time: f32) {
+    let Ok(mut snapshot) = SNAPSHOT.lock() else { return };
+    let front = framebuffer.front();
+    match snapshot.as_mut() {
+        Some(snapshot) if (snapshot.framebuffer.width, snapshot.framebuffer.height) == (framebuffer.width, framebuffer.height) => {
+            for region in framebuffer.dirty_regions() {
+                for y in region.y..region.y + region.height {
+                    let start = y * framebuffer.width + region.x;
+                    snapshot.framebuffer.buffer[start..start + region.width].copy_from_slice(&front[start..start + region.width]);
+                }
+            }
+            snapshot.camera.clone_from(camera);
+            snapshot.frame = frame;
+            snapshot.sim_time = sim_time;
+        }
+        _ => {
+            let mut copy = Framebuffer::new(framebuffer.width, framebuffer.height);
+            copy.buffer.copy_from_slice(front);
+            *snapshot = Some(Snapshot { frame, sim_time, framebuffer: copy, camera: camera.clone() });
+        }
+    }
+}
+
+/// Escribe los archivos del volcado; sin escena solo el fotograma y el resumen.
+fn write_dump(directory: &Path, snapshot: &Snapshot, scene: Option<&Scene>, info: &PanicHookInfo) -> io::Result<()> {
+    fs::create_dir_all(directory)?;
+    snapshot.framebuffer.save_ppm(directory.join("framebuffer.ppm"))?;
+    let scene_path = directory.join("scene.scene");
+    if let Some(scene) = scene {
+        scene.save(&scene_path).map_err(|error| io::Error::other(error.to_string()))?;
+    }
+
+    let camera = &snapshot.camera;
+    let mut lines = vec![
+        format!("panic: {}", info),
+        format!("fotograma: {}", snapshot.frame),
+        format!("tiempo de simulación: {}", snapshot.sim_time),
+        format!("framebuffer: {}x{}", snapshot.framebuffer.width, snapshot.framebuffer.height),
+        format!("cámara: {:?}", camera.mode),
+        format!("  eye = {}, {}, {}", camera.eye.x, camera.eye.y, camera.eye.z),
+        format!("  center = {}, {}, {}", camera.center.x, camera.center.y, camera.center.z),
+        format!("  up = {}, {}, {}", camera.up.x, camera.up.y, camera.up.z),
+        format!("  fov = {}, aspect = {}, near = {}, far = {}", camera.fov, camera.aspect, camera.near, camera.far),
+        format!("  capas = {:#x}", camera.layer_mask),
+    ];
+    if let Some(scene) = scene {
+        lines.push(format!("cuerpos ({}), escena completa en {}:", scene.nodes.len(), scene_path.display()));
+        lines.extend(scene.nodes.iter()
+            .map(|node| format!("  {}: shader {}, semilla {}", node.name, node.shader.name(), node.seed)));
+    }
+    lines.push(String::new());
+    fs::write(directory.join("state.txt"), lines.join("\n"))
+}
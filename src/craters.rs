@@ -0,0 +1,90 @@
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+/// Cantidad de cráteres con densidad 1.
+pub const BASE_CRATER_COUNT: f32 = 48.0;
+
+/// Distancia (en radios del cráter) hasta la que llega el material eyectado.
+const EJECTA_REACH: f32 = 2.5;
+
+/// Un cráter sobre una esfera centrada en el origen.
+#[derive(Debug, Clone, Copy)]
+pub struct Crater {
+    /// Dirección (normalizada) del centro del cráter.
+    pub center: Vec3,
+    /// Radio angular, en radianes.
+    pub radius: f32,
+    /// Profundidad del cuenco, relativa al radio del cuerpo.
+    pub depth: f32,
+}
+
+/// Lo que aportan los cráteres a un punto de la superficie.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CraterSample {
+    /// Elevación: negativa en los cuencos y positiva en los bordes.
+    pub elevation: f32,
+    /// Cubrimiento de material eyectado, entre 0 y 1.
+    pub ejecta: f32,
+}
+
+/// Reparte cráteres sobre la esfera. La misma semilla da siempre los mismos cráteres.
+///
+/// # Parámetros
+/// - `seed`: Semilla del cuerpo.
+/// - `density`: Multiplicador de la cantidad de cráteres (`BASE_CRATER_COUNT` con 1; 0 no genera ninguno).
+pub fn generate(seed: u64, density: f32) -> Vec<Crater> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let count = (BASE_CRATER_COUNT * density.max(0.0)).round() as usize;
+
+    (0..count)
+        .map(|_| {
+            // Dirección uniforme sobre la esfera.
+            let z: f32 = rng.gen_range(-1.0..1.0);
+            let angle: f32 = rng.gen_range(0.0..2.0 * PI);
+            let ring = (1.0 - z * z).sqrt();
+            let center = Vec3::new(ring * angle.cos(), ring * angle.sin(), z);
+
+            // Muchos cráteres pequeños y pocos grandes.
+            let size: f32 = rng.gen();
+            let radius = 0.03 + 0.25 * size.powi(3);
+            let depth = radius * rng.gen_range(0.15..0.3);
+
+            Crater { center, radius, depth }
+        })
+        .collect()
+}
+
+/// Suma el efecto de todos los cráteres en una dirección de la superficie.
+///
+/// Cada cráter tiene un cuenco parabólico, un borde elevado justo fuera del radio
+/// y un anillo de material eyectado que se desvanece hasta `EJECTA_REACH` radios.
+///
+/// # Parámetros
+/// - `craters`: Cráteres del cuerpo.
+/// - `direction`: Dirección desde el centro del cuerpo (no hace falta normalizarla).
+pub fn sample(craters: &[Crater], direction: Vec3) -> CraterSample {
+    let Some(direction) = direction.try_normalize(f32::EPSILON) else {
+        return CraterSample::default();
+    };
+
+    craters.iter().fold(CraterSample::default(), |mut total, crater| {
+        let cosine = direction.dot(&crater.center);
+        // Descarte rápido: el punto está lejos del cráter.
+        if cosine < (crater.radius * EJECTA_REACH).min(PI).cos() {
+            return total;
+        }
+
+        let distance = cosine.clamp(-1.0, 1.0).acos() / crater.radius;
+        let rim = (-((distance - 1.0) / 0.2).powi(2)).exp();
+        let bowl = if distance < 1.0 { 1.0 - distance * distance } else { 0.0 };
+        total.elevation += crater.depth * (0.4 * rim - bowl);
+
+        if distance > 1.0 {
+            let ejecta = 1.0 - (distance - 1.0) / (EJECTA_REACH - 1.0);
+            total.ejecta = total.ejecta.max(ejecta.clamp(0.0, 1.0));
+        }
+        total
+    })
+}
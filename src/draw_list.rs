@@ -0,0 +1,201 @@
+use nalgebra_glm::Mat4;
+use crate::framebuffer::Framebuffer;
+use crate::vertex::Vertex;
+use crate::pipeline::{render_instanced, render_profiled, PipelineHooks, TiledFrame, Uniforms};
+use crate::profiler::Profiler;
+
+/// Un objeto opaco pendiente de dibujar.
+pub struct DrawCall<'a> {
+    /// Uniforms con los que se dibuja el objeto.
+    pub uniforms: Uniforms,
+    /// Buffer de vértices de la malla.
+    pub vertices: &'a [Vertex],
+    /// Índices de los triángulos dentro de `vertices`.
+    pub indices: &'a [u32],
+    /// Distancia aproximada del objeto a la cámara, usada para ordenar.
+    pub depth: f32,
+    /// Matrices de las copias de la malla, relativas a la de modelo; vacío para un
+    /// objeto que se dibuja una sola vez.
+    pub instances: Vec<Mat4>,
+}
+
+/// Orden en que se dibujan los objetos opacos de una `DrawList`. Los
+/// semitransparentes siempre van al final y de atrás hacia adelante, que es lo
+/// que necesita la mezcla.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DrawOrder {
+    /// Agrupados por shader, variante y malla, y de adelante hacia atrás dentro de cada grupo.
+    #[default]
+    ByState,
+    /// Solo por distancia, de adelante hacia atrás: el z-buffer descarta la mayor
+    /// cantidad posible de fragmentos tapados antes del shader de fragmentos.
+    FrontToBack,
+    /// En el orden en que se agregaron, para medir cuánto ahorra ordenar.
+    Submission,
+}
+
+impl DrawOrder {
+    /// Todos los órdenes, en el orden en que se alternan desde el teclado.
+    pub const ALL: [DrawOrder; 3] = [DrawOrder::ByState, DrawOrder::FrontToBack, DrawOrder::Submission];
+
+    /// Devuelve el nombre corto del orden.
+    pub fn name(self) -> &'static str {
+        match self {
+            DrawOrder::ByState => "state",
+            DrawOrder::FrontToBack => "front_to_back",
+            DrawOrder::Submission => "submission",
+        }
+    }
+
+    /// Devuelve el orden siguiente de `ALL`, volviendo al primero después del último.
+    pub fn next(self) -> Self {
+        let index = DrawOrder::ALL.iter().position(|&order| order == self).unwrap_or(0);
+        DrawOrder::ALL[(index + 1) % DrawOrder::ALL.len()]
+    }
+}
+
+/// Lista de objetos a dibujar en un fotograma.
+///
+/// En lugar de dibujar cada objeto en cuanto se recorre la escena, se acumulan
+/// y se envían juntos: primero agrupados por shader, variante y malla (para
+/// que los objetos que comparten estado queden seguidos) y dentro de cada
+/// grupo de adelante hacia atrás, para que el z-buffer descarte cuanto antes
+/// los fragmentos tapados (ver `DrawOrder` para otros órdenes). Los objetos
+/// semitransparentes se dibujan al final, de atrás hacia adelante, para que se
+/// mezclen sobre lo que tienen detrás.
+#[derive(Default)]
+pub struct DrawList<'a> {
+    draws: Vec<DrawCall<'a>>,
+    transparent: Vec<DrawCall<'a>>,
+    order: DrawOrder,
+    threads: usize,
+}
+
+impl<'a> DrawList<'a> {
+    /// Crea una lista vacía con el orden `DrawOrder::ByState`.
+    pub fn new() -> Self {
+        DrawList::with_order(DrawOrder::default())
+    }
+
+    /// Crea una lista vacía que dibuja los objetos opacos en el orden indicado.
+    pub fn with_order(order: DrawOrder) -> Self {
+        DrawList { draws: Vec::new(), transparent: Vec::new(), order, threads: 1 }
+    }
+
+    /// Orden de los objetos opacos.
+    pub fn order(&self) -> DrawOrder {
+        self.order
+    }
+
+    /// Cambia el orden de los objetos opacos.
+    pub fn set_order(&mut self, order: DrawOrder) {
+        self.order = order;
+    }
+
+    /// Hilos entre los que se reparte el dibujo.
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Reparte el dibujo entre varios hilos: con más de uno, `submit` anota los
+    /// triángulos de todos los objetos en los tiles del framebuffer y los dibuja
+    /// en una sola pasada por los tiles (ver `pipeline::TiledFrame`). La imagen es
+    /// la misma. Si el framebuffer tiene una prueba de stencil activa se dibuja en
+    /// un solo hilo, porque los tiles no tienen stencil.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Agrega un objeto a la lista.
+    ///
+    /// # Parámetros
+    /// - `uniforms`: Matrices, ruido, shader y materiales del objeto.
+    /// - `vertices`: Buffer de vértices de la malla.
+    /// - `indices`: Índices de los triángulos, tres por triángulo.
+    /// - `depth`: Distancia del objeto a la cámara (la más cercana de su volumen, si se conoce).
+    pub fn push(&mut self, uniforms: Uniforms, vertices: &'a [Vertex], indices: &'a [u32], depth: f32) {
+        self.push_instanced(uniforms, vertices, indices, Vec::new(), depth);
+    }
+
+    /// Agrega una malla que se dibuja muchas veces, una por cada matriz de `instances`
+    /// (ver `pipeline::render_instanced`); el conjunto se ordena como un solo objeto.
+    ///
+    /// # Parámetros
+    /// - `uniforms`: Uniforms compartidos; `matrices.model` ubica el conjunto en el mundo.
+    /// - `vertices`: Buffer de vértices de la malla.
+    /// - `indices`: Índices de los triángulos, tres por triángulo.
+    /// - `instances`: Matriz de cada copia, relativa a la de modelo.
+    /// - `depth`: Distancia del conjunto a la cámara.
+    pub fn push_instanced(&mut self, uniforms: Uniforms, vertices: &'a [Vertex], indices: &'a [u32], instances: Vec<Mat4>, depth: f32) {
+        let draw = DrawCall { uniforms, vertices, indices, depth, instances };
+        if draw.uniforms.material.shader.is_transparent() {
+            self.transparent.push(draw);
+        } else {
+            self.draws.push(draw);
+        }
+    }
+
+    /// Número de objetos en la lista.
+    pub fn len(&self) -> usize {
+        self.draws.len() + self.transparent.len()
+    }
+
+    /// Indica si la lista está vacía.
+    pub fn is_empty(&self) -> bool {
+        self.draws.is_empty() && self.transparent.is_empty()
+    }
+
+    /// Ordena los objetos opacos según `order` (por omisión por shader, variante y
+    /// malla, y de adelante hacia atrás); los semitransparentes solo de atrás hacia adelante.
+    pub fn sort(&mut self) {
+        match self.order {
+            DrawOrder::ByState => self.draws.sort_by(|a, b| {
+                state_key(a).cmp(&state_key(b)).then(a.depth.total_cmp(&b.depth))
+            }),
+            DrawOrder::FrontToBack => self.draws.sort_by(|a, b| a.depth.total_cmp(&b.depth)),
+            DrawOrder::Submission => {}
+        }
+        self.transparent.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+    }
+
+    /// Ordena y dibuja todos los objetos, dejando la lista vacía.
+    ///
+    /// # Parámetros
+    /// - `framebuffer`: El framebuffer de destino.
+    /// - `hooks`: Hooks del pipeline que se ejecutan para cada objeto.
+    pub fn submit(&mut self, framebuffer: &mut Framebuffer, hooks: &mut PipelineHooks) {
+        self.submit_profiled(framebuffer, hooks, None);
+    }
+
+    /// Igual que `submit`, pero mide las etapas del pipeline de cada objeto con `profiler`.
+    pub fn submit_profiled(&mut self, framebuffer: &mut Framebuffer, hooks: &mut PipelineHooks, mut profiler: Option<&mut Profiler>) {
+        self.sort();
+        if self.threads > 1 && !self.is_empty() && !framebuffer.uses_stencil() {
+            let mut draws: Vec<DrawCall> = self.draws.drain(..).chain(self.transparent.drain(..)).collect();
+            let mut frame = TiledFrame::new(framebuffer, self.threads);
+            for draw in &mut draws {
+                if draw.instances.is_empty() {
+                    let draw: &DrawCall = draw;
+                    frame.render(&draw.uniforms, draw.vertices, draw.indices, hooks, profiler.as_deref_mut());
+                } else {
+                    frame.render_instanced(&mut draw.uniforms, draw.vertices, draw.indices, &draw.instances, hooks, profiler.as_deref_mut());
+                }
+            }
+            frame.finish(framebuffer, hooks, profiler);
+            return;
+        }
+        for mut draw in self.draws.drain(..).chain(self.transparent.drain(..)) {
+            if draw.instances.is_empty() {
+                render_profiled(framebuffer, &draw.uniforms, draw.vertices, draw.indices, hooks, profiler.as_deref_mut());
+            } else {
+                render_instanced(framebuffer, &mut draw.uniforms, draw.vertices, draw.indices, &draw.instances, hooks, profiler.as_deref_mut());
+            }
+        }
+    }
+}
+
+/// Clave del estado que comparten los objetos de un mismo grupo: shader,
+/// variante y malla (la malla determina también sus materiales).
+fn state_key(draw: &DrawCall) -> (usize, u32, usize) {
+    (draw.uniforms.material.shader as usize, draw.uniforms.material.features, draw.vertices.as_ptr() as usize)
+}
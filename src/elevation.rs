@@ -0,0 +1,200 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use nalgebra_glm::Vec2;
+use crate::vertex::Vertex;
+
+/// Radio de referencia de las mallas de la escena: las primitivas y `sphere.obj`
+/// caben en una esfera de radio 0.5.
+pub const MESH_RADIUS: f32 = 0.5;
+
+/// Forma de calcular la elevación de cada vértice de un objeto.
+///
+/// La elevación se calcula en el shader de vértices, así que un mismo modelo
+/// puede usarse con estrategias distintas en cada objeto de la escena.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ElevationMode {
+    /// La altura sobre el plano XZ (`position.y`); útil para terrenos planos.
+    Height,
+    /// La distancia al centro menos `MESH_RADIUS`; la adecuada para esferas.
+    #[default]
+    Radial,
+    /// El valor del mapa de alturas en las coordenadas de textura del vértice.
+    Texture,
+    /// Sin elevación: siempre 0.
+    None,
+}
+
+impl ElevationMode {
+    /// Todas las estrategias, en el orden en que se presentan al usuario.
+    pub const ALL: [ElevationMode; 4] = [
+        ElevationMode::Height,
+        ElevationMode::Radial,
+        ElevationMode::Texture,
+        ElevationMode::None,
+    ];
+
+    /// Devuelve el nombre corto de la estrategia.
+    pub fn name(self) -> &'static str {
+        match self {
+            ElevationMode::Height => "height",
+            ElevationMode::Radial => "radial",
+            ElevationMode::Texture => "texture",
+            ElevationMode::None => "none",
+        }
+    }
+
+    /// Busca una estrategia por su nombre corto.
+    pub fn from_name(name: &str) -> Option<ElevationMode> {
+        ElevationMode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    /// Calcula la elevación de un vértice.
+    ///
+    /// # Parámetros
+    /// - `vertex`: Vértice en espacio del modelo.
+    /// - `heightmap`: Mapa de alturas del objeto; sin él, `Texture` da 0.
+    pub fn elevation(self, vertex: &Vertex, heightmap: Option<&Heightmap>) -> f32 {
+        match self {
+            ElevationMode::Height => vertex.position.y,
+            ElevationMode::Radial => vertex.position.magnitude() - MESH_RADIUS,
+            ElevationMode::Texture => heightmap.map_or(0.0, |map| map.sample(vertex.tex_coords)),
+            ElevationMode::None => 0.0,
+        }
+    }
+}
+
+/// Mapa de alturas en escala de grises, con valores entre 0 y 1.
+#[derive(Debug, Clone)]
+pub struct Heightmap {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+/// Error al leer un mapa de alturas.
+#[derive(Debug)]
+pub enum HeightmapError {
+    /// No se pudo leer el archivo.
+    Io(io::Error),
+    /// El archivo no es un PGM válido.
+    Format(String),
+}
+
+impl fmt::Display for HeightmapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeightmapError::Io(error) => write!(f, "error de E/S: {}", error),
+            HeightmapError::Format(message) => write!(f, "PGM inválido: {}", message),
+        }
+    }
+}
+
+impl From<io::Error> for HeightmapError {
+    fn from(error: io::Error) -> Self {
+        HeightmapError::Io(error)
+    }
+}
+
+impl Heightmap {
+    /// Crea un mapa de alturas a partir de sus valores, fila por fila.
+    ///
+    /// # Retorna
+    /// `None` si las dimensiones son cero o no coinciden con la cantidad de valores.
+    pub fn new(width: usize, height: usize, values: Vec<f32>) -> Option<Self> {
+        if width == 0 || height == 0 || values.len() != width * height {
+            return None;
+        }
+        Some(Heightmap { width, height, values })
+    }
+
+    /// Carga un mapa de alturas desde una imagen PGM (`P2` de texto o `P5` binario).
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, HeightmapError> {
+        Heightmap::from_pgm(&fs::read(path)?)
+    }
+
+    /// Interpreta el contenido de una imagen PGM.
+    pub fn from_pgm(bytes: &[u8]) -> Result<Self, HeightmapError> {
+        let error = |message: &str| HeightmapError::Format(message.to_string());
+        let mut position = 0;
+
+        // La cabecera son cuatro números en texto separados por espacios; `#` inicia un comentario.
+        let next_token = |position: &mut usize| -> Option<String> {
+            loop {
+                while bytes.get(*position).is_some_and(u8::is_ascii_whitespace) {
+                    *position += 1;
+                }
+                if bytes.get(*position) == Some(&b'#') {
+                    while bytes.get(*position).is_some_and(|&byte| byte != b'\n') {
+                        *position += 1;
+                    }
+                    continue;
+                }
+                break;
+            }
+            let start = *position;
+            while bytes.get(*position).is_some_and(|byte| !byte.is_ascii_whitespace()) {
+                *position += 1;
+            }
+            (start < *position).then(|| String::from_utf8_lossy(&bytes[start..*position]).into_owned())
+        };
+        let number = |position: &mut usize, name: &str| -> Result<usize, HeightmapError> {
+            next_token(position)
+                .and_then(|token| token.parse().ok())
+                .ok_or_else(|| error(&format!("falta {}", name)))
+        };
+
+        let magic = next_token(&mut position).ok_or_else(|| error("archivo vacío"))?;
+        let width = number(&mut position, "el ancho")?;
+        let height = number(&mut position, "el alto")?;
+        let max_value = number(&mut position, "el valor máximo")?;
+        if max_value == 0 || max_value > u16::MAX as usize {
+            return Err(error("valor máximo fuera de rango"));
+        }
+        let count = width.checked_mul(height).ok_or_else(|| error("dimensiones demasiado grandes"))?;
+
+        let raw: Vec<usize> = match magic.as_str() {
+            "P2" => (0..count)
+                .map(|_| number(&mut position, "un valor"))
+                .collect::<Result<_, _>>()?,
+            "P5" => {
+                // Un solo espacio separa la cabecera de los datos.
+                let data = bytes.get(position + 1..).unwrap_or(&[]);
+                let sample_size = if max_value < 256 { 1 } else { 2 };
+                if data.len() < count * sample_size {
+                    return Err(error("faltan datos"));
+                }
+                data.chunks_exact(sample_size)
+                    .take(count)
+                    .map(|sample| sample.iter().fold(0, |value, &byte| value << 8 | byte as usize))
+                    .collect()
+            }
+            _ => return Err(error("solo se aceptan imágenes P2 y P5")),
+        };
+
+        let values = raw.into_iter()
+            .map(|value| value.min(max_value) as f32 / max_value as f32)
+            .collect();
+        Heightmap::new(width, height, values).ok_or_else(|| error("imagen vacía"))
+    }
+
+    /// Devuelve la altura interpolada en unas coordenadas de textura. `u` da la
+    /// vuelta (como la longitud de una esfera) y `v` se limita al borde.
+    pub fn sample(&self, tex_coords: Vec2) -> f32 {
+        let x = tex_coords.x.rem_euclid(1.0) * self.width as f32 - 0.5;
+        let y = tex_coords.y.clamp(0.0, 1.0) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+
+        let value = |column: f32, row: f32| {
+            let column = (column as i64).rem_euclid(self.width as i64) as usize;
+            let row = (row as i64).clamp(0, self.height as i64 - 1) as usize;
+            self.values[row * self.width + column]
+        };
+
+        let top = value(x0, y0) * (1.0 - tx) + value(x0 + 1.0, y0) * tx;
+        let bottom = value(x0, y0 + 1.0) * (1.0 - tx) + value(x0 + 1.0, y0 + 1.0) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
@@ -0,0 +1,266 @@
+use std::f32::consts::PI;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::Path;
+use nalgebra_glm::Vec3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::color::Color;
+use crate::stars::StarCatalog;
+
+/// Imagen del espacio que el visor usa si existe; si no, genera un campo de estrellas.
+pub const ENVIRONMENT_PATH: &str = "assets/environment.ppm";
+
+/// Imagen equirectangular del cielo (longitud en horizontal, latitud en vertical)
+/// que los shaders muestrean en la dirección de un reflejo, para que el agua y el
+/// hielo reflejen tenuemente las estrellas.
+#[derive(Debug, Clone)]
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+/// Error al leer una imagen de entorno.
+#[derive(Debug)]
+pub enum EnvironmentError {
+    /// No se pudo leer el archivo.
+    Io(io::Error),
+    /// El archivo no es una imagen válida.
+    Format(String),
+}
+
+impl fmt::Display for EnvironmentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnvironmentError::Io(error) => write!(f, "error de E/S: {}", error),
+            EnvironmentError::Format(message) => write!(f, "imagen inválida: {}", message),
+        }
+    }
+}
+
+impl From<io::Error> for EnvironmentError {
+    fn from(error: io::Error) -> Self {
+        EnvironmentError::Io(error)
+    }
+}
+
+impl EnvironmentMap {
+    /// Crea una imagen de entorno a partir de sus píxeles, fila por fila desde el polo norte.
+    ///
+    /// # Retorna
+    /// `None` si las dimensiones son cero o no coinciden con la cantidad de píxeles.
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Option<Self> {
+        if width == 0 || height == 0 || pixels.len() != width * height {
+            return None;
+        }
+        Some(EnvironmentMap { width, height, pixels })
+    }
+
+    /// Ancho y alto de la imagen, en píxeles.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Píxeles de la imagen, fila por fila desde el polo norte.
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// Carga una imagen PNG (por la extensión `.png`) o PPM (`P3` de texto o `P6` binario).
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, EnvironmentError> {
+        let path = path.as_ref();
+        let is_png = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+        if is_png {
+            EnvironmentMap::from_png(File::open(path)?)
+        } else {
+            EnvironmentMap::from_ppm(&fs::read(path)?)
+        }
+    }
+
+    /// Interpreta el contenido de una imagen PPM.
+    pub fn from_ppm(bytes: &[u8]) -> Result<Self, EnvironmentError> {
+        let error = |message: &str| EnvironmentError::Format(message.to_string());
+        let mut position = 0;
+
+        // Cabecera de cuatro números en texto, como en los PGM (ver `Heightmap::from_pgm`).
+        let next_token = |position: &mut usize| -> Option<String> {
+            loop {
+                while bytes.get(*position).is_some_and(u8::is_ascii_whitespace) {
+                    *position += 1;
+                }
+                if bytes.get(*position) == Some(&b'#') {
+                    while bytes.get(*position).is_some_and(|&byte| byte != b'\n') {
+                        *position += 1;
+                    }
+                    continue;
+                }
+                break;
+            }
+            let start = *position;
+            while bytes.get(*position).is_some_and(|byte| !byte.is_ascii_whitespace()) {
+                *position += 1;
+            }
+            (start < *position).then(|| String::from_utf8_lossy(&bytes[start..*position]).into_owned())
+        };
+        let number = |position: &mut usize, name: &str| -> Result<usize, EnvironmentError> {
+            next_token(position)
+                .and_then(|token| token.parse().ok())
+                .ok_or_else(|| error(&format!("falta {}", name)))
+        };
+
+        let magic = next_token(&mut position).ok_or_else(|| error("archivo vacío"))?;
+        let width = number(&mut position, "el ancho")?;
+        let height = number(&mut position, "el alto")?;
+        let max_value = number(&mut position, "el valor máximo")?;
+        if max_value == 0 || max_value > u16::MAX as usize {
+            return Err(error("valor máximo fuera de rango"));
+        }
+        let count = width.checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(|| error("dimensiones demasiado grandes"))?;
+
+        let raw: Vec<usize> = match magic.as_str() {
+            "P3" => (0..count)
+                .map(|_| number(&mut position, "un valor"))
+                .collect::<Result<_, _>>()?,
+            "P6" => {
+                // Un solo espacio separa la cabecera de los datos.
+                let data = bytes.get(position + 1..).unwrap_or(&[]);
+                let sample_size = if max_value < 256 { 1 } else { 2 };
+                if data.len() < count * sample_size {
+                    return Err(error("faltan datos"));
+                }
+                data.chunks_exact(sample_size)
+                    .take(count)
+                    .map(|sample| sample.iter().fold(0, |value, &byte| value << 8 | byte as usize))
+                    .collect()
+            }
+            _ => return Err(error("solo se aceptan imágenes P3 y P6")),
+        };
+
+        let channel = |value: usize| (value.min(max_value) * 255 / max_value) as u8;
+        let pixels = raw.chunks_exact(3)
+            .map(|rgb| Color::new(channel(rgb[0]), channel(rgb[1]), channel(rgb[2])))
+            .collect();
+        EnvironmentMap::new(width, height, pixels).ok_or_else(|| error("imagen vacía"))
+    }
+
+    /// Interpreta una imagen PNG en cualquier formato de color; la transparencia se ignora.
+    pub fn from_png<R: io::Read>(reader: R) -> Result<Self, EnvironmentError> {
+        let error = |message: String| EnvironmentError::Format(message);
+        let mut decoder = png::Decoder::new(BufReader::new(reader));
+        // Paletas, grises de pocos bits y 16 bits pasan a 8 bits por canal.
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|decode| error(decode.to_string()))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut buffer).map_err(|decode| error(decode.to_string()))?;
+        let data = &buffer[..frame.buffer_size()];
+
+        let pixels = match frame.color_type {
+            png::ColorType::Rgb => data.chunks_exact(3).map(|pixel| Color::new(pixel[0], pixel[1], pixel[2])).collect(),
+            png::ColorType::Rgba => data.chunks_exact(4).map(|pixel| Color::new(pixel[0], pixel[1], pixel[2])).collect(),
+            png::ColorType::Grayscale => data.iter().map(|&gray| Color::new(gray, gray, gray)).collect(),
+            png::ColorType::GrayscaleAlpha => data.chunks_exact(2).map(|pixel| Color::new(pixel[0], pixel[0], pixel[0])).collect(),
+            png::ColorType::Indexed => return Err(error("paleta sin expandir".to_string())),
+        };
+        EnvironmentMap::new(frame.width as usize, frame.height as usize, pixels)
+            .ok_or_else(|| error("imagen vacía".to_string()))
+    }
+
+    /// Genera un cielo negro con estrellas de brillo y tono al azar, repartidas de
+    /// forma pareja sobre la esfera (no se amontonan en los polos de la imagen).
+    ///
+    /// # Parámetros
+    /// - `width`, `height`: Tamaño de la imagen; conviene el doble de ancho que de alto.
+    /// - `stars`: Cantidad de estrellas.
+    /// - `seed`: Semilla; la misma semilla da el mismo cielo.
+    pub fn starfield(width: usize, height: usize, stars: usize, seed: u64) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+        let mut pixels = vec![Color::black(); width * height];
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..stars {
+            // Coseno de la latitud uniforme: misma densidad en toda la esfera.
+            let y: f32 = rng.gen_range(-1.0..1.0);
+            let longitude: f32 = rng.gen_range(0.0..2.0 * PI);
+            let radius = (1.0 - y * y).sqrt();
+            let direction = Vec3::new(radius * longitude.sin(), y, -radius * longitude.cos());
+            let (column, row) = equirect_pixel(direction, width, height);
+
+            let brightness: f32 = rng.gen_range(0.2_f32..1.0).powi(3);
+            let warmth: f32 = rng.gen_range(-1.0..1.0);
+            let tint = if warmth > 0.0 {
+                Color::new(255, 255, 255).lerp(&Color::new(255, 190, 140), warmth)
+            } else {
+                Color::new(255, 255, 255).lerp(&Color::new(160, 190, 255), -warmth)
+            };
+            let pixel = &mut pixels[row * width + column];
+            *pixel += tint * brightness;
+        }
+        EnvironmentMap { width, height, pixels }
+    }
+
+    /// Pinta las estrellas de un catálogo en un cielo negro, para que las superficies
+    /// reflejen el mismo cielo que se ve de fondo (ver `StarCatalog::draw`).
+    ///
+    /// # Parámetros
+    /// - `width`, `height`: Tamaño de la imagen; conviene el doble de ancho que de alto.
+    /// - `catalog`: Estrellas a pintar, cada una en el píxel de su dirección.
+    pub fn from_stars(width: usize, height: usize, catalog: &StarCatalog) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+        let mut pixels = vec![Color::black(); width * height];
+        for star in catalog.stars() {
+            let (column, row) = equirect_pixel(star.direction, width, height);
+            pixels[row * width + column] += star.color * star.brightness();
+        }
+        EnvironmentMap { width, height, pixels }
+    }
+
+    /// Devuelve el color del cielo en una dirección, interpolando los cuatro píxeles vecinos.
+    ///
+    /// La columna sale de la longitud (la dirección `-Z` cae en el centro de la
+    /// imagen y la longitud crece hacia `+X`) y la fila de la latitud (`+Y` arriba).
+    ///
+    /// # Parámetros
+    /// - `direction`: Dirección en el mundo; no hace falta normalizarla.
+    pub fn sample_equirect(&self, direction: Vec3) -> Color {
+        let Some((u, v)) = equirect_coords(direction) else { return Color::black() };
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+
+        // La longitud da la vuelta y la latitud se limita a los polos.
+        let pixel = |column: f32, row: f32| {
+            let column = (column as i64).rem_euclid(self.width as i64) as usize;
+            let row = (row as i64).clamp(0, self.height as i64 - 1) as usize;
+            self.pixels[row * self.width + column]
+        };
+
+        let top = pixel(x0, y0).lerp(&pixel(x0 + 1.0, y0), tx);
+        let bottom = pixel(x0, y0 + 1.0).lerp(&pixel(x0 + 1.0, y0 + 1.0), tx);
+        top.lerp(&bottom, ty)
+    }
+}
+
+/// Coordenadas `(u, v)` entre 0 y 1 de una dirección en la imagen equirectangular,
+/// o `None` para el vector nulo.
+fn equirect_coords(direction: Vec3) -> Option<(f32, f32)> {
+    let length = direction.magnitude();
+    if length <= 1e-6 {
+        return None;
+    }
+    let direction = direction / length;
+    let u = 0.5 + direction.x.atan2(-direction.z) / (2.0 * PI);
+    let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+    Some((u, v))
+}
+
+/// Píxel de la imagen donde cae una dirección.
+fn equirect_pixel(direction: Vec3, width: usize, height: usize) -> (usize, usize) {
+    let (u, v) = equirect_coords(direction).unwrap_or((0.5, 0.5));
+    let column = ((u * width as f32) as usize).min(width - 1);
+    let row = ((v * height as f32) as usize).min(height - 1);
+    (column, row)
+}
@@ -0,0 +1,252 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use nalgebra_glm::Vec3;
+use crate::elevation::MESH_RADIUS;
+use crate::scene::Scene;
+use crate::scene_diff::{SceneChange, SceneDiff};
+
+/// Algo que pasó en la escena o en el renderizador.
+///
+/// Los cuerpos se identifican por nombre y no por índice, porque los índices
+/// cambian al agregar o quitar nodos.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Apareció un cuerpo nuevo en la escena.
+    NodeAdded { name: String },
+    /// Se quitó un cuerpo de la escena.
+    NodeRemoved { name: String },
+    /// Dos cuerpos empezaron a tocarse.
+    Collision { first: String, second: String },
+    /// `occluder` empezó a tapar la luz que llega a `body`.
+    EclipseStarted { occluder: String, body: String },
+    /// La cámara pasó cerca de un cuerpo (a menos de `FLYBY_DISTANCE` radios).
+    Flyby { body: String },
+    /// Se volvió a cargar un archivo (escena, malla o parámetros).
+    AssetReloaded { path: String },
+    /// Se terminó de dibujar un fotograma.
+    FrameCompleted { frame: u32 },
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::NodeAdded { name } => write!(f, "{} agregado", name),
+            Event::NodeRemoved { name } => write!(f, "{} eliminado", name),
+            Event::Collision { first, second } => write!(f, "choque: {} y {}", first, second),
+            Event::EclipseStarted { occluder, body } => write!(f, "eclipse: {} tapa a {}", occluder, body),
+            Event::Flyby { body } => write!(f, "sobrevuelo de {}", body),
+            Event::AssetReloaded { path } => write!(f, "recargado: {}", path),
+            Event::FrameCompleted { frame } => write!(f, "fotograma {}", frame),
+        }
+    }
+}
+
+/// Distancia a la que la cámara sobrevuela un cuerpo, en radios del cuerpo.
+pub const FLYBY_DISTANCE: f32 = 3.0;
+
+/// Función que reacciona a los eventos.
+pub type EventHandler = Box<dyn FnMut(&Event)>;
+
+/// Cola de eventos con suscriptores.
+///
+/// Los módulos publican eventos con `emit` sin saber quién los escucha; los
+/// suscriptores (interfaz, sonido, scripts) los reciben todos juntos al llamar a
+/// `dispatch`, normalmente una vez por fotograma, en el orden en que se publicaron.
+#[derive(Default)]
+pub struct EventBus {
+    handlers: Vec<(usize, EventHandler)>,
+    queue: Vec<Event>,
+    next_id: usize,
+}
+
+impl EventBus {
+    /// Crea un bus sin suscriptores ni eventos pendientes.
+    pub fn new() -> Self {
+        EventBus::default()
+    }
+
+    /// Registra un suscriptor que recibe todos los eventos.
+    ///
+    /// # Retorna
+    /// Un identificador para `unsubscribe`.
+    pub fn subscribe<F: FnMut(&Event) + 'static>(&mut self, handler: F) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.handlers.push((id, Box::new(handler)));
+        id
+    }
+
+    /// Quita un suscriptor.
+    ///
+    /// # Retorna
+    /// `false` si no había ninguno con ese identificador.
+    pub fn unsubscribe(&mut self, id: usize) -> bool {
+        let count = self.handlers.len();
+        self.handlers.retain(|(handler_id, _)| *handler_id != id);
+        self.handlers.len() != count
+    }
+
+    /// Publica un evento; se entrega en el próximo `dispatch`.
+    pub fn emit(&mut self, event: Event) {
+        self.queue.push(event);
+    }
+
+    /// Publica varios eventos.
+    pub fn emit_all<I: IntoIterator<Item = Event>>(&mut self, events: I) {
+        self.queue.extend(events);
+    }
+
+    /// Entrega los eventos pendientes a todos los suscriptores y vacía la cola.
+    pub fn dispatch(&mut self) {
+        for event in std::mem::take(&mut self.queue) {
+            for (_, handler) in &mut self.handlers {
+                handler(&event);
+            }
+        }
+    }
+}
+
+/// Compara dos versiones de una escena y devuelve los cuerpos agregados y quitados.
+pub fn node_changes(before: &Scene, after: &Scene) -> Vec<Event> {
+    let names = |scene: &Scene| scene.nodes.iter().map(|node| node.name.clone()).collect::<HashSet<_>>();
+    let (old, new) = (names(before), names(after));
+
+    let removed = before.nodes.iter()
+        .filter(|node| !new.contains(&node.name))
+        .map(|node| Event::NodeRemoved { name: node.name.clone() });
+    let added = after.nodes.iter()
+        .filter(|node| !old.contains(&node.name))
+        .map(|node| Event::NodeAdded { name: node.name.clone() });
+    removed.chain(added).collect()
+}
+
+/// Devuelve los cuerpos agregados y quitados por un diff, como `node_changes` pero
+/// sin necesitar la escena anterior.
+pub fn diff_node_changes(changes: &SceneDiff) -> Vec<Event> {
+    changes.changes.iter()
+        .filter_map(|change| match change {
+            SceneChange::Added { name, .. } => Some(Event::NodeAdded { name: name.clone() }),
+            SceneChange::Removed { name } => Some(Event::NodeRemoved { name: name.clone() }),
+            SceneChange::Changed { .. } | SceneChange::FieldRemoved { .. } => None,
+        })
+        .collect()
+}
+
+/// Detecta choques, eclipses y sobrevuelos de la cámara, y avisa solo cuando empiezan.
+#[derive(Debug, Default)]
+pub struct ContactTracker {
+    /// Pares de cuerpos que se tocaban en la última actualización.
+    collisions: BTreeSet<(String, String)>,
+    /// Pares (ocultador, cuerpo) en eclipse en la última actualización.
+    eclipses: BTreeSet<(String, String)>,
+    /// Cuerpos que la cámara tenía cerca en la última actualización.
+    flybys: BTreeSet<String>,
+}
+
+impl ContactTracker {
+    /// Crea un detector sin contactos previos.
+    pub fn new() -> Self {
+        ContactTracker::default()
+    }
+
+    /// Revisa la escena en un instante.
+    ///
+    /// Cada cuerpo visible se trata como una esfera del tamaño de su malla. No se
+    /// comparan un cuerpo y sus capas pegadas (hijos sin órbita, como nubes o anillos).
+    ///
+    /// # Parámetros
+    /// - `scene`: Escena a revisar.
+    /// - `time`: Tiempo de simulación en fotogramas.
+    ///
+    /// # Retorna
+    /// Los choques y eclipses que empezaron desde la última llamada.
+    pub fn update(&mut self, scene: &Scene, time: f32) -> Vec<Event> {
+        let bodies: Vec<(usize, Vec3, f32)> = scene.nodes.iter().enumerate()
+            .filter(|(_, node)| node.visible)
+            .map(|(index, node)| (index, scene.world_position(index, time), node.radius * MESH_RADIUS))
+            .collect();
+        let attached = |a: usize, b: usize| {
+            let is_layer = |child: usize, parent: usize| {
+                scene.nodes[child].parent == Some(parent) && scene.nodes[child].orbit.is_none()
+            };
+            is_layer(a, b) || is_layer(b, a)
+        };
+        let name = |index: usize| scene.nodes[index].name.clone();
+        let mut events = Vec::new();
+
+        let mut collisions = BTreeSet::new();
+        for (i, &(a, position_a, radius_a)) in bodies.iter().enumerate() {
+            for &(b, position_b, radius_b) in &bodies[i + 1..] {
+                if !attached(a, b) && (position_a - position_b).magnitude() < radius_a + radius_b {
+                    collisions.insert((name(a), name(b)));
+                }
+            }
+        }
+
+        let mut eclipses = BTreeSet::new();
+        if let Some(light) = scene.light_source() {
+            let light_position = scene.world_position(light, time);
+            for &(body, body_position, body_radius) in &bodies {
+                for &(occluder, occluder_position, occluder_radius) in &bodies {
+                    if body == light || occluder == light || body == occluder || attached(body, occluder) {
+                        continue;
+                    }
+                    if shadows(light_position, occluder_position, occluder_radius, body_position, body_radius) {
+                        eclipses.insert((name(occluder), name(body)));
+                    }
+                }
+            }
+        }
+
+        events.extend(collisions.difference(&self.collisions)
+            .map(|(first, second)| Event::Collision { first: first.clone(), second: second.clone() }));
+        events.extend(eclipses.difference(&self.eclipses)
+            .map(|(occluder, body)| Event::EclipseStarted { occluder: occluder.clone(), body: body.clone() }));
+        self.collisions = collisions;
+        self.eclipses = eclipses;
+        events
+    }
+
+    /// Revisa qué cuerpos tiene cerca la cámara.
+    ///
+    /// # Parámetros
+    /// - `scene`: Escena a revisar.
+    /// - `time`: Tiempo de simulación en fotogramas.
+    /// - `eye`: Posición de la cámara.
+    ///
+    /// # Retorna
+    /// Un `Flyby` por cada cuerpo al que la cámara se acercó desde la última llamada.
+    pub fn update_camera(&mut self, scene: &Scene, time: f32, eye: Vec3) -> Vec<Event> {
+        let near: BTreeSet<String> = scene.nodes.iter().enumerate()
+            // las capas pegadas (nubes, anillos) ya cuentan con su cuerpo
+            .filter(|(_, node)| node.visible && (node.parent.is_none() || node.orbit.is_some()))
+            .filter(|&(index, node)| {
+                let distance = (scene.world_position(index, time) - eye).magnitude();
+                distance < node.radius * MESH_RADIUS * FLYBY_DISTANCE
+            })
+            .map(|(_, node)| node.name.clone())
+            .collect();
+
+        let events = near.difference(&self.flybys)
+            .map(|body| Event::Flyby { body: body.clone() })
+            .collect();
+        self.flybys = near;
+        events
+    }
+}
+
+/// Indica si una esfera queda entre la luz y un cuerpo lo bastante cerca de la
+/// línea que los une como para proyectarle sombra (se aproxima la sombra con un cilindro).
+fn shadows(light: Vec3, occluder: Vec3, occluder_radius: f32, body: Vec3, body_radius: f32) -> bool {
+    let axis = body - light;
+    let length_squared = axis.magnitude_squared();
+    if length_squared <= f32::EPSILON {
+        return false;
+    }
+    let t = (occluder - light).dot(&axis) / length_squared;
+    if t <= 0.0 || t >= 1.0 {
+        return false;
+    }
+    let closest = light + axis * t;
+    (occluder - closest).magnitude() < occluder_radius + body_radius
+}
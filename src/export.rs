@@ -0,0 +1,150 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use crate::framebuffer::Framebuffer;
+use crate::color::Color;
+
+/// Ruta donde el visor guarda la grabación de `F12`.
+pub const RECORDING_PATH: &str = "assets/recording.gif";
+
+/// Formato de una exportación animada.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Un único GIF animado que se repite sin fin. Cada fotograma lleva su propia
+    /// paleta de 256 colores.
+    Gif,
+    /// Una carpeta con `frame_00000.png`, `frame_00001.png`, ... sin pérdida.
+    PngSequence,
+}
+
+impl ExportFormat {
+    /// Elige el formato según la ruta: un archivo `.gif` da un GIF animado y
+    /// cualquier otra ruta se toma como la carpeta de una secuencia PNG.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> ExportFormat {
+        let is_gif = path.as_ref().extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gif"));
+        if is_gif { ExportFormat::Gif } else { ExportFormat::PngSequence }
+    }
+}
+
+/// Destino de los fotogramas según el formato.
+enum Output {
+    Gif(gif::Encoder<BufWriter<File>>),
+    PngSequence(PathBuf),
+}
+
+/// Graba framebuffers sucesivos como un GIF animado o una secuencia de PNG
+/// numerados, para compartir una animación sin programas de captura de pantalla.
+///
+/// Todos los fotogramas deben tener el tamaño con que se creó la grabación.
+pub struct Recorder {
+    output: Output,
+    width: usize,
+    height: usize,
+    /// Duración de cada fotograma del GIF, en centésimas de segundo.
+    delay: u16,
+    recorded: u32,
+}
+
+impl Recorder {
+    /// Crea una grabación.
+    ///
+    /// # Parámetros
+    /// - `path`: Archivo GIF o carpeta de la secuencia (se crea si no existe).
+    /// - `format`: Formato de la grabación (ver `ExportFormat::from_path`).
+    /// - `width`, `height`: Tamaño de los fotogramas.
+    /// - `fps`: Fotogramas por segundo del GIF. Los GIF cuentan el tiempo en
+    ///   centésimas de segundo, así que 60 fps se reproducen a 50.
+    pub fn create<P: AsRef<Path>>(path: P, format: ExportFormat, width: usize, height: usize, fps: f32) -> io::Result<Self> {
+        let path = path.as_ref();
+        let output = match format {
+            ExportFormat::Gif => {
+                let (gif_width, gif_height) = gif_size(width, height)?;
+                if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                    fs::create_dir_all(parent)?;
+                }
+                let mut encoder = gif::Encoder::new(BufWriter::new(File::create(path)?), gif_width, gif_height, &[])
+                    .map_err(io::Error::other)?;
+                encoder.set_repeat(gif::Repeat::Infinite).map_err(io::Error::other)?;
+                Output::Gif(encoder)
+            }
+            ExportFormat::PngSequence => {
+                fs::create_dir_all(path)?;
+                Output::PngSequence(path.to_path_buf())
+            }
+        };
+        let delay = (100.0 / fps.max(1.0)).round().max(2.0) as u16;
+        Ok(Recorder { output, width, height, delay, recorded: 0 })
+    }
+
+    /// Fotogramas ya grabados.
+    pub fn recorded(&self) -> u32 {
+        self.recorded
+    }
+
+    /// Agrega un fotograma a la grabación.
+    ///
+    /// # Parámetros
+    /// - `framebuffer`: Fotograma terminado; debe tener el tamaño de la grabación.
+    ///
+    /// # Retorna
+    /// Un error si el tamaño no coincide o si no se pudo escribir.
+    pub fn record(&mut self, framebuffer: &Framebuffer) -> io::Result<()> {
+        if (framebuffer.width, framebuffer.height) != (self.width, self.height) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "el fotograma mide {}x{} y la grabación {}x{}",
+                    framebuffer.width, framebuffer.height, self.width, self.height,
+                ),
+            ));
+        }
+        let rgb = rgb_bytes(framebuffer);
+        match &mut self.output {
+            Output::Gif(encoder) => {
+                // la cuantización a 256 colores es lo más caro; 10 es la velocidad
+                // recomendada por la biblioteca entre calidad y tiempo
+                let mut frame = gif::Frame::from_rgb_speed(self.width as u16, self.height as u16, &rgb, 10);
+                frame.delay = self.delay;
+                encoder.write_frame(&frame).map_err(io::Error::other)?;
+            }
+            Output::PngSequence(directory) => {
+                let path = directory.join(format!("frame_{:05}.png", self.recorded));
+                let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), self.width as u32, self.height as u32);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(png::BitDepth::Eight);
+                let mut writer = encoder.write_header().map_err(io::Error::other)?;
+                writer.write_image_data(&rgb).map_err(io::Error::other)?;
+            }
+        }
+        self.recorded += 1;
+        Ok(())
+    }
+
+    /// Termina la grabación y cierra el archivo. El GIF también se cierra al
+    /// soltar la grabación, pero así se ven los errores de escritura.
+    pub fn finish(self) -> io::Result<()> {
+        match self.output {
+            Output::Gif(encoder) => {
+                let writer = encoder.into_inner()?;
+                writer.into_inner().map_err(|error| error.into_error())?;
+                Ok(())
+            }
+            Output::PngSequence(_) => Ok(()),
+        }
+    }
+}
+
+/// Comprueba que el tamaño cabe en un GIF, que guarda cada lado en 16 bits.
+fn gif_size(width: usize, height: usize) -> io::Result<(u16, u16)> {
+    match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("un GIF no puede medir {}x{}", width, height))),
+    }
+}
+
+/// Convierte los píxeles `0xRRGGBB` del framebuffer a bytes RGB consecutivos.
+fn rgb_bytes(framebuffer: &Framebuffer) -> Vec<u8> {
+    framebuffer.buffer.iter()
+        .flat_map(|&pixel| <[u8; 3]>::from(Color::from_u32(pixel)))
+        .collect()
+}
@@ -19,6 +19,15 @@ pub struct Fragment {
     pub intensity: f32,
     /// Posición del vértice en el espacio 3D.
     pub vertex_position: Vec3,
+    /// Índice del material de la cara a la que pertenece el fragmento, si tiene.
+    pub material: Option<usize>,
+    /// Coordenadas de textura interpoladas.
+    pub tex_coords: Vec2,
+    /// Elevación interpolada de los vértices (ver `ElevationMode`).
+    pub elevation: f32,
+    /// Dirección (normalizada, en el mundo) desde el fragmento hacia la cámara,
+    /// para los brillos especulares y los halos.
+    pub view_direction: Vec3,
 }
 
 impl Fragment {
@@ -52,6 +61,10 @@ impl Fragment {
             normal,                     // Asigna la normal del fragmento.
             intensity,                  // Asigna la intensidad de la luz.
             vertex_position,            // Asigna la posición del vértice en 3D.
+            material: None,             // El rasterizador asigna el material de la cara.
+            tex_coords: Vec2::zeros(),  // El rasterizador interpola las coordenadas de textura.
+            elevation: 0.0,             // El rasterizador interpola la elevación.
+            view_direction: Vec3::new(0.0, 0.0, 1.0), // El pipeline la calcula antes del shader de fragmentos.
         }
     }
 }
@@ -1,3 +1,159 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use nalgebra_glm::{Mat4, Vec3};
+use crate::color::{Color, PixelFormat};
+use crate::text;
+
+/// Lado en píxeles de las celdas en que `Framebuffer::swap_buffers` busca cambios.
+pub const DIRTY_CELL: usize = 32;
+
+/// Rectángulo de píxeles dentro del framebuffer.
+///
+/// Como viewport indica dónde se dibuja la imagen: las coordenadas normalizadas
+/// (-1 a 1) se llevan a este rectángulo. Como tijera (scissor) limita los píxeles
+/// que se pueden escribir, así lo que se sale de una vista no pisa a las vecinas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    /// Columna de la esquina superior izquierda.
+    pub x: usize,
+    /// Fila de la esquina superior izquierda.
+    pub y: usize,
+    /// Ancho del rectángulo.
+    pub width: usize,
+    /// Alto del rectángulo.
+    pub height: usize,
+}
+
+impl Viewport {
+    /// Crea un rectángulo.
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Viewport { x, y, width, height }
+    }
+
+    /// Rectángulo que cubre un framebuffer entero de `width` x `height`.
+    pub fn full(width: usize, height: usize) -> Self {
+        Viewport::new(0, 0, width, height)
+    }
+
+    /// Relación de aspecto (ancho / alto), para la proyección de la cámara.
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height.max(1) as f32
+    }
+
+    /// Indica si el píxel (x, y) está dentro del rectángulo.
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// Divide el rectángulo en `count` columnas del mismo ancho, de izquierda a derecha;
+    /// la última se queda con los píxeles que sobran.
+    pub fn columns(&self, count: usize) -> Vec<Viewport> {
+        let count = count.max(1);
+        let width = self.width / count;
+        (0..count)
+            .map(|column| {
+                let x = self.x + column * width;
+                let column_width = if column + 1 == count { self.x + self.width - x } else { width };
+                Viewport::new(x, self.y, column_width, self.height)
+            })
+            .collect()
+    }
+
+    /// Matriz que lleva coordenadas normalizadas (-1 a 1) a los píxeles del
+    /// rectángulo, con el eje y hacia abajo (ver `pipeline::create_viewport_matrix`).
+    pub fn matrix(&self) -> Mat4 {
+        let (half_width, half_height) = (self.width as f32 / 2.0, self.height as f32 / 2.0);
+        Mat4::new(
+            half_width, 0.0, 0.0, self.x as f32 + half_width,
+            0.0, -half_height, 0.0, self.y as f32 + half_height,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        )
+    }
+}
+
+/// Comparación de la prueba de stencil entre el valor de referencia y el que
+/// guarda el píxel: `Less` pasa cuando la referencia es menor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StencilCompare {
+    /// Pasa siempre: el stencil no limita el dibujo.
+    #[default]
+    Always,
+    Never,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl StencilCompare {
+    /// Indica si un píxel con el valor `value` pasa la prueba contra `reference`.
+    pub fn passes(self, reference: u8, value: u8) -> bool {
+        match self {
+            StencilCompare::Always => true,
+            StencilCompare::Never => false,
+            StencilCompare::Equal => reference == value,
+            StencilCompare::NotEqual => reference != value,
+            StencilCompare::Less => reference < value,
+            StencilCompare::LessEqual => reference <= value,
+            StencilCompare::Greater => reference > value,
+            StencilCompare::GreaterEqual => reference >= value,
+        }
+    }
+}
+
+/// Lo que se escribe en el stencil de un píxel cuando se dibuja un punto en él.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StencilOp {
+    /// Deja el valor como está.
+    #[default]
+    Keep,
+    /// Lo pone en 0.
+    Zero,
+    /// Lo cambia por el valor de referencia.
+    Replace,
+    /// Le suma 1, sin pasar de 255.
+    Increment,
+    /// Le resta 1, sin bajar de 0.
+    Decrement,
+    /// Invierte sus bits.
+    Invert,
+}
+
+impl StencilOp {
+    /// Valor que queda en el stencil de un píxel que tenía `value`.
+    pub fn apply(self, reference: u8, value: u8) -> u8 {
+        match self {
+            StencilOp::Keep => value,
+            StencilOp::Zero => 0,
+            StencilOp::Replace => reference,
+            StencilOp::Increment => value.saturating_add(1),
+            StencilOp::Decrement => value.saturating_sub(1),
+            StencilOp::Invert => !value,
+        }
+    }
+}
+
+/// Prueba y escritura del stencil con las que se dibujan los puntos (ver
+/// `Framebuffer::set_stencil_state`). Por defecto el stencil no hace nada.
+///
+/// Por ejemplo, para que la atmósfera se vea solo sobre la silueta del planeta se
+/// dibuja el planeta con `Always` y `Replace` (marca sus píxeles con la referencia)
+/// y después la atmósfera con `Equal` y `Keep`; con `NotEqual` se recorta un
+/// portal: lo de adentro tapa y deja ver lo que se dibuje en él.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StencilState {
+    /// Comparación que tiene que pasar un píxel para dibujarse.
+    pub compare: StencilCompare,
+    /// Valor de referencia de la comparación y de `StencilOp::Replace`.
+    pub reference: u8,
+    /// Lo que se escribe en los píxeles que pasan la prueba de stencil y la de profundidad.
+    pub write: StencilOp,
+}
+
 /// Estructura que representa un framebuffer para renderizar gráficos.
 pub struct Framebuffer {
     /// Ancho del framebuffer.
@@ -8,10 +164,24 @@ pub struct Framebuffer {
     pub buffer: Vec<u32>,
     /// Z-buffer que almacena la profundidad de cada píxel para el manejo de la superposición.
     pub zbuffer: Vec<f32>,
+    /// Búfer frontal: la imagen ya terminada que se muestra (ver `swap_buffers`).
+    front: Vec<u32>,
+    /// Regiones que cambiaron en el último `swap_buffers`.
+    dirty: Vec<Viewport>,
     /// Color de fondo del framebuffer.
     background_color: u32,
     /// Color actual que se usará para dibujar.
     current_color: u32,
+    /// Rectángulo donde se dibuja la imagen (ver `Viewport::matrix`).
+    viewport: Viewport,
+    /// Si hay, solo se escriben y limpian los píxeles dentro de este rectángulo.
+    scissor: Option<Viewport>,
+    /// Stencil de cada píxel, si se activó con `enable_stencil`.
+    stencil: Option<Vec<u8>>,
+    /// Prueba y escritura del stencil con las que se dibujan los puntos.
+    stencil_state: StencilState,
+    /// Cómo se guardan en `buffer` los colores calculados en punto flotante.
+    pixel_format: PixelFormat,
 }
 
 impl Framebuffer {
@@ -31,18 +201,221 @@ impl Framebuffer {
             buffer: vec![0; width * height],
             // Inicializa el z-buffer con infinito, indicando que no hay píxeles dibujados.
             zbuffer: vec![f32::INFINITY; width * height],
+            front: vec![0; width * height],
+            dirty: Vec::new(),
             background_color: 0x000000, // Color de fondo inicial.
-            current_color: 0xFFFFFF      // Color actual inicial (blanco).
+            current_color: 0xFFFFFF,     // Color actual inicial (blanco).
+            viewport: Viewport::full(width, height),
+            scissor: None,
+            stencil: None,
+            stencil_state: StencilState::default(),
+            pixel_format: PixelFormat::default(),
         }
     }
 
     /// Limpia el framebuffer y el z-buffer, restableciendo los colores de fondo.
+    /// Con una tijera activa solo limpia los píxeles dentro de ella.
     pub fn clear(&mut self) {
-        for pixel in self.buffer.iter_mut() {
-            *pixel = self.background_color; // Establece cada píxel al color de fondo.
+        let area = self.scissor.unwrap_or(Viewport::full(self.width, self.height));
+        let (x0, x1) = (area.x.min(self.width), (area.x + area.width).min(self.width));
+        for y in area.y.min(self.height)..(area.y + area.height).min(self.height) {
+            let row = y * self.width;
+            self.buffer[row + x0..row + x1].fill(self.background_color); // Establece cada píxel al color de fondo.
+            self.zbuffer[row + x0..row + x1].fill(f32::INFINITY); // Restablece la profundidad a infinito.
+        }
+    }
+
+    /// Cambia el tamaño del framebuffer, por ejemplo cuando cambia el de la ventana.
+    ///
+    /// Conserva el color y la profundidad de la parte que sigue dentro (la esquina
+    /// superior izquierda) y llena lo nuevo con el color de fondo; el stencil, si hay,
+    /// vuelve a 0. El viewport vuelve
+    /// a cubrir el framebuffer entero y se quita la tijera, porque los rectángulos
+    /// anteriores ya no corresponden; la proyección de la cámara debe ajustarse al
+    /// nuevo aspecto (`Viewport::aspect`).
+    ///
+    /// # Parámetros
+    /// - `width`: Nuevo ancho.
+    /// - `height`: Nuevo alto.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if width != self.width || height != self.height {
+            let mut buffer = vec![self.background_color; width * height];
+            let mut zbuffer = vec![f32::INFINITY; width * height];
+            let kept = width.min(self.width);
+            for y in 0..height.min(self.height) {
+                let (old, new) = (y * self.width, y * width);
+                buffer[new..new + kept].copy_from_slice(&self.buffer[old..old + kept]);
+                zbuffer[new..new + kept].copy_from_slice(&self.zbuffer[old..old + kept]);
+            }
+            self.width = width;
+            self.height = height;
+            self.buffer = buffer;
+            self.zbuffer = zbuffer;
+            self.front = self.buffer.clone();
+            self.dirty.clear();
+            if self.stencil.is_some() {
+                self.stencil = Some(vec![0; width * height]);
+            }
+        }
+        self.viewport = Viewport::full(width, height);
+        self.scissor = None;
+    }
+
+    /// Pasa la imagen dibujada en `buffer` al búfer frontal, copiando solo las
+    /// regiones que cambiaron desde el anterior.
+    ///
+    /// Compara los dos búferes por celdas de `DIRTY_CELL` píxeles, así también
+    /// encuentra lo que se escribió directo en `buffer` (el posproceso, los tiles) y
+    /// no cuenta lo que se volvió a dibujar igual: con la cámara quieta y un solo
+    /// planeta animado, solo cambian las celdas de ese planeta. Las celdas vecinas de
+    /// una fila se juntan en un rectángulo.
+    ///
+    /// # Retorna
+    /// Las regiones copiadas, las mismas que devuelve `dirty_regions` hasta la próxima
+    /// llamada. Un presentador puede copiar solo esas a la pantalla.
+    pub fn swap_buffers(&mut self) -> &[Viewport] {
+        self.dirty.clear();
+        let columns = self.width.div_ceil(DIRTY_CELL);
+        for top in (0..self.height).step_by(DIRTY_CELL) {
+            let rows = DIRTY_CELL.min(self.height - top);
+            let mut start = None;
+            // una columna de más cierra el rectángulo que llega al borde derecho
+            for column in 0..=columns {
+                let changed = column < columns && self.cell_changed(column * DIRTY_CELL, top, rows);
+                match (changed, start) {
+                    (true, None) => start = Some(column * DIRTY_CELL),
+                    (false, Some(left)) => {
+                        let right = (column * DIRTY_CELL).min(self.width);
+                        self.dirty.push(Viewport::new(left, top, right - left, rows));
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for region in &self.dirty {
+            for y in region.y..region.y + region.height {
+                let start = y * self.width + region.x;
+                self.front[start..start + region.width].copy_from_slice(&self.buffer[start..start + region.width]);
+            }
+        }
+        &self.dirty
+    }
+
+    /// Indica si alguna fila de la celda que empieza en (x, y) difiere entre `buffer` y el búfer frontal.
+    fn cell_changed(&self, x: usize, y: usize, rows: usize) -> bool {
+        let width = DIRTY_CELL.min(self.width - x);
+        (y..y + rows).any(|line| {
+            let start = line * self.width + x;
+            self.buffer[start..start + width] != self.front[start..start + width]
+        })
+    }
+
+    /// Búfer frontal: la imagen del último `swap_buffers`, fila por fila.
+    pub fn front(&self) -> &[u32] {
+        &self.front
+    }
+
+    /// Regiones que cambiaron en el último `swap_buffers`; vacío si la imagen quedó igual.
+    pub fn dirty_regions(&self) -> &[Viewport] {
+        &self.dirty
+    }
+
+    /// Cambia el rectángulo donde se dibuja la imagen. No limita la escritura:
+    /// para eso está `set_scissor`.
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+
+    /// Rectángulo donde se dibuja la imagen; al crear el framebuffer es el framebuffer entero.
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    /// Limita la escritura a un rectángulo, o la vuelve a permitir en todo el
+    /// framebuffer con `None`. Afecta a `point`, `blend_point`, `passes_depth_test` y `clear`.
+    pub fn set_scissor(&mut self, scissor: Option<Viewport>) {
+        self.scissor = scissor;
+    }
+
+    /// Rectángulo de la tijera activa, si hay.
+    pub fn scissor(&self) -> Option<Viewport> {
+        self.scissor
+    }
+
+    /// Cambia cómo se codifican los colores al escribirlos (ver `PixelFormat`). No
+    /// convierte lo que ya está dibujado: conviene cambiarlo antes de limpiar.
+    pub fn set_pixel_format(&mut self, pixel_format: PixelFormat) {
+        self.pixel_format = pixel_format;
+    }
+
+    /// Cómo se codifican los colores de `buffer`; al crear el framebuffer es `PixelFormat::default()`.
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.pixel_format
+    }
+
+    /// Agrega un stencil de un byte por píxel, en 0, si todavía no hay. Sin stencil
+    /// la prueba siempre pasa y no se escribe nada, sea cual sea `stencil_state`.
+    pub fn enable_stencil(&mut self) {
+        if self.stencil.is_none() {
+            self.stencil = Some(vec![0; self.width * self.height]);
         }
-        for depth in self.zbuffer.iter_mut() {
-            *depth = f32::INFINITY; // Restablece la profundidad a infinito.
+    }
+
+    /// Quita el stencil y vuelve al estado por defecto.
+    pub fn disable_stencil(&mut self) {
+        self.stencil = None;
+        self.stencil_state = StencilState::default();
+    }
+
+    /// Valores del stencil de cada píxel, fila por fila, si está activado.
+    pub fn stencil(&self) -> Option<&[u8]> {
+        self.stencil.as_deref()
+    }
+
+    /// Pone el stencil en `value`; con una tijera activa, solo dentro de ella. `clear`
+    /// no lo toca, así una máscara puede durar varios fotogramas.
+    pub fn clear_stencil(&mut self, value: u8) {
+        let area = self.scissor.unwrap_or(Viewport::full(self.width, self.height));
+        let (x0, x1) = (area.x.min(self.width), (area.x + area.width).min(self.width));
+        let Some(stencil) = &mut self.stencil else { return };
+        for y in area.y.min(self.height)..(area.y + area.height).min(self.height) {
+            stencil[y * self.width + x0..y * self.width + x1].fill(value);
+        }
+    }
+
+    /// Cambia la prueba y la escritura del stencil de los próximos puntos. Afecta a
+    /// `point`, `blend_point`, `add_point` y `passes_depth_test`.
+    pub fn set_stencil_state(&mut self, state: StencilState) {
+        self.stencil_state = state;
+    }
+
+    /// Prueba y escritura del stencil activas.
+    pub fn stencil_state(&self) -> StencilState {
+        self.stencil_state
+    }
+
+    /// Indica si el stencil cambia algo al dibujar: hay stencil y un estado distinto
+    /// del por defecto. Los tiles (`tiles::Tile`) no tienen stencil, así que con esto activo
+    /// la lista de dibujo no reparte el trabajo en hilos.
+    pub fn uses_stencil(&self) -> bool {
+        self.stencil.is_some() && self.stencil_state != StencilState::default()
+    }
+
+    /// Indica si se puede escribir el píxel (x, y): dentro del framebuffer y de la
+    /// tijera, y que pase la prueba de stencil.
+    fn writable(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.scissor.is_none_or(|scissor| scissor.contains(x, y))
+            && self.stencil.as_ref().is_none_or(|stencil| {
+                self.stencil_state.compare.passes(self.stencil_state.reference, stencil[y * self.width + x])
+            })
+    }
+
+    /// Aplica la escritura del stencil al píxel `index`, que se acaba de dibujar.
+    fn write_stencil(&mut self, index: usize) {
+        let StencilState { reference, write, .. } = self.stencil_state;
+        if let Some(stencil) = &mut self.stencil {
+            stencil[index] = write.apply(reference, stencil[index]);
         }
     }
 
@@ -53,13 +426,110 @@ impl Framebuffer {
     /// - `y`: Coordenada y del punto a dibujar.
     /// - `depth`: La profundidad del punto; solo se dibuja si es menor que la profundidad actual en el z-buffer.
     pub fn point(&mut self, x: usize, y: usize, depth: f32) {
-        if x < self.width && y < self.height { // Verifica que las coordenadas estén dentro del framebuffer.
+        if self.writable(x, y) { // Verifica que las coordenadas estén dentro del framebuffer y la tijera.
             let index = y * self.width + x; // Calcula el índice del píxel en el buffer.
 
             // Si la nueva profundidad es menor que la profundidad almacenada, se actualiza el píxel.
             if self.zbuffer[index] > depth {
                 self.buffer[index] = self.current_color; // Establece el color actual en el buffer.
                 self.zbuffer[index] = depth; // Actualiza el z-buffer con la nueva profundidad.
+                self.write_stencil(index);
+            }
+        }
+    }
+
+    /// Indica si un punto con esta profundidad se vería: si está dentro del framebuffer
+    /// (y de la tijera), pasa la prueba de stencil y está delante de lo que ya hay en el z-buffer. Es la misma prueba que hacen `point`
+    /// y `blend_point`, para poder descartar fragmentos antes de sombrearlos.
+    ///
+    /// # Parámetros
+    /// - `x`: Coordenada x del punto (puede ser negativa).
+    /// - `y`: Coordenada y del punto (puede ser negativa).
+    /// - `depth`: La profundidad del punto.
+    pub fn passes_depth_test(&self, x: i32, y: i32, depth: f32) -> bool {
+        if x < 0 || y < 0 || !self.writable(x as usize, y as usize) {
+            return false;
+        }
+        self.zbuffer[y as usize * self.width + x as usize] > depth
+    }
+
+    /// Mezcla el color actual sobre el píxel (x, y) con la opacidad dada, para
+    /// superficies semitransparentes. Respeta el z-buffer pero no lo actualiza,
+    /// así lo que está detrás sigue viéndose a través de la superficie. La mezcla
+    /// se hace con los valores lineales (ver `PixelFormat::unpack`).
+    ///
+    /// # Parámetros
+    /// - `x`: Coordenada x del punto.
+    /// - `y`: Coordenada y del punto.
+    /// - `depth`: La profundidad del punto.
+    /// - `alpha`: Opacidad entre 0 (invisible) y 1 (opaco).
+    pub fn blend_point(&mut self, x: usize, y: usize, depth: f32, alpha: f32) {
+        if self.writable(x, y) {
+            let index = y * self.width + x;
+            if self.zbuffer[index] > depth {
+                let destination = self.pixel_format.unpack(self.buffer[index]);
+                let source = self.pixel_format.unpack(self.current_color);
+                self.buffer[index] = self.pixel_format.pack(destination.lerp(&source, alpha.clamp(0.0, 1.0)), x, y);
+                self.write_stencil(index);
+            }
+        }
+    }
+
+    /// Suma el color actual, escalado por `intensity`, al píxel (x, y): la mezcla
+    /// aditiva de las partículas brillantes (ver `particles::draw_particles`). Como
+    /// `blend_point`, respeta el z-buffer pero no lo actualiza.
+    ///
+    /// # Parámetros
+    /// - `x`: Coordenada x del punto.
+    /// - `y`: Coordenada y del punto.
+    /// - `depth`: La profundidad del punto.
+    /// - `intensity`: Cuánto del color actual se suma, normalmente entre 0 y 1.
+    pub fn add_point(&mut self, x: usize, y: usize, depth: f32, intensity: f32) {
+        if self.writable(x, y) {
+            let index = y * self.width + x;
+            if self.zbuffer[index] > depth {
+                let destination = self.pixel_format.unpack(self.buffer[index]);
+                let source = self.pixel_format.unpack(self.current_color);
+                self.buffer[index] = self.pixel_format.pack(destination + source * intensity.max(0.0), x, y);
+                self.write_stencil(index);
+            }
+        }
+    }
+
+    /// Guarda el contenido del framebuffer como imagen PPM binaria (P6), un formato
+    /// sin compresión que leen la mayoría de los editores y `ffmpeg`.
+    ///
+    /// # Parámetros
+    /// - `path`: Archivo de destino.
+    pub fn save_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for &pixel in &self.buffer {
+            writer.write_all(&<[u8; 3]>::from(Color::from_u32(pixel)))?;
+        }
+        writer.flush()
+    }
+
+    /// Reduce la imagen de un framebuffer más grande a este, promediando cada
+    /// bloque de píxeles (supermuestreo) con sus valores lineales. El z-buffer no
+    /// se copia.
+    ///
+    /// # Parámetros
+    /// - `source`: Framebuffer de origen, un número entero de veces más grande que este
+    ///   en cada eje.
+    pub fn downsample_from(&mut self, source: &Framebuffer) {
+        let factor = (source.width / self.width.max(1)).max(1);
+        let count = (factor * factor) as f32;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut sum = Vec3::zeros();
+                for sample_y in y * factor..(y + 1) * factor {
+                    let row = &source.buffer[sample_y * source.width..];
+                    for &pixel in &row[x * factor..(x + 1) * factor] {
+                        sum += source.pixel_format.unpack(pixel);
+                    }
+                }
+                self.buffer[y * self.width + x] = self.pixel_format.pack(sum / count, x, y);
             }
         }
     }
@@ -79,4 +549,18 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: u32) {
         self.current_color = color; // Asigna el nuevo color actual.
     }
+
+    /// Escribe un texto con la fuente de mapa de bits de `text`, delante de toda la geometría.
+    /// El color actual no cambia.
+    ///
+    /// # Parámetros
+    /// - `x`: Coordenada x de la esquina superior izquierda del texto.
+    /// - `y`: Coordenada y de la esquina superior izquierda del texto.
+    /// - `text`: Texto de una sola línea.
+    /// - `color`: Color del texto.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, color: Color) {
+        let current_color = self.current_color;
+        text::draw_text(self, x, y, text, color, 1);
+        self.current_color = current_color;
+    }
 }
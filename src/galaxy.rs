@@ -0,0 +1,228 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::f32::consts::PI;
+use crate::generator::generate_moons;
+use crate::scene::{Orbit, Scene, SceneNode};
+use crate::shaders::PlanetShader;
+
+/// Clase espectral de una estrella, de la más caliente a la más fría.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StarClass {
+    O,
+    B,
+    A,
+    F,
+    G,
+    K,
+    M,
+}
+
+impl StarClass {
+    /// Sortea una clase con una distribución parecida a la real: abundan las enanas frías.
+    fn random(rng: &mut StdRng) -> StarClass {
+        match rng.gen_range(0..100) {
+            0 => StarClass::O,
+            1..=2 => StarClass::B,
+            3..=7 => StarClass::A,
+            8..=17 => StarClass::F,
+            18..=32 => StarClass::G,
+            33..=55 => StarClass::K,
+            _ => StarClass::M,
+        }
+    }
+
+    /// Radio con el que se dibuja la estrella.
+    pub fn radius(self) -> f32 {
+        match self {
+            StarClass::O => 3.0,
+            StarClass::B => 2.6,
+            StarClass::A => 2.2,
+            StarClass::F => 1.9,
+            StarClass::G => 1.7,
+            StarClass::K => 1.4,
+            StarClass::M => 1.1,
+        }
+    }
+
+    /// Shader con el que se dibuja la estrella: las calientes usan el del sol.
+    pub fn shader(self) -> PlanetShader {
+        match self {
+            StarClass::O | StarClass::B | StarClass::A => PlanetShader::Star,
+            _ => PlanetShader::Sun,
+        }
+    }
+}
+
+/// Descripción de un planeta dentro de un sistema estelar.
+#[derive(Debug, Clone)]
+pub struct PlanetDesc {
+    /// Nombre del planeta.
+    pub name: String,
+    /// Tipo de superficie.
+    pub shader: PlanetShader,
+    /// Radio del planeta.
+    pub radius: f32,
+    /// Distancia orbital a la estrella.
+    pub distance: f32,
+    /// Número de lunas.
+    pub moons: usize,
+    /// Semilla propia del planeta (ruido y lunas).
+    pub seed: u64,
+}
+
+impl PlanetDesc {
+    /// Indica si el planeta tiene anillos: la mitad de los gigantes gaseosos,
+    /// según su semilla (no consume números del generador del sistema).
+    pub fn has_rings(&self) -> bool {
+        self.shader == PlanetShader::Gas && self.seed.is_multiple_of(2)
+    }
+}
+
+/// Sistema estelar generado: una estrella y sus planetas.
+#[derive(Debug, Clone)]
+pub struct StarSystem {
+    /// Nombre del sistema (y de su estrella).
+    pub name: String,
+    /// Semilla de la que se derivó el sistema.
+    pub seed: u64,
+    /// Clase espectral de la estrella.
+    pub star_class: StarClass,
+    /// Planetas ordenados del más cercano al más lejano.
+    pub planets: Vec<PlanetDesc>,
+}
+
+impl StarSystem {
+    /// Genera un sistema estelar a partir de una semilla.
+    pub fn generate(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let name = random_name(&mut rng);
+        let star_class = StarClass::random(&mut rng);
+        let star_radius = star_class.radius();
+
+        let planet_types = [
+            PlanetShader::Earth,
+            PlanetShader::Gas,
+            PlanetShader::Rocky,
+            PlanetShader::Fantasy,
+        ];
+
+        let planet_count = rng.gen_range(1..=6);
+        let mut distance = star_radius * 2.0;
+        let planets = (0..planet_count).map(|i| {
+            let shader = planet_types[rng.gen_range(0..planet_types.len())];
+            let radius = match shader {
+                PlanetShader::Gas => rng.gen_range(0.7..1.2),
+                _ => rng.gen_range(0.25..0.6),
+            };
+            distance += radius * 2.0 + rng.gen_range(1.0..3.0);
+            let moons = rng.gen_range(0..=3);
+            let planet = PlanetDesc {
+                name: format!("{} {}", name, roman(i + 1)),
+                shader,
+                radius,
+                distance,
+                moons,
+                seed: rng.gen(),
+            };
+            // Deja espacio para las lunas antes del siguiente planeta.
+            distance += radius * (2.0 + moons as f32);
+            planet
+        }).collect();
+
+        StarSystem { name, seed, star_class, planets }
+    }
+
+    /// Radio de la órbita más externa, útil para encuadrar el sistema con la cámara.
+    pub fn extent(&self) -> f32 {
+        self.planets.last()
+            .map(|planet| planet.distance + planet.radius * (2.0 + planet.moons as f32))
+            .unwrap_or(self.star_class.radius())
+    }
+
+    /// Construye la escena del sistema: la estrella en el origen, los planetas
+    /// orbitándola y las lunas de cada planeta.
+    pub fn build_scene(&self) -> Scene {
+        let mut scene = Scene::new();
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let star_radius = self.star_class.radius();
+        let mut star = SceneNode::new(&self.name, self.star_class.shader(), star_radius);
+        star.seed = rng.gen();
+        let star = scene.add_node(star);
+
+        for desc in &self.planets {
+            let mut planet = SceneNode::new(&desc.name, desc.shader, desc.radius);
+            planet.parent = Some(star);
+            planet.seed = desc.seed as i32;
+            planet.orbit = Some(Orbit {
+                distance: desc.distance,
+                inclination: rng.gen_range(-0.05..0.05),
+                speed: 0.01 * (star_radius / desc.distance).powf(1.5),
+                phase: rng.gen_range(0.0..2.0 * PI),
+            });
+            let planet = scene.add_node(planet);
+            if desc.has_rings() {
+                let mut ring = SceneNode::new(&format!("{} anillo", desc.name), PlanetShader::Ring, desc.radius * 2.2);
+                ring.parent = Some(planet);
+                ring.mesh = "primitive:ring".to_string();
+                ring.seed = desc.seed as i32;
+                scene.add_node(ring);
+            }
+            if desc.shader == PlanetShader::Earth {
+                scene.add_cloud_layer(planet);
+            }
+            generate_moons(&mut scene, planet, desc.seed, desc.moons);
+        }
+
+        scene
+    }
+}
+
+/// Conjunto de sistemas estelares derivados de una semilla maestra.
+#[derive(Debug, Clone)]
+pub struct Galaxy {
+    /// Semilla maestra de la galaxia.
+    pub seed: u64,
+    /// Sistemas estelares de la galaxia.
+    pub systems: Vec<StarSystem>,
+}
+
+impl Galaxy {
+    /// Genera `count` sistemas estelares a partir de la semilla maestra.
+    ///
+    /// Cada sistema recibe su propia semilla, de modo que regenerar un solo
+    /// sistema da exactamente el mismo resultado que generarlo con la galaxia.
+    pub fn generate(seed: u64, count: usize) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let systems = (0..count)
+            .map(|_| StarSystem::generate(rng.gen()))
+            .collect();
+
+        Galaxy { seed, systems }
+    }
+}
+
+/// Genera un nombre pronunciable juntando sílabas al azar.
+fn random_name(rng: &mut StdRng) -> String {
+    const SYLLABLES: [&str; 16] = [
+        "ka", "lo", "ri", "xe", "mu", "ta", "no", "vi",
+        "sa", "ze", "dra", "quo", "pel", "ny", "or", "hu",
+    ];
+
+    let count = rng.gen_range(2..=3);
+    let name: String = (0..count)
+        .map(|_| SYLLABLES[rng.gen_range(0..SYLLABLES.len())])
+        .collect();
+
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
+/// Convierte un número pequeño a números romanos, como se nombran los planetas.
+fn roman(number: usize) -> &'static str {
+    const NUMERALS: [&str; 10] = ["I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X"];
+    NUMERALS.get(number.wrapping_sub(1)).copied().unwrap_or("?")
+}
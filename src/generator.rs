@@ -0,0 +1,52 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::f32::consts::PI;
+use crate::scene::{Orbit, Scene, SceneNode};
+use crate::shaders::PlanetShader;
+
+/// Genera `count` lunas alrededor de un planeta de la escena.
+///
+/// Los radios, distancias, inclinaciones, velocidades y parámetros del shader
+/// se sortean a partir de `seed`, por lo que la misma semilla produce siempre
+/// el mismo sistema.
+///
+/// # Parámetros
+/// - `scene`: Escena a la que se agregan las lunas.
+/// - `planet`: Índice del planeta padre.
+/// - `seed`: Semilla del generador.
+/// - `count`: Número de lunas a crear.
+///
+/// # Retorna
+/// Los índices de las lunas agregadas.
+pub fn generate_moons(scene: &mut Scene, planet: usize, seed: u64, count: usize) -> Vec<usize> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let planet_radius = scene.nodes[planet].radius;
+    let planet_name = scene.nodes[planet].name.clone();
+
+    // Cada luna se coloca más lejos que la anterior para que las órbitas no se crucen.
+    let mut distance = planet_radius * 1.6;
+    let mut moons = Vec::with_capacity(count);
+
+    for i in 0..count {
+        let radius = planet_radius * rng.gen_range(0.08..0.3);
+        distance += radius + planet_radius * rng.gen_range(0.3..0.9);
+
+        let shader = if rng.gen_bool(0.7) { PlanetShader::Moon } else { PlanetShader::Rocky };
+
+        let mut moon = SceneNode::new(&format!("{} {}", planet_name, i + 1), shader, radius);
+        moon.parent = Some(planet);
+        moon.seed = rng.gen();
+        moon.orbit = Some(Orbit {
+            distance,
+            inclination: rng.gen_range(-0.3..0.3),
+            // Las lunas lejanas orbitan más despacio, como en la tercera ley de Kepler.
+            speed: 0.02 * (planet_radius / distance).powf(1.5),
+            phase: rng.gen_range(0.0..2.0 * PI),
+        });
+
+        distance += radius;
+        moons.push(scene.add_node(moon));
+    }
+
+    moons
+}
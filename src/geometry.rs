@@ -0,0 +1,4 @@
+//! Utilidades geométricas que no dependen del pipeline de dibujo.
+
+pub mod intersect;
+pub mod plane;
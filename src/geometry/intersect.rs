@@ -0,0 +1,257 @@
+use nalgebra_glm::Vec3;
+use crate::bounds::{Aabb, BoundingSphere};
+
+/// Tolerancia para descartar rayos paralelos a un triángulo o a un plano.
+const PARALLEL_EPSILON: f32 = 1e-7;
+
+/// Semirrecta que parte de `origin` en la dirección `direction`.
+///
+/// La dirección no tiene que estar normalizada; las distancias `t` que devuelven
+/// las intersecciones se miden en múltiplos de ella (`origin + direction * t`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    /// Punto de partida.
+    pub origin: Vec3,
+    /// Dirección del rayo.
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// Crea un rayo.
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Ray { origin, direction }
+    }
+
+    /// Devuelve el punto del rayo a la distancia `t`.
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+}
+
+/// Punto donde un rayo corta un triángulo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TriangleHit {
+    /// Distancia a lo largo del rayo.
+    pub t: f32,
+    /// Coordenadas baricéntricas del punto respecto a los vértices `a`, `b` y `c`;
+    /// suman 1 y sirven para interpolar normales, colores o coordenadas de textura.
+    pub barycentric: Vec3,
+}
+
+/// Busca el primer punto donde el rayo entra en una esfera.
+///
+/// # Retorna
+/// La distancia más cercana con `t >= 0`: si el rayo parte dentro de la esfera, es
+/// el punto de salida. `None` si no la toca o la esfera queda detrás.
+pub fn ray_sphere(ray: &Ray, sphere: &BoundingSphere) -> Option<f32> {
+    // Se resuelve |origin + direction * t - center|² = radius².
+    let offset = ray.origin - sphere.center;
+    let a = ray.direction.magnitude_squared();
+    if a <= f32::EPSILON {
+        return None;
+    }
+    let half_b = offset.dot(&ray.direction);
+    let c = offset.magnitude_squared() - sphere.radius * sphere.radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+    let near = (-half_b - root) / a;
+    let far = (-half_b + root) / a;
+    if near >= 0.0 {
+        Some(near)
+    } else if far >= 0.0 {
+        Some(far)
+    } else {
+        None
+    }
+}
+
+/// Corta un rayo con una caja alineada con los ejes (método de las franjas).
+///
+/// # Retorna
+/// Las distancias de entrada y salida `(t_min, t_max)`; la entrada es 0 si el rayo
+/// parte dentro de la caja. `None` si no la toca o la caja queda detrás.
+pub fn ray_aabb(ray: &Ray, aabb: &Aabb) -> Option<(f32, f32)> {
+    let mut t_min = 0.0_f32;
+    let mut t_max = f32::INFINITY;
+
+    for axis in 0..3 {
+        let origin = ray.origin[axis];
+        let direction = ray.direction[axis];
+        if direction.abs() <= f32::EPSILON {
+            // Paralelo a esta franja: o está dentro de ella o no la toca nunca.
+            if origin < aabb.min[axis] || origin > aabb.max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let inverse = 1.0 / direction;
+        let (near, far) = {
+            let a = (aabb.min[axis] - origin) * inverse;
+            let b = (aabb.max[axis] - origin) * inverse;
+            (a.min(b), a.max(b))
+        };
+        t_min = t_min.max(near);
+        t_max = t_max.min(far);
+        if t_min > t_max {
+            return None;
+        }
+    }
+
+    Some((t_min, t_max))
+}
+
+/// Corta un rayo con un triángulo (Möller–Trumbore). Cuenta ambas caras, porque
+/// el pipeline tampoco descarta las caras traseras.
+///
+/// # Parámetros
+/// - `ray`: Rayo a probar.
+/// - `a`, `b`, `c`: Vértices del triángulo.
+///
+/// # Retorna
+/// La distancia y las coordenadas baricéntricas del punto de corte, o `None` si el
+/// rayo no toca el triángulo, es paralelo a él o lo toca detrás del origen.
+pub fn ray_triangle(ray: &Ray, a: Vec3, b: Vec3, c: Vec3) -> Option<TriangleHit> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = ray.direction.cross(&edge2);
+    let determinant = edge1.dot(&p);
+    if determinant.abs() < PARALLEL_EPSILON {
+        return None;
+    }
+
+    let inverse = 1.0 / determinant;
+    let offset = ray.origin - a;
+    let u = offset.dot(&p) * inverse;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = offset.cross(&edge1);
+    let v = ray.direction.dot(&q) * inverse;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(&q) * inverse;
+    (t >= 0.0).then(|| TriangleHit { t, barycentric: Vec3::new(1.0 - u - v, u, v) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    fn unit_sphere() -> BoundingSphere {
+        BoundingSphere { center: Vec3::new(0.0, 0.0, -5.0), radius: 1.0 }
+    }
+
+    fn unit_box() -> Aabb {
+        Aabb { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0) }
+    }
+
+    #[test]
+    fn ray_sphere_hits_the_near_side() {
+        let ray = Ray::new(Vec3::zeros(), Vec3::new(0.0, 0.0, -1.0));
+        let t = ray_sphere(&ray, &unit_sphere()).expect("el rayo apunta al centro");
+        assert!(approx(t, 4.0));
+        assert!(approx(ray.at(t).z, -4.0));
+    }
+
+    #[test]
+    fn ray_sphere_scales_with_the_direction() {
+        let ray = Ray::new(Vec3::zeros(), Vec3::new(0.0, 0.0, -2.0));
+        assert!(approx(ray_sphere(&ray, &unit_sphere()).unwrap(), 2.0));
+    }
+
+    #[test]
+    fn ray_sphere_misses() {
+        let beside = Ray::new(Vec3::new(1.5, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(ray_sphere(&beside, &unit_sphere()), None);
+        let behind = Ray::new(Vec3::zeros(), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(ray_sphere(&behind, &unit_sphere()), None);
+    }
+
+    #[test]
+    fn ray_sphere_from_inside_returns_the_exit() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(approx(ray_sphere(&ray, &unit_sphere()).unwrap(), 1.0));
+    }
+
+    #[test]
+    fn ray_aabb_hits_with_an_axis_parallel_ray() {
+        // paralelo a los ejes x e y, dentro de sus franjas
+        let ray = Ray::new(Vec3::new(0.5, -0.5, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let (t_min, t_max) = ray_aabb(&ray, &unit_box()).expect("el rayo cruza la caja");
+        assert!(approx(t_min, 4.0));
+        assert!(approx(t_max, 6.0));
+    }
+
+    #[test]
+    fn ray_aabb_from_inside_starts_at_zero() {
+        let ray = Ray::new(Vec3::zeros(), Vec3::new(1.0, 1.0, 0.0));
+        let (t_min, t_max) = ray_aabb(&ray, &unit_box()).unwrap();
+        assert_eq!(t_min, 0.0);
+        assert!(approx(t_max, 1.0));
+    }
+
+    #[test]
+    fn ray_aabb_misses() {
+        // paralelo al eje z pero fuera de la franja de x
+        let outside = Ray::new(Vec3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(ray_aabb(&outside, &unit_box()), None);
+        let away = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(ray_aabb(&away, &unit_box()), None);
+        let diagonal = Ray::new(Vec3::new(0.0, 3.0, 5.0), Vec3::new(1.0, 0.0, -1.0));
+        assert_eq!(ray_aabb(&diagonal, &unit_box()), None);
+    }
+
+    const A: Vec3 = Vec3::new(0.0, 0.0, 0.0);
+    const B: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+    const C: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+
+    #[test]
+    fn ray_triangle_hit_has_barycentrics() {
+        let ray = Ray::new(Vec3::new(0.25, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = ray_triangle(&ray, A, B, C).expect("el rayo cruza el triángulo");
+        assert!(approx(hit.t, 2.0));
+        assert!(approx(hit.barycentric.x, 0.25));
+        assert!(approx(hit.barycentric.y, 0.25));
+        assert!(approx(hit.barycentric.z, 0.5));
+        // las baricéntricas reconstruyen el punto de corte
+        let point = A * hit.barycentric.x + B * hit.barycentric.y + C * hit.barycentric.z;
+        assert!((point - ray.at(hit.t)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn ray_triangle_counts_both_faces() {
+        let ray = Ray::new(Vec3::new(0.25, 0.25, -2.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(approx(ray_triangle(&ray, A, B, C).unwrap().t, 2.0));
+    }
+
+    #[test]
+    fn ray_triangle_misses_outside_the_edges() {
+        let ray = Ray::new(Vec3::new(0.75, 0.75, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(ray_triangle(&ray, A, B, C), None);
+    }
+
+    #[test]
+    fn ray_triangle_ignores_parallel_rays() {
+        let in_plane = Ray::new(Vec3::new(-1.0, 0.25, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(ray_triangle(&in_plane, A, B, C), None);
+        let above = Ray::new(Vec3::new(-1.0, 0.25, 1.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(ray_triangle(&above, A, B, C), None);
+    }
+
+    #[test]
+    fn ray_triangle_ignores_triangles_behind_the_origin() {
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 2.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(ray_triangle(&ray, A, B, C), None);
+    }
+}
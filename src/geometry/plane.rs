@@ -0,0 +1,205 @@
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+use crate::bounds::{Aabb, BoundingSphere};
+
+/// Plano `normal · p + distance = 0`, con la normal normalizada.
+///
+/// La normal apunta hacia el semiespacio positivo: `signed_distance` es positiva
+/// de ese lado, negativa del otro y cero sobre el plano.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    /// Normal unitaria del plano.
+    pub normal: Vec3,
+    /// Distancia con signo del origen al plano, medida contra la normal.
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Crea un plano a partir de su normal y su distancia; ambas se escalan para
+    /// que la normal quede unitaria.
+    ///
+    /// # Retorna
+    /// `None` si la normal es nula.
+    pub fn new(normal: Vec3, distance: f32) -> Option<Plane> {
+        let length = normal.magnitude();
+        (length > f32::EPSILON).then(|| Plane { normal: normal / length, distance: distance / length })
+    }
+
+    /// Crea el plano que pasa por un punto con la normal dada.
+    ///
+    /// # Retorna
+    /// `None` si la normal es nula.
+    pub fn from_point_normal(point: Vec3, normal: Vec3) -> Option<Plane> {
+        let normal = normal.try_normalize(f32::EPSILON)?;
+        Some(Plane { normal, distance: -normal.dot(&point) })
+    }
+
+    /// Crea el plano que pasa por tres puntos. La normal sigue la regla de la mano
+    /// derecha: los puntos en sentido antihorario la dejan mirando hacia quien los ve.
+    ///
+    /// # Retorna
+    /// `None` si los puntos están alineados.
+    pub fn from_points(a: Vec3, b: Vec3, c: Vec3) -> Option<Plane> {
+        Plane::from_point_normal(a, (b - a).cross(&(c - a)))
+    }
+
+    /// Interpreta los coeficientes `(a, b, c, d)` de `a·x + b·y + c·z + d = 0`.
+    fn from_coefficients(coefficients: Vec4) -> Option<Plane> {
+        Plane::new(coefficients.xyz(), coefficients.w)
+    }
+
+    /// Distancia con signo de un punto al plano.
+    pub fn signed_distance(&self, point: &Vec3) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+
+    /// Indica si el punto está en el semiespacio positivo (o sobre el plano).
+    pub fn is_in_front(&self, point: &Vec3) -> bool {
+        self.signed_distance(point) >= 0.0
+    }
+}
+
+/// Los seis planos de un frustum, con las normales hacia adentro.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Planos izquierdo, derecho, inferior, superior, cercano y lejano, en ese orden.
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extrae los planos de una matriz de vista y proyección (método de Gribb y
+    /// Hartmann): cada plano es la suma o la resta de la última fila de la matriz
+    /// con una de las otras. Los planos quedan en el espacio de entrada de la matriz,
+    /// así que con `proyección * vista` se obtienen en el mundo.
+    ///
+    /// # Retorna
+    /// `None` si la matriz es degenerada y algún plano no tiene normal.
+    pub fn from_matrix(view_projection: &Mat4) -> Option<Frustum> {
+        let row = |index: usize| -> Vec4 { view_projection.row(index).transpose() };
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        Some(Frustum {
+            planes: [
+                Plane::from_coefficients(w + x)?,
+                Plane::from_coefficients(w - x)?,
+                Plane::from_coefficients(w + y)?,
+                Plane::from_coefficients(w - y)?,
+                Plane::from_coefficients(w + z)?,
+                Plane::from_coefficients(w - z)?,
+            ],
+        })
+    }
+
+    /// Indica si el punto está dentro del frustum.
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        self.planes.iter().all(|plane| plane.is_in_front(point))
+    }
+
+    /// Indica si una esfera toca el frustum. Es conservador: cerca de las esquinas
+    /// puede aceptar una esfera que en realidad queda fuera.
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(&sphere.center) >= -sphere.radius)
+    }
+
+    /// Indica si una caja toca el frustum, probando para cada plano la esquina que
+    /// más avanza en la dirección de su normal. También es conservador en las esquinas.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let corner = Vec3::new(
+                if plane.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+            plane.is_in_front(&corner)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{look_at, perspective};
+
+    /// Cámara en z = 5 mirando al origen, con 90° de campo de visión, cerca a 1 y
+    /// lejos a 11: los planos laterales son `|x| = 5 - z` y `|y| = 5 - z`, el cercano
+    /// `z = 4` y el lejano `z = -6`.
+    fn frustum() -> Frustum {
+        let view = look_at(&Vec3::new(0.0, 0.0, 5.0), &Vec3::zeros(), &Vec3::new(0.0, 1.0, 0.0));
+        let projection = perspective(1.0, std::f32::consts::FRAC_PI_2, 1.0, 11.0);
+        Frustum::from_matrix(&(projection * view)).expect("la matriz no es degenerada")
+    }
+
+    fn sphere(x: f32, y: f32, z: f32) -> BoundingSphere {
+        BoundingSphere { center: Vec3::new(x, y, z), radius: 1.0 }
+    }
+
+    #[test]
+    fn extracted_planes_are_normalized() {
+        for plane in frustum().planes {
+            assert!((plane.normal.magnitude() - 1.0).abs() < 1e-5, "{:?}", plane);
+        }
+    }
+
+    #[test]
+    fn planes_are_in_order() {
+        let frustum = frustum();
+        let on_planes = [
+            Vec3::new(-5.0, 0.0, 0.0),
+            Vec3::new(5.0, 0.0, 0.0),
+            Vec3::new(0.0, -5.0, 0.0),
+            Vec3::new(0.0, 5.0, 0.0),
+            Vec3::new(0.0, 0.0, 4.0),
+            Vec3::new(0.0, 0.0, -6.0),
+        ];
+        for (plane, point) in frustum.planes.iter().zip(&on_planes) {
+            assert!(plane.signed_distance(point).abs() < 1e-4, "{:?} {:?}", plane, point);
+            // las normales miran hacia adentro
+            assert!(plane.signed_distance(&Vec3::zeros()) > 0.0);
+        }
+    }
+
+    #[test]
+    fn accepts_points_and_spheres_inside() {
+        let frustum = frustum();
+        assert!(frustum.contains_point(&Vec3::zeros()));
+        assert!(frustum.contains_point(&Vec3::new(3.0, -3.0, -2.0)));
+        assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, 0.0)));
+        assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -5.0)));
+    }
+
+    #[test]
+    fn rejects_spheres_outside_each_plane() {
+        let frustum = frustum();
+        let outside = [
+            sphere(-8.0, 0.0, 0.0),
+            sphere(8.0, 0.0, 0.0),
+            sphere(0.0, -8.0, 0.0),
+            sphere(0.0, 8.0, 0.0),
+            sphere(0.0, 0.0, 5.5),
+            sphere(0.0, 0.0, -7.5),
+        ];
+        for (index, sphere) in outside.iter().enumerate() {
+            assert!(!frustum.contains_point(&sphere.center));
+            assert!(!frustum.intersects_sphere(sphere), "{:?}", sphere);
+            // la descarta el plano que le corresponde
+            assert!(frustum.planes[index].signed_distance(&sphere.center) < -sphere.radius);
+        }
+    }
+
+    #[test]
+    fn accepts_spheres_straddling_each_plane() {
+        let frustum = frustum();
+        let straddling = [
+            sphere(-5.5, 0.0, 0.0),
+            sphere(5.5, 0.0, 0.0),
+            sphere(0.0, -5.5, 0.0),
+            sphere(0.0, 5.5, 0.0),
+            sphere(0.0, 0.0, 4.5),
+            sphere(0.0, 0.0, -6.5),
+        ];
+        for (index, sphere) in straddling.iter().enumerate() {
+            // el centro queda fuera, pero la esfera todavía toca el frustum
+            assert!(!frustum.contains_point(&sphere.center));
+            assert!(frustum.planes[index].signed_distance(&sphere.center) < 0.0);
+            assert!(frustum.intersects_sphere(sphere), "{:?}", sphere);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::line::line_fragments;
+use crate::vertex::Vertex;
+
+/// Tramos rectos con los que se aproxima un círculo.
+const CIRCLE_SEGMENTS: usize = 96;
+
+/// Distancia mínima a la cámara (la `w` del espacio de recorte) a la que se corta
+/// una línea que pasa por detrás de ella.
+const NEAR_W: f32 = 1e-3;
+
+/// Color y modo de dibujo de un gizmo de depuración.
+#[derive(Debug, Clone, Copy)]
+pub struct GizmoStyle {
+    /// Color de las líneas.
+    pub color: Color,
+    /// Si es `true`, la geometría tapa las líneas que pasan por detrás; si es
+    /// `false`, se dibujan encima de todo.
+    pub depth_test: bool,
+}
+
+impl GizmoStyle {
+    /// Crea un estilo con prueba de profundidad.
+    pub fn new(color: Color) -> Self {
+        GizmoStyle { color, depth_test: true }
+    }
+
+    /// Devuelve el mismo estilo dibujado encima de toda la geometría.
+    pub fn on_top(self) -> Self {
+        GizmoStyle { depth_test: false, ..self }
+    }
+}
+
+/// Dibuja un segmento del mundo con `line`. Se recorta contra el plano de la
+/// cámara y contra los bordes del framebuffer, así un segmento que pasa por detrás
+/// de la cámara o sale muy lejos de la pantalla dibuja solo su parte visible.
+///
+/// Las líneas nunca escriben en el z-buffer: no tapan a la geometría ni a otros gizmos.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer de destino.
+/// - `screen_matrix`: Viewport por proyección por vista de la cámara.
+/// - `a`: Punto de inicio en el mundo.
+/// - `b`: Punto de fin en el mundo.
+/// - `style`: Color y prueba de profundidad.
+pub fn draw_line_3d(framebuffer: &mut Framebuffer, screen_matrix: &Mat4, a: Vec3, b: Vec3, style: GizmoStyle) {
+    let mut start = screen_matrix * Vec4::new(a.x, a.y, a.z, 1.0);
+    let mut end = screen_matrix * Vec4::new(b.x, b.y, b.z, 1.0);
+    if start.w < NEAR_W && end.w < NEAR_W {
+        return;
+    }
+    // El extremo que queda detrás de la cámara se mueve sobre el segmento hasta el plano cercano.
+    if start.w < NEAR_W {
+        start = start.lerp(&end, (NEAR_W - start.w) / (end.w - start.w));
+    } else if end.w < NEAR_W {
+        end = end.lerp(&start, (NEAR_W - end.w) / (start.w - end.w));
+    }
+    let start = start.xyz() / start.w;
+    let end = end.xyz() / end.w;
+    let Some((t0, t1)) = clip_to_rectangle(start, end, framebuffer.width as f32, framebuffer.height as f32) else { return };
+
+    let mut from = Vertex::new_with_color(a, style.color);
+    from.set_transformed(start.lerp(&end, t0), Vec3::new(0.0, 0.0, 1.0));
+    let mut to = Vertex::new_with_color(b, style.color);
+    to.set_transformed(start.lerp(&end, t1), Vec3::new(0.0, 0.0, 1.0));
+
+    framebuffer.set_current_color(style.color.to_u32());
+    for fragment in line_fragments(&from, &to) {
+        let (x, y) = (fragment.position.x, fragment.position.y);
+        if x < 0.0 || y < 0.0 {
+            continue;
+        }
+        let depth = if style.depth_test { fragment.depth } else { f32::NEG_INFINITY };
+        framebuffer.blend_point(x as usize, y as usize, depth, 1.0);
+    }
+}
+
+/// Dibuja un círculo en el mundo, por ejemplo la trayectoria de una órbita.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer de destino.
+/// - `screen_matrix`: Viewport por proyección por vista de la cámara.
+/// - `center`: Centro del círculo.
+/// - `normal`: Normal del plano del círculo; no hace falta que esté normalizada.
+/// - `radius`: Radio del círculo.
+/// - `style`: Color y prueba de profundidad.
+pub fn draw_circle_3d(framebuffer: &mut Framebuffer, screen_matrix: &Mat4, center: Vec3, normal: Vec3, radius: f32, style: GizmoStyle) {
+    let Some(normal) = normal.try_normalize(1e-6) else { return };
+    // Dos ejes perpendiculares sobre el plano del círculo.
+    let helper = if normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+    let u = normal.cross(&helper).normalize() * radius;
+    let v = normal.cross(&u);
+    let point = |step: usize| {
+        let (sin, cos) = (step as f32 / CIRCLE_SEGMENTS as f32 * 2.0 * PI).sin_cos();
+        center + u * cos + v * sin
+    };
+    for step in 0..CIRCLE_SEGMENTS {
+        draw_line_3d(framebuffer, screen_matrix, point(step), point(step + 1), style);
+    }
+}
+
+/// Dibuja un eje que atraviesa un punto, por ejemplo el eje de rotación de un planeta,
+/// con una marca en el extremo que apunta en la dirección de `axis`.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer de destino.
+/// - `screen_matrix`: Viewport por proyección por vista de la cámara.
+/// - `center`: Punto por el que pasa el eje.
+/// - `axis`: Dirección del eje; no hace falta que esté normalizada.
+/// - `length`: Distancia del centro a cada extremo.
+/// - `style`: Color y prueba de profundidad.
+pub fn draw_axis(framebuffer: &mut Framebuffer, screen_matrix: &Mat4, center: Vec3, axis: Vec3, length: f32, style: GizmoStyle) {
+    let Some(axis) = axis.try_normalize(1e-6) else { return };
+    let tip = center + axis * length;
+    draw_line_3d(framebuffer, screen_matrix, center - axis * length, tip, style);
+    // Un anillo chico cerca de la punta distingue el polo norte del sur.
+    draw_circle_3d(framebuffer, screen_matrix, center + axis * (length * 0.85), axis, length * 0.08, style);
+}
+
+/// Dibuja una cuadrícula cuadrada sobre un plano paralelo al XZ, como referencia
+/// del plano de las órbitas.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer de destino.
+/// - `screen_matrix`: Viewport por proyección por vista de la cámara.
+/// - `center`: Centro de la cuadrícula.
+/// - `size`: Largo de cada lado.
+/// - `divisions`: Cantidad de celdas por lado.
+/// - `style`: Color y prueba de profundidad.
+pub fn draw_grid(framebuffer: &mut Framebuffer, screen_matrix: &Mat4, center: Vec3, size: f32, divisions: usize, style: GizmoStyle) {
+    let divisions = divisions.max(1);
+    let half = size / 2.0;
+    for line in 0..=divisions {
+        let offset = -half + size * line as f32 / divisions as f32;
+        draw_line_3d(framebuffer, screen_matrix, center + Vec3::new(offset, 0.0, -half), center + Vec3::new(offset, 0.0, half), style);
+        draw_line_3d(framebuffer, screen_matrix, center + Vec3::new(-half, 0.0, offset), center + Vec3::new(half, 0.0, offset), style);
+    }
+}
+
+/// Recorta un segmento en pantalla al rectángulo del framebuffer (Liang-Barsky).
+///
+/// # Retorna
+/// Los parámetros de inicio y fin de la parte visible, o `None` si queda afuera.
+fn clip_to_rectangle(start: Vec3, end: Vec3, width: f32, height: f32) -> Option<(f32, f32)> {
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let mut range = (0.0_f32, 1.0_f32);
+    for (p, q) in [(-dx, start.x), (dx, width - start.x), (-dy, start.y), (dy, height - start.y)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = q / p;
+        if p < 0.0 {
+            range.0 = range.0.max(t);
+        } else {
+            range.1 = range.1.min(t);
+        }
+    }
+    (range.0 <= range.1).then_some(range)
+}
@@ -0,0 +1,578 @@
+use std::collections::HashMap;
+use std::fmt;
+use nalgebra_glm::{Mat4, Vec3};
+use wgpu::util::DeviceExt;
+use crate::camera::Camera;
+use crate::color::{Color, ColorRamp};
+use crate::framebuffer::Framebuffer;
+use crate::lod::LodMesh;
+use crate::obj::Model;
+use crate::pipeline::create_model_matrix;
+use crate::scene::{Scene, SceneNode};
+use crate::shader_params::SurfaceParams;
+use crate::shaders::PlanetShader;
+
+/// Modos de color del shader WGSL (`gpu.wgsl`): albedo con luz difusa.
+const MODE_LIT: f32 = 0.0;
+/// La normal como color, como `PlanetShader::DebugNormal`.
+const MODE_NORMAL: f32 = 1.0;
+/// Las coordenadas de textura como color, como `PlanetShader::DebugUv`.
+const MODE_UV: f32 = 2.0;
+/// La luz difusa en grises, como `PlanetShader::DebugIntensity`.
+const MODE_INTENSITY: f32 = 3.0;
+/// Los colores por vértice del modelo con luz difusa.
+const MODE_VERTEX_COLOR: f32 = 4.0;
+/// Los shaders de planeta con ruido, en el orden de `PlanetShader`.
+const MODE_EARTH: f32 = 5.0;
+const MODE_MOON: f32 = 6.0;
+const MODE_SUN: f32 = 7.0;
+const MODE_GAS: f32 = 8.0;
+const MODE_ROCKY: f32 = 9.0;
+const MODE_STAR: f32 = 10.0;
+const MODE_FANTASY: f32 = 11.0;
+const MODE_LAVA: f32 = 12.0;
+const MODE_OCEAN: f32 = 13.0;
+
+/// Floats por vértice: posición, normal, coordenadas de textura y color.
+const VERTEX_FLOATS: usize = 11;
+
+/// Bytes de los datos de un dibujo en el shader: dos matrices, cuatro vectores,
+/// cuatro colores y las paradas de una rampa.
+const DRAW_SIZE: u64 = 16 * (4 + 4 + 4 + 4 + ColorRamp::MAX_STOPS as u64);
+
+const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+const DEPTH_VALUE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Error al preparar la GPU.
+#[derive(Debug)]
+pub enum GpuError {
+    /// No hay ningún adaptador gráfico disponible.
+    NoAdapter,
+    /// El adaptador no pudo crear el dispositivo.
+    Device(String),
+}
+
+impl fmt::Display for GpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GpuError::NoAdapter => write!(f, "no hay un adaptador gráfico compatible con wgpu"),
+            GpuError::Device(message) => write!(f, "no se pudo crear el dispositivo: {}", message),
+        }
+    }
+}
+
+/// Malla ya copiada a la memoria de la GPU.
+struct GpuMesh {
+    vertices: wgpu::Buffer,
+    indices: wgpu::Buffer,
+    index_count: u32,
+}
+
+/// Imágenes de color y profundidad del tamaño del viewport, y los buffers por los
+/// que vuelven a la memoria principal.
+struct RenderTarget {
+    width: u32,
+    height: u32,
+    color: wgpu::Texture,
+    // Los bits de la profundidad de cada píxel como color, que sí se puede copiar a un buffer.
+    depth_value: wgpu::Texture,
+    // El z-buffer de la GPU, que solo se usa para la prueba de profundidad.
+    depth: wgpu::Texture,
+    color_readback: wgpu::Buffer,
+    depth_readback: wgpu::Buffer,
+    // Bytes por fila en los buffers de lectura, alineados como exige wgpu.
+    bytes_per_row: u32,
+}
+
+/// Vista previa por GPU con wgpu: dibuja los cuerpos de la escena con los mismos
+/// datos de vértices y la misma cámara que el rasterizador por software, y deja el
+/// resultado (color y profundidad) en el `Framebuffer`, así las estrellas, las
+/// órbitas, las partículas y el posproceso se dibujan encima igual que siempre.
+///
+/// Los shaders WGSL (`gpu.wgsl`) son una aproximación: los de depuración coinciden
+/// con los de `shaders.rs`, y los de planeta siguen los mismos pasos con los mismos
+/// parámetros (`ShaderParams::surface`), pero con otro ruido y luz difusa en lugar
+/// del modelo físico, así que los continentes, las bandas y los ríos no caen en el
+/// mismo lugar. No hay atmósfera, sombras ni cráteres, y las capas semitransparentes
+/// (anillos y nubes) no se dibujan. Sirve para mover mallas grandes a una velocidad
+/// interactiva; el rasterizador por software sigue siendo la referencia.
+///
+/// Solo existe con la característica `gpu`.
+pub struct GpuRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::RenderPipeline,
+    draw_layout: wgpu::BindGroupLayout,
+    // Datos de cada dibujo, uno cada `draw_stride` bytes.
+    draw_buffer: wgpu::Buffer,
+    draw_capacity: u64,
+    draw_stride: u64,
+    meshes: HashMap<String, GpuMesh>,
+    target: Option<RenderTarget>,
+    adapter_name: String,
+}
+
+impl GpuRenderer {
+    /// Abre el adaptador gráfico por defecto y compila los shaders.
+    pub fn new() -> Result<Self, GpuError> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .ok_or(GpuError::NoAdapter)?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+            .map_err(|error| GpuError::Device(error.to_string()))?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("gpu.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("gpu.wgsl").into()),
+        });
+        let draw_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("draw"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(DRAW_SIZE),
+                },
+                count: None,
+            }],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("bodies"),
+            bind_group_layouts: &[&draw_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("bodies"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: (VERTEX_FLOATS * 4) as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2, 3 => Float32x3],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(COLOR_FORMAT.into()), Some(DEPTH_VALUE_FORMAT.into())],
+            }),
+            // Los modelos importados no siempre tienen las caras orientadas igual: no se descarta ninguna.
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let draw_stride = DRAW_SIZE.next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+        let draw_buffer = create_draw_buffer(&device, draw_stride);
+        Ok(GpuRenderer {
+            device,
+            queue,
+            pipeline,
+            draw_layout,
+            draw_buffer,
+            draw_capacity: 1,
+            draw_stride,
+            meshes: HashMap::new(),
+            target: None,
+            adapter_name: adapter.get_info().name,
+        })
+    }
+
+    /// Nombre del adaptador gráfico en uso.
+    pub fn adapter_name(&self) -> &str {
+        &self.adapter_name
+    }
+
+    /// Olvida una malla ya copiada a la GPU, para que se vuelva a copiar en el
+    /// próximo dibujo (por ejemplo, después de recargar su archivo).
+    pub fn forget_mesh(&mut self, name: &str) {
+        self.meshes.remove(name);
+    }
+
+    /// Dibuja los cuerpos visibles de la escena en el viewport del framebuffer,
+    /// respetando su z-buffer. Las mallas se copian a la GPU la primera vez que se
+    /// usan, siempre en su nivel de detalle más fino.
+    ///
+    /// # Parámetros
+    /// - `framebuffer`: Framebuffer de destino; se usan su viewport y su tijera.
+    /// - `scene`: Escena a dibujar.
+    /// - `meshes`: Mallas de la escena por nombre.
+    /// - `camera`: Cámara de la vista, con su proyección.
+    /// - `frame`: Instante del fotograma y parámetros de los shaders.
+    pub fn draw_scene(&mut self, framebuffer: &mut Framebuffer, scene: &Scene, meshes: &HashMap<String, LodMesh>, camera: &Camera, frame: &GpuFrame) {
+        let viewport = framebuffer.viewport();
+        if viewport.width == 0 || viewport.height == 0 {
+            return;
+        }
+        let (width, height) = (viewport.width as u32, viewport.height as u32);
+        if self.target.as_ref().is_none_or(|target| (target.width, target.height) != (width, height)) {
+            self.target = Some(create_target(&self.device, width, height));
+        }
+
+        // wgpu guarda la profundidad entre 0 y 1, no entre -1 y 1 como el rasterizador.
+        let depth_range = Mat4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 0.5, 0.5,
+            0.0, 0.0, 0.0, 1.0,
+        );
+        let view_projection = depth_range * camera.projection_matrix() * camera.view_matrix();
+        let light_position = scene.light_source().map(|light| scene.world_position(light, frame.sim_time));
+        let rotation = Vec3::new(0.0, frame.spin, 0.0);
+
+        let mut draws = Vec::new();
+        for (index, node) in scene.nodes.iter().enumerate() {
+            if !node.visible || !camera.sees(node.layers) || node.shader.is_transparent() {
+                continue;
+            }
+            let Some(mesh) = meshes.get(&node.mesh) else { continue };
+            if !self.meshes.contains_key(&node.mesh) {
+                let uploaded = upload_mesh(&self.device, mesh.finest());
+                self.meshes.insert(node.mesh.clone(), uploaded);
+            }
+            let translation = scene.world_position(index, frame.sim_time);
+            let light = light_position.map(|light| light - translation)
+                .and_then(|direction| direction.try_normalize(1e-4))
+                .unwrap_or_else(|| Vec3::new(0.0, 0.0, 1.0));
+            let (color, mode) = preview_color(node.shader);
+            let data = DrawData { view_projection, color, light, mode, surface: surface_data(node, frame) };
+            match node.belt {
+                Some(belt) => {
+                    let placement = create_model_matrix(translation, 1.0, rotation);
+                    for instance in belt.instances(node.radius, node.seed) {
+                        draws.push((node.mesh.as_str(), data.bytes(&(placement * instance))));
+                    }
+                }
+                None => {
+                    let model = create_model_matrix(translation, node.radius, rotation);
+                    draws.push((node.mesh.as_str(), data.bytes(&model)));
+                }
+            }
+        }
+
+        if draws.len() as u64 > self.draw_capacity {
+            self.draw_capacity = (draws.len() as u64).next_power_of_two();
+            self.draw_buffer = create_draw_buffer(&self.device, self.draw_stride * self.draw_capacity);
+        }
+        for (slot, (_, bytes)) in draws.iter().enumerate() {
+            self.queue.write_buffer(&self.draw_buffer, slot as u64 * self.draw_stride, bytes);
+        }
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("draw"),
+            layout: &self.draw_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &self.draw_buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(DRAW_SIZE),
+                }),
+            }],
+        });
+
+        let Some(target) = &self.target else { return };
+        let color_view = target.color.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_value_view = target.depth_value.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_view = target.depth.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("bodies") });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("bodies"),
+                color_attachments: &[
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: &color_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT), store: wgpu::StoreOp::Store },
+                    }),
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: &depth_value_view,
+                        resolve_target: None,
+                        // Sin geometría la profundidad es 1, la del plano lejano.
+                        ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color { r: 1.0_f32.to_bits() as f64, g: 0.0, b: 0.0, a: 0.0 }), store: wgpu::StoreOp::Store },
+                    }),
+                ],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(wgpu::Operations { load: wgpu::LoadOp::Clear(1.0), store: wgpu::StoreOp::Store }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            for (slot, (mesh, _)) in draws.iter().enumerate() {
+                let mesh = &self.meshes[*mesh];
+                pass.set_bind_group(0, &bind_group, &[(slot as u64 * self.draw_stride) as u32]);
+                pass.set_vertex_buffer(0, mesh.vertices.slice(..));
+                pass.set_index_buffer(mesh.indices.slice(..), wgpu::IndexFormat::Uint32);
+                pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+            }
+        }
+        for (texture, buffer) in [(&target.color, &target.color_readback), (&target.depth_value, &target.depth_readback)] {
+            encoder.copy_texture_to_buffer(
+                texture.as_image_copy(),
+                wgpu::TexelCopyBufferInfo {
+                    buffer,
+                    layout: wgpu::TexelCopyBufferLayout { offset: 0, bytes_per_row: Some(target.bytes_per_row), rows_per_image: Some(height) },
+                },
+                wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            );
+        }
+        self.queue.submit([encoder.finish()]);
+
+        let colors = read_back(&self.device, &target.color_readback);
+        let depths = read_back(&self.device, &target.depth_readback);
+        for y in 0..viewport.height {
+            let row = y * target.bytes_per_row as usize;
+            for x in 0..viewport.width {
+                let depth_bytes = &depths[row + x * 4..row + x * 4 + 4];
+                let depth = f32::from_le_bytes([depth_bytes[0], depth_bytes[1], depth_bytes[2], depth_bytes[3]]);
+                if depth >= 1.0 {
+                    continue;
+                }
+                let rgba = &colors[row + x * 4..row + x * 4 + 4];
+                framebuffer.set_current_color(Color::new(rgba[0], rgba[1], rgba[2]).to_u32());
+                framebuffer.point(viewport.x + x, viewport.y + y, depth * 2.0 - 1.0);
+            }
+        }
+    }
+}
+
+/// Instante y parámetros de los shaders con que `GpuRenderer::draw_scene` dibuja un fotograma.
+#[derive(Debug, Clone, Copy)]
+pub struct GpuFrame<'a> {
+    /// Parámetros de los shaders de planeta (`ShaderParams::surface`).
+    pub surface: &'a SurfaceParams,
+    /// Tiempo de simulación, para las órbitas.
+    pub sim_time: f32,
+    /// Fotograma de la animación, para lo que se mueve sobre la superficie (nubes, lava, olas).
+    pub time: u32,
+    /// Giro de todos los cuerpos alrededor de su eje Y, en radianes.
+    pub spin: f32,
+}
+
+/// Datos de un dibujo, en el orden de `struct Draw` de `gpu.wgsl`, sin la matriz de modelo.
+struct DrawData {
+    view_projection: Mat4,
+    color: Color,
+    light: Vec3,
+    mode: f32,
+    surface: SurfaceData,
+}
+
+/// Datos de un cuerpo para los shaders de planeta de `gpu.wgsl`.
+struct SurfaceData {
+    noise_scale: f32,
+    seed: u32,
+    /// Fotograma de la animación por 0.01, como en `shaders.rs`.
+    time: f32,
+    features: u32,
+    /// Números propios del shader (umbrales, anchos, flujo).
+    params: [f32; 3],
+    /// Colores propios del shader, en el orden de sus parámetros.
+    colors: [Color; 4],
+    /// Rampa de colores por elevación de la Tierra y la Luna.
+    ramp: Option<ColorRamp>,
+}
+
+impl DrawData {
+    /// Bytes del dibujo con una matriz de modelo.
+    fn bytes(&self, model: &Mat4) -> Vec<u8> {
+        let surface = &self.surface;
+        let rgba = |color: Color, alpha: f32| {
+            let color = Vec3::from(color);
+            [color.x, color.y, color.z, alpha]
+        };
+        let stops = surface.ramp.as_ref().map_or(&[][..], ColorRamp::stops);
+        let mut floats: Vec<f32> = model.as_slice().iter().chain(self.view_projection.as_slice()).copied().collect();
+        floats.extend(rgba(self.color, 1.0));
+        floats.extend([self.light.x, self.light.y, self.light.z, self.mode]);
+        // la semilla y las características van como bits; el shader las lee con `bitcast`
+        floats.extend([surface.noise_scale, 0.0, surface.time, 0.0]);
+        floats.extend(surface.params.iter().copied().chain([stops.len() as f32]));
+        floats.extend(surface.colors.iter().flat_map(|&color| rgba(color, 1.0)));
+        floats.extend((0..ColorRamp::MAX_STOPS).flat_map(|index| {
+            stops.get(index).map_or([0.0; 4], |&(elevation, color)| rgba(color, elevation))
+        }));
+
+        let mut bytes: Vec<u8> = floats.iter().flat_map(|value| value.to_le_bytes()).collect();
+        let noise = (16 + 4 + 4) * 4;
+        bytes[noise + 4..noise + 8].copy_from_slice(&surface.seed.to_le_bytes());
+        bytes[noise + 12..noise + 16].copy_from_slice(&surface.features.to_le_bytes());
+        bytes
+    }
+}
+
+/// Parámetros de un cuerpo para su shader de planeta en `gpu.wgsl`; los demás
+/// shaders no los usan.
+fn surface_data(node: &SceneNode, frame: &GpuFrame) -> SurfaceData {
+    let surface = frame.surface;
+    let black = Color::black();
+    let (noise_scale, params, colors, ramp) = match node.shader {
+        PlanetShader::Earth => {
+            let earth = &surface.earth;
+            (earth.noise_scale, [earth.land_threshold, 0.0, 0.0], [black; 4], Some(earth.terrain_ramp))
+        }
+        PlanetShader::Moon => (surface.moon.noise_scale, [0.0; 3], [black; 4], Some(surface.moon.terrain_ramp)),
+        PlanetShader::Sun => {
+            let sun = &surface.sun;
+            (sun.noise_scale, [0.0; 3], [sun.deep_color, sun.light_color, black, black], None)
+        }
+        PlanetShader::Gas => {
+            let gas = &surface.gas;
+            (gas.noise_scale, [0.0; 3], [gas.base_color, gas.cloud_color, gas.shadow_color, black], None)
+        }
+        PlanetShader::Rocky => {
+            let rocky = &surface.rocky;
+            (rocky.noise_scale, [0.0; 3], [rocky.base_color, rocky.highlight_color, rocky.shadow_color, black], None)
+        }
+        PlanetShader::Star => {
+            let star = &surface.star;
+            (star.noise_scale, [0.0; 3], [star.base_color, star.star_color, black, black], None)
+        }
+        PlanetShader::Fantasy => {
+            let fantasy = &surface.fantasy;
+            (fantasy.noise_scale, [0.0; 3], [fantasy.base_color, fantasy.accent_color, fantasy.cloud_color, black], None)
+        }
+        PlanetShader::Lava => {
+            let lava = &surface.lava;
+            let params = [lava.river_width, node.flow.speed, node.flow.turbulence];
+            (lava.noise_scale, params, [lava.crust_color, lava.hot_color, lava.core_color, black], None)
+        }
+        PlanetShader::Ocean => {
+            let ocean = &surface.ocean;
+            let params = [ocean.foam_threshold, node.flow.speed, node.flow.turbulence];
+            (ocean.noise_scale, params, [ocean.deep_color, ocean.shallow_color, ocean.foam_color, black], None)
+        }
+        _ => (1.0, [0.0; 3], [black; 4], None),
+    };
+    SurfaceData {
+        noise_scale,
+        seed: node.seed as u32,
+        time: frame.time as f32 * 0.01,
+        features: node.features,
+        params,
+        colors,
+        ramp,
+    }
+}
+
+/// Color representativo y modo de color del shader WGSL para cada shader de planeta.
+/// Los de planeta con ruido toman sus colores de `surface_data`; el suyo solo se usa
+/// con `MODE_LIT`.
+fn preview_color(shader: PlanetShader) -> (Color, f32) {
+    match shader {
+        PlanetShader::Earth => (Color::new(0, 105, 148), MODE_EARTH),
+        PlanetShader::Moon => (Color::new(150, 150, 150), MODE_MOON),
+        PlanetShader::Sun => (Color::new(255, 190, 60), MODE_SUN),
+        PlanetShader::Gas => (Color::new(70, 130, 180), MODE_GAS),
+        PlanetShader::Rocky => (Color::new(139, 69, 19), MODE_ROCKY),
+        PlanetShader::Star => (Color::new(255, 240, 210), MODE_STAR),
+        PlanetShader::Fantasy => (Color::new(150, 90, 200), MODE_FANTASY),
+        PlanetShader::Lava => (Color::new(170, 50, 15), MODE_LAVA),
+        PlanetShader::Ocean => (Color::new(0, 90, 160), MODE_OCEAN),
+        PlanetShader::Ring => (Color::new(225, 205, 170), MODE_LIT),
+        PlanetShader::Clouds => (Color::new(255, 255, 255), MODE_LIT),
+        PlanetShader::VertexColor => (Color::new(255, 255, 255), MODE_VERTEX_COLOR),
+        PlanetShader::DebugNormal => (Color::new(255, 255, 255), MODE_NORMAL),
+        PlanetShader::DebugUv => (Color::new(255, 255, 255), MODE_UV),
+        PlanetShader::DebugIntensity => (Color::new(255, 255, 255), MODE_INTENSITY),
+        PlanetShader::Material | PlanetShader::DebugElevation => (Color::new(190, 190, 190), MODE_LIT),
+    }
+}
+
+/// Copia los vértices y los índices de un modelo a la GPU.
+fn upload_mesh(device: &wgpu::Device, model: &Model) -> GpuMesh {
+    let vertices: Vec<u8> = model.vertices.iter()
+        .flat_map(|vertex| {
+            let color = Vec3::from(vertex.color);
+            [
+                vertex.position.x, vertex.position.y, vertex.position.z,
+                vertex.normal.x, vertex.normal.y, vertex.normal.z,
+                vertex.tex_coords.x, vertex.tex_coords.y,
+                color.x, color.y, color.z,
+            ]
+        })
+        .flat_map(f32::to_le_bytes)
+        .collect();
+    let indices: Vec<u8> = model.indices.iter().flat_map(|index| index.to_le_bytes()).collect();
+    GpuMesh {
+        vertices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("vertices"),
+            contents: &vertices,
+            usage: wgpu::BufferUsages::VERTEX,
+        }),
+        indices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("indices"),
+            contents: &indices,
+            usage: wgpu::BufferUsages::INDEX,
+        }),
+        index_count: model.indices.len() as u32,
+    }
+}
+
+fn create_draw_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("draws"),
+        size,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_target(device: &wgpu::Device, width: u32, height: u32) -> RenderTarget {
+    let texture = |label: &str, format: wgpu::TextureFormat, usage: wgpu::TextureUsages| device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage,
+        view_formats: &[],
+    });
+    let copied = wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC;
+    // Color y profundidad ocupan 4 bytes por píxel, así que comparten el paso entre filas.
+    let bytes_per_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let readback = |label: &str| device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: bytes_per_row as u64 * height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    RenderTarget {
+        width,
+        height,
+        color: texture("color", COLOR_FORMAT, copied),
+        depth_value: texture("depth value", DEPTH_VALUE_FORMAT, copied),
+        depth: texture("depth", DEPTH_FORMAT, wgpu::TextureUsages::RENDER_ATTACHMENT),
+        color_readback: readback("color readback"),
+        depth_readback: readback("depth readback"),
+        bytes_per_row,
+    }
+}
+
+/// Espera a que la GPU termine y copia el contenido de un buffer de lectura.
+fn read_back(device: &wgpu::Device, buffer: &wgpu::Buffer) -> Vec<u8> {
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |_| ());
+    device.poll(wgpu::Maintain::Wait);
+    let bytes = slice.get_mapped_range().to_vec();
+    buffer.unmap();
+    bytes
+}
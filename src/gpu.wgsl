@@ -0,0 +1,278 @@
+// Shaders de la vista previa por GPU (ver `gpu::GpuRenderer`). Aproximan a los
+// de `shaders.rs`: los de depuración dan el mismo resultado, y los de planeta
+// siguen los mismos pasos con los parámetros de `shader_params`, pero con otro
+// ruido (gradiente en vez de OpenSimplex2), luz difusa en lugar del modelo físico
+// y sin los cráteres de la Luna, así que el dibujo de la superficie no coincide.
+
+struct Draw {
+    model: mat4x4<f32>,
+    view_projection: mat4x4<f32>,
+    // Albedo del cuerpo; `a` no se usa.
+    color: vec4<f32>,
+    // Dirección hacia la luz en el mundo y, en `w`, el modo de color (`MODE_*` en gpu.rs).
+    light: vec4<f32>,
+    // Escala del ruido, semilla (los bits de un u32), tiempo de la animación y
+    // características (`FEATURE_*`, también como bits).
+    noise: vec4<f32>,
+    // Hasta tres números propios de cada shader (ver `surface_data` en gpu.rs) y,
+    // en `w`, cuántas paradas de `ramp` se usan.
+    params: vec4<f32>,
+    // Colores propios de cada shader, en el orden de sus parámetros.
+    colors: array<vec4<f32>, 4>,
+    // Paradas de la rampa de colores por elevación: el color en `rgb` y la elevación en `a`.
+    ramp: array<vec4<f32>, 16>,
+};
+
+@group(0) @binding(0) var<uniform> draw: Draw;
+
+// Luz que reciben los puntos del lado oscuro, para que no queden negros.
+const AMBIENT: f32 = 0.08;
+
+// Frecuencia del ruido, la de FastNoiseLite por defecto.
+const NOISE_FREQUENCY: f32 = 0.01;
+
+// Los mismos bits que `shaders::FEATURE_CLOUDS` y `shaders::FEATURE_CITY_LIGHTS`.
+const FEATURE_CLOUDS: u32 = 1u;
+const FEATURE_CITY_LIGHTS: u32 = 2u;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tex_coords: vec2<f32>,
+    @location(3) color: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) color: vec3<f32>,
+    // Posición en el espacio del modelo, la que usan los shaders de planeta para el ruido.
+    @location(3) position: vec3<f32>,
+};
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.clip_position = draw.view_projection * draw.model * vec4<f32>(input.position, 1.0);
+    // Las matrices de modelo de la escena tienen escala uniforme: no hace falta la inversa transpuesta.
+    output.normal = (draw.model * vec4<f32>(input.normal, 0.0)).xyz;
+    output.tex_coords = input.tex_coords;
+    output.color = input.color;
+    output.position = input.position;
+    return output;
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    // Los bits de la profundidad también se escriben como color: no todos los
+    // adaptadores dejan copiar una textura de profundidad, ni dibujar en una de floats.
+    @location(1) depth: u32,
+};
+
+// Mezcla de bits (PCG) para el ruido y los valores aleatorios por celda.
+fn hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn cell_hash(cell: vec3<i32>) -> u32 {
+    let seed = bitcast<u32>(draw.noise.y);
+    return hash(bitcast<u32>(cell.x) ^ hash(bitcast<u32>(cell.y) ^ hash(bitcast<u32>(cell.z) ^ seed)));
+}
+
+// Producto de `offset` con uno de los doce gradientes del ruido de Perlin mejorado.
+fn gradient_dot(cell: vec3<i32>, corner: vec3<i32>, offset: vec3<f32>) -> f32 {
+    let h = cell_hash(cell + corner) & 15u;
+    let p = offset - vec3<f32>(corner);
+    let u = select(p.y, p.x, h < 8u);
+    let v = select(select(p.z, p.x, h == 12u || h == 14u), p.y, h < 4u);
+    return select(-u, u, (h & 1u) == 0u) + select(-v, v, (h & 2u) == 0u);
+}
+
+// Ruido de gradiente entre -1 y 1, con la semilla del cuerpo.
+fn gradient_noise(point: vec3<f32>) -> f32 {
+    let base = floor(point);
+    let cell = vec3<i32>(base);
+    let f = point - base;
+    let u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    let x00 = mix(gradient_dot(cell, vec3<i32>(0, 0, 0), f), gradient_dot(cell, vec3<i32>(1, 0, 0), f), u.x);
+    let x10 = mix(gradient_dot(cell, vec3<i32>(0, 1, 0), f), gradient_dot(cell, vec3<i32>(1, 1, 0), f), u.x);
+    let x01 = mix(gradient_dot(cell, vec3<i32>(0, 0, 1), f), gradient_dot(cell, vec3<i32>(1, 0, 1), f), u.x);
+    let x11 = mix(gradient_dot(cell, vec3<i32>(0, 1, 1), f), gradient_dot(cell, vec3<i32>(1, 1, 1), f), u.x);
+    return mix(mix(x00, x10, u.y), mix(x01, x11, u.y), u.z);
+}
+
+// Como `get_noise_2d(x * scale, z * scale)` en `shaders.rs`.
+fn noise_2d(position: vec3<f32>, scale: f32) -> f32 {
+    return gradient_noise(vec3<f32>(position.x, position.z, 0.0) * scale * NOISE_FREQUENCY);
+}
+
+// Como `get_noise_3d` en `shaders.rs`.
+fn noise_3d(point: vec3<f32>) -> f32 {
+    return gradient_noise(point * NOISE_FREQUENCY);
+}
+
+// Como `shaders::surface_random`: un valor entre 0 y 1 fijo para cada celda de 1/256.
+fn surface_random(position: vec3<f32>) -> f32 {
+    return f32(cell_hash(vec3<i32>(floor(position * 256.0))) >> 8u) / 16777216.0;
+}
+
+// Como `shaders::warped_noise`: el dominio se deforma con otras tres muestras que
+// se mueven con el tiempo. `speed` y `turbulence` son los del flujo del cuerpo.
+fn warped_noise(position: vec3<f32>, scale: f32, speed: f32, turbulence: f32) -> f32 {
+    let time = draw.noise.z * speed;
+    let p = position * scale;
+    let warp = vec3<f32>(
+        noise_3d(vec3<f32>(p.x + time, p.y, p.z)),
+        noise_3d(vec3<f32>(p.x + 5.2, p.y - time * 0.7, p.z + 1.3)),
+        noise_3d(vec3<f32>(p.x - 3.1, p.y + 2.8, p.z + time * 0.5)),
+    );
+    return noise_3d(p + warp * turbulence);
+}
+
+// Como `ColorRamp::sample`, con las `count` primeras paradas de `draw.ramp`.
+fn sample_ramp(count: u32, t: f32) -> vec3<f32> {
+    if t <= draw.ramp[0].a {
+        return draw.ramp[0].rgb;
+    }
+    for (var index = 1u; index < count; index++) {
+        let start = draw.ramp[index - 1u];
+        let end = draw.ramp[index];
+        if t <= end.a {
+            let span = end.a - start.a;
+            if span <= 0.0 {
+                return end.rgb;
+            }
+            return mix(start.rgb, end.rgb, (t - start.a) / span);
+        }
+    }
+    return draw.ramp[count - 1u].rgb;
+}
+
+// params: umbral de la tierra.
+fn earth(position: vec3<f32>, diffuse: f32, lit: f32) -> vec3<f32> {
+    let features = bitcast<u32>(draw.noise.w);
+    let elevation = noise_2d(position, draw.noise.x);
+    var color = sample_ramp(u32(draw.params.w), elevation);
+    let is_land = elevation > draw.params.x;
+
+    var cover = 0.0;
+    if (features & FEATURE_CLOUDS) != 0u {
+        let drift = draw.noise.z;
+        let clouds = noise_3d(vec3<f32>(position.x * 4.0 + drift, position.y * 4.0, position.z * 4.0 + 100.0));
+        cover = clamp((clouds - 0.1) * 2.0, 0.0, 0.9);
+        color = mix(color, vec3<f32>(1.0), cover);
+    }
+    color *= lit;
+
+    if (features & FEATURE_CITY_LIGHTS) != 0u && is_land {
+        let darkness = clamp(1.0 - diffuse * 4.0, 0.0, 1.0);
+        let cities = noise_3d(position * 60.0);
+        if cities > 0.4 {
+            color += vec3<f32>(1.0, 0.784, 0.471) * (darkness * (1.0 - cover) * (cities - 0.4) / 0.6);
+        }
+    }
+    return color;
+}
+
+fn moon(position: vec3<f32>, lit: f32) -> vec3<f32> {
+    return sample_ramp(u32(draw.params.w), noise_2d(position, draw.noise.x)) * lit;
+}
+
+// colors: naranja profundo, naranja claro.
+fn sun(position: vec3<f32>) -> vec3<f32> {
+    let noise = noise_2d(position, draw.noise.x);
+    let gradient = clamp(1.0 - abs(position.y - 5.0) / 10.0, 0.0, 1.0);
+    var color = draw.colors[0].rgb * gradient * 0.5
+        + draw.colors[1].rgb * (1.0 - gradient) * 0.5
+        + vec3<f32>(1.0, 0.784, 0.392) * gradient * 0.3
+        + vec3<f32>(0.3 * gradient);
+    if noise > 0.2 {
+        color += vec3<f32>(1.0, 0.627, 0.196) * min(noise - 0.2, 0.5);
+    }
+    return color;
+}
+
+// colors: base, nubes, sombras.
+fn gas(position: vec3<f32>) -> vec3<f32> {
+    let noise = noise_2d(position, draw.noise.x);
+    let clouds = pow(noise * 0.5 + 0.5, 2.0);
+    var color = draw.colors[1].rgb * clouds;
+    if surface_random(position) < 0.5 {
+        color = mix(draw.colors[0].rgb, draw.colors[1].rgb, clouds);
+    }
+    let glow = max(1.0 - clamp(position.y / 10.0, 0.0, 1.0), 0.0);
+    return color + draw.colors[2].rgb * max(1.0 - noise, 0.0) * 0.3 + vec3<f32>(0.784, 0.784, 1.0) * glow * 0.1;
+}
+
+// colors: base, vetas claras, vetas oscuras.
+fn rocky(position: vec3<f32>) -> vec3<f32> {
+    let noise = noise_2d(position, draw.noise.x);
+    let texture = pow(noise * 0.5 + 0.5, 2.0);
+    let line = select(draw.colors[2].rgb, draw.colors[1].rgb, sin((position.x + position.z) * 10.0) > 0.0);
+    return mix(draw.colors[0].rgb, line, texture) + draw.colors[2].rgb * max(1.0 - noise, 0.0) * 0.3;
+}
+
+// colors: fondo, estrellas.
+fn star(position: vec3<f32>) -> vec3<f32> {
+    let stars = pow(noise_2d(position, draw.noise.x) * 0.5 + 0.5, 2.0);
+    if surface_random(position) < stars {
+        return draw.colors[1].rgb * 1.7;
+    }
+    return draw.colors[0].rgb;
+}
+
+// colors: base, acento, nubes.
+fn fantasy(position: vec3<f32>) -> vec3<f32> {
+    let clouds = pow(noise_2d(position, draw.noise.x) * 0.5 + 0.5, 3.0);
+    var color = draw.colors[1].rgb * (1.0 + sin(position.y * 0.5) * 0.5);
+    if position.y > 0.0 {
+        color = mix(draw.colors[0].rgb, draw.colors[2].rgb, clouds);
+    }
+    return color + vec3<f32>(0.1);
+}
+
+// params: ancho de los ríos, velocidad y turbulencia del flujo. colors: corteza, borde, centro.
+fn lava(position: vec3<f32>, lit: f32) -> vec3<f32> {
+    let value = warped_noise(position, draw.noise.x, draw.params.y, draw.params.z);
+    let river = clamp(1.0 - abs(value) / draw.params.x, 0.0, 1.0);
+    let hot = mix(draw.colors[1].rgb, draw.colors[2].rgb, river * river);
+    let crust = draw.colors[0].rgb * (0.2 + 0.8 * lit);
+    return mix(crust, hot, river);
+}
+
+// params: umbral de la espuma, velocidad y turbulencia del flujo. colors: fondo, crestas, espuma.
+fn ocean(position: vec3<f32>, lit: f32) -> vec3<f32> {
+    let value = warped_noise(position, draw.noise.x, draw.params.y, draw.params.z);
+    let water = mix(draw.colors[0].rgb, draw.colors[1].rgb, clamp(value * 0.5 + 0.5, 0.0, 1.0));
+    let foam = clamp((value - draw.params.x) * 4.0, 0.0, 1.0);
+    return mix(water, draw.colors[2].rgb, foam) * lit;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> FragmentOutput {
+    let normal = normalize(input.normal);
+    let diffuse = max(dot(normal, draw.light.xyz), 0.0);
+    let lit = AMBIENT + (1.0 - AMBIENT) * diffuse;
+    let position = input.position;
+    var color: vec3<f32>;
+    switch u32(draw.light.w) {
+        case 1u: { color = normal * 0.5 + 0.5; }
+        case 2u: { color = vec3<f32>(clamp(input.tex_coords, vec2<f32>(0.0), vec2<f32>(1.0)), 0.0); }
+        case 3u: { color = vec3<f32>(diffuse); }
+        case 4u: { color = input.color * lit; }
+        case 5u: { color = earth(position, diffuse, lit); }
+        case 6u: { color = moon(position, lit); }
+        case 7u: { color = sun(position); }
+        case 8u: { color = gas(position); }
+        case 9u: { color = rocky(position); }
+        case 10u: { color = star(position); }
+        case 11u: { color = fantasy(position); }
+        case 12u: { color = lava(position, lit); }
+        case 13u: { color = ocean(position, lit); }
+        default: { color = draw.color.rgb * lit; }
+    }
+    return FragmentOutput(vec4<f32>(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0), bitcast<u32>(input.clip_position.z));
+}
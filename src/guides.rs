@@ -0,0 +1,88 @@
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+/// Cuadrícula de tercios: dos líneas verticales y dos horizontales.
+pub const GUIDE_THIRDS: u32 = 1 << 0;
+/// Cruz en el centro de la imagen.
+pub const GUIDE_CENTER: u32 = 1 << 1;
+/// Márgenes de acción y de títulos (ver `ACTION_SAFE` y `TITLE_SAFE`).
+pub const GUIDE_SAFE_AREAS: u32 = 1 << 2;
+/// Todas las guías.
+pub const GUIDE_ALL: u32 = GUIDE_THIRDS | GUIDE_CENTER | GUIDE_SAFE_AREAS;
+
+/// Fracción de la imagen dentro de la cual debe quedar la acción importante (EBU R 95).
+pub const ACTION_SAFE: f32 = 0.93;
+/// Fracción de la imagen dentro de la cual deben quedar los textos (EBU R 95).
+pub const TITLE_SAFE: f32 = 0.90;
+
+/// Mitad del largo de cada brazo de la cruz central, en píxeles.
+const CENTER_CROSS_SIZE: usize = 12;
+
+/// Dibuja guías de composición sobre la imagen terminada, para encuadrar tomas
+/// antes de exportar una imagen o un video.
+///
+/// Las líneas son semitransparentes y quedan delante de toda la geometría, así
+/// que conviene dibujarlas al final, después de guardar los fotogramas que se
+/// exportan, igual que la capa `ui`.
+///
+/// # Parámetros
+/// - `framebuffer`: Imagen terminada.
+/// - `guides`: Máscara con las guías que se dibujan (`GUIDE_THIRDS`, `GUIDE_CENTER`, `GUIDE_SAFE_AREAS`).
+pub fn draw_guides(framebuffer: &mut Framebuffer, guides: u32) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    if guides & GUIDE_THIRDS != 0 {
+        framebuffer.set_current_color(Color::new(255, 255, 255).to_u32());
+        for third in 1..3 {
+            vertical_line(framebuffer, width * third / 3, 0, height, 0.35, 1);
+            horizontal_line(framebuffer, height * third / 3, 0, width, 0.35, 1);
+        }
+    }
+
+    if guides & GUIDE_SAFE_AREAS != 0 {
+        // acción en verde con trazo continuo y títulos en cian punteado
+        framebuffer.set_current_color(Color::new(80, 230, 120).to_u32());
+        safe_area(framebuffer, ACTION_SAFE, 1);
+        framebuffer.set_current_color(Color::new(80, 210, 255).to_u32());
+        safe_area(framebuffer, TITLE_SAFE, 4);
+    }
+
+    if guides & GUIDE_CENTER != 0 {
+        let (x, y) = (width / 2, height / 2);
+        framebuffer.set_current_color(Color::new(255, 220, 60).to_u32());
+        horizontal_line(framebuffer, y, x.saturating_sub(CENTER_CROSS_SIZE), (x + CENTER_CROSS_SIZE + 1).min(width), 0.8, 1);
+        vertical_line(framebuffer, x, y.saturating_sub(CENTER_CROSS_SIZE), (y + CENTER_CROSS_SIZE + 1).min(height), 0.8, 1);
+    }
+}
+
+/// Dibuja el rectángulo centrado que ocupa la fracción `fraction` de la imagen en cada eje.
+fn safe_area(framebuffer: &mut Framebuffer, fraction: f32, dash: usize) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let margin_x = (width as f32 * (1.0 - fraction) / 2.0).round() as usize;
+    let margin_y = (height as f32 * (1.0 - fraction) / 2.0).round() as usize;
+    let (left, right) = (margin_x, width.saturating_sub(margin_x + 1));
+    let (top, bottom) = (margin_y, height.saturating_sub(margin_y + 1));
+    horizontal_line(framebuffer, top, left, right + 1, 0.6, dash);
+    horizontal_line(framebuffer, bottom, left, right + 1, 0.6, dash);
+    vertical_line(framebuffer, left, top, bottom + 1, 0.6, dash);
+    vertical_line(framebuffer, right, top, bottom + 1, 0.6, dash);
+}
+
+/// Mezcla el color actual sobre la fila `y`, de `start` a `end` sin incluirlo.
+/// Con `dash` mayor que 1 se alternan tramos dibujados y vacíos de ese largo.
+fn horizontal_line(framebuffer: &mut Framebuffer, y: usize, start: usize, end: usize, alpha: f32, dash: usize) {
+    for x in (start..end).filter(|x| dash == 1 || (x / dash).is_multiple_of(2)) {
+        framebuffer.blend_point(x, y, f32::NEG_INFINITY, alpha);
+    }
+}
+
+/// Mezcla el color actual sobre la columna `x`, de `start` a `end` sin incluirlo.
+/// Con `dash` mayor que 1 se alternan tramos dibujados y vacíos de ese largo.
+fn vertical_line(framebuffer: &mut Framebuffer, x: usize, start: usize, end: usize, alpha: f32, dash: usize) {
+    for y in (start..end).filter(|y| dash == 1 || (y / dash).is_multiple_of(2)) {
+        framebuffer.blend_point(x, y, f32::NEG_INFINITY, alpha);
+    }
+}
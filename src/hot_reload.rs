@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Vigila un conjunto de archivos comparando su fecha de modificación.
+///
+/// No usa hilos ni notificaciones del sistema operativo: basta con llamar a
+/// `poll` cada cierto número de fotogramas desde el bucle principal.
+#[derive(Debug, Default)]
+pub struct FileWatcher {
+    /// Última fecha de modificación conocida de cada archivo vigilado.
+    files: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl FileWatcher {
+    /// Crea un vigilante sin archivos.
+    pub fn new() -> Self {
+        FileWatcher { files: HashMap::new() }
+    }
+
+    /// Empieza a vigilar un archivo, tomando su estado actual como referencia.
+    /// Si ya estaba vigilado, actualiza la referencia (útil tras escribirlo uno mismo).
+    pub fn watch<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified_time(&path);
+        self.files.insert(path, modified);
+    }
+
+    /// Deja de vigilar un archivo.
+    pub fn unwatch<P: AsRef<Path>>(&mut self, path: P) {
+        self.files.remove(path.as_ref());
+    }
+
+    /// Revisa los archivos vigilados.
+    ///
+    /// # Retorna
+    /// Las rutas de los archivos que cambiaron desde la última revisión.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, last_modified) in self.files.iter_mut() {
+            let modified = modified_time(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+}
+
+/// Fecha de modificación de un archivo, o `None` si no existe o no se puede leer.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
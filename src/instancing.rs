@@ -0,0 +1,70 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{Mat4, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::bounds::BoundingSphere;
+use crate::pipeline::create_model_matrix;
+
+/// Cinturón de asteroides: una misma malla repetida miles de veces en un anillo
+/// alrededor del centro del nodo, cada copia con su propia posición, tamaño y giro.
+///
+/// Las copias se dibujan con `pipeline::render_instanced`, que comparte el shader de
+/// vértices entre todas, y su distribución sale solo de la semilla del nodo, así que
+/// el cinturón se ve igual en cada fotograma y en cada ejecución.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AsteroidBelt {
+    /// Cantidad de asteroides.
+    pub count: usize,
+    /// Distancia del centro al borde interior del cinturón.
+    pub inner_radius: f32,
+    /// Distancia del centro al borde exterior del cinturón.
+    pub outer_radius: f32,
+    /// Alto del cinturón sobre y bajo el plano XZ; los asteroides se juntan cerca del plano.
+    pub thickness: f32,
+}
+
+impl Default for AsteroidBelt {
+    fn default() -> Self {
+        AsteroidBelt { count: 2000, inner_radius: 6.0, outer_radius: 8.0, thickness: 0.3 }
+    }
+}
+
+impl AsteroidBelt {
+    /// Genera la matriz de modelo de cada asteroide, relativa al centro del cinturón.
+    ///
+    /// Los asteroides se reparten con densidad pareja sobre el anillo, con un tamaño
+    /// entre la mitad y una vez y media `asteroid_radius` y un giro cualquiera.
+    ///
+    /// # Parámetros
+    /// - `asteroid_radius`: Radio medio de cada asteroide (escala de la malla).
+    /// - `seed`: Semilla de la distribución.
+    pub fn instances(&self, asteroid_radius: f32, seed: i32) -> Vec<Mat4> {
+        let mut rng = StdRng::seed_from_u64(seed as u32 as u64);
+        let inner = self.inner_radius.max(0.0);
+        let outer = self.outer_radius.max(inner);
+        (0..self.count)
+            .map(|_| {
+                // El radio al cuadrado uniforme da la misma densidad en todo el anillo.
+                let distance = (inner * inner + (outer * outer - inner * inner) * rng.gen::<f32>()).sqrt();
+                let angle = rng.gen_range(0.0..2.0 * PI);
+                // La suma de dos uniformes amontona los asteroides cerca del plano.
+                let height = (rng.gen::<f32>() + rng.gen::<f32>() - 1.0) * self.thickness;
+                let position = Vec3::new(distance * angle.cos(), height, distance * angle.sin());
+                let scale = asteroid_radius * rng.gen_range(0.5..1.5);
+                let rotation = Vec3::new(rng.gen_range(0.0..2.0 * PI), rng.gen_range(0.0..2.0 * PI), rng.gen_range(0.0..2.0 * PI));
+                create_model_matrix(position, scale, rotation)
+            })
+            .collect()
+    }
+
+    /// Esfera que envuelve todo el cinturón.
+    ///
+    /// # Parámetros
+    /// - `center`: Centro del cinturón en el mundo.
+    /// - `asteroid_radius`: Radio medio de cada asteroide.
+    pub fn bounding_sphere(&self, center: Vec3, asteroid_radius: f32) -> BoundingSphere {
+        let reach = Vec3::new(self.outer_radius.max(self.inner_radius), self.thickness, 0.0).magnitude();
+        // La malla mide 0.5 de radio y los asteroides más grandes, 1.5 veces el medio.
+        BoundingSphere { center, radius: reach + asteroid_radius * 0.75 }
+    }
+}
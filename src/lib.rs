@@ -0,0 +1,68 @@
+//! Renderizador por software de planetas y sistemas estelares.
+//!
+//! El binario (`main.rs`) es el visor interactivo; este crate expone el
+//! pipeline, la escena y las utilidades para usarlos desde otros programas.
+
+pub mod framebuffer;
+pub mod tiles;
+pub mod triangle;
+pub mod vertex;
+pub mod obj;
+pub mod color;
+pub mod fragment;
+pub mod shaders;
+pub mod line;
+pub mod camera;
+pub mod postprocess;
+pub mod scene;
+pub mod generator;
+pub mod galaxy;
+pub mod scene_diff;
+pub mod hot_reload;
+pub mod undo;
+pub mod pipeline;
+pub mod draw_list;
+pub mod small_mesh;
+pub mod primitives;
+pub mod elevation;
+pub mod ply;
+pub mod stl;
+pub mod craters;
+pub mod bounds;
+pub mod shader_params;
+pub mod text;
+pub mod shapes;
+pub mod geometry;
+pub mod events;
+pub mod profiler;
+pub mod audio;
+pub mod timelapse;
+pub mod still;
+pub mod progressive;
+pub mod motion_blur;
+pub mod settings;
+pub mod metrics;
+pub mod crash;
+pub mod export;
+pub mod guides;
+pub mod turntable;
+pub mod shadow;
+pub mod shader_info;
+pub mod pbr;
+pub mod environment;
+pub mod lod;
+pub mod instancing;
+pub mod particles;
+pub mod stars;
+pub mod gizmos;
+pub mod stereo;
+pub mod panorama;
+pub mod bake;
+pub mod present;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
+pub mod contact_sheet;
+
+pub use pipeline::Uniforms;
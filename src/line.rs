@@ -1,57 +1,145 @@
 use crate::fragment::Fragment; // Importa la estructura Fragment del módulo fragment.
+use crate::framebuffer::Framebuffer; // Importa la estructura Framebuffer del módulo framebuffer.
 use crate::vertex::Vertex; // Importa la estructura Vertex del módulo vertex.
-use crate::color::Color; // Importa la estructura Color del módulo color.
 
 /// Dibuja una línea entre dos vértices `a` y `b` utilizando el algoritmo de Bresenham.
-/// 
+///
+/// Equivale a recoger `line_fragments` en un vector.
+///
 /// # Parámetros
-/// 
+///
 /// - `a`: Una referencia al primer vértice (punto de inicio).
 /// - `b`: Una referencia al segundo vértice (punto de fin).
-/// 
+///
 /// # Retorna
-/// 
-/// Una vector de fragmentos que representan los píxeles de la línea dibujada.
+///
+/// Un vector de fragmentos que representan los píxeles de la línea dibujada.
 pub fn line(a: &Vertex, b: &Vertex) -> Vec<Fragment> {
-    let mut fragments = Vec::new(); // Inicializa un vector para almacenar los fragmentos.
+    line_fragments(a, b).collect() // Retorna el vector de fragmentos que representa la línea.
+}
 
-    let start = a.transformed_position; // Obtiene la posición transformada del vértice de inicio.
-    let end = b.transformed_position; // Obtiene la posición transformada del vértice de fin.
+/// Recorre los fragmentos de una línea sin guardarlos en un vector.
+///
+/// # Parámetros
+///
+/// - `a`: Una referencia al primer vértice (punto de inicio).
+/// - `b`: Una referencia al segundo vértice (punto de fin).
+pub fn line_fragments<'a>(a: &'a Vertex, b: &'a Vertex) -> impl Iterator<Item = Fragment> + 'a {
+    LineFragments::new(a, b)
+}
 
-    let mut x0 = start.x as i32; // Coordenada x del punto de inicio como un entero.
-    let mut y0 = start.y as i32; // Coordenada y del punto de inicio como un entero.
-    let x1 = end.x as i32; // Coordenada x del punto de fin como un entero.
-    let y1 = end.y as i32; // Coordenada y del punto de fin como un entero.
+/// Iterador sobre los píxeles de una línea, con el algoritmo de Bresenham.
+///
+/// La profundidad, el color, la normal y la posición del vértice se interpolan
+/// con el parámetro `t` del recorrido (de 0 en `a` a 1 en `b`), que avanza un
+/// paso por píxel a lo largo del eje dominante; así la interpolación funciona
+/// igual para líneas horizontales, verticales y diagonales.
+pub struct LineFragments<'a> {
+    a: &'a Vertex, // Vértice de inicio.
+    b: &'a Vertex, // Vértice de fin.
+    x: i32, // Píxel en curso.
+    y: i32,
+    end: (i32, i32), // Píxel final.
+    dx: i32, // Diferencia absoluta en x.
+    dy: i32, // Diferencia absoluta en y (negativa, como en la forma general del algoritmo).
+    sx: i32, // Dirección del movimiento en x.
+    sy: i32, // Dirección del movimiento en y.
+    err: i32, // Error acumulado respecto a la línea ideal.
+    step: i32, // Píxeles ya recorridos.
+    steps: i32, // Número de píxeles que recorre la línea: el largo del eje dominante.
+    done: bool, // Si ya se entregó el píxel final.
+}
 
-    let dx = (x1 - x0).abs(); // Diferencia absoluta en x.
-    let dy = (y1 - y0).abs(); // Diferencia absoluta en y.
+impl<'a> LineFragments<'a> {
+    /// Prepara el recorrido de la línea de `a` a `b`.
+    pub fn new(a: &'a Vertex, b: &'a Vertex) -> Self {
+        let start = a.transformed_position; // Posición transformada del vértice de inicio.
+        let end = b.transformed_position; // Posición transformada del vértice de fin.
 
-    let sx = if x0 < x1 { 1 } else { -1 }; // Dirección del movimiento en x.
-    let sy = if y0 < y1 { 1 } else { -1 }; // Dirección del movimiento en y.
+        let (x0, y0) = (start.x as i32, start.y as i32); // Punto de inicio como enteros.
+        let (x1, y1) = (end.x as i32, end.y as i32); // Punto de fin como enteros.
 
-    // Inicializa el error en función de la diferencia en x o y.
-    let mut err = if dx > dy { dx / 2 } else { -dy / 2 };
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
 
-    loop {
-        // Calcula la coordenada z interpolada entre los vértices.
-        let z = start.z + (end.z - start.z) * (x0 - start.x as i32) as f32 / (end.x - start.x) as f32;
-        
-        // Crea un nuevo fragmento con la posición actual y el color blanco, y lo añade al vector.
-        fragments.push(Fragment::new(x0 as f32, y0 as f32, Color::new(255, 255, 255), z));
+        LineFragments {
+            a,
+            b,
+            x: x0,
+            y: y0,
+            end: (x1, y1),
+            dx,
+            dy,
+            sx: if x0 < x1 { 1 } else { -1 },
+            sy: if y0 < y1 { 1 } else { -1 },
+            err: dx + dy,
+            step: 0,
+            steps: dx.max(-dy),
+            done: false,
+        }
+    }
+}
 
-        // Si hemos alcanzado el punto final, salimos del bucle.
-        if x0 == x1 && y0 == y1 { break; }
+impl Iterator for LineFragments<'_> {
+    type Item = Fragment;
 
-        let e2 = err; // Almacena el error actual para usarlo en las siguientes decisiones.
-        if e2 > -dx {
-            err -= dy; // Ajusta el error.
-            x0 += sx; // Avanza en la dirección de x.
+    fn next(&mut self) -> Option<Fragment> {
+        if self.done {
+            return None;
         }
-        if e2 < dy {
-            err += dx; // Ajusta el error.
-            y0 += sy; // Avanza en la dirección de y.
+        let (a, b) = (self.a, self.b);
+        let (start, end) = (a.transformed_position, b.transformed_position);
+
+        // Parámetro de interpolación a lo largo de la línea.
+        let t = if self.steps == 0 { 0.0 } else { self.step as f32 / self.steps as f32 };
+
+        let depth = start.z + (end.z - start.z) * t;
+        let color = a.color.lerp(&b.color, t);
+        let normal = a.transformed_normal + (b.transformed_normal - a.transformed_normal) * t;
+        let vertex_position = a.position + (b.position - a.position) * t;
+        let fragment = Fragment::new(self.x as f32, self.y as f32, color, depth, normal, 1.0, vertex_position);
+
+        // Si hemos alcanzado el punto final, este es el último fragmento.
+        if (self.x, self.y) == self.end {
+            self.done = true;
+            return Some(fragment);
+        }
+
+        let e2 = 2 * self.err;
+        if e2 >= self.dy {
+            self.err += self.dy; // Ajusta el error.
+            self.x += self.sx; // Avanza en la dirección de x.
         }
+        if e2 <= self.dx {
+            self.err += self.dx; // Ajusta el error.
+            self.y += self.sy; // Avanza en la dirección de y.
+        }
+        self.step += 1;
+        Some(fragment)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Bresenham da exactamente un píxel por paso del eje dominante.
+        let remaining = if self.done { 0 } else { (self.steps - self.step + 1) as usize };
+        (remaining, Some(remaining))
     }
+}
 
-    fragments // Retorna el vector de fragmentos que representa la línea.
+/// Rasteriza una línea y la escribe en el framebuffer con prueba de profundidad,
+/// usando el color interpolado de cada fragmento.
+///
+/// # Parámetros
+///
+/// - `framebuffer`: El framebuffer donde se dibuja.
+/// - `a`: El vértice de inicio, con su posición ya transformada a pantalla.
+/// - `b`: El vértice de fin, con su posición ya transformada a pantalla.
+pub fn draw_line(framebuffer: &mut Framebuffer, a: &Vertex, b: &Vertex) {
+    for fragment in line_fragments(a, b) {
+        // Los fragmentos fuera de la pantalla (incluidas coordenadas negativas) se descartan.
+        if fragment.position.x < 0.0 || fragment.position.y < 0.0 {
+            continue;
+        }
+        framebuffer.set_current_color(fragment.color.to_u32());
+        framebuffer.point(fragment.position.x as usize, fragment.position.y as usize, fragment.depth);
+    }
 }
@@ -0,0 +1,109 @@
+use nalgebra_glm::{Mat4, Vec4};
+use crate::bounds::BoundingSphere;
+use crate::obj::Model;
+use crate::primitives;
+
+/// Radio en pantalla (en píxeles) desde el que se usa la malla más detallada de
+/// una primitiva con `lod_bias` 0; cada nivel menos detallado sirve hasta la mitad.
+/// Con este valor los lados de los triángulos quedan por debajo de unos 8 píxeles.
+pub const FULL_DETAIL_RADIUS: f32 = 40.0;
+
+/// Niveles que se generan para cada primitiva.
+const PRIMITIVE_LEVELS: i32 = 3;
+
+/// Una malla con varios niveles de detalle, de la más detallada a la más simple;
+/// cada fotograma se dibuja el nivel que corresponde al tamaño del cuerpo en
+/// pantalla (`select`), así los planetas lejanos cuestan muchos menos triángulos.
+///
+/// Las mallas leídas de un archivo tienen un solo nivel.
+#[derive(Debug, Clone)]
+pub struct LodMesh {
+    /// Cada nivel con el radio en pantalla, en píxeles, desde el que se usa.
+    levels: Vec<(Model, f32)>,
+}
+
+impl LodMesh {
+    /// Crea una malla de un solo nivel, que se usa a cualquier tamaño.
+    pub fn single(model: Model) -> Self {
+        LodMesh { levels: vec![(model, 0.0)] }
+    }
+
+    /// Crea una malla a partir de sus niveles.
+    ///
+    /// # Parámetros
+    /// - `levels`: Cada malla con el radio en pantalla (en píxeles) desde el que se
+    ///   usa; se ordenan de mayor a menor radio y el último nivel se usa a cualquier tamaño.
+    ///
+    /// # Retorna
+    /// `None` si no hay niveles.
+    pub fn new(mut levels: Vec<(Model, f32)>) -> Option<Self> {
+        levels.sort_by(|a, b| b.1.total_cmp(&a.1));
+        levels.last_mut()?.1 = 0.0;
+        Some(LodMesh { levels })
+    }
+
+    /// Genera los niveles de una primitiva (ver `primitives::from_name_with_lod`):
+    /// el detalle normal y dos niveles con la mitad y la cuarta parte de las divisiones.
+    ///
+    /// # Parámetros
+    /// - `name`: Nombre de la malla en la escena.
+    /// - `lod_bias`: Detalle del nivel más fino; también duplica o divide a la mitad
+    ///   el radio en pantalla desde el que se usa cada nivel.
+    ///
+    /// # Retorna
+    /// La malla, o `None` si el nombre no es una primitiva conocida.
+    pub fn for_primitive(name: &str, lod_bias: i32) -> Option<Self> {
+        let levels = (0..PRIMITIVE_LEVELS)
+            .map(|level| {
+                let model = primitives::from_name_with_lod(name, lod_bias - level)?;
+                Some((model, FULL_DETAIL_RADIUS * 2.0_f32.powi(lod_bias - level)))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        LodMesh::new(levels)
+    }
+
+    /// Malla más detallada, la que da las dimensiones del cuerpo.
+    pub fn finest(&self) -> &Model {
+        &self.levels[0].0
+    }
+
+    /// Cantidad de niveles.
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Índice del nivel que corresponde a un radio en pantalla (0 es el más detallado).
+    pub fn level_for(&self, radius_px: f32) -> usize {
+        self.levels.iter()
+            .position(|&(_, min_radius)| radius_px >= min_radius)
+            .unwrap_or(self.levels.len() - 1)
+    }
+
+    /// Devuelve la malla que corresponde a un radio en pantalla.
+    ///
+    /// # Parámetros
+    /// - `radius_px`: Radio del cuerpo en pantalla, en píxeles (ver `projected_radius`).
+    pub fn select(&self, radius_px: f32) -> &Model {
+        &self.levels[self.level_for(radius_px)].0
+    }
+}
+
+/// Calcula el radio aproximado en píxeles de una esfera vista por la cámara.
+///
+/// # Parámetros
+/// - `sphere`: Esfera envolvente del cuerpo en el mundo.
+/// - `view_projection`: Proyección por vista de la cámara (en perspectiva u ortográfica).
+/// - `viewport_height`: Alto de la imagen en píxeles.
+///
+/// # Retorna
+/// El radio en píxeles, o infinito si la cámara está dentro de la esfera o muy cerca.
+pub fn projected_radius(sphere: &BoundingSphere, view_projection: &Mat4, viewport_height: f32) -> f32 {
+    let center = sphere.center;
+    let clip = view_projection * Vec4::new(center.x, center.y, center.z, 1.0);
+    // En perspectiva `w` es la distancia a lo largo de la vista; en ortográfica vale 1.
+    if clip.w <= sphere.radius.max(1e-6) && view_projection[(3, 2)] != 0.0 {
+        return f32::INFINITY;
+    }
+    let scale = view_projection.fixed_view::<3, 3>(0, 0).row(1).norm();
+    sphere.radius * scale / clip.w * viewport_height / 2.0
+}
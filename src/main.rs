@@ -1,238 +1,2042 @@
-use nalgebra_glm::{Vec3, Mat4, look_at, perspective};
-use minifb::{Key, Window, WindowOptions};
-use std::time::Duration;
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+use std::time::{Duration, Instant};
 use std::f32::consts::PI;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::cell::RefCell;
 
-mod framebuffer;
-mod triangle;
-mod vertex;
-mod obj;
-mod color;
-mod fragment;
-mod shaders;
-mod camera;
-
-use framebuffer::Framebuffer;
-use vertex::Vertex;
-use obj::Obj;
-use camera::Camera;
-use triangle::triangle;
-use shaders::{vertex_shader, fragment_shader};
-use fastnoise_lite::{FastNoiseLite, NoiseType, FractalType};
-
-pub struct Uniforms {
-    model_matrix: Mat4,
-    view_matrix: Mat4,
-    projection_matrix: Mat4,
-    viewport_matrix: Mat4,
+use sr_02_line::framebuffer::{Framebuffer, Viewport};
+use sr_02_line::tiles;
+use sr_02_line::obj::{Obj, Model, Center, NormalizeOptions};
+use sr_02_line::primitives::{self, PRIMITIVE_PREFIX};
+use sr_02_line::ply;
+use sr_02_line::stl;
+use sr_02_line::craters;
+use sr_02_line::elevation::{Heightmap, MESH_RADIUS};
+use sr_02_line::camera::{Camera, CameraMode, CameraPath, MouseControls, Projection};
+use sr_02_line::postprocess::{PostProcess, ColorVisionFilter, FlareSource};
+use sr_02_line::shaders::{PlanetShader, FEATURE_CLOUDS, FEATURE_CITY_LIGHTS};
+use sr_02_line::scene::{Scene, SceneNode, LAYER_BODIES, LAYER_ORBITS, LAYER_DEBUG, LAYER_UI, layers_to_string};
+use sr_02_line::generator::generate_moons;
+use sr_02_line::galaxy::Galaxy;
+use sr_02_line::hot_reload::FileWatcher;
+use sr_02_line::shader_params::{ShaderParams, SHADER_PARAMS_PATH};
+use sr_02_line::undo::UndoStack;
+use sr_02_line::scene_diff;
+use sr_02_line::gizmos::{GizmoStyle, draw_line_3d, draw_circle_3d, draw_axis, draw_grid};
+use sr_02_line::text::{draw_text, GLYPH_HEIGHT};
+use sr_02_line::guides::{draw_guides, GUIDE_THIRDS, GUIDE_CENTER, GUIDE_SAFE_AREAS, GUIDE_ALL};
+use sr_02_line::geometry::plane::Frustum;
+use sr_02_line::events::{self, Event, EventBus, ContactTracker};
+use sr_02_line::profiler::{Profiler, Stage};
+use sr_02_line::metrics::MetricsLog;
+use sr_02_line::crash::{self, CRASH_DIR};
+use sr_02_line::audio::{CueSheet, CUE_SHEET_PATH};
+use sr_02_line::timelapse::{self, TimeLapse, TIMELAPSE_DIR};
+use sr_02_line::still;
+use sr_02_line::export::{ExportFormat, Recorder, RECORDING_PATH};
+use sr_02_line::turntable::{Turntable, TURNTABLE_PATH};
+use sr_02_line::contact_sheet::{shader_swatches, ContactSheet, CONTACT_SHEET_PATH};
+use sr_02_line::shadow::ShadowMap;
+use sr_02_line::shader_info::{list_shaders, ShaderRegistry};
+use sr_02_line::environment::{EnvironmentMap, ENVIRONMENT_PATH};
+use sr_02_line::bounds::BoundingSphere;
+use sr_02_line::particles::draw_particles;
+use sr_02_line::stars::{StarCatalog, STAR_CATALOG_PATH};
+use sr_02_line::stereo::{self, Stereo};
+use sr_02_line::panorama::{self, CubeFace};
+use sr_02_line::bake::{Baker, BAKE_DIR, CUBE_FACE_NAMES};
+use sr_02_line::present::{FilePresenter, InputEvent, Presenter, WindowPresenter};
+use sr_02_line::lod::{projected_radius, LodMesh};
+use sr_02_line::progressive::{self, Progressive};
+use sr_02_line::motion_blur::MotionBlur;
+use sr_02_line::settings::{QualityPreset, RenderSettings};
+use sr_02_line::color::Color;
+use sr_02_line::draw_list::{DrawList, DrawOrder};
+use sr_02_line::pipeline::{Uniforms, Matrices, Lighting, PipelineHooks, create_model_matrix};
+use sr_02_line::small_mesh::{billboard, render_small_instances, Quad};
+
+fn main() {
+    // a panic leaves the last finished frame, the camera and the scene in assets/crash
+    crash::install_panic_hook(CRASH_DIR);
+
+    let mut window_width = 800;
+    let mut window_height = 600;
+    let frame_delay = Duration::from_millis(16);
+
+    // quality preset from --quality low|medium|high|ultra, cycled at runtime with F10;
+    // below full resolution the window stretches the image
+    let args = parse_args();
+    if args.list_shaders {
+        print_shader_list();
+        return;
+    }
+    // --contact-sheet, --bake, --turntable and --out write to disk without opening a window
+    if args.contact_sheet {
+        run_contact_sheet(&args, args.out.as_deref().unwrap_or(CONTACT_SHEET_PATH));
+        return;
+    }
+    if args.bake {
+        run_bake(&args, args.out.as_deref().unwrap_or(BAKE_DIR));
+        return;
+    }
+    if let Some(mesh) = &args.turntable {
+        run_turntable(&args, mesh, args.out.as_deref().unwrap_or(TURNTABLE_PATH));
+        return;
+    }
+    if let Some(out) = &args.out {
+        run_headless(&args, out);
+        return;
+    }
+    let mut scene_path = args.scene_path.clone();
+    let mut quality = args.quality;
+    let mut settings = quality.settings();
+    let (framebuffer_width, framebuffer_height) = settings.output_size(window_width, window_height);
+    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    let mut supersampled = supersample_target(&settings, window_width, window_height);
+    let mut window = WindowPresenter::new(Window::new(
+        "LAB GRAFICAS",
+        window_width,
+        window_height,
+        WindowOptions { resize: true, ..WindowOptions::default() },
+    )
+    .unwrap());
+
+    window.set_position(500, 500);
+    window.update();
+
+    framebuffer.set_background_color(0x333355);
+
+
+    // camera parameters
+    let mut camera = Camera::new(
+        Vec3::new(0.0, 0.0, 5.0),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0)
+    );
+    camera.set_aspect(window_width as f32 / window_height as f32);
+    camera.projection = args.projection;
+
+    let mut post_process = PostProcess::default();
+    settings.apply_post_process(&mut post_process);
+
+    let mut scene = load_initial_scene(scene_path.as_deref(), args.shader);
+    // the crash dump keeps its own copy of the scene, refreshed only when it changes
+    crash::record_scene(&scene);
+
+    // the scene file and every loaded mesh are reloaded when they change on disk
+    let mut watcher = FileWatcher::new();
+    if let Some(path) = &scene_path {
+        watcher.watch(path);
+    }
+
+    let mut meshes: HashMap<String, LodMesh> = HashMap::new();
+    load_scene_meshes(&scene, &mut meshes, &mut watcher, settings.lod_bias);
+
+    // shader parameters (thresholds, palettes, noise scales) tuned live from a config file
+    let mut shader_params = load_shader_params().unwrap_or_default();
+    watcher.watch(SHADER_PARAMS_PATH);
+
+    // background stars, also reflected by water and other smooth surfaces
+    let stars = load_star_catalog();
+    let environment = load_environment(&stars);
+
+    // heightmaps for ElevationMode::Texture, loaded the first time a node uses them
+    let mut heightmaps: HashMap<String, Option<Arc<Heightmap>>> = HashMap::new();
+
+    // procedural galaxy, explored with [ and ]
+    let galaxy = Galaxy::generate(2024, 64);
+    let mut current_system: Option<usize> = None;
+
+    // recorded camera flyby: K adds a waypoint, P plays it, Backspace clears it
+    let mut camera_path = CameraPath::new();
+    let mut path_playback: Option<f32> = None;
+    let waypoint_interval = 120.0;
+
+    // interactive scene edits (Tab selects, +/- resize, Insert adds a moon,
+    // Delete removes) can be undone with Ctrl+Z and redone with Ctrl+Y
+    let mut selected = 0;
+    let mut undo_stack = UndoStack::new(100);
+    // a left click also selects, and the camera glides over to the body
+    let mut mouse = MouseState::default();
+    // dragging and the wheel move the camera with some inertia; --mouse-sensitivity
+    // and --mouse-damping tune them
+    let mut mouse_controls = MouseControls::default();
+    mouse_controls.scale_sensitivity(args.mouse_sensitivity);
+    if let Some(damping) = args.mouse_damping {
+        mouse_controls.damping = damping;
+    }
+
+    // G freezes the culling camera where it is; the live camera keeps flying as an
+    // observer so the culled result of the frozen view can be inspected from outside
+    let mut frozen_camera: Option<Camera> = None;
+
+    // composition guides drawn over the finished frame, cycled with J
+    let mut guides = 0;
+
+    // I shows what the selected body's shader draws and its parameters in the HUD
+    let shader_registry = ShaderRegistry::new();
+    let mut show_shader_info = false;
+
+    // no custom stages in the viewer itself; see pipeline::PipelineHooks
+    let mut hooks = PipelineHooks::new();
+
+    // frames drawn, frames of shader animation and simulation time, which drives the
+    // orbits; the first two only drift apart while refining a progressive preview and
+    // the last one while exporting a time-lapse
+    let mut time = 0;
+    let mut animation_time = 0;
+    let mut sim_time = 0.0;
+    // F8 exports one full orbit of the selected body as numbered frames
+    let mut time_lapse: Option<TimeLapse> = None;
+    // F12 starts and stops recording what is on screen to an animated GIF
+    let mut recorder: Option<Recorder> = None;
+
+    // scene and renderer notifications; the HUD shows the latest one for a couple of seconds
+    let mut event_bus = EventBus::new();
+    let mut contacts = ContactTracker::new();
+    let notification: Rc<RefCell<Option<(String, u32)>>> = Rc::new(RefCell::new(None));
+    {
+        let notification = Rc::clone(&notification);
+        let mut frame = 0;
+        event_bus.subscribe(move |event| match event {
+            Event::FrameCompleted { frame: completed } => frame = *completed,
+            _ => *notification.borrow_mut() = Some((event.to_string(), frame)),
+        });
+    }
+
+    // sound cues for every event, so recorded videos can be scored later; F7 writes them out
+    let cue_sheet = Rc::new(RefCell::new(CueSheet::new()));
+    {
+        let cue_sheet = Rc::clone(&cue_sheet);
+        event_bus.subscribe(move |event| {
+            cue_sheet.borrow_mut().observe(event);
+        });
+    }
+    // with the `audio` feature the cues are also played, over an ambient loop
+    #[cfg(feature = "audio")]
+    match sr_02_line::audio::Player::new() {
+        Some(player) => {
+            event_bus.subscribe(move |event| {
+                if let Some((cue, _)) = sr_02_line::audio::SoundCue::for_event(event) {
+                    player.play(cue);
+                }
+            });
+        }
+        None => eprintln!("No hay dispositivo de audio; las señales solo se registran"),
+    }
+
+    // R toggles a progressive preview for heavy settings: the simulation pauses and the
+    // image goes from a quarter of the resolution to full, then averages jittered samples
+    let mut progressive: Option<Progressive> = None;
+
+    // F11 splits the window: the right half shows the selected body with another shader;
+    // each press moves to the next shader and after the last one the split closes
+    let mut compare_shader: Option<PlanetShader> = None;
+    let mut draw_order = DrawOrder::default();
+    // ; switches the red-cyan anaglyph on and off
+    let mut stereo = args.stereo;
+    // ' switches the motion blur trail on and off
+    let mut motion_blur = args.motion_blur.clone().unwrap_or_default();
+    motion_blur.set_enabled(args.motion_blur.is_some());
+    // , switches the bodies to the wgpu preview (`gpu` feature) and back
+    let mut gpu: Option<GpuBackend> = None;
+
+    // per-stage timings averaged over the last second, shown in the HUD; F6 prints them
+    let mut profiler = Profiler::new(60);
+    // --metrics appends each frame's timings, triangle and fragment counts and memory as a JSON line
+    let mut metrics = args.metrics_path.and_then(|path| match MetricsLog::append(&path) {
+        Ok(log) => Some(log),
+        Err(error) => {
+            eprintln!("No se pudo abrir el registro de métricas {}: {}", path, error);
+            None
+        }
+    });
+
+    while window.is_open() {
+        if window.is_key_down(Key::Escape) {
+            break;
+        }
+
+        time += 1;
+        if progressive.is_none() {
+            animation_time += 1;
+            sim_time += time_lapse.as_ref().map_or(1.0, |time_lapse| time_lapse.step);
+        }
+
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            progressive = match progressive {
+                Some(_) => None,
+                None => Some(Progressive::new(framebuffer.width, framebuffer.height, 16)),
+            };
+            println!("Vista previa progresiva {}", if progressive.is_some() { "activada" } else { "desactivada" });
+        }
+
+        if window.is_key_pressed(Key::F6, KeyRepeat::No) {
+            println!("{}", profiler.report());
+        }
+        if window.is_key_pressed(Key::F10, KeyRepeat::No) {
+            quality = quality.next();
+            settings = quality.settings();
+            resize_targets(&mut framebuffer, &mut supersampled, &mut progressive, &settings, (window_width, window_height));
+            settings.apply_post_process(&mut post_process);
+            // generated meshes are rebuilt with the preset's level of detail
+            meshes.retain(|name, _| !name.starts_with(PRIMITIVE_PREFIX));
+            load_scene_meshes(&scene, &mut meshes, &mut watcher, settings.lod_bias);
+            println!("Calidad {}: {}x{}, suavizado {}", quality.name(), framebuffer.width, framebuffer.height, settings.antialiasing.name());
+        }
+        // a resized window gets an image drawn at its new size and aspect instead of a stretched one;
+        // keys and the mouse are still read from the window directly below
+        for event in window.poll_input() {
+            if let InputEvent::Resized { width, height } = event {
+                window_width = width;
+                window_height = height;
+                resize_targets(&mut framebuffer, &mut supersampled, &mut progressive, &settings, (window_width, window_height));
+                camera.set_aspect(framebuffer.viewport().aspect());
+            }
+        }
+        if window.is_key_pressed(Key::F8, KeyRepeat::No) {
+            time_lapse = match time_lapse.take() {
+                Some(stopped) => {
+                    println!("Time-lapse detenido: {} fotogramas en {}", stopped.exported(), TIMELAPSE_DIR);
+                    None
+                }
+                None => start_time_lapse(&scene, selected),
+            };
+        }
+        if window.is_key_pressed(Key::F12, KeyRepeat::No) {
+            recorder = match recorder.take() {
+                Some(stopped) => {
+                    finish_recording(stopped);
+                    None
+                }
+                None => match Recorder::create(RECORDING_PATH, ExportFormat::Gif, framebuffer.width, framebuffer.height, 60.0) {
+                    Ok(started) => {
+                        println!("Grabando en {}; F12 para terminar", RECORDING_PATH);
+                        Some(started)
+                    }
+                    Err(error) => {
+                        eprintln!("No se pudo crear {}: {}", RECORDING_PATH, error);
+                        None
+                    }
+                },
+            };
+        }
+        if window.is_key_pressed(Key::F11, KeyRepeat::No) {
+            compare_shader = match compare_shader {
+                None => Some(PlanetShader::ALL[0]),
+                Some(shader) => PlanetShader::ALL.iter()
+                    .position(|&other| other == shader)
+                    .and_then(|index| PlanetShader::ALL.get(index + 1).copied()),
+            };
+            match compare_shader {
+                Some(shader) => println!("Comparando con el shader {}", shader.name()),
+                None => println!("Comparación cerrada"),
+            }
+            if let Some(progressive) = &mut progressive {
+                progressive.restart();
+            }
+        }
+        // F9 renders the current view as a poster-sized still, tile by tile
+        if window.is_key_pressed(Key::F9, KeyRepeat::No) {
+            let view = SceneView { scene: &scene, meshes: &meshes, shader_params: &shader_params, camera: &camera, sim_time, time: animation_time, spin: 0.0, light: None, shadow_map_size: settings.shadow_map_size, environment: &environment, stars: Some(&stars), stereo, draw_order, threads: args.threads, gpu: None };
+            render_still(&view, &mut heightmaps, &mut hooks);
+        }
+        if window.is_key_pressed(Key::F7, KeyRepeat::No) {
+            let cue_sheet = cue_sheet.borrow();
+            match cue_sheet.write(CUE_SHEET_PATH, 60.0) {
+                Ok(()) => println!("{} señales guardadas en {}", cue_sheet.records().len(), CUE_SHEET_PATH),
+                Err(error) => eprintln!("No se pudo guardar {}: {}", CUE_SHEET_PATH, error),
+            }
+        }
+
+        if window.is_key_pressed(Key::K, KeyRepeat::No) {
+            let waypoint_time = if camera_path.waypoints().is_empty() {
+                0.0
+            } else {
+                camera_path.duration() + waypoint_interval
+            };
+            camera_path.record(&camera, waypoint_time);
+            println!("Punto de paso {} grabado", camera_path.waypoints().len());
+        }
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            path_playback = match path_playback {
+                None if camera_path.waypoints().len() >= 2 => Some(0.0),
+                _ => None,
+            };
+        }
+        if window.is_key_pressed(Key::Backspace, KeyRepeat::No) {
+            camera_path.clear();
+            path_playback = None;
+        }
+
+        match path_playback {
+            Some(path_time) => {
+                camera.follow_path(&camera_path, path_time);
+                path_playback = Some(path_time + 1.0).filter(|&t| t <= camera_path.duration());
+                mouse_controls.stop();
+            }
+            None => {
+                handle_input(&window, &mut camera);
+                if let Some((x, y)) = handle_mouse_input(&window, &mut mouse, &mut mouse_controls, &mut camera) {
+                    let panes = if compare_shader.is_some() { 2.0 } else { 1.0 };
+                    match pick_body(&scene, sim_time, &camera, (x, y), (window_width as f32 / panes, window_height as f32)) {
+                        Some(index) => {
+                            selected = index;
+                            camera.focus_on(index);
+                            println!("Seleccionado: {}", scene.nodes[index].name);
+                        }
+                        // a click on empty space lets go of the body
+                        None => camera.release_focus(),
+                    }
+                }
+            }
+        }
+        match camera.focus {
+            Some(index) if path_playback.is_none() && index < scene.nodes.len() => {
+                camera.track_focus(scene.world_position(index, sim_time));
+            }
+            Some(_) => camera.release_focus(),
+            None => {}
+        }
+        // the eye stops just above the surface of every solid body; \ lets it pass through
+        if window.is_key_pressed(Key::Backslash, KeyRepeat::No) {
+            camera.collision = !camera.collision;
+            println!("Colisión de la cámara {}", if camera.collision { "activada" } else { "desactivada" });
+        }
+        if camera.collision && path_playback.is_none() {
+            for (_, sphere) in scene.solid_spheres(sim_time, camera.layer_mask) {
+                camera.collide(&sphere);
+            }
+        }
+        // anything that changes the image restarts a progressive preview
+        let mut image_changed = handle_post_process_input(&window, &mut post_process);
+        handle_layer_input(&window, &mut camera);
+        if window.is_key_pressed(Key::J, KeyRepeat::No) {
+            guides = match guides {
+                0 => GUIDE_THIRDS,
+                GUIDE_THIRDS => GUIDE_CENTER,
+                GUIDE_CENTER => GUIDE_SAFE_AREAS,
+                GUIDE_SAFE_AREAS => GUIDE_ALL,
+                _ => 0,
+            };
+        }
+        if window.is_key_pressed(Key::I, KeyRepeat::No) {
+            show_shader_info = !show_shader_info;
+        }
+        if window.is_key_pressed(Key::Semicolon, KeyRepeat::No) {
+            stereo = match stereo {
+                Some(_) => None,
+                None => Some(args.stereo.unwrap_or_default()),
+            };
+            println!("Anaglifo {}", if stereo.is_some() { "activado" } else { "desactivado" });
+            image_changed = true;
+        }
+        if window.is_key_pressed(Key::Apostrophe, KeyRepeat::No) {
+            motion_blur.set_enabled(!motion_blur.is_enabled());
+            println!("Desenfoque de movimiento {}", if motion_blur.is_enabled() { "activado" } else { "desactivado" });
+        }
+        if window.is_key_pressed(Key::Comma, KeyRepeat::No) {
+            toggle_gpu_preview(&mut gpu);
+            image_changed = true;
+        }
+        // M cycles the order of the opaque bodies: by state, front to back, unsorted
+        if window.is_key_pressed(Key::M, KeyRepeat::No) {
+            draw_order = draw_order.next();
+            println!("Orden de dibujo: {}", draw_order.name());
+        }
+
+        if window.is_key_pressed(Key::G, KeyRepeat::No) {
+            frozen_camera = match frozen_camera {
+                Some(_) => None,
+                None => {
+                    // the frozen frustum lives on the debug layer
+                    camera.layer_mask |= LAYER_DEBUG;
+                    Some(camera.clone())
+                }
+            };
+            println!("Cámara de recorte {}", if frozen_camera.is_some() { "congelada" } else { "liberada" });
+        }
+
+        if let Some(index) = handle_galaxy_input(&window, current_system, galaxy.systems.len()) {
+            let system = &galaxy.systems[index];
+            println!("Galaxia {} - sistema {}/{}: {} (clase {:?}, {} planetas)",
+                galaxy.seed, index + 1, galaxy.systems.len(), system.name, system.star_class, system.planets.len());
+
+            scene = system.build_scene();
+            load_scene_meshes(&scene, &mut meshes, &mut watcher, settings.lod_bias);
+            crash::record_scene(&scene);
+            current_system = Some(index);
+            selected = 0;
+            undo_stack.clear();
+            camera.release_focus();
+            // a cut to another system shouldn't leave a trail of the previous one
+            motion_blur.reset();
+            if let Some(path) = scene_path.take() {
+                watcher.unwatch(path);
+            }
+
+            // frame the whole system and stretch the fog to its size
+            let extent = system.extent();
+            camera.eye = Vec3::new(0.0, extent * 0.6, extent * 1.6);
+            camera.center = Vec3::new(0.0, 0.0, 0.0);
+            camera.up = Vec3::new(0.0, 1.0, 0.0);
+            camera.has_changed = true;
+            post_process.fog_start = extent * 1.5;
+            post_process.fog_end = extent * 6.0;
+        }
+
+        if handle_edit_input(&window, &mut scene, &mut selected, &mut undo_stack) {
+            load_scene_meshes(&scene, &mut meshes, &mut watcher, settings.lod_bias);
+            event_bus.emit_all(events::diff_node_changes(undo_stack.last_change()));
+            crash::record_scene(&scene);
+            image_changed = true;
+        }
+
+        // F5 exports the current scene so it can be edited and reloaded
+        if window.is_key_pressed(Key::F5, KeyRepeat::No) {
+            if let Some(path) = save_scene(&scene) {
+                watcher.watch(&path);
+                scene_path = Some(path);
+            }
+        }
+
+        if time % 30 == 0 {
+            for changed in watcher.poll() {
+                let changed = changed.to_string_lossy().into_owned();
+                if scene_path.as_deref() == Some(changed.as_str()) {
+                    let before_reload = scene.clone();
+                    if reload_scene(&mut scene, &changed) {
+                        load_scene_meshes(&scene, &mut meshes, &mut watcher, settings.lod_bias);
+                        event_bus.emit_all(events::node_changes(&before_reload, &scene));
+                        crash::record_scene(&scene);
+                        event_bus.emit(Event::AssetReloaded { path: changed });
+                        image_changed = true;
+                    }
+                } else if changed == SHADER_PARAMS_PATH {
+                    // a broken file keeps the previous values until it is fixed
+                    if let Some(params) = load_shader_params() {
+                        shader_params = params;
+                        println!("Parámetros de shaders recargados: {}", changed);
+                        event_bus.emit(Event::AssetReloaded { path: changed });
+                        image_changed = true;
+                    }
+                } else if reload_mesh(&changed, &mut meshes) {
+                    #[cfg(feature = "gpu")]
+                    if let Some(gpu) = &gpu {
+                        gpu.borrow_mut().forget_mesh(&changed);
+                    }
+                    event_bus.emit(Event::AssetReloaded { path: changed });
+                    image_changed = true;
+                }
+            }
+        }
+
+        event_bus.emit_all(contacts.update(&scene, sim_time));
+        event_bus.emit_all(contacts.update_camera(&scene, sim_time, camera.eye));
+
+        framebuffer.clear();
+        // colors are encoded as they are written, so the output encoding is set on
+        // every framebuffer that is drawn into or blended
+        framebuffer.set_pixel_format(post_process.pixel_format());
+
+        if let Some(progressive) = &mut progressive {
+            if camera.check_if_changed() || image_changed {
+                progressive.restart();
+            }
+        }
+
+        let jitter = progressive.as_ref().map_or((0.0, 0.0), |progressive| progressive.jitter());
+
+        framebuffer.set_background_color(0x000000);
+
+        // a progressive pass is drawn into its own, possibly smaller, framebuffer;
+        // once the preview has converged its image is simply shown again
+        let mut present_start = Instant::now();
+        let target = match &mut progressive {
+            Some(progressive) if progressive.is_converged() => None,
+            Some(progressive) => Some(progressive.begin_pass()),
+            None => Some(supersampled.as_mut().unwrap_or(&mut framebuffer)),
+        };
+        if let Some(target) = target {
+            target.set_pixel_format(post_process.pixel_format());
+            let full = Viewport::full(target.width, target.height);
+            let compared_scene;
+            let panes = match compare_shader {
+                Some(shader) if selected < scene.nodes.len() => {
+                    let mut compared = scene.clone();
+                    compared.nodes[selected].shader = shader;
+                    compared_scene = compared;
+                    full.columns(2).into_iter().zip([&scene, &compared_scene]).collect()
+                }
+                _ => vec![(full, &scene)],
+            };
+            for (viewport, pane_scene) in panes {
+                // each pane only writes its own pixels and has its own aspect ratio
+                target.set_viewport(viewport);
+                target.set_scissor(Some(viewport));
+                let mut pane_camera = camera.clone();
+                pane_camera.set_aspect(viewport.aspect());
+                let view = SceneView { scene: pane_scene, meshes: &meshes, shader_params: &shader_params, camera: &pane_camera, sim_time, time: animation_time, spin: 0.0, light: None, shadow_map_size: settings.shadow_map_size, environment: &environment, stars: Some(&stars), stereo, draw_order, threads: args.threads, gpu: gpu.as_ref() };
+                draw_pane(target, &view, frozen_camera.as_ref(), jitter, &mut heightmaps, &mut hooks, &mut profiler);
+            }
+            target.set_viewport(full);
+            target.set_scissor(None);
+
+            present_start = Instant::now();
+            post_process.near = camera.near;
+            post_process.far = camera.far;
+            post_process.orthographic = camera.projection == Projection::Orthographic;
+            post_process.flare_source = flare_source(&scene, sim_time, &camera, target.width, target.height);
+            post_process.apply(target);
+        }
+        if let Some(progressive) = &mut progressive {
+            let image = if progressive.is_converged() { progressive.image() } else { progressive.end_pass() };
+            framebuffer.buffer.copy_from_slice(image);
+        } else if let Some(supersampled) = &supersampled {
+            framebuffer.downsample_from(supersampled);
+        }
+        // the progressive preview is a still image, so the trail starts over when it ends
+        if progressive.is_none() {
+            motion_blur.apply(&mut framebuffer);
+        } else {
+            motion_blur.reset();
+        }
+        // exported frames leave out the HUD
+        if let Some(recording) = &mut time_lapse {
+            if let Err(error) = recording.record(&framebuffer) {
+                eprintln!("No se pudo guardar el fotograma del time-lapse: {}", error);
+                time_lapse = None;
+            } else if recording.is_finished() {
+                println!("Time-lapse terminado: {} fotogramas en {}", recording.exported(), TIMELAPSE_DIR);
+                time_lapse = None;
+            }
+        }
+        if let Some(recording) = &mut recorder {
+            if let Err(error) = recording.record(&framebuffer) {
+                eprintln!("Grabación detenida: {}", error);
+                finish_recording(recorder.take().unwrap());
+            }
+        }
+        // like the HUD, the guides stay out of exported frames
+        if guides != 0 {
+            draw_guides(&mut framebuffer, guides);
+        }
+        if camera.sees(LAYER_UI) {
+            // the active color-blindness filter, then notifications, which fade out after two seconds
+            let mut status = Vec::new();
+            if post_process.color_vision != ColorVisionFilter::Off {
+                status.push(format!("visión: {}", post_process.color_vision.label()));
+            }
+            status.extend(notification.borrow().clone().filter(|(_, frame)| time - frame < 120).map(|(text, _)| text));
+            let info = scene.nodes.get(selected).and_then(|node| Some((shader_registry.get(node.shader.name())?, node.shader)));
+            if let (true, Some((info, shader))) = (show_shader_info, info) {
+                status.extend(wrap_words(info.description, 48));
+                status.extend(info.describe_params(shader_params.for_shader(shader)));
+            }
+            draw_hud(&mut framebuffer, &scene, selected, &camera, &profiler, quality, &status);
+        }
+        hooks.run_post_frame(&mut framebuffer);
+
+        framebuffer.swap_buffers();
+        window.present(&framebuffer).unwrap();
+        profiler.record(Stage::Present, present_start.elapsed());
+        crash::record_frame(&framebuffer, &camera, time, sim_time);
+        profiler.end_frame();
+        if let (Some(log), Some(timing)) = (&mut metrics, profiler.last()) {
+            if let Err(error) = log.record(time, timing) {
+                eprintln!("No se pudo escribir el registro de métricas: {}", error);
+                metrics = None;
+            }
+        }
+
+        event_bus.emit(Event::FrameCompleted { frame: time });
+        event_bus.dispatch();
+
+        std::thread::sleep(frame_delay);
+    }
+}
+
+fn draw_pane(
+    framebuffer: &mut Framebuffer,
+    view: &SceneView,
+    frozen_camera: Option<&Camera>,
+    jitter: (f32, f32),
+    heightmaps: &mut HashMap<String, Option<Arc<Heightmap>>>,
+    hooks: &mut PipelineHooks,
+    profiler: &mut Profiler,
+) {
+    // in stereo each eye is drawn over the same pixels and the two are merged into a
+    // red-cyan anaglyph; the right eye's depth is what post-processing sees
+    let Some(stereo) = view.stereo else {
+        draw_eye(framebuffer, view, frozen_camera, jitter, heightmaps, hooks, profiler);
+        return;
+    };
+    let [left, right] = stereo.eye_cameras(view.camera);
+    draw_eye(framebuffer, &SceneView { camera: &left, ..*view }, frozen_camera, jitter, heightmaps, hooks, profiler);
+    let left_image = framebuffer.buffer.clone();
+    framebuffer.clear();
+    draw_eye(framebuffer, &SceneView { camera: &right, ..*view }, frozen_camera, jitter, heightmaps, hooks, profiler);
+    stereo::composite_anaglyph(framebuffer, &left_image);
+}
+
+fn draw_eye(
+    framebuffer: &mut Framebuffer,
+    view: &SceneView,
+    frozen_camera: Option<&Camera>,
+    jitter: (f32, f32),
+    heightmaps: &mut HashMap<String, Option<Arc<Heightmap>>>,
+    hooks: &mut PipelineHooks,
+    profiler: &mut Profiler,
+) {
+    // bodies, orbits and the frozen frustum into the framebuffer's current viewport
+    let camera = view.camera;
+    let viewport = framebuffer.viewport();
+    let view_matrix = camera.view_matrix();
+    let projection_matrix = progressive::jitter_projection(&camera.projection_matrix(), jitter, viewport.width, viewport.height);
+    let cull_camera = frozen_camera.unwrap_or(camera);
+    let cull_matrix = cull_camera.projection_matrix() * cull_camera.view_matrix();
+    if let Some(stars) = view.stars {
+        stars.draw(framebuffer, &view_matrix, &projection_matrix);
+    }
+    if !draw_bodies_on_gpu(framebuffer, view) {
+        draw_bodies(framebuffer, view, projection_matrix, cull_matrix, heightmaps, hooks, Some(profiler));
+    }
+
+    draw_scene_particles(framebuffer, view, &(projection_matrix * view_matrix));
+    let view_projection = viewport.matrix() * projection_matrix * view_matrix;
+    if camera.sees(LAYER_ORBITS) {
+        draw_orbits(framebuffer, view.scene, view.sim_time, &view_projection);
+    }
+    if camera.sees(LAYER_DEBUG) {
+        draw_debug_gizmos(framebuffer, view.scene, view.sim_time, &view_projection);
+        if let Some(frozen) = frozen_camera {
+            draw_frustum(framebuffer, &view_projection, frozen);
+        }
+    }
+}
+
+// everything a frame of the scene is drawn from, shared by the window and the still render
+#[derive(Clone, Copy)]
+struct SceneView<'a> {
+    scene: &'a Scene,
+    meshes: &'a HashMap<String, LodMesh>,
+    shader_params: &'a ShaderParams,
+    camera: &'a Camera,
+    sim_time: f32,
     time: u32,
-    noise: FastNoiseLite
+    // turntable rotation of every body around its Y axis, in radians
+    spin: f32,
+    // fixed direction towards the light instead of the scene's star
+    light: Option<Vec3>,
+    // side of the shadow maps in pixels, 0 turns shadows between bodies off
+    shadow_map_size: usize,
+    // sky reflected by smooth surfaces
+    environment: &'a Arc<EnvironmentMap>,
+    // background stars; turntable previews stay on black
+    stars: Option<&'a StarCatalog>,
+    // red-cyan anaglyph, if on; stills drawn tile by tile stay flat
+    stereo: Option<Stereo>,
+    // order of the opaque bodies, front to back by default to help early-Z
+    draw_order: DrawOrder,
+    // threads that rasterize the bodies, each on its own tiles of the framebuffer
+    threads: usize,
+    // wgpu preview that draws the bodies instead of the rasterizer, if on;
+    // stills and headless renders always use the rasterizer
+    gpu: Option<&'a GpuBackend>,
+}
+
+// the wgpu preview of the bodies; without the `gpu` feature it can't be turned on
+#[cfg(feature = "gpu")]
+type GpuBackend = RefCell<sr_02_line::gpu::GpuRenderer>;
+#[cfg(not(feature = "gpu"))]
+type GpuBackend = ();
+
+#[cfg(feature = "gpu")]
+fn toggle_gpu_preview(gpu: &mut Option<GpuBackend>) {
+    if gpu.take().is_some() {
+        println!("Vista previa por GPU desactivada");
+        return;
+    }
+    match sr_02_line::gpu::GpuRenderer::new() {
+        Ok(renderer) => {
+            println!("Vista previa por GPU en {}", renderer.adapter_name());
+            *gpu = Some(RefCell::new(renderer));
+        }
+        Err(error) => eprintln!("No se pudo activar la vista previa por GPU: {}", error),
+    }
 }
 
-fn create_noise() -> FastNoiseLite {
-    create_cloud_noise()
+#[cfg(not(feature = "gpu"))]
+fn toggle_gpu_preview(_gpu: &mut Option<GpuBackend>) {
+    eprintln!("La vista previa por GPU necesita compilar con --features gpu");
 }
 
-fn create_cloud_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
-    noise.set_noise_type(Some(NoiseType::OpenSimplex2));
-    noise
+// draws the bodies with the wgpu preview if it is on, leaving depth in the z-buffer
+// for what is drawn over them; false if the rasterizer has to draw them
+#[cfg(feature = "gpu")]
+fn draw_bodies_on_gpu(framebuffer: &mut Framebuffer, view: &SceneView) -> bool {
+    let Some(gpu) = view.gpu else { return false };
+    let frame = sr_02_line::gpu::GpuFrame { surface: view.shader_params.surface(), sim_time: view.sim_time, time: view.time, spin: view.spin };
+    gpu.borrow_mut().draw_scene(framebuffer, view.scene, view.meshes, view.camera, &frame);
+    true
 }
 
-fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
-    let (sin_x, cos_x) = rotation.x.sin_cos();
-    let (sin_y, cos_y) = rotation.y.sin_cos();
-    let (sin_z, cos_z) = rotation.z.sin_cos();
+#[cfg(not(feature = "gpu"))]
+fn draw_bodies_on_gpu(_framebuffer: &mut Framebuffer, view: &SceneView) -> bool {
+    // always None: toggle_gpu_preview never turns it on
+    view.gpu.is_some()
+}
 
-    let rotation_matrix_x = Mat4::new(
-        1.0,  0.0,    0.0,   0.0,
-        0.0,  cos_x, -sin_x, 0.0,
-        0.0,  sin_x,  cos_x, 0.0,
-        0.0,  0.0,    0.0,   1.0,
-    );
+// a body ready to be drawn, kept until the shadow maps are done
+struct Body<'a> {
+    node: usize,
+    model: &'a Model,
+    uniforms: Uniforms,
+    sphere: BoundingSphere,
+    // outside the culling frustum: only kept to cast shadows
+    culled: bool,
+    // copies of the mesh for an asteroid belt, empty for a single body
+    instances: Vec<Mat4>,
+}
 
-    let rotation_matrix_y = Mat4::new(
-        cos_y,  0.0,  sin_y, 0.0,
-        0.0,    1.0,  0.0,   0.0,
-        -sin_y, 0.0,  cos_y, 0.0,
-        0.0,    0.0,  0.0,   1.0,
-    );
+// asteroids smaller than this on screen (radius in pixels) are drawn as impostors
+const IMPOSTOR_RADIUS: f32 = 1.5;
 
-    let rotation_matrix_z = Mat4::new(
-        cos_z, -sin_z, 0.0, 0.0,
-        sin_z,  cos_z, 0.0, 0.0,
-        0.0,    0.0,  1.0, 0.0,
-        0.0,    0.0,  0.0, 1.0,
+fn draw_bodies(
+    framebuffer: &mut Framebuffer,
+    view: &SceneView,
+    projection_matrix: Mat4,
+    cull_matrix: Mat4,
+    heightmaps: &mut HashMap<String, Option<Arc<Heightmap>>>,
+    hooks: &mut PipelineHooks,
+    mut profiler: Option<&mut Profiler>,
+) {
+    // model rotation
+    let rotation = Vec3::new(0.0, view.spin, 0.0);
+    let view_matrix = view.camera.view_matrix();
+    let cull_frustum = Frustum::from_matrix(&cull_matrix);
+    let viewport_matrix = framebuffer.viewport().matrix();
+    let view_projection = projection_matrix * view_matrix;
+    let viewport_height = framebuffer.viewport().height as f32;
+
+    // the first star of the scene lights everything else
+    let light_position = view.scene.light_source().map(|light| view.scene.world_position(light, view.sim_time));
+
+    let mut bodies = Vec::new();
+    // bodies that would be drawn if they were inside the frustum
+    let mut candidates = 0;
+    for (index, node) in view.scene.nodes.iter().enumerate() {
+        if !node.visible || !view.camera.sees(node.layers) {
+            continue;
+        }
+
+        let Some(mesh) = view.meshes.get(&node.mesh) else { continue };
+
+        let translation = view.scene.world_position(index, view.sim_time);
+        let (model_matrix, sphere, model) = match node.belt {
+            // a belt spins as a whole and picks the detail of one asteroid at its center
+            Some(belt) => {
+                let asteroid = BoundingSphere { center: translation, radius: 0.5 * node.radius };
+                let model = mesh.select(projected_radius(&asteroid, &view_projection, viewport_height));
+                (create_model_matrix(translation, 1.0, rotation), belt.bounding_sphere(translation, node.radius), model)
+            }
+            None => {
+                let model_matrix = create_model_matrix(translation, node.radius, rotation);
+                let Some(bounds) = mesh.finest().world_bounds(&model_matrix) else { continue };
+                // fewer triangles the smaller the body is on screen
+                let model = mesh.select(projected_radius(&bounds.sphere, &view_projection, viewport_height));
+                (model_matrix, bounds.sphere, model)
+            }
+        };
+        // whole bodies outside the culling frustum skip the pipeline entirely,
+        // unless they can still shadow something on screen
+        candidates += 1;
+        let culled = cull_frustum.is_some_and(|frustum| !frustum.intersects_sphere(&sphere));
+        if culled && (view.shadow_map_size == 0 || !node.shader.casts_shadow() || node.belt.is_some()) {
+            continue;
+        }
+        let light_direction = view.light.or(light_position.map(|light| light - translation))
+            .filter(|direction| direction.magnitude() > 1e-4)
+            .map_or(Vec3::new(0.0, 0.0, 1.0), |direction| direction.normalize());
+        // a node without its own orbit sits on its parent (e.g. a ring), which
+        // then casts a shadow on it; meshes have a radius of 0.5
+        let occluder_radius = match (node.parent, node.orbit) {
+            (Some(parent), None) if node.belt.is_none() => 0.5 * view.scene.nodes[parent].radius / node.radius,
+            _ => 0.0,
+        };
+        let heightmap = node.heightmap.as_ref().and_then(|path| {
+            heightmaps.entry(path.clone())
+                .or_insert_with(|| load_heightmap(path))
+                .clone()
+        });
+        let mut uniforms = Uniforms::new(node.shader, node.seed);
+        uniforms.matrices = Matrices {
+            model: model_matrix,
+            view: view_matrix,
+            projection: projection_matrix,
+            viewport: viewport_matrix,
+            cull: cull_matrix,
+        };
+        uniforms.lighting = Lighting {
+            light_direction,
+            light_position: light_position.filter(|_| view.light.is_none()),
+            camera_position: view.camera.eye,
+            atmosphere: node.shader.atmosphere(),
+            occluder_radius,
+        };
+        uniforms.material.features = node.features;
+        uniforms.material.materials = Arc::clone(&model.materials);
+        uniforms.material.shading = node.shading;
+        uniforms.material.elevation = node.elevation;
+        uniforms.material.heightmap = heightmap;
+        uniforms.material.flow = node.flow;
+        uniforms.surface = *view.shader_params.surface();
+        if node.shader == PlanetShader::Moon {
+            uniforms.material.craters = craters::generate(uniforms.noise.seed, node.crater_density);
+        }
+        uniforms.time = view.time;
+        uniforms.environment = Some(Arc::clone(view.environment));
+        let instances = node.belt.map_or_else(Vec::new, |belt| belt.instances(node.radius, node.seed));
+        bodies.push(Body { node: index, model, uniforms, sphere, culled, instances });
+    }
+
+    // shadows come from the scene's star only, not from a fixed turntable light
+    if let Some(light) = light_position.filter(|_| view.light.is_none() && view.shadow_map_size > 0) {
+        cast_shadows(view.scene, &mut bodies, light, view.shadow_map_size);
+    }
+
+    if let Some(profiler) = profiler.as_deref_mut() {
+        let culled = candidates - bodies.iter().filter(|body| !body.culled).count();
+        profiler.count_objects(candidates - culled, culled);
+    }
+
+    let mut draw_list = DrawList::with_order(view.draw_order);
+    draw_list.set_threads(view.threads);
+    let impostor = Quad::quad(1.0);
+    for mut body in bodies.into_iter().filter(|body| !body.culled) {
+        // sort by the nearest point of the bounding sphere rather than the center
+        let depth = (body.sphere.center - view.camera.eye).magnitude() - body.sphere.radius;
+        if !body.instances.is_empty() && !body.uniforms.material.shader.is_transparent() {
+            // asteroids of a pixel or two become quads facing the camera, drawn right
+            // away without bounds, sorting or the draw list; they are opaque, so
+            // drawing them before the rest of the list changes nothing
+            let placement = body.uniforms.matrices.model;
+            let mut impostors = Vec::new();
+            body.instances.retain(|instance| {
+                let model = placement * instance;
+                let asteroid = BoundingSphere { center: model.column(3).xyz(), radius: 0.5 * model.column(0).xyz().magnitude() };
+                let far = projected_radius(&asteroid, &view_projection, viewport_height) < IMPOSTOR_RADIUS;
+                if far {
+                    impostors.push(billboard(asteroid.center, 2.0 * asteroid.radius, &view_matrix));
+                }
+                !far
+            });
+            render_small_instances(framebuffer, &mut body.uniforms, &impostor, &impostors);
+            if body.instances.is_empty() {
+                continue;
+            }
+        }
+        draw_list.push_instanced(body.uniforms, &body.model.vertices, &body.model.indices, body.instances, depth);
+    }
+    draw_list.submit_profiled(framebuffer, hooks, profiler);
+}
+
+// where the scene's star is on an image of the given size, for the lens flare
+fn flare_source(scene: &Scene, sim_time: f32, camera: &Camera, width: usize, height: usize) -> Option<FlareSource> {
+    // a flare is an artifact of a real lens, out of place in a schematic map view
+    if camera.projection == Projection::Orthographic {
+        return None;
+    }
+    let light = scene.light_source()?;
+    let node = &scene.nodes[light];
+    if !node.visible || !camera.sees(node.layers) {
+        return None;
+    }
+    let center = scene.world_position(light, sim_time);
+    let sphere = BoundingSphere { center, radius: 0.5 * node.radius };
+    let view_projection = camera.projection_matrix() * camera.view_matrix();
+    let screen_matrix = Viewport::full(width, height).matrix() * view_projection;
+    let project = |point: Vec3| {
+        let clip = screen_matrix * Vec4::new(point.x, point.y, point.z, 1.0);
+        (clip.w > 1e-6).then(|| clip.xyz() / clip.w)
+    };
+    let screen = project(center)?;
+    // the point of the star nearest to the camera is what the z-buffer holds in front
+    let front = project(center + (camera.eye - center).normalize() * sphere.radius)?;
+    let radius = projected_radius(&sphere, &view_projection, height as f32);
+    Some(FlareSource { x: screen.x, y: screen.y, depth: front.z, radius: radius.min(height as f32) })
+}
+
+// comet tails and flares of the visible bodies, added on top of the bodies
+fn draw_scene_particles(framebuffer: &mut Framebuffer, view: &SceneView, view_projection: &Mat4) {
+    let scene = view.scene;
+    let light = scene.light_source();
+    for (index, node) in scene.nodes.iter().enumerate() {
+        let Some(effect) = node.particles else { continue };
+        if !node.visible || !view.camera.sees(node.layers) {
+            continue;
+        }
+        let emitter = effect.emitter(node.radius, node.seed);
+        let particles = emitter.particles_at(view.sim_time, |time| {
+            let position = scene.world_position(index, time);
+            let light_position = light.map(|light| scene.world_position(light, time));
+            (position, effect.direction(position, light_position))
+        });
+        draw_particles(framebuffer, &particles, &emitter, view_projection);
+    }
+}
+
+// gives every body on screen a shadow map with the bodies between it and the light
+fn cast_shadows(scene: &Scene, bodies: &mut [Body], light: Vec3, size: usize) {
+    for receiver in 0..bodies.len() {
+        let node = &scene.nodes[bodies[receiver].node];
+        // belts neither receive nor cast shadows: a single map could not cover them
+        if bodies[receiver].culled || node.shader.is_emissive() || !bodies[receiver].instances.is_empty() {
+            continue;
+        }
+        let Some(mut map) = ShadowMap::new(size, light, &bodies[receiver].sphere) else { continue };
+        // a ring already gets its planet's shadow from the shader
+        let parent = node.parent.filter(|_| bodies[receiver].uniforms.lighting.occluder_radius > 0.0);
+        let mut shadowed = false;
+        for (occluder, body) in bodies.iter_mut().enumerate() {
+            if occluder == receiver
+                || !body.instances.is_empty()
+                || Some(body.node) == parent
+                || !scene.nodes[body.node].shader.casts_shadow()
+                || !map.covers(&body.sphere)
+            {
+                continue;
+            }
+            map.render(&mut body.uniforms, &body.model.vertices, &body.model.indices);
+            shadowed = true;
+        }
+        if shadowed {
+            bodies[receiver].uniforms.shadow = Some(Arc::new(map));
+        }
+    }
+}
+
+fn render_still(view: &SceneView, heightmaps: &mut HashMap<String, Option<Arc<Heightmap>>>, hooks: &mut PipelineHooks) {
+    let (width, height, tile_size) = (8192, 8192, 512);
+    let path = "assets/still.ppm";
+
+    let mut camera = view.camera.clone();
+    camera.set_aspect(width as f32 / height as f32);
+    let view = SceneView { camera: &camera, ..*view };
+    let view_matrix = camera.view_matrix();
+    let projection_matrix = camera.projection_matrix();
+
+    println!("Dibujando {}x{} en partes de {}...", width, height, tile_size);
+    let start = Instant::now();
+    let result = still::render_tiled(path, width, height, tile_size, |framebuffer, tile| {
+        // each tile gets its own slice of the projection, so culling is per tile too;
+        // post-processing is skipped because it would leave seams between tiles
+        let tile_projection = tile.projection(&projection_matrix, width, height);
+        framebuffer.set_background_color(0x000000);
+        framebuffer.clear();
+        if let Some(stars) = view.stars {
+            stars.draw(framebuffer, &view_matrix, &tile_projection);
+        }
+        draw_bodies(framebuffer, &view, tile_projection, tile_projection * view_matrix, heightmaps, hooks, None);
+        draw_scene_particles(framebuffer, &view, &(tile_projection * view_matrix));
+        if camera.sees(LAYER_ORBITS) {
+            let viewport_matrix = framebuffer.viewport().matrix();
+            draw_orbits(framebuffer, view.scene, view.sim_time, &(viewport_matrix * tile_projection * view_matrix));
+        }
+    });
+    match result {
+        Ok(()) => println!("Imagen guardada en {} ({:.1} s)", path, start.elapsed().as_secs_f32()),
+        Err(error) => eprintln!("No se pudo guardar {}: {}", path, error),
+    }
+}
+
+// usage: sr_02_line [scene file] [--quality low|medium|high|ultra] [--metrics file.jsonl]
+//                   [--shader name] [--size WxH --frames N --out directory|file.gif --format ppm|png|gif]
+//                   [--turntable mesh] [--list-shaders] [--mouse-sensitivity factor] [--mouse-damping 0-1]
+//                   [--projection perspective|orthographic] [--stereo] [--eye-separation d] [--convergence d]
+//                   [--panorama] [--bake] [--contact-sheet] [--threads N] [--motion-blur N] [--motion-blur-factor 0-1]
+struct Args {
+    scene_path: Option<String>,
+    quality: QualityPreset,
+    metrics_path: Option<String>,
+    shader: Option<PlanetShader>,
+    // headless mode: image size, number of frames and where they are saved
+    size: (usize, usize),
+    frames: Option<u32>,
+    out: Option<String>,
+    // None saves numbered PPM files
+    format: Option<ExportFormat>,
+    // mesh or primitive spun once by the turntable mode
+    turntable: Option<String>,
+    // save one labeled sphere per shader in a single image
+    contact_sheet: bool,
+    // print the shaders and their parameters, then exit
+    list_shaders: bool,
+    // multiplies how far the camera moves per pixel dragged or wheel step
+    mouse_sensitivity: f32,
+    // how much of the camera's motion is left each frame after letting go
+    mouse_damping: Option<f32>,
+    // perspective, or orthographic for schematic map views; / toggles it in the viewer
+    projection: Projection,
+    // red-cyan anaglyph from --stereo, --eye-separation or --convergence; ; toggles it in the viewer
+    stereo: Option<Stereo>,
+    // headless frames are 360° equirectangular panoramas from the camera position,
+    // twice as wide as they are tall
+    panorama: bool,
+    // save the first body's surface as textures instead of rendering the scene
+    bake: bool,
+    // threads that share the framebuffer's tiles; 1 draws straight into the framebuffer
+    threads: usize,
+    // frames blended into a trail, from --motion-blur or --motion-blur-factor; ' toggles it in the viewer
+    motion_blur: Option<MotionBlur>,
+}
+
+fn parse_args() -> Args {
+    let mut parsed = Args {
+        scene_path: None,
+        quality: QualityPreset::default(),
+        metrics_path: None,
+        shader: None,
+        size: (800, 600),
+        frames: None,
+        out: None,
+        format: None,
+        turntable: None,
+        contact_sheet: false,
+        list_shaders: false,
+        mouse_sensitivity: 1.0,
+        mouse_damping: None,
+        projection: Projection::default(),
+        stereo: None,
+        panorama: false,
+        bake: false,
+        threads: tiles::available_threads(),
+        motion_blur: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        // options take their value after '=' or as the next argument
+        let (option, inline_value) = match arg.split_once('=') {
+            Some((option, value)) if option.starts_with("--") => (option, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        match option {
+            "--quality" => {
+                let name = inline_value.or_else(|| args.next()).unwrap_or_default();
+                match QualityPreset::from_name(&name) {
+                    Some(preset) => parsed.quality = preset,
+                    None => eprintln!("Calidad desconocida '{}'; se usa {}", name, parsed.quality.name()),
+                }
+            }
+            "--metrics" => parsed.metrics_path = inline_value.or_else(|| args.next()),
+            "--shader" => {
+                let name = inline_value.or_else(|| args.next()).unwrap_or_default();
+                match PlanetShader::from_name(&name) {
+                    Some(shader) => parsed.shader = Some(shader),
+                    None => eprintln!("Shader desconocido '{}'", name),
+                }
+            }
+            "--size" => {
+                let text = inline_value.or_else(|| args.next()).unwrap_or_default();
+                let size = text.split_once('x')
+                    .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+                    .filter(|&(width, height)| width > 0 && height > 0);
+                match size {
+                    Some(size) => parsed.size = size,
+                    None => eprintln!("Tamaño inválido '{}'; se espera ANCHOxALTO, por ejemplo 1920x1080", text),
+                }
+            }
+            "--frames" => {
+                let text = inline_value.or_else(|| args.next()).unwrap_or_default();
+                match text.parse() {
+                    Ok(frames) => parsed.frames = Some(frames),
+                    Err(_) => eprintln!("Cantidad de fotogramas inválida '{}'", text),
+                }
+            }
+            "--out" => parsed.out = inline_value.or_else(|| args.next()),
+            "--turntable" => parsed.turntable = inline_value.or_else(|| args.next()),
+            "--contact-sheet" => parsed.contact_sheet = true,
+            "--list-shaders" => parsed.list_shaders = true,
+            "--projection" => {
+                let name = inline_value.or_else(|| args.next()).unwrap_or_default();
+                match Projection::from_name(&name) {
+                    Some(projection) => parsed.projection = projection,
+                    None => eprintln!("Proyección desconocida '{}'; se espera perspective u orthographic", name),
+                }
+            }
+            "--panorama" => parsed.panorama = true,
+            "--bake" => parsed.bake = true,
+            "--stereo" => parsed.stereo = Some(parsed.stereo.unwrap_or_default()),
+            "--eye-separation" | "--convergence" => {
+                let text = inline_value.or_else(|| args.next()).unwrap_or_default();
+                let stereo = parsed.stereo.get_or_insert_with(Stereo::default);
+                match text.parse::<f32>() {
+                    Ok(value) if value > 0.0 && option == "--eye-separation" => stereo.eye_separation = value,
+                    Ok(value) if value > 0.0 => stereo.convergence = Some(value),
+                    _ => eprintln!("Valor inválido '{}' para {}; se espera un número positivo", text, option),
+                }
+            }
+            "--threads" => {
+                let text = inline_value.or_else(|| args.next()).unwrap_or_default();
+                match text.parse::<usize>() {
+                    Ok(threads) if threads > 0 => parsed.threads = threads,
+                    _ => eprintln!("Cantidad de hilos inválida '{}'; se espera un entero positivo", text),
+                }
+            }
+            "--motion-blur" => {
+                let text = inline_value.or_else(|| args.next()).unwrap_or_default();
+                match text.parse::<usize>() {
+                    Ok(frames) if frames > 0 => parsed.motion_blur.get_or_insert_with(MotionBlur::default).set_frames(frames),
+                    _ => eprintln!("Cantidad de fotogramas de desenfoque inválida '{}'; se espera un entero positivo", text),
+                }
+            }
+            "--motion-blur-factor" => {
+                let text = inline_value.or_else(|| args.next()).unwrap_or_default();
+                match text.parse::<f32>() {
+                    Ok(factor) if (0.0..=1.0).contains(&factor) => parsed.motion_blur.get_or_insert_with(MotionBlur::default).blend_factor = factor,
+                    _ => eprintln!("Factor de desenfoque inválido '{}'; se espera un número entre 0 y 1", text),
+                }
+            }
+            "--mouse-sensitivity" => {
+                let text = inline_value.or_else(|| args.next()).unwrap_or_default();
+                match text.parse::<f32>() {
+                    Ok(factor) if factor > 0.0 => parsed.mouse_sensitivity = factor,
+                    _ => eprintln!("Sensibilidad del mouse inválida '{}'; se espera un número positivo", text),
+                }
+            }
+            "--mouse-damping" => {
+                let text = inline_value.or_else(|| args.next()).unwrap_or_default();
+                match text.parse::<f32>() {
+                    Ok(damping) if (0.0..1.0).contains(&damping) => parsed.mouse_damping = Some(damping),
+                    _ => eprintln!("Amortiguación del mouse inválida '{}'; se espera un número entre 0 y 1", text),
+                }
+            }
+            "--format" => {
+                let name = inline_value.or_else(|| args.next()).unwrap_or_default();
+                match name.to_ascii_lowercase().as_str() {
+                    "ppm" => parsed.format = None,
+                    "png" => parsed.format = Some(ExportFormat::PngSequence),
+                    "gif" => parsed.format = Some(ExportFormat::Gif),
+                    _ => eprintln!("Formato desconocido '{}'; se espera ppm, png o gif", name),
+                }
+            }
+            _ => parsed.scene_path = Some(arg.clone()),
+        }
+    }
+    parsed
+}
+
+// scene from the file given on the command line, or a planet with a procedurally
+// generated moon system; --shader replaces the shader of the first body
+fn load_initial_scene(path: Option<&str>, shader: Option<PlanetShader>) -> Scene {
+    let mut scene = match path {
+        Some(path) => Scene::load(path).unwrap_or_else(|error| {
+            panic!("Failed to load scene {}: {}", path, error)
+        }),
+        None => {
+            let mut scene = Scene::new();
+            let planet = scene.add_node(SceneNode::new("Planeta", PlanetShader::Fantasy, 1.0));
+            generate_moons(&mut scene, planet, 7, 3);
+            scene
+        }
+    };
+    if let (Some(shader), Some(first)) = (shader, scene.nodes.first_mut()) {
+        first.shader = shader;
+    }
+    scene
+}
+
+// renders the scene without a window, advancing the orbits and shader animations
+// one frame at a time like the viewer does
+fn run_headless(args: &Args, out: &str) {
+    let scene = load_initial_scene(args.scene_path.as_deref(), args.shader);
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    camera.set_aspect(args.size.0 as f32 / args.size.1 as f32);
+    camera.projection = args.projection;
+    render_sequence(args, out, &scene, &camera, args.frames.unwrap_or(1), None, |frame| (0.0, frame as f32, frame));
+}
+
+// spins a single mesh once in front of a fixed camera and light; the shader
+// animations stay still so the sequence loops without a jump
+fn run_turntable(args: &Args, mesh: &str, out: &str) {
+    let turntable = Turntable::new(args.frames.unwrap_or(120));
+    let scene = Turntable::scene(mesh, args.shader.unwrap_or(PlanetShader::Fantasy));
+    let camera = turntable.camera(args.size.0 as f32 / args.size.1 as f32);
+    render_sequence(args, out, &scene, &camera, turntable.frames, Some(turntable.light_direction), |frame| (turntable.angle(frame), 0.0, 0));
+}
+
+// one sphere per shader, with the parameters from assets/shader_params.toml, in a
+// grid as wide as --size
+fn run_contact_sheet(args: &Args, out: &str) {
+    let start = Instant::now();
+    let mut swatches = shader_swatches(&load_shader_params().unwrap_or_default());
+    let sheet = ContactSheet::fit_width(args.size.0, swatches.len());
+    let sphere = primitives::uv_sphere(0.5, 64, 32);
+    let framebuffer = sheet.render(&mut swatches, &sphere);
+    match framebuffer.save_ppm(out) {
+        Ok(()) => println!("Hoja de contactos de {} shaders guardada en {} ({:.1} s)", swatches.len(), out, start.elapsed().as_secs_f32()),
+        Err(error) => eprintln!("No se pudo guardar {}: {}", out, error),
+    }
+}
+
+// evaluates the first body's shader over its whole surface and saves it as an
+// equirectangular texture as wide as --size plus the six faces of a cubemap
+fn run_bake(args: &Args, out: &str) {
+    let scene = load_initial_scene(args.scene_path.as_deref(), args.shader);
+    let Some(node) = scene.nodes.first() else {
+        eprintln!("La escena no tiene cuerpos que hornear");
+        return;
+    };
+    let shader_params = load_shader_params().unwrap_or_default();
+    let mut uniforms = Uniforms::new(node.shader, node.seed);
+    uniforms.material.features = node.features;
+    uniforms.material.elevation = node.elevation;
+    uniforms.material.heightmap = node.heightmap.as_deref().and_then(load_heightmap);
+    uniforms.material.flow = node.flow;
+    uniforms.surface = *shader_params.surface();
+    if node.shader == PlanetShader::Moon {
+        uniforms.material.craters = craters::generate(uniforms.noise.seed, node.crater_density);
+    }
+
+    let start = Instant::now();
+    let width = args.size.0;
+    let mut baker = Baker::new(uniforms);
+    let saved = std::fs::create_dir_all(out).and_then(|()| {
+        let path = std::path::Path::new(out);
+        baker.equirect(width, (width / 2).max(1)).save_ppm(path.join("surface.ppm"))?;
+        for (face, name) in baker.cubemap((width / 4).max(1)).iter().zip(CUBE_FACE_NAMES) {
+            face.save_ppm(path.join(format!("surface_{}.ppm", name)))?;
+        }
+        Ok(())
+    });
+    match saved {
+        Ok(()) => println!("Superficie de {} horneada en {} ({:.1} s)", node.name, out, start.elapsed().as_secs_f32()),
+        Err(error) => eprintln!("No se pudo guardar la superficie en {}: {}", out, error),
+    }
+}
+
+// renders numbered PPM files (or a GIF or PNG sequence, see export::Recorder);
+// `pose` gives the spin, simulation time and animation time of each frame
+fn render_sequence(
+    args: &Args,
+    out: &str,
+    scene: &Scene,
+    camera: &Camera,
+    frames: u32,
+    light: Option<Vec3>,
+    pose: impl Fn(u32) -> (f32, f32, u32),
+) {
+    let (width, height) = if args.panorama { (args.size.0, (args.size.0 / 2).max(1)) } else { args.size };
+    // the size is given exactly, so only the rest of the quality preset applies
+    let settings = RenderSettings { resolution_scale: 1.0, ..args.quality.settings() };
+    let mut meshes = HashMap::new();
+    load_scene_meshes(scene, &mut meshes, &mut FileWatcher::new(), settings.lod_bias);
+    let shader_params = load_shader_params().unwrap_or_default();
+    let stars = load_star_catalog();
+    let environment = load_environment(&stars);
+    let mut heightmaps = HashMap::new();
+    let mut hooks = PipelineHooks::new();
+    let mut profiler = Profiler::new(60);
+
+    let mut post_process = PostProcess::default();
+    settings.apply_post_process(&mut post_process);
+    post_process.near = camera.near;
+    post_process.far = camera.far;
+    post_process.orthographic = camera.projection == Projection::Orthographic;
+
+    let mut framebuffer = Framebuffer::new(width, height);
+    let mut supersampled = supersample_target(&settings, width, height).filter(|_| !args.panorama);
+    let mut motion_blur = args.motion_blur.clone();
+    // a face a quarter of the panorama wide keeps about one pixel per pixel on the equator
+    let face_size = (width / 4).max(1);
+    let mut face_targets = args.panorama.then(|| (Framebuffer::new(face_size, face_size), supersample_target(&settings, face_size, face_size)));
+    // colors are encoded as they are written (see `PixelFormat`)
+    let pixel_format = post_process.pixel_format();
+    framebuffer.set_pixel_format(pixel_format);
+    if let Some(supersampled) = &mut supersampled {
+        supersampled.set_pixel_format(pixel_format);
+    }
+    if let Some((face, face_supersampled)) = &mut face_targets {
+        face.set_pixel_format(pixel_format);
+        if let Some(face_supersampled) = face_supersampled {
+            face_supersampled.set_pixel_format(pixel_format);
+        }
+    }
+    // an --out ending in .gif is an animated GIF even without --format
+    let format = args.format.or((ExportFormat::from_path(out) == ExportFormat::Gif).then_some(ExportFormat::Gif));
+    let mut output = match FilePresenter::create(out, format, width, height) {
+        Ok(output) => output,
+        Err(error) => {
+            eprintln!("No se pudo crear {}: {}", out, error);
+            return;
+        }
+    };
+
+    crash::record_scene(scene);
+    let start = Instant::now();
+    for frame in 0..frames {
+        let (spin, sim_time, time) = pose(frame);
+        if let Some((face, face_supersampled)) = &mut face_targets {
+            // six 90° faces stitched together; post-processing is skipped because bloom
+            // and the other screen effects would leave seams between the faces
+            let mut faces = Vec::with_capacity(6);
+            for face_camera in panorama::face_cameras(camera) {
+                let target = face_supersampled.as_mut().unwrap_or(face);
+                target.set_background_color(0x000000);
+                target.clear();
+                let view = SceneView { scene, meshes: &meshes, shader_params: &shader_params, camera: &face_camera, sim_time, time, spin, light, shadow_map_size: settings.shadow_map_size, environment: &environment, stars: light.is_none().then_some(&stars), stereo: None, draw_order: DrawOrder::default(), threads: args.threads, gpu: None };
+                draw_pane(target, &view, None, (0.0, 0.0), &mut heightmaps, &mut hooks, &mut profiler);
+                if let Some(face_supersampled) = face_supersampled {
+                    face.downsample_from(face_supersampled);
+                }
+                faces.push(CubeFace { camera: face_camera, size: face_size, pixels: face.buffer.clone() });
+            }
+            panorama::stitch(&mut framebuffer, &faces);
+        } else {
+            let target = supersampled.as_mut().unwrap_or(&mut framebuffer);
+            target.set_background_color(0x000000);
+            target.clear();
+            let view = SceneView { scene, meshes: &meshes, shader_params: &shader_params, camera, sim_time, time, spin, light, shadow_map_size: settings.shadow_map_size, environment: &environment, stars: light.is_none().then_some(&stars), stereo: args.stereo.filter(|_| light.is_none()), draw_order: DrawOrder::default(), threads: args.threads, gpu: None };
+            draw_pane(target, &view, None, (0.0, 0.0), &mut heightmaps, &mut hooks, &mut profiler);
+            // a fixed turntable light has no sun on screen to flare
+            post_process.flare_source = flare_source(scene, sim_time, camera, target.width, target.height).filter(|_| light.is_none());
+            post_process.apply(target);
+            if let Some(supersampled) = &supersampled {
+                framebuffer.downsample_from(supersampled);
+            }
+        }
+        if let Some(motion_blur) = &mut motion_blur {
+            motion_blur.apply(&mut framebuffer);
+        }
+        profiler.end_frame();
+        // nothing is shown, but the crash snapshot copies what changed since the last swap
+        framebuffer.swap_buffers();
+        crash::record_frame(&framebuffer, camera, frame, sim_time);
+
+        if let Err(error) = output.present(&framebuffer) {
+            eprintln!("No se pudo guardar el fotograma {} en {}: {}", frame, out, error);
+            return;
+        }
+    }
+    if let Err(error) = output.finish() {
+        eprintln!("No se pudo terminar {}: {}", out, error);
+        return;
+    }
+    println!(
+        "{} fotogramas de {}x{} guardados en {} ({:.1} s)",
+        frames, width, height, out, start.elapsed().as_secs_f32(),
     );
+}
+
+// reallocates everything drawn into for the window size and quality settings
+fn resize_targets(
+    framebuffer: &mut Framebuffer,
+    supersampled: &mut Option<Framebuffer>,
+    progressive: &mut Option<Progressive>,
+    settings: &RenderSettings,
+    window_size: (usize, usize),
+) {
+    let (width, height) = settings.output_size(window_size.0, window_size.1);
+    framebuffer.resize(width, height);
+    *supersampled = supersample_target(settings, window_size.0, window_size.1);
+    if let Some(progressive) = progressive {
+        *progressive = Progressive::new(width, height, progressive.max_samples);
+    }
+}
+
+// with supersampling the scene is drawn at a higher resolution and averaged down
+fn supersample_target(settings: &RenderSettings, window_width: usize, window_height: usize) -> Option<Framebuffer> {
+    (settings.antialiasing.factor() > 1).then(|| {
+        let (width, height) = settings.render_size(window_width, window_height);
+        Framebuffer::new(width, height)
+    })
+}
 
-    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
+fn finish_recording(recorder: Recorder) {
+    let frames = recorder.recorded();
+    match recorder.finish() {
+        Ok(()) => println!("Grabación terminada: {} fotogramas en {}", frames, RECORDING_PATH),
+        Err(error) => eprintln!("No se pudo terminar {}: {}", RECORDING_PATH, error),
+    }
+}
 
-    let transform_matrix = Mat4::new(
-        scale, 0.0,   0.0,   translation.x,
-        0.0,   scale, 0.0,   translation.y,
-        0.0,   0.0,   scale, translation.z,
-        0.0,   0.0,   0.0,   1.0,
+fn pick_body(scene: &Scene, sim_time: f32, camera: &Camera, cursor: (f32, f32), pane: (f32, f32)) -> Option<usize> {
+    // the body under the cursor; with the comparison split open only the left pane,
+    // the live scene, can be clicked
+    let ((x, y), (width, height)) = (cursor, pane);
+    if x >= width {
+        return None;
+    }
+    let mut pane_camera = camera.clone();
+    pane_camera.set_aspect(width / height);
+    let ray = pane_camera.ray_through(x, y, width, height);
+    scene.pick(&ray, sim_time, camera.layer_mask)
+}
+
+fn start_time_lapse(scene: &Scene, selected: usize) -> Option<TimeLapse> {
+    // one orbit of the selected body (or the body it belongs to) spread over ten seconds of video
+    let Some(period) = timelapse::orbital_period(scene, selected) else {
+        eprintln!("{} no tiene órbita; selecciona una luna o un planeta con Tab", scene.nodes[selected].name);
+        return None;
+    };
+    let time_lapse = TimeLapse::full_period(period, 600, TIMELAPSE_DIR);
+    println!(
+        "Time-lapse de {}: {} fotogramas, {:.1} fotogramas de simulación por fotograma",
+        scene.nodes[selected].name, time_lapse.frames, time_lapse.step,
     );
+    Some(time_lapse)
+}
 
-    transform_matrix * rotation_matrix
+fn draw_orbits(framebuffer: &mut Framebuffer, scene: &Scene, time: f32, screen_matrix: &Mat4) {
+    // each orbit is a circle around the parent's current position
+    let style = GizmoStyle::new(Color::new(70, 90, 140));
+
+    for node in &scene.nodes {
+        let (Some(orbit), Some(parent)) = (node.orbit, node.parent) else { continue };
+        if !node.visible {
+            continue;
+        }
+        let center = scene.world_position(parent, time);
+        draw_circle_3d(framebuffer, screen_matrix, center, orbit.normal(), orbit.distance, style);
+    }
 }
 
+fn draw_debug_gizmos(framebuffer: &mut Framebuffer, scene: &Scene, time: f32, screen_matrix: &Mat4) {
+    // a grid on the orbital plane, a little wider than the outermost orbit
+    let extent = scene.nodes.iter().filter_map(|node| node.orbit).map(|orbit| orbit.distance).fold(0.0, f32::max);
+    if extent > 0.0 {
+        let style = GizmoStyle::new(Color::new(40, 40, 55));
+        draw_grid(framebuffer, screen_matrix, Vec3::zeros(), extent * 2.2, 22, style);
+    }
 
-fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
-    look_at(&eye, &center, &up)
+    // every body spins around +Y; the axis sticks out a quarter radius past each pole
+    let style = GizmoStyle::new(Color::new(255, 90, 90));
+    for (index, node) in scene.nodes.iter().enumerate() {
+        if !node.visible || node.belt.is_some() {
+            continue;
+        }
+        let position = scene.world_position(index, time);
+        draw_axis(framebuffer, screen_matrix, position, Vec3::y(), node.radius * 0.75, style);
+    }
 }
 
-fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
-    let fov = 45.0 * PI / 180.0;
-    let aspect_ratio = window_width / window_height;
-    let near = 0.1;
-    let far = 1000.0;
+fn draw_frustum(framebuffer: &mut Framebuffer, view_projection: &Mat4, frozen: &Camera) {
+    // the far face is drawn at a short distance so the whole frustum fits on screen,
+    // over the bodies so it stays readable from anywhere
+    let depth = (frozen.center - frozen.eye).magnitude().max(1.0) * 3.0;
+    let corners = frozen.frustum_corners(frozen.near, depth.min(frozen.far));
+    let style = GizmoStyle::new(Color::new(255, 200, 0)).on_top();
 
-    perspective(fov, aspect_ratio, near, far)
+    for i in 0..4 {
+        let next = (i + 1) % 4;
+        draw_line_3d(framebuffer, view_projection, corners[i], corners[next], style);
+        draw_line_3d(framebuffer, view_projection, corners[i + 4], corners[next + 4], style);
+        draw_line_3d(framebuffer, view_projection, corners[i], corners[i + 4], style);
+    }
 }
 
-fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
-    Mat4::new(
-        width / 2.0, 0.0, 0.0, width / 2.0,
-        0.0, -height / 2.0, 0.0, height / 2.0,
-        0.0, 0.0, 1.0, 0.0,
-        0.0, 0.0, 0.0, 1.0
-    )
+fn draw_hud(framebuffer: &mut Framebuffer, scene: &Scene, selected: usize, camera: &Camera, profiler: &Profiler, quality: QualityPreset, status: &[String]) {
+    // frame rate, stage timings, camera position and the selected node's shader in the
+    // top-left corner, with a drop shadow so they stay readable over bright planets
+    let eye = camera.eye;
+    let stages: Vec<String> = Stage::ALL.iter()
+        .map(|&stage| format!("{} {:.1}", stage.name(), profiler.average(stage).as_secs_f32() * 1000.0))
+        .collect();
+    let mut lines = vec![
+        format!("fps: {:.0} ({})", profiler.fps(), quality.name()),
+        format!("ms: {}", stages.join(" ")),
+    ];
+    if let Some(timing) = profiler.last() {
+        lines.push(format!("objetos: {} ({} fuera de cuadro)", timing.objects, timing.culled_objects));
+    }
+    lines.push(format!("cámara: {:.1}, {:.1}, {:.1}", eye.x, eye.y, eye.z));
+    if let Some(node) = scene.nodes.get(selected) {
+        lines.push(node.name.clone());
+        lines.push(format!("shader: {}", node.shader.name()));
+    }
+    lines.extend_from_slice(status);
+    // the window stretches reduced-resolution presets, so the text shrinks with them
+    let scale = if framebuffer.width >= 600 { 2 } else { 1 };
+
+    for (row, line) in lines.iter().enumerate() {
+        let y = 10 + row * (GLYPH_HEIGHT + 1) * scale;
+        draw_text(framebuffer, 12, y + 2, line, Color::black(), scale);
+        draw_text(framebuffer, 10, y, line, Color::new(255, 255, 255), scale);
+    }
 }
 
-fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex]) {
-    // Vertex Shader
-    let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
-    for vertex in vertex_array {
-        let transformed = vertex_shader(vertex, uniforms);
-        transformed_vertices.push(transformed);
+// every shader with its description and parameters, for --list-shaders
+fn print_shader_list() {
+    for info in list_shaders() {
+        println!("{}: {}", info.name, info.description);
+        for param in info.params {
+            println!("    {} = {}  {}", param.name, param.kind, param.description);
+        }
     }
+}
 
-    // Primitive Assembly
-    let mut triangles = Vec::new();
-    for i in (0..transformed_vertices.len()).step_by(3) {
-        if i + 2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i + 1].clone(),
-                transformed_vertices[i + 2].clone(),
-            ]);
+// splits text into lines of at most `columns` characters, breaking between words
+fn wrap_words(text: &str, columns: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= columns => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
         }
     }
+    lines
+}
 
-    // Rasterization
-    let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
+fn load_scene_meshes(scene: &Scene, meshes: &mut HashMap<String, LodMesh>, watcher: &mut FileWatcher, lod_bias: i32) {
+    for node in &scene.nodes {
+        if meshes.contains_key(&node.mesh) {
+            continue;
+        }
+        // generated meshes don't come from a file, so there is nothing to watch
+        if let Some(mesh) = LodMesh::for_primitive(&node.mesh, lod_bias) {
+            meshes.insert(node.mesh.clone(), mesh);
+            continue;
+        }
+        match load_model(&node.mesh) {
+            Ok(model) => {
+                meshes.insert(node.mesh.clone(), LodMesh::single(model));
+                watcher.watch(&node.mesh);
+            }
+            Err(error) => eprintln!("Failed to load mesh {}: {}", node.mesh, error),
+        }
     }
+}
 
-    // Fragment Processing
-    for fragment in fragments {
-        let x = fragment.position.x as usize;
-        let y = fragment.position.y as usize;
+// picks the loader from the file extension; anything that isn't .ply or .stl is read as OBJ.
+// Files are centered and scaled to the size of the generated meshes, so the node radius
+// means the same for every mesh
+fn load_model(path: &str) -> Result<Model, String> {
+    let lower = path.to_ascii_lowercase();
+    let mut model = if lower.ends_with(".ply") {
+        ply::load(path).map_err(|error| error.to_string())?
+    } else if lower.ends_with(".stl") {
+        stl::load(path).map_err(|error| error.to_string())?
+    } else {
+        Obj::load(path).map(|obj| obj.get_model()).map_err(|error| error.to_string())?
+    };
+    model.normalize(&NormalizeOptions { center: Some(Center::BoundingBox), radius: Some(MESH_RADIUS) });
+    Ok(model)
+}
 
-        if x < framebuffer.width && y < framebuffer.height {
-            let shaded_color = fragment_shader(&fragment, &uniforms);
-            let color = shaded_color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, fragment.depth);
+fn load_heightmap(path: &str) -> Option<Arc<Heightmap>> {
+    match Heightmap::load(path) {
+        Ok(heightmap) => Some(Arc::new(heightmap)),
+        Err(error) => {
+            eprintln!("Failed to load heightmap {}: {}", path, error);
+            None
         }
     }
 }
 
-fn main() {
-    let window_width = 800;
-    let window_height = 600;
-    let framebuffer_width = 800;
-    let framebuffer_height = 600;
-    let frame_delay = Duration::from_millis(16);
+fn reload_mesh(path: &str, meshes: &mut HashMap<String, LodMesh>) -> bool {
+    match load_model(path) {
+        Ok(model) => {
+            meshes.insert(path.to_string(), LodMesh::single(model));
+            println!("Malla recargada: {}", path);
+            true
+        }
+        Err(error) => {
+            eprintln!("No se pudo recargar la malla {}: {}", path, error);
+            false
+        }
+    }
+}
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let mut window = Window::new(
-        "LAB GRAFICAS",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    )
-    .unwrap();
+// A missing file just means the factory values; a malformed one is reported.
+fn load_shader_params() -> Option<ShaderParams> {
+    if !std::path::Path::new(SHADER_PARAMS_PATH).exists() {
+        return Some(ShaderParams::new());
+    }
+    ShaderParams::load(SHADER_PARAMS_PATH)
+        .map_err(|error| eprintln!("No se pudieron leer los parámetros {}: {}", SHADER_PARAMS_PATH, error))
+        .ok()
+}
 
-    window.set_position(500, 500);
-    window.update();
+// the sky reflected by smooth surfaces: assets/environment.ppm if there is one,
+// or the background stars
+fn load_environment(stars: &StarCatalog) -> Arc<EnvironmentMap> {
+    if std::path::Path::new(ENVIRONMENT_PATH).exists() {
+        match EnvironmentMap::load(ENVIRONMENT_PATH) {
+            Ok(environment) => return Arc::new(environment),
+            Err(error) => eprintln!("No se pudo leer el entorno {}: {}", ENVIRONMENT_PATH, error),
+        }
+    }
+    Arc::new(EnvironmentMap::from_stars(1024, 512, stars))
+}
 
-    framebuffer.set_background_color(0x333355);
+// background stars: assets/stars.txt if there is one, or a generated sky
+fn load_star_catalog() -> StarCatalog {
+    if std::path::Path::new(STAR_CATALOG_PATH).exists() {
+        match StarCatalog::load(STAR_CATALOG_PATH) {
+            Ok(catalog) => return catalog,
+            Err(error) => eprintln!("No se pudo leer el catálogo de estrellas {}: {}", STAR_CATALOG_PATH, error),
+        }
+    }
+    StarCatalog::generate(6000, 1)
+}
 
-    // model position
-    let translation = Vec3::new(0.0, 0.0, 0.0);
-    let rotation = Vec3::new(0.0, 0.0, 0.0);
-    let scale = 1.0f32;
+// returns whether the scene changed
+fn reload_scene(scene: &mut Scene, path: &str) -> bool {
+    // only the nodes touched by the diff change; camera and time are preserved
+    match Scene::load(path) {
+        Ok(new_scene) => {
+            let changes = scene_diff::diff(scene, &new_scene);
+            if changes.is_empty() {
+                return false;
+            }
+            for conflict in scene.merge(&changes) {
+                eprintln!("Conflicto en {}.{}: {}", conflict.name, conflict.key, conflict.message);
+            }
+            println!("Escena recargada ({}): {}", path, changes.touched_nodes().join(", "));
+            true
+        }
+        Err(error) => {
+            eprintln!("No se pudo recargar la escena {}: {}", path, error);
+            false
+        }
+    }
+}
 
-    // camera parameters
-    let mut camera = Camera::new(
-        Vec3::new(0.0, 0.0, 5.0),
-        Vec3::new(0.0, 0.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0)
-    );
+// File name for a scene saved under assets/scenes/: only [a-z0-9_], so a hand-edited
+// node name like "../../x" cannot point the file outside the folder
+fn scene_file_name(name: &str) -> String {
+    let file_name: String = name.to_lowercase().chars()
+        .filter_map(|character| match character {
+            'a'..='z' | '0'..='9' | '_' => Some(character),
+            ' ' | '-' => Some('_'),
+            _ => None,
+        })
+        .collect();
+    if file_name.is_empty() { "escena".to_string() } else { file_name }
+}
 
-    let obj = Obj::load("assets/models/sphere.obj").expect("Failed to load obj");
-    let vertex_arrays = obj.get_vertex_array(); 
-    let mut time = 0;
+fn save_scene(scene: &Scene) -> Option<String> {
+    let name = scene.nodes.first().map_or("escena", |node| node.name.as_str());
+    let path = format!("assets/scenes/{}.scene", scene_file_name(name));
 
-    while window.is_open() {
-        if window.is_key_down(Key::Escape) {
-            break;
+    // report what changed against the previously saved version
+    if let Ok(previous) = Scene::load(&path) {
+        let changes = scene_diff::diff(&previous, scene);
+        if !changes.is_empty() {
+            print!("Cambios respecto a {}:\n{}", path, changes);
         }
+    }
 
-        time += 1;
+    match scene.save(&path) {
+        Ok(()) => {
+            println!("Escena guardada en {}", path);
+            Some(path)
+        }
+        Err(error) => {
+            eprintln!("No se pudo guardar la escena en {}: {}", path, error);
+            None
+        }
+    }
+}
 
-        handle_input(&window, &mut camera);
+fn handle_edit_input(window: &Window, scene: &mut Scene, selected: &mut usize, undo_stack: &mut UndoStack) -> bool {
+    let ctrl = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
+    if ctrl && window.is_key_pressed(Key::Z, KeyRepeat::No) {
+      if let Some(description) = undo_stack.undo(scene) {
+        println!("Deshacer: {}", description);
+        return true;
+      }
+    }
+    if ctrl && window.is_key_pressed(Key::Y, KeyRepeat::No) {
+      if let Some(description) = undo_stack.redo(scene) {
+        println!("Rehacer: {}", description);
+        return true;
+      }
+    }
 
-        framebuffer.clear();
+    if scene.nodes.is_empty() {
+      return false;
+    }
+    *selected = (*selected).min(scene.nodes.len() - 1);
 
-        let noise = create_noise();
-        let model_matrix = create_model_matrix(translation, scale, rotation);
-        let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
-        let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32);
-        let viewport_matrix = create_viewport_matrix(framebuffer_width as f32, framebuffer_height as f32);
-        let uniforms = Uniforms { 
-            model_matrix, 
-            view_matrix, 
-            projection_matrix, 
-            viewport_matrix,
-            time,
-            noise
-        };
+    if window.is_key_pressed(Key::Tab, KeyRepeat::No) {
+      *selected = (*selected + 1) % scene.nodes.len();
+      println!("Seleccionado: {}", scene.nodes[*selected].name);
+    }
 
+    let index = *selected;
+    let name = scene.nodes[index].name.clone();
 
-        framebuffer.set_background_color(0x000000);
+    // 1-7 switch the selected body to one of the planet shaders and 8-0 to the debug
+    // views of its normals, UVs and lighting
+    let shader_keys = [
+      (Key::Key1, PlanetShader::Earth),
+      (Key::Key2, PlanetShader::Moon),
+      (Key::Key3, PlanetShader::Sun),
+      (Key::Key4, PlanetShader::Gas),
+      (Key::Key5, PlanetShader::Rocky),
+      (Key::Key6, PlanetShader::Star),
+      (Key::Key7, PlanetShader::Fantasy),
+      (Key::Key8, PlanetShader::DebugNormal),
+      (Key::Key9, PlanetShader::DebugUv),
+      (Key::Key0, PlanetShader::DebugIntensity),
+    ];
+    for (key, shader) in shader_keys {
+      if window.is_key_pressed(key, KeyRepeat::No) {
+        return undo_stack.apply(scene, &format!("shader {} en {}", shader.name(), name), |scene| {
+          scene.nodes[index].shader = shader
+        });
+      }
+    }
 
-        render(&mut framebuffer, &uniforms, &vertex_arrays);
+    if window.is_key_pressed(Key::Equal, KeyRepeat::Yes) {
+      undo_stack.apply(scene, &format!("agrandar {}", name), |scene| scene.nodes[index].radius *= 1.1)
+    } else if window.is_key_pressed(Key::Minus, KeyRepeat::Yes) {
+      undo_stack.apply(scene, &format!("achicar {}", name), |scene| scene.nodes[index].radius /= 1.1)
+    } else if window.is_key_pressed(Key::Insert, KeyRepeat::No) {
+      undo_stack.apply(scene, &format!("agregar luna a {}", name), |scene| add_moon(scene, index))
+    } else if window.is_key_pressed(Key::N, KeyRepeat::No) {
+      undo_stack.apply(scene, &format!("nubes de {}", name), |scene| scene.nodes[index].features ^= FEATURE_CLOUDS)
+    } else if window.is_key_pressed(Key::L, KeyRepeat::No) {
+      undo_stack.apply(scene, &format!("luces de {}", name), |scene| scene.nodes[index].features ^= FEATURE_CITY_LIGHTS)
+    } else if window.is_key_pressed(Key::T, KeyRepeat::No) {
+      let shading = scene.nodes[index].shading.next();
+      undo_stack.apply(scene, &format!("sombreado {} de {}", shading.name(), name), |scene| {
+        scene.nodes[index].shading = shading
+      })
+    } else if window.is_key_pressed(Key::H, KeyRepeat::No) {
+      let action = if scene.nodes[index].visible { "ocultar" } else { "mostrar" };
+      undo_stack.apply(scene, &format!("{} {}", action, name), |scene| {
+        scene.nodes[index].visible = !scene.nodes[index].visible
+      })
+    } else if window.is_key_pressed(Key::Delete, KeyRepeat::No) {
+      undo_stack.apply(scene, &format!("eliminar {}", name), |scene| scene.remove_node(index))
+    } else {
+      false
+    }
+}
 
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
+fn add_moon(scene: &mut Scene, planet: usize) {
+    // outermost existing orbit around the planet, so the new moon doesn't overlap it
+    let children: Vec<usize> = (0..scene.nodes.len())
+        .filter(|&child| scene.nodes[child].parent == Some(planet))
+        .collect();
+    let outer = children.iter()
+        .filter_map(|&child| scene.nodes[child].orbit.map(|orbit| orbit.distance + scene.nodes[child].radius))
+        .fold(0.0, f32::max);
 
-        std::thread::sleep(frame_delay);
+    let seed = scene.nodes.len() as u64 + scene.nodes[planet].seed as u64;
+    if let Some(&moon) = generate_moons(scene, planet, seed, 1).first() {
+        let name = (children.len() + 1..)
+            .map(|number| format!("{} {}", scene.nodes[planet].name, number))
+            .find(|name| scene.find(name).is_none())
+            .unwrap_or_default();
+        let radius = scene.nodes[moon].radius;
+        let node = &mut scene.nodes[moon];
+        node.name = name;
+        if let Some(orbit) = node.orbit.as_mut() {
+            orbit.distance = orbit.distance.max(outer + radius * 3.0);
+        }
     }
 }
 
+fn handle_galaxy_input(window: &Window, current: Option<usize>, count: usize) -> Option<usize> {
+    // ] salta al siguiente sistema estelar, [ al anterior.
+    if window.is_key_pressed(Key::RightBracket, KeyRepeat::No) {
+      return Some(current.map_or(0, |index| (index + 1) % count));
+    }
+    if window.is_key_pressed(Key::LeftBracket, KeyRepeat::No) {
+      return Some(current.map_or(count - 1, |index| (index + count - 1) % count));
+    }
+    None
+}
+
 fn handle_input(window: &Window, camera: &mut Camera) {
-    let movement_speed = 1.0;
+    let movement_speed = 0.1;
     let rotation_speed = PI/50.0;
     let zoom_speed = 0.1;
-   
-    //  camera orbit controls
-    if window.is_key_down(Key::Left) {
-      camera.orbit(rotation_speed, 0.0);
+    let roll_speed = PI/200.0;
+
+    // C alterna entre orbitar y volar libremente
+    if window.is_key_pressed(Key::C, KeyRepeat::No) {
+      camera.toggle_mode();
+    }
+
+    // PageUp/PageDown tilt the horizon in either mode and End levels it again
+    if window.is_key_down(Key::PageUp) {
+      camera.roll(-roll_speed);
+    }
+    if window.is_key_down(Key::PageDown) {
+      camera.roll(roll_speed);
     }
-    if window.is_key_down(Key::Right) {
-      camera.orbit(-rotation_speed, 0.0);
+    if window.is_key_pressed(Key::End, KeyRepeat::No) {
+      camera.level();
     }
 
-    // Camera zoom controls
-    if window.is_key_down(Key::Up) {
-      camera.zoom(zoom_speed);
+    // / switches between perspective and an orthographic map view
+    if window.is_key_pressed(Key::Slash, KeyRepeat::No) {
+      camera.toggle_projection();
+      println!("Proyección: {}", camera.projection.name());
     }
-    if window.is_key_down(Key::Down) {
-      camera.zoom(-zoom_speed);
+
+    match camera.mode {
+      CameraMode::Orbit => {
+        //  camera orbit controls
+        if window.is_key_down(Key::Left) {
+          camera.orbit(rotation_speed, 0.0);
+        }
+        if window.is_key_down(Key::Right) {
+          camera.orbit(-rotation_speed, 0.0);
+        }
+
+        // Camera zoom controls
+        if window.is_key_down(Key::Up) {
+          camera.zoom(zoom_speed);
+        }
+        if window.is_key_down(Key::Down) {
+          camera.zoom(-zoom_speed);
+        }
+      }
+      CameraMode::Fly => {
+        // WASD to move, Q/E to rise and sink
+        if window.is_key_down(Key::W) {
+          camera.move_forward(movement_speed);
+        }
+        if window.is_key_down(Key::S) {
+          camera.move_forward(-movement_speed);
+        }
+        if window.is_key_down(Key::D) {
+          camera.strafe(movement_speed);
+        }
+        if window.is_key_down(Key::A) {
+          camera.strafe(-movement_speed);
+        }
+        if window.is_key_down(Key::E) {
+          camera.rise(movement_speed);
+        }
+        if window.is_key_down(Key::Q) {
+          camera.rise(-movement_speed);
+        }
+
+        // arrows to look around
+        if window.is_key_down(Key::Left) {
+          camera.move_center(Vec3::new(1.0, 0.0, 0.0));
+        }
+        if window.is_key_down(Key::Right) {
+          camera.move_center(Vec3::new(-1.0, 0.0, 0.0));
+        }
+        if window.is_key_down(Key::Up) {
+          camera.move_center(Vec3::new(0.0, 1.0, 0.0));
+        }
+        if window.is_key_down(Key::Down) {
+          camera.move_center(Vec3::new(0.0, -1.0, 0.0));
+        }
+      }
+    }
+}
+
+// pixels the cursor may wander between press and release for it to still count as a click
+const CLICK_SLOP: f32 = 4.0;
+
+// where the cursor was and what the left button was doing on the previous frame
+#[derive(Default)]
+struct MouseState {
+    position: Option<(f32, f32)>,
+    left_down: bool,
+    // pixels travelled since the left button went down
+    dragged: f32,
+}
+
+fn handle_mouse_input(window: &Window, state: &mut MouseState, controls: &mut MouseControls, camera: &mut Camera) -> Option<(f32, f32)> {
+  // left drag orbits, right drag looks around and the wheel zooms; a left press and
+  // release that barely moved is a click, returned so the body under it can be picked
+  let position = window.get_mouse_pos(MouseMode::Clamp);
+  let left = window.get_mouse_down(MouseButton::Left);
+  let right = window.get_mouse_down(MouseButton::Right);
+
+  if let (Some((x, y)), Some((last_x, last_y))) = (position, state.position) {
+    let (dx, dy) = (x - last_x, y - last_y);
+    if left && state.left_down {
+      state.dragged += dx.abs() + dy.abs();
+      controls.drag_orbit(dx, dy);
+    } else if right {
+      controls.drag_pan(dx, dy);
+    }
+  }
+  if let Some((_, steps)) = window.get_scroll_wheel() {
+    controls.scroll(steps);
+  }
+  controls.apply(camera);
+
+  let click = if !left && state.left_down && state.dragged < CLICK_SLOP { state.position } else { None };
+  if left && !state.left_down {
+    state.dragged = 0.0;
+  }
+  state.left_down = left;
+  state.position = position;
+  click
+}
+
+fn handle_post_process_input(window: &Window, post_process: &mut PostProcess) -> bool {
+    // F activa/desactiva la niebla, V la profundidad de campo y B el resplandor.
+    let mut changed = false;
+    if window.is_key_pressed(Key::F, KeyRepeat::No) {
+      post_process.fog_enabled = !post_process.fog_enabled;
+      changed = true;
+    }
+    if window.is_key_pressed(Key::V, KeyRepeat::No) {
+      post_process.dof_enabled = !post_process.dof_enabled;
+      changed = true;
+    }
+    if window.is_key_pressed(Key::B, KeyRepeat::No) {
+      post_process.bloom_enabled = !post_process.bloom_enabled;
+      changed = true;
+    }
+    // U cycles the color-blindness filters: simulated, then compensated
+    if window.is_key_pressed(Key::U, KeyRepeat::No) {
+      post_process.color_vision = post_process.color_vision.next();
+      println!("Visión: {}", post_process.color_vision.label());
+      changed = true;
+    }
+    // O cycles the output encoding: linear, gamma 2.2, sRGB
+    if window.is_key_pressed(Key::O, KeyRepeat::No) {
+      post_process.output_encoding = post_process.output_encoding.next();
+      println!("Salida: {:?}", post_process.output_encoding);
+      changed = true;
+    }
+    // X toggles the ordered dithering applied when quantizing to 8 bits
+    if window.is_key_pressed(Key::X, KeyRepeat::No) {
+      post_process.dither = !post_process.dither;
+      println!("Tramado: {}", if post_process.dither { "sí" } else { "no" });
+      changed = true;
+    }
+    // period toggles the lens flare of the sun
+    if window.is_key_pressed(Key::Period, KeyRepeat::No) {
+      post_process.flare_enabled = !post_process.flare_enabled;
+      println!("Destello de lente: {}", if post_process.flare_enabled { "sí" } else { "no" });
+      changed = true;
+    }
+    changed
+}
+
+fn handle_layer_input(window: &Window, camera: &mut Camera) {
+    // F1-F4 show or hide the bodies, orbits, debug and ui layers
+    let layers = [
+      (Key::F1, LAYER_BODIES),
+      (Key::F2, LAYER_ORBITS),
+      (Key::F3, LAYER_DEBUG),
+      (Key::F4, LAYER_UI),
+    ];
+    for (key, layer) in layers {
+      if window.is_key_pressed(key, KeyRepeat::No) {
+        camera.toggle_layer(layer);
+        println!("Capas visibles: {}", layers_to_string(camera.layer_mask));
+      }
     }
 }
@@ -0,0 +1,65 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use crate::profiler::{FrameTiming, Stage};
+
+/// Registro de métricas por fotograma en formato JSON Lines: un objeto JSON por línea.
+///
+/// Cada línea tiene el número de fotograma, el tiempo total y el de cada etapa en
+/// milisegundos, los triángulos y fragmentos dibujados y la memoria residente del
+/// proceso en bytes (`null` donde no se puede medir):
+///
+/// ```text
+/// {"frame":120,"frame_ms":16.812,"stages_ms":{"vertex":1.204,"raster":2.310,"fragment":6.871,"present":0.954},"triangles":5120,"fragments":183402,"memory_bytes":48312320}
+/// ```
+///
+/// Las líneas se agregan al final del archivo, así que varias ejecuciones (por
+/// ejemplo de dos ramas) pueden ir al mismo registro o a registros separados para
+/// graficarlas juntas.
+pub struct MetricsLog {
+    writer: BufWriter<File>,
+}
+
+impl MetricsLog {
+    /// Abre un registro para agregar líneas; lo crea si no existe.
+    ///
+    /// # Parámetros
+    /// - `path`: Archivo del registro.
+    pub fn append<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(MetricsLog { writer: BufWriter::new(file) })
+    }
+
+    /// Agrega la línea de un fotograma y la escribe en el archivo enseguida, para
+    /// no perder las últimas si el programa termina de golpe.
+    ///
+    /// # Parámetros
+    /// - `frame`: Número del fotograma.
+    /// - `timing`: Mediciones del fotograma (ver `Profiler::last`).
+    pub fn record(&mut self, frame: u32, timing: &FrameTiming) -> io::Result<()> {
+        let milliseconds = |duration: std::time::Duration| duration.as_secs_f64() * 1000.0;
+        let stages: Vec<String> = Stage::ALL.iter()
+            .map(|&stage| format!("\"{}\":{:.3}", stage.name(), milliseconds(timing.stage(stage))))
+            .collect();
+        let memory = resident_memory().map_or("null".to_string(), |bytes| bytes.to_string());
+        writeln!(
+            self.writer,
+            "{{\"frame\":{},\"frame_ms\":{:.3},\"stages_ms\":{{{}}},\"triangles\":{},\"fragments\":{},\"objects\":{},\"culled_objects\":{},\"memory_bytes\":{}}}",
+            frame, milliseconds(timing.frame), stages.join(","), timing.triangles, timing.fragments,
+            timing.objects, timing.culled_objects, memory,
+        )?;
+        self.writer.flush()
+    }
+}
+
+/// Memoria residente del proceso en bytes, leída de `/proc/self/status`.
+///
+/// # Retorna
+/// `None` en sistemas sin `/proc` (como Windows o macOS).
+pub fn resident_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    // "VmRSS:     48312 kB"
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+use nalgebra_glm::Vec3;
+use crate::framebuffer::Framebuffer;
+
+/// Fotogramas que se mezclan por defecto, el actual incluido.
+pub const DEFAULT_FRAMES: usize = 6;
+
+/// Factor de mezcla por defecto (ver `MotionBlur::blend_factor`).
+pub const DEFAULT_BLEND_FACTOR: f32 = 0.6;
+
+/// Búfer de acumulación para desenfoque de movimiento: cada fotograma se mezcla
+/// con los anteriores, con pesos que decaen con la edad.
+///
+/// Guarda los últimos `frames` fotogramas sin mezclar; el de hace `n` fotogramas
+/// pesa `blend_factor` elevado a `n` respecto del actual. Lo que se mueve rápido
+/// (una luna en una órbita cerrada, un paneo de la cámara) deja una estela, y lo
+/// que está quieto no cambia. Se aplica a la imagen terminada, después del
+/// posproceso y antes del HUD.
+#[derive(Debug, Clone)]
+pub struct MotionBlur {
+    /// Fotogramas que se mezclan, el actual incluido (al menos uno).
+    frames: usize,
+    /// Peso de cada fotograma respecto del siguiente más nuevo, entre 0 (sin estela)
+    /// y 1 (todos pesan lo mismo).
+    pub blend_factor: f32,
+    /// Si es `false`, `apply` deja pasar los fotogramas sin mezclar.
+    enabled: bool,
+    /// Fotogramas anteriores sin mezclar, del más nuevo al más viejo.
+    history: VecDeque<Vec<u32>>,
+    /// Búfer del último fotograma que salió de `history`, para copiar ahí el
+    /// siguiente sin pedir memoria nueva.
+    spare: Vec<u32>,
+    /// Tamaño de los fotogramas de `history`.
+    size: (usize, usize),
+}
+
+impl Default for MotionBlur {
+    fn default() -> Self {
+        MotionBlur::new(DEFAULT_FRAMES, DEFAULT_BLEND_FACTOR)
+    }
+}
+
+impl MotionBlur {
+    /// Crea un desenfoque activado y sin historia.
+    ///
+    /// # Parámetros
+    /// - `frames`: Fotogramas que se mezclan, el actual incluido (al menos uno).
+    /// - `blend_factor`: Peso de cada fotograma respecto del siguiente, entre 0 y 1.
+    pub fn new(frames: usize, blend_factor: f32) -> Self {
+        MotionBlur {
+            frames: frames.max(1),
+            blend_factor: blend_factor.clamp(0.0, 1.0),
+            enabled: true,
+            history: VecDeque::new(),
+            spare: Vec::new(),
+            size: (0, 0),
+        }
+    }
+
+    /// Fotogramas que se mezclan, el actual incluido.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Cambia cuántos fotogramas se mezclan; si son menos, se olvidan los más viejos.
+    pub fn set_frames(&mut self, frames: usize) {
+        self.frames = frames.max(1);
+        self.history.truncate(self.frames - 1);
+    }
+
+    /// Indica si `apply` mezcla los fotogramas.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Activa o desactiva la mezcla; se puede cambiar en cualquier fotograma. Al
+    /// desactivarla se olvida la historia, así al volver a activarla no reaparecen
+    /// fotogramas viejos.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.reset();
+        }
+        self.enabled = enabled;
+    }
+
+    /// Olvida los fotogramas anteriores, por ejemplo en un corte de cámara.
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+
+    /// Mezcla el fotograma del framebuffer con los anteriores y lo guarda para los
+    /// siguientes. Si el tamaño cambió desde el último, empieza de nuevo.
+    ///
+    /// # Parámetros
+    /// - `framebuffer`: Imagen terminada; se reemplaza por la mezcla.
+    pub fn apply(&mut self, framebuffer: &mut Framebuffer) {
+        if !self.enabled || self.frames == 1 {
+            return;
+        }
+        let size = (framebuffer.width, framebuffer.height);
+        if size != self.size {
+            self.size = size;
+            self.reset();
+        }
+
+        let mut current = std::mem::take(&mut self.spare);
+        current.resize(framebuffer.buffer.len(), 0);
+        current.copy_from_slice(&framebuffer.buffer);
+        if !self.history.is_empty() {
+            // pesos normalizados, del fotograma actual al más viejo
+            let mut weights: Vec<f32> = (0..=self.history.len()).map(|age| self.blend_factor.powi(age as i32)).collect();
+            let total: f32 = weights.iter().sum();
+            weights.iter_mut().for_each(|weight| *weight /= total);
+
+            let (width, format) = (framebuffer.width, framebuffer.pixel_format());
+            for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+                let mut sum = Vec3::zeros();
+                let frames = std::iter::once(&current).chain(self.history.iter());
+                for (frame, weight) in frames.zip(&weights) {
+                    sum += format.unpack(frame[index]) * *weight;
+                }
+                *pixel = format.pack(sum, index % width, index / width);
+            }
+        }
+
+        // entra el actual y, si ya hay `frames - 1`, sale el más viejo y su búfer queda para el siguiente
+        if self.history.len() == self.frames - 1 {
+            self.spare = self.history.pop_back().unwrap_or_default();
+        }
+        self.history.push_front(current);
+    }
+}
@@ -1,12 +1,141 @@
 // Importa las bibliotecas necesarias.
 use tobj; // Para cargar archivos OBJ.
-use nalgebra_glm::{Vec2, Vec3}; // Para trabajar con vectores 2D y 3D.
+use nalgebra_glm::{Vec2, Vec3, Mat4}; // Para trabajar con vectores 2D y 3D y matrices.
+use std::f32::consts::PI; // Para las coordenadas de textura esféricas.
+use std::io::BufRead; // Para cargar objetos desde memoria.
+use std::cell::OnceCell; // Para guardar los volúmenes envolventes ya calculados.
+use std::path::Path; // Para resolver las rutas de las texturas.
+use std::sync::Arc; // Para compartir los materiales sin copiarlos.
+use crate::bounds::Bounds; // Caja y esfera envolventes de la malla.
 use crate::vertex::Vertex; // Importa la estructura Vertex desde el módulo vertex.
+use crate::color::Color; // Importa la estructura Color para los colores de los materiales.
 
 // Define la estructura principal que representa un objeto 3D.
 pub struct Obj {
     // Contiene un vector de mallas (meshes) del objeto.
     meshes: Vec<Mesh>,
+    // Materiales leídos del archivo .mtl que acompaña al objeto.
+    materials: Vec<Material>,
+}
+
+// Propiedades de un material de un archivo .mtl.
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub name: String,                     // Nombre del material (`newmtl`).
+    pub ambient: Color,                   // Color ambiental (`Ka`).
+    pub diffuse: Color,                   // Color difuso (`Kd`).
+    pub specular: Color,                  // Color especular (`Ks`).
+    pub shininess: f32,                   // Exponente especular (`Ns`).
+    pub roughness: Option<f32>,           // Rugosidad de la extensión PBR (`Pr`), si la trae.
+    pub metallic: Option<f32>,            // Metalicidad de la extensión PBR (`Pm`), si la trae.
+    pub diffuse_texture: Option<String>,  // Textura difusa (`map_Kd`), relativa al directorio actual.
+    pub specular_texture: Option<String>, // Textura especular (`map_Ks`).
+    pub normal_texture: Option<String>,   // Mapa de normales (`map_Bump` / `norm`).
+}
+
+// Material gris mate que se usa cuando una cara no tiene material.
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            name: String::new(),
+            ambient: Color::new(20, 20, 20),
+            diffuse: Color::new(200, 200, 200),
+            specular: Color::black(),
+            shininess: 1.0,
+            roughness: None,
+            metallic: None,
+            diffuse_texture: None,
+            specular_texture: None,
+            normal_texture: None,
+        }
+    }
+}
+
+// Malla lista para dibujar: buffer de vértices sin repetir, índices de los triángulos
+// y los materiales a los que apunta el campo `material` de cada vértice.
+// Si se cambian las posiciones de `vertices` a mano hay que llamar a `invalidate_bounds`.
+#[derive(Debug, Clone, Default)]
+pub struct Model {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub materials: Arc<[Material]>, // Compartidos con los uniforms de cada objeto que usa la malla.
+    bounds: OnceCell<Option<Bounds>>, // Se calcula la primera vez que se pide.
+}
+
+// Punto de la malla que se lleva al origen al normalizarla.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Center {
+    BoundingBox, // Centro de la caja que envuelve la malla.
+    Centroid,    // Promedio de las posiciones de los vértices.
+}
+
+// Opciones para normalizar una malla al cargarla, de modo que un modelo descargado
+// quede centrado y con un tamaño razonable en la escena sin ajustar matrices a mano.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormalizeOptions {
+    pub center: Option<Center>, // Si se indica, la malla se traslada para que ese punto quede en el origen.
+    pub radius: Option<f32>,    // Si se indica, la malla se escala para que su vértice más lejano quede a esa distancia.
+}
+
+impl Model {
+    // Crea un modelo a partir de su buffer de vértices, sus índices y sus materiales.
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>, materials: Vec<Material>) -> Self {
+        Model { vertices, indices, materials: materials.into(), bounds: OnceCell::new() }
+    }
+
+    // Caja y esfera envolventes en espacio del modelo; `None` si la malla no tiene vértices.
+    // Se calculan una sola vez y quedan guardadas.
+    pub fn bounds(&self) -> Option<Bounds> {
+        *self.bounds.get_or_init(|| Bounds::from_points(self.vertices.iter().map(|vertex| &vertex.position)))
+    }
+
+    // Caja y esfera envolventes en el mundo, con la matriz de modelo del objeto.
+    pub fn world_bounds(&self, model_matrix: &Mat4) -> Option<Bounds> {
+        self.bounds().map(|bounds| bounds.transformed(model_matrix))
+    }
+
+    // Descarta los volúmenes guardados; se recalculan al pedirlos otra vez.
+    pub fn invalidate_bounds(&mut self) {
+        self.bounds = OnceCell::new();
+    }
+
+    // Centra y escala la malla según las opciones. Las normales no cambian, porque
+    // la escala es uniforme.
+    pub fn normalize(&mut self, options: &NormalizeOptions) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        let center = match options.center {
+            Some(Center::BoundingBox) => {
+                let (min, max) = self.vertices.iter().fold(
+                    (Vec3::repeat(f32::INFINITY), Vec3::repeat(f32::NEG_INFINITY)),
+                    |(min, max), vertex| (min.inf(&vertex.position), max.sup(&vertex.position)),
+                );
+                (min + max) / 2.0
+            }
+            Some(Center::Centroid) => {
+                self.vertices.iter().map(|vertex| vertex.position).sum::<Vec3>() / self.vertices.len() as f32
+            }
+            None => Vec3::zeros(),
+        };
+
+        // Una malla de un solo punto (o vacía tras centrar) no se puede escalar.
+        let scale = options.radius
+            .map(|radius| {
+                let extent = self.vertices.iter()
+                    .map(|vertex| (vertex.position - center).magnitude())
+                    .fold(0.0, f32::max);
+                if extent > f32::EPSILON { radius / extent } else { 1.0 }
+            })
+            .unwrap_or(1.0);
+
+        for vertex in &mut self.vertices {
+            vertex.position = (vertex.position - center) * scale;
+            vertex.transformed_position = vertex.position;
+        }
+        self.invalidate_bounds();
+    }
 }
 
 // Define la estructura que representa una malla, que contiene vértices, normales, coordenadas de textura e índices.
@@ -14,7 +143,9 @@ struct Mesh {
     vertices: Vec<Vec3>, // Vértices de la malla en 3D.
     normals: Vec<Vec3>,  // Normales para cada vértice.
     texcoords: Vec<Vec2>, // Coordenadas de textura para cada vértice.
+    colors: Vec<Color>,   // Colores por vértice (extensión `v x y z r g b`), si el archivo los trae.
     indices: Vec<u32>,    // Índices que definen el orden de los vértices.
+    material: Option<usize>, // Material de todas las caras de la malla (tobj separa las mallas por material).
 }
 
 // Implementación de métodos para la estructura Obj.
@@ -22,65 +153,266 @@ impl Obj {
     // Carga un objeto desde un archivo .obj.
     pub fn load(filename: &str) -> Result<Self, tobj::LoadError> {
         // Carga el archivo OBJ utilizando tobj.
-        let (models, _) = tobj::load_obj(filename, &tobj::LoadOptions {
-            single_index: true, // Usa un solo índice para los vértices.
-            triangulate: true,  // Triangula la malla.
-            ..Default::default() // Usa las opciones predeterminadas.
+        let (models, materials) = tobj::load_obj(filename, &load_options())?;
+        let directory = Path::new(filename).parent().unwrap_or(Path::new(""));
+        Obj::from_models(models, materials, directory, filename)
+    }
+
+    // Carga un objeto desde un buffer en memoria. Las referencias a archivos .mtl se ignoran.
+    pub fn load_from_buf<R: BufRead>(reader: &mut R) -> Result<Self, tobj::LoadError> {
+        let (models, materials) = tobj::load_obj_buf(reader, &load_options(), |_| {
+            Err(tobj::LoadError::OpenFileFailed)
         })?;
+        Obj::from_models(models, materials, Path::new(""), "<buffer>")
+    }
 
+    // Convierte el resultado de tobj, validando la geometría: las coordenadas deben ser
+    // finitas y los índices deben apuntar a vértices existentes. Las normales y coordenadas
+    // de textura que faltan (o no cuadran con los vértices) se generan a partir de las caras.
+    fn from_models(
+        models: Vec<tobj::Model>,
+        materials: Result<Vec<tobj::Material>, tobj::LoadError>,
+        directory: &Path,
+        source: &str,
+    ) -> Result<Self, tobj::LoadError> {
         // Convierte cada modelo en una malla.
-        let meshes = models.into_iter().map(|model| {
+        let meshes: Vec<Mesh> = models.into_iter().map(|model| {
             let mesh = model.mesh; // Obtiene la malla del modelo.
-            Mesh {
+            let mut converted = Mesh {
                 // Convierte las posiciones de los vértices en Vec3.
-                vertices: mesh.positions.chunks(3)
+                vertices: mesh.positions.chunks_exact(3)
                     .map(|v| Vec3::new(v[0], v[1], v[2]))
                     .collect(),
                 // Convierte las normales en Vec3.
-                normals: mesh.normals.chunks(3)
+                normals: mesh.normals.chunks_exact(3)
                     .map(|n| Vec3::new(n[0], n[1], n[2]))
                     .collect(),
                 // Convierte las coordenadas de textura en Vec2, invirtiendo el eje Y.
-                texcoords: mesh.texcoords.chunks(2)
+                texcoords: mesh.texcoords.chunks_exact(2)
                     .map(|t| Vec2::new(t[0], 1.0 - t[1]))
                     .collect(),
+                // Convierte los colores por vértice (de 0 a 1) en Color.
+                colors: mesh.vertex_color.chunks_exact(3)
+                    .map(|c| {
+                        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                        Color::new(channel(c[0]), channel(c[1]), channel(c[2]))
+                    })
+                    .collect(),
                 // Usa los índices directamente de la malla.
                 indices: mesh.indices,
+                material: mesh.material_id,
+            };
+            validate_mesh(&converted)?;
+            complete_attributes(&mut converted, &model.name, source);
+            Ok(converted)
+        }).collect::<Result<_, tobj::LoadError>>()?;
+
+        // Un .mtl ausente o inválido no impide usar la geometría.
+        let materials = match materials {
+            Ok(materials) => {
+                materials.into_iter().map(|material| convert_material(material, directory)).collect()
             }
-        }).collect();
+            Err(error) => {
+                if models_reference_materials(&meshes) {
+                    eprintln!("No se pudieron cargar los materiales de {}: {}", source, error);
+                }
+                Vec::new()
+            }
+        };
 
         // Devuelve el objeto cargado.
-        Ok(Obj { meshes })
+        Ok(Obj { meshes, materials })
+    }
+
+    // Devuelve los materiales del objeto.
+    pub fn materials(&self) -> &[Material] {
+        &self.materials
+    }
+
+    // Devuelve los buffers de vértices e índices junto con los materiales, listos para dibujar.
+    pub fn get_model(&self) -> Model {
+        Model::new(self.get_vertex_buffer(), self.get_index_buffer(), self.materials.clone())
     }
 
-    // Devuelve un vector de vértices (Vertex) a partir de la malla.
+    // Devuelve un vector de vértices (Vertex) a partir de la malla, repitiendo
+    // cada vértice por cada triángulo que lo usa (tres vértices por triángulo).
     pub fn get_vertex_array(&self) -> Vec<Vertex> {
+        let vertex_buffer = self.get_vertex_buffer();
+        self.get_index_buffer().into_iter()
+            .map(|index| vertex_buffer[index as usize].clone())
+            .collect()
+    }
+
+    // Devuelve un vértice por cada vértice distinto del archivo, con las mallas una tras otra.
+    pub fn get_vertex_buffer(&self) -> Vec<Vertex> {
         let mut vertices = Vec::new(); // Vector para almacenar los vértices.
 
         // Itera sobre cada malla.
         for mesh in &self.meshes {
-            // Itera sobre cada índice en la malla.
-            for &index in &mesh.indices {
-                // Obtiene la posición del vértice usando el índice.
-                let position = mesh.vertices[index as usize];
-                // Obtiene la normal, o usa una normal predeterminada si no está disponible.
-                let normal = mesh.normals.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
-                // Obtiene las coordenadas de textura, o usa (0, 0) si no están disponibles.
-                let tex_coords = mesh.texcoords.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec2::new(0.0, 0.0));
-
-                // Calcula la elevación (puedes personalizar este cálculo).
-                let elevation = position.y; // Usa la componente Y como elevación.
-
-                // Crea un nuevo vértice y lo agrega al vector.
-                vertices.push(Vertex::new(position, normal, tex_coords, elevation));
+            let material = mesh.material.filter(|&material| material < self.materials.len());
+
+            // Itera sobre cada vértice de la malla.
+            for (index, &position) in mesh.vertices.iter().enumerate() {
+                // La carga garantiza una normal y unas coordenadas de textura por vértice.
+                let normal = mesh.normals[index];
+                let tex_coords = mesh.texcoords[index];
+
+                // Crea un nuevo vértice con el material de su malla y lo agrega al vector. La
+                // elevación la calcula el shader de vértices según el `ElevationMode` del cuerpo.
+                let mut vertex = Vertex::new(position, normal, tex_coords, 0.0);
+                vertex.material = material;
+                if let Some(&color) = mesh.colors.get(index) {
+                    vertex.color = color;
+                }
+                vertices.push(vertex);
             }
         }
 
         // Devuelve el vector de vértices.
         vertices
     }
+
+    // Devuelve los índices de los triángulos (tres por triángulo) dentro de `get_vertex_buffer`.
+    pub fn get_index_buffer(&self) -> Vec<u32> {
+        let mut indices = Vec::new();
+        let mut base = 0; // Posición del primer vértice de la malla en el buffer.
+
+        for mesh in &self.meshes {
+            indices.extend(mesh.indices.iter().map(|&index| base + index));
+            base += mesh.vertices.len() as u32;
+        }
+
+        indices
+    }
+}
+
+// Opciones de carga comunes: un solo índice por vértice y caras trianguladas.
+fn load_options() -> tobj::LoadOptions {
+    tobj::LoadOptions {
+        single_index: true, // Usa un solo índice para los vértices.
+        triangulate: true,  // Triangula la malla.
+        ..Default::default() // Usa las opciones predeterminadas.
+    }
+}
+
+// Rechaza las mallas con coordenadas no finitas (NaN o infinito) o con índices fuera de rango.
+fn validate_mesh(mesh: &Mesh) -> Result<(), tobj::LoadError> {
+    if mesh.vertices.iter().any(|v| !v.iter().all(|c| c.is_finite())) {
+        return Err(tobj::LoadError::PositionParseError);
+    }
+    if mesh.normals.iter().any(|n| !n.iter().all(|c| c.is_finite())) {
+        return Err(tobj::LoadError::NormalParseError);
+    }
+    if mesh.texcoords.iter().any(|t| !t.iter().all(|c| c.is_finite())) {
+        return Err(tobj::LoadError::TexcoordParseError);
+    }
+    if mesh.indices.iter().any(|&index| index as usize >= mesh.vertices.len()) {
+        return Err(tobj::LoadError::FaceVertexOutOfBounds);
+    }
+    Ok(())
+}
+
+// Garantiza una normal y unas coordenadas de textura por vértice. Si el archivo no las trae
+// se generan sin aviso; si trae una cantidad que no coincide con los vértices (caras con y
+// sin normales mezcladas) se avisa y se descartan, porque no se sabe a qué vértice corresponden.
+fn complete_attributes(mesh: &mut Mesh, name: &str, source: &str) {
+    let count = mesh.vertices.len();
+
+    if mesh.normals.len() != count {
+        if !mesh.normals.is_empty() {
+            eprintln!(
+                "{}: la malla '{}' tiene {} normales para {} vértices; se calculan a partir de las caras",
+                source, name, mesh.normals.len(), count
+            );
+        }
+        mesh.normals = face_normals(&mesh.vertices, &mesh.indices);
+    }
+
+    if mesh.texcoords.len() != count {
+        if !mesh.texcoords.is_empty() {
+            eprintln!(
+                "{}: la malla '{}' tiene {} coordenadas de textura para {} vértices; se usa una proyección esférica",
+                source, name, mesh.texcoords.len(), count
+            );
+        }
+        mesh.texcoords = spherical_texcoords(&mesh.vertices);
+    }
+
+    // Los colores son opcionales: si no cuadran se descartan y la malla queda sin color.
+    if !mesh.colors.is_empty() && mesh.colors.len() != count {
+        eprintln!(
+            "{}: la malla '{}' tiene {} colores para {} vértices; se ignoran",
+            source, name, mesh.colors.len(), count
+        );
+        mesh.colors.clear();
+    }
+}
+
+// Calcula la normal de cada vértice sumando las normales de las caras que lo usan,
+// ponderadas por su área. Los vértices sueltos o de caras degeneradas apuntan hacia
+// afuera del origen.
+pub(crate) fn face_normals(vertices: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
+    let mut normals = vec![Vec3::zeros(); vertices.len()];
+
+    for face in indices.chunks_exact(3) {
+        let [a, b, c] = [face[0] as usize, face[1] as usize, face[2] as usize];
+        // El producto cruz tiene largo igual al doble del área del triángulo.
+        let normal = (vertices[b] - vertices[a]).cross(&(vertices[c] - vertices[a]));
+        normals[a] += normal;
+        normals[b] += normal;
+        normals[c] += normal;
+    }
+
+    normals.iter().zip(vertices)
+        .map(|(normal, position)| {
+            normal.try_normalize(f32::EPSILON)
+                .or_else(|| position.try_normalize(f32::EPSILON))
+                .unwrap_or(Vec3::new(0.0, 1.0, 0.0))
+        })
+        .collect()
+}
+
+// Proyecta cada vértice sobre una esfera centrada en el origen (longitud y latitud).
+pub(crate) fn spherical_texcoords(vertices: &[Vec3]) -> Vec<Vec2> {
+    vertices.iter()
+        .map(|position| {
+            let direction = position.try_normalize(f32::EPSILON).unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+            let u = 0.5 + direction.z.atan2(direction.x) / (2.0 * PI);
+            let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+            Vec2::new(u, v)
+        })
+        .collect()
+}
+
+// Indica si alguna malla usa un material (si ninguna lo hace, la falta del .mtl no es un error).
+fn models_reference_materials(meshes: &[Mesh]) -> bool {
+    meshes.iter().any(|mesh| mesh.material.is_some())
+}
+
+// Convierte un material de tobj, resolviendo las texturas relativas al directorio del .obj.
+fn convert_material(material: tobj::Material, directory: &Path) -> Material {
+    let defaults = Material::default();
+    let color = |rgb: Option<[f32; 3]>, default: Color| match rgb {
+        Some([r, g, b]) => {
+            let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            Color::new(channel(r), channel(g), channel(b))
+        }
+        None => default,
+    };
+    let texture = |path: Option<String>| path.map(|path| directory.join(path).to_string_lossy().into_owned());
+    // tobj deja las claves que no conoce, como las de la extensión PBR, en `unknown_param`.
+    let number = |key: &str| material.unknown_param.get(key).and_then(|value| value.trim().parse::<f32>().ok());
+
+    Material {
+        ambient: color(material.ambient, defaults.ambient),
+        diffuse: color(material.diffuse, defaults.diffuse),
+        specular: color(material.specular, defaults.specular),
+        shininess: material.shininess.unwrap_or(defaults.shininess),
+        roughness: number("Pr"),
+        metallic: number("Pm"),
+        diffuse_texture: texture(material.diffuse_texture),
+        specular_texture: texture(material.specular_texture),
+        normal_texture: texture(material.normal_texture),
+        name: material.name,
+    }
 }
@@ -0,0 +1,93 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::camera::{Camera, Projection};
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, Viewport};
+
+/// Direcciones de las seis caras del cubo y el "arriba" de cada una.
+const FACE_DIRECTIONS: [(Vec3, Vec3); 6] = [
+    (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+    (Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+    (Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+    (Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+    (Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 0.0)),
+    (Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0)),
+];
+
+/// Una cara del cubo ya dibujada: la cámara con que se dibujó y sus píxeles.
+#[derive(Debug, Clone)]
+pub struct CubeFace {
+    /// Cámara de la cara (ver `face_cameras`).
+    pub camera: Camera,
+    /// Lado de la imagen, en píxeles.
+    pub size: usize,
+    /// Píxeles de la imagen, fila por fila.
+    pub pixels: Vec<u32>,
+}
+
+/// Crea las seis cámaras que, juntas, ven toda la esfera desde la posición de `camera`.
+///
+/// Las caras miran a lo largo de los ejes del mundo (`+X`, `-X`, `+Y`, `-Y`, `+Z`, `-Z`)
+/// y no siguen la orientación de la cámara, así el panorama queda alineado con el
+/// mundo y sirve como cielo (ver `EnvironmentMap::sample_equirect`). Cada una abarca
+/// 90 grados con una imagen cuadrada y proyección en perspectiva.
+pub fn face_cameras(camera: &Camera) -> [Camera; 6] {
+    FACE_DIRECTIONS.map(|(direction, up)| {
+        let mut face = camera.clone();
+        face.center = camera.eye + direction;
+        face.up = up;
+        face.projection = Projection::Perspective;
+        face.lens_shift = 0.0;
+        face.set_projection(FRAC_PI_2, 1.0, camera.near, camera.far);
+        face
+    })
+}
+
+/// Arma un panorama equirectangular con las seis caras del cubo: cada píxel toma el
+/// color de la cara que ve su dirección, interpolando los cuatro píxeles vecinos.
+///
+/// La columna es la longitud (`-Z` en el centro, creciendo hacia `+X`) y la fila la
+/// latitud (`+Y` arriba), como en `EnvironmentMap`. Una imagen el doble de ancha que
+/// de alta tiene los píxeles cuadrados.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer de destino; se llena entero.
+/// - `faces`: Las caras dibujadas con las cámaras de `face_cameras`.
+pub fn stitch(framebuffer: &mut Framebuffer, faces: &[CubeFace]) {
+    let screen_matrices: Vec<Mat4> = faces.iter()
+        .map(|face| Viewport::full(face.size, face.size).matrix() * face.camera.projection_matrix() * face.camera.view_matrix())
+        .collect();
+    let forwards: Vec<Vec3> = faces.iter().map(|face| (face.camera.center - face.camera.eye).normalize()).collect();
+
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    for row in 0..height {
+        let latitude = (row as f32 + 0.5) / height as f32 * PI;
+        for column in 0..width {
+            let longitude = ((column as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+            let direction = Vec3::new(longitude.sin() * latitude.sin(), latitude.cos(), -longitude.cos() * latitude.sin());
+            // La cara que mira más de frente a la dirección es la que la contiene.
+            let Some((index, _)) = forwards.iter()
+                .map(|forward| forward.dot(&direction))
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(&b.1)) else { return };
+            let clip = screen_matrices[index] * Vec4::new(direction.x, direction.y, direction.z, 0.0);
+            framebuffer.buffer[row * width + column] = sample(&faces[index], clip.x / clip.w - 0.5, clip.y / clip.w - 0.5).to_u32();
+        }
+    }
+}
+
+/// Color de una cara en una posición de pantalla, interpolando los cuatro píxeles
+/// vecinos; los bordes se repiten para no mezclar con lo que está afuera.
+fn sample(face: &CubeFace, x: f32, y: f32) -> Color {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let last = face.size as i64 - 1;
+    let pixel = |column: f32, row: f32| {
+        let column = (column as i64).clamp(0, last) as usize;
+        let row = (row as i64).clamp(0, last) as usize;
+        Color::from_u32(face.pixels[row * face.size + column])
+    };
+    let top = pixel(x0, y0).lerp(&pixel(x0 + 1.0, y0), tx);
+    let bottom = pixel(x0, y0 + 1.0).lerp(&pixel(x0 + 1.0, y0 + 1.0), tx);
+    top.lerp(&bottom, ty)
+}
@@ -0,0 +1,233 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::bounds::BoundingSphere;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::lod::projected_radius;
+use crate::shaders::splitmix64;
+
+/// Una partícula viva en un instante de la simulación.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    /// Posición en el mundo.
+    pub position: Vec3,
+    /// Velocidad en unidades del mundo por fotograma de simulación.
+    pub velocity: Vec3,
+    /// Fotogramas desde que se emitió.
+    pub age: f32,
+    /// Fotogramas que vive en total.
+    pub lifetime: f32,
+}
+
+impl Particle {
+    /// Fracción de la vida ya transcurrida, de 0 (recién emitida) a 1 (por desaparecer).
+    pub fn progress(&self) -> f32 {
+        (self.age / self.lifetime).clamp(0.0, 1.0)
+    }
+}
+
+/// Fuente de partículas: cuántas emite, cuánto viven, cómo salen y cómo se apagan.
+///
+/// Las partículas no se guardan de un fotograma al otro: la partícula número `k` se
+/// emite siempre en el instante `k / rate` con valores que salen de `seed` y de `k`,
+/// así que las que están vivas en un instante se pueden calcular directamente
+/// (`particles_at`), igual que las órbitas. El resultado no depende de cuántos
+/// fotogramas se dibujaron, y una imagen fija o un time-lapse ven la misma cola.
+#[derive(Debug, Clone, Copy)]
+pub struct Emitter {
+    /// Partículas emitidas por fotograma de simulación.
+    pub rate: f32,
+    /// Vida media de cada partícula, en fotogramas; cada una vive entre la mitad y una vez y media.
+    pub lifetime: f32,
+    /// Rapidez media de salida, en unidades del mundo por fotograma.
+    pub speed: f32,
+    /// Cuánto se desvía cada partícula de la dirección de la fuente; con una
+    /// dirección nula las partículas salen en la dirección del punto donde nacen.
+    pub spread: f32,
+    /// Radio de la esfera, alrededor de la fuente, sobre la que nacen las partículas.
+    pub radius: f32,
+    /// Radio de cada partícula en el mundo.
+    pub size: f32,
+    /// Color al nacer.
+    pub start_color: Color,
+    /// Color al final de la vida; el brillo además se apaga hasta 0.
+    pub end_color: Color,
+    /// Semilla de los valores de cada partícula.
+    pub seed: u64,
+}
+
+impl Emitter {
+    /// Calcula las partículas vivas en un instante.
+    ///
+    /// # Parámetros
+    /// - `time`: Tiempo de simulación en fotogramas.
+    /// - `source`: Posición de la fuente y dirección en que emite, en un instante dado;
+    ///   se consulta en el momento en que nació cada partícula, así una fuente que se
+    ///   mueve deja una estela curva.
+    pub fn particles_at<F: Fn(f32) -> (Vec3, Vec3)>(&self, time: f32, source: F) -> Vec<Particle> {
+        if self.rate <= 0.0 || self.lifetime <= 0.0 {
+            return Vec::new();
+        }
+        // La partícula más vieja que puede seguir viva nació hace una vida y media.
+        let first = ((time - self.lifetime * 1.5) * self.rate).floor() as i64;
+        let last = (time * self.rate).floor() as i64;
+        (first..=last)
+            .filter_map(|index| {
+                let birth = index as f32 / self.rate;
+                let random = |channel: u64| {
+                    let hash = splitmix64(splitmix64(self.seed ^ index as u64) ^ channel);
+                    (hash >> 40) as f32 / (1u64 << 24) as f32
+                };
+                let lifetime = self.lifetime * (0.5 + random(0));
+                let age = time - birth;
+                if age < 0.0 || age >= lifetime {
+                    return None;
+                }
+                let (origin, direction) = source(birth);
+                let offset = unit_vector(random(1), random(2));
+                let heading = (direction + offset * self.spread).try_normalize(1e-6).unwrap_or(offset);
+                let velocity = heading * self.speed * (0.5 + random(3));
+                Some(Particle { position: origin + offset * self.radius + velocity * age, velocity, age, lifetime })
+            })
+            .collect()
+    }
+
+    /// Color y brillo de una partícula según su edad: el color pasa de `start_color`
+    /// a `end_color` y el brillo baja de 1 a 0.
+    pub fn color(&self, particle: &Particle) -> (Color, f32) {
+        let progress = particle.progress();
+        (self.start_color.lerp(&self.end_color, progress), 1.0 - progress)
+    }
+}
+
+/// Efectos de partículas que un nodo de la escena puede llevar (campo `particles`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleEffect {
+    /// Cola de cometa: polvo y gas que salen hacia el lado opuesto a la estrella.
+    CometTail,
+    /// Llamaradas: chispas que saltan de la superficie de una estrella y se apagan cerca.
+    SolarFlare,
+}
+
+impl ParticleEffect {
+    /// Todos los efectos.
+    pub const ALL: [ParticleEffect; 2] = [ParticleEffect::CometTail, ParticleEffect::SolarFlare];
+
+    /// Nombre del efecto en el archivo de escena.
+    pub fn name(self) -> &'static str {
+        match self {
+            ParticleEffect::CometTail => "comet_tail",
+            ParticleEffect::SolarFlare => "solar_flare",
+        }
+    }
+
+    /// Busca un efecto por su nombre.
+    pub fn from_name(name: &str) -> Option<ParticleEffect> {
+        ParticleEffect::ALL.into_iter().find(|effect| effect.name() == name)
+    }
+
+    /// Emisor del efecto para un cuerpo.
+    ///
+    /// # Parámetros
+    /// - `radius`: Radio del cuerpo en la escena.
+    /// - `seed`: Semilla del cuerpo.
+    pub fn emitter(self, radius: f32, seed: i32) -> Emitter {
+        let seed = seed as u32 as u64;
+        // Las mallas miden 0.5 de radio, así que el cuerpo ocupa la mitad de `radius`.
+        let surface = radius * 0.5;
+        match self {
+            ParticleEffect::CometTail => Emitter {
+                rate: 12.0,
+                lifetime: 120.0,
+                speed: 0.02,
+                spread: 0.15,
+                radius: surface,
+                size: 0.06,
+                start_color: Color::new(200, 230, 255),
+                end_color: Color::new(60, 90, 200),
+                seed,
+            },
+            ParticleEffect::SolarFlare => Emitter {
+                rate: 6.0,
+                lifetime: 40.0,
+                speed: surface * 0.01,
+                spread: 1.0,
+                radius: surface,
+                size: surface * 0.05,
+                start_color: Color::new(255, 230, 150),
+                end_color: Color::new(255, 60, 0),
+                seed,
+            },
+        }
+    }
+
+    /// Dirección en que emite la fuente.
+    ///
+    /// # Parámetros
+    /// - `position`: Posición del cuerpo.
+    /// - `light`: Posición de la estrella de la escena, si hay.
+    ///
+    /// # Retorna
+    /// Para la cola de cometa, la dirección que se aleja de la estrella; para las
+    /// llamaradas, el vector nulo, así cada chispa sale hacia afuera desde donde nace.
+    pub fn direction(self, position: Vec3, light: Option<Vec3>) -> Vec3 {
+        match (self, light) {
+            (ParticleEffect::CometTail, Some(light)) => (position - light).try_normalize(1e-6).unwrap_or_else(Vec3::zeros),
+            _ => Vec3::zeros(),
+        }
+    }
+}
+
+/// Dibuja partículas como discos que miran a la cámara y se suman a lo que hay
+/// detrás, más brillantes en el centro. Respetan el z-buffer pero no escriben en él.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer de destino; se usa su viewport.
+/// - `particles`: Partículas a dibujar (ver `Emitter::particles_at`).
+/// - `emitter`: Emisor del que salen, que da su tamaño y color.
+/// - `view_projection`: Proyección por vista de la cámara.
+pub fn draw_particles(framebuffer: &mut Framebuffer, particles: &[Particle], emitter: &Emitter, view_projection: &Mat4) {
+    let viewport = framebuffer.viewport();
+    let screen_matrix = viewport.matrix() * view_projection;
+    for particle in particles {
+        let position = particle.position;
+        let clip = screen_matrix * Vec4::new(position.x, position.y, position.z, 1.0);
+        if clip.w <= 1e-6 {
+            continue;
+        }
+        let sphere = BoundingSphere { center: position, radius: emitter.size };
+        let radius = projected_radius(&sphere, view_projection, viewport.height as f32);
+        if !radius.is_finite() {
+            continue;
+        }
+        let (center_x, center_y, depth) = (clip.x / clip.w, clip.y / clip.w, clip.z / clip.w);
+        let (color, brightness) = emitter.color(particle);
+        framebuffer.set_current_color(color.to_u32());
+
+        let (column, row) = (center_x.floor() as i32, center_y.floor() as i32);
+        if radius < 1.0 {
+            // Más chica que un píxel: ilumina solo el suyo, según cuánto lo cubre.
+            if framebuffer.passes_depth_test(column, row, depth) {
+                framebuffer.add_point(column as usize, row as usize, depth, brightness * (radius * 2.0).powi(2).min(1.0));
+            }
+            continue;
+        }
+        let reach = radius.ceil() as i32;
+        for y in row - reach..=row + reach {
+            for x in column - reach..=column + reach {
+                let (dx, dy) = (x as f32 + 0.5 - center_x, y as f32 + 0.5 - center_y);
+                let falloff = 1.0 - (dx * dx + dy * dy) / (radius * radius);
+                if falloff > 0.0 && framebuffer.passes_depth_test(x, y, depth) {
+                    framebuffer.add_point(x as usize, y as usize, depth, brightness * falloff * falloff);
+                }
+            }
+        }
+    }
+}
+
+/// Vector unitario a partir de dos números entre 0 y 1, repartido de forma pareja sobre la esfera.
+fn unit_vector(u: f32, v: f32) -> Vec3 {
+    let y = 2.0 * u - 1.0;
+    let angle = v * std::f32::consts::TAU;
+    let radius = (1.0 - y * y).max(0.0).sqrt();
+    Vec3::new(radius * angle.cos(), y, radius * angle.sin())
+}
@@ -0,0 +1,139 @@
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::obj;
+use crate::environment::EnvironmentMap;
+
+/// Reflectancia frontal de los materiales no metálicos (agua, roca, plástico).
+pub const DIELECTRIC_F0: f32 = 0.04;
+
+/// Rugosidad mínima: por debajo el brillo de una luz puntual se vuelve un punto
+/// más pequeño que un píxel.
+const MIN_ROUGHNESS: f32 = 0.05;
+
+/// Material físico con el modelo metálico/rugosidad, el mismo para los modelos
+/// importados y los planetas procedurales, así que todos responden igual a la luz.
+///
+/// Los shaders de planeta calculan el albedo de cada fragmento con su ruido y
+/// eligen la rugosidad según el terreno (el agua lisa, la roca rugosa); los modelos
+/// OBJ lo toman de su `.mtl` (`Material::from_mtl`).
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    /// Color de la superficie: el difuso de los no metálicos y el del reflejo de los metales.
+    pub albedo: Color,
+    /// 0 para un dieléctrico y 1 para un metal; los valores intermedios mezclan ambos.
+    pub metallic: f32,
+    /// 0 para un espejo y 1 para una superficie completamente mate.
+    pub roughness: f32,
+    /// Luz que la superficie suma sin depender de la iluminación (el ambiente `Ka` de un `.mtl`).
+    pub emission: Color,
+}
+
+impl Material {
+    /// Crea un material sin emisión.
+    ///
+    /// # Parámetros
+    /// - `albedo`: Color de la superficie.
+    /// - `metallic`: Entre 0 (dieléctrico) y 1 (metal).
+    /// - `roughness`: Entre 0 (espejo) y 1 (mate).
+    pub fn new(albedo: Color, metallic: f32, roughness: f32) -> Self {
+        Material { albedo, metallic, roughness, emission: Color::black() }
+    }
+
+    /// Convierte un material de un archivo `.mtl`. Usa la extensión PBR (`Pr` y `Pm`)
+    /// si el archivo la trae; si no, deduce la rugosidad del exponente especular `Ns`
+    /// y considera el material no metálico.
+    pub fn from_mtl(material: &obj::Material) -> Self {
+        // La equivalencia habitual entre el exponente de Blinn-Phong y la rugosidad.
+        let roughness = material.roughness
+            .unwrap_or_else(|| (2.0 / (material.shininess.max(0.0) + 2.0)).sqrt());
+        Material {
+            albedo: material.diffuse,
+            metallic: material.metallic.unwrap_or(0.0),
+            roughness,
+            emission: material.ambient,
+        }
+    }
+}
+
+/// Ilumina un punto de la superficie con una luz direccional blanca, con la
+/// aproximación de Cook-Torrance: distribución GGX, sombreado de Smith-Schlick y
+/// Fresnel de Schlick, más un difuso de Lambert para la luz que no se refleja.
+///
+/// La luz está escalada para que un material mate y no metálico dé casi lo mismo
+/// que un difuso simple, `albedo * max(N·L, 0)`.
+///
+/// # Parámetros
+/// - `material`: Material de la superficie.
+/// - `normal`: Normal de la superficie en el mundo.
+/// - `view`: Dirección normalizada desde el punto hacia la cámara.
+/// - `light`: Dirección normalizada desde el punto hacia la luz.
+///
+/// # Retorna
+/// El color lineal, sin saturar: el reflejo de una superficie lisa puede pasar de 1.
+pub fn shade(material: &Material, normal: Vec3, view: Vec3, light: Vec3) -> Vec3 {
+    let normal = normal.normalize();
+    let n_dot_l = normal.dot(&light);
+    if n_dot_l <= 0.0 {
+        return Vec3::from(material.emission);
+    }
+    let n_dot_v = normal.dot(&view).max(1e-4);
+    let half = (light + view).normalize();
+    let n_dot_h = normal.dot(&half).max(0.0);
+    let v_dot_h = view.dot(&half).max(0.0);
+
+    let roughness = material.roughness.clamp(MIN_ROUGHNESS, 1.0);
+    let metallic = material.metallic.clamp(0.0, 1.0);
+
+    // Distribución de microfacetas GGX (Trowbridge-Reitz).
+    let alpha = roughness * roughness;
+    let alpha2 = alpha * alpha;
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    let distribution = alpha2 / (PI * denominator * denominator);
+
+    // Microfacetas que se tapan entre sí, hacia la luz y hacia la cámara.
+    let k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    let geometry = n_dot_l / (n_dot_l * (1.0 - k) + k) * n_dot_v / (n_dot_v * (1.0 - k) + k);
+
+    let specular = distribution * geometry / (4.0 * n_dot_l * n_dot_v);
+    let fresnel = (1.0 - v_dot_h).powi(5);
+
+    let albedo = Vec3::from(material.albedo);
+    let mut lit = Vec3::zeros();
+    for channel in 0..3 {
+        // Los metales reflejan con su propio color y no tienen difuso.
+        let f0 = DIELECTRIC_F0 + (albedo[channel] - DIELECTRIC_F0) * metallic;
+        let reflected = f0 + (1.0 - f0) * fresnel;
+        let diffuse = (1.0 - reflected) * (1.0 - metallic) * albedo[channel];
+        // La radiancia de la luz vale π, que cancela el 1/π del difuso de Lambert.
+        lit[channel] = (diffuse + reflected * specular * PI) * n_dot_l;
+    }
+    lit + Vec3::from(material.emission)
+}
+
+/// Reflejo del cielo en la superficie: la imagen de entorno en la dirección
+/// reflejada de la vista, con el Fresnel de Schlick (tenue de frente y más fuerte de
+/// canto en los dieléctricos) y apagado por la rugosidad, que lo desenfocaría.
+///
+/// # Parámetros
+/// - `material`: Material de la superficie.
+/// - `normal`: Normal de la superficie en el mundo.
+/// - `view`: Dirección normalizada desde el punto hacia la cámara.
+/// - `environment`: Imagen equirectangular del cielo.
+pub fn environment_reflection(material: &Material, normal: Vec3, view: Vec3, environment: &EnvironmentMap) -> Vec3 {
+    let normal = normal.normalize();
+    let n_dot_v = normal.dot(&view).max(0.0);
+    let reflected = normal * (2.0 * n_dot_v) - view;
+    let sky = Vec3::from(environment.sample_equirect(reflected));
+
+    let albedo = Vec3::from(material.albedo);
+    let metallic = material.metallic.clamp(0.0, 1.0);
+    let smoothness = 1.0 - material.roughness.clamp(0.0, 1.0);
+    let fresnel = (1.0 - n_dot_v).powi(5);
+    let mut reflection = Vec3::zeros();
+    for channel in 0..3 {
+        let f0 = DIELECTRIC_F0 + (albedo[channel] - DIELECTRIC_F0) * metallic;
+        reflection[channel] = sky[channel] * (f0 + (1.0 - f0) * fresnel) * smoothness;
+    }
+    reflection
+}
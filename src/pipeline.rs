@@ -0,0 +1,880 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+// En el navegador `std::time::Instant::now` entra en pánico.
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+use nalgebra_glm::{Vec3, Vec4, Mat3, Mat4, mat4_to_mat3};
+use fastnoise_lite::{FastNoiseLite, NoiseType};
+use crate::framebuffer::Framebuffer;
+use crate::vertex::Vertex;
+use crate::fragment::Fragment;
+use crate::triangle::{rasterize, Shading, TriangleFragments};
+use crate::obj::Material;
+use crate::color::Color;
+use crate::elevation::{ElevationMode, Heightmap};
+use crate::craters::Crater;
+use crate::profiler::{Profiler, Stage};
+use crate::shadow::ShadowMap;
+use crate::environment::EnvironmentMap;
+use crate::shader_params::SurfaceParams;
+use crate::bounds::BoundingSphere;
+use crate::geometry::plane::Frustum;
+use crate::tiles::{self, Tile, TileGrid};
+use crate::shaders::{vertex_shader, fragment_shader, fragment_alpha, Atmosphere, Flow, PlanetShader};
+
+/// Matrices de transformación de un objeto.
+#[derive(Debug, Clone, Copy)]
+pub struct Matrices {
+    /// Transforma de espacio del modelo a espacio del mundo.
+    pub model: Mat4,
+    /// Transforma de espacio del mundo a espacio de la cámara.
+    pub view: Mat4,
+    /// Proyección de la cámara, en perspectiva u ortográfica (ver `is_orthographic`).
+    pub projection: Mat4,
+    /// Transforma de coordenadas normalizadas a píxeles.
+    pub viewport: Mat4,
+    /// Proyección por vista de la cámara que decide qué triángulos se descartan.
+    pub cull: Mat4,
+}
+
+impl Matrices {
+    /// Indica si la proyección es ortográfica: su última fila no depende de la
+    /// profundidad, así que todos los rayos de vista son paralelos.
+    pub fn is_orthographic(&self) -> bool {
+        self.projection[(3, 2)] == 0.0
+    }
+}
+
+impl Default for Matrices {
+    fn default() -> Self {
+        Matrices {
+            model: Mat4::identity(),
+            view: Mat4::identity(),
+            projection: Mat4::identity(),
+            viewport: Mat4::identity(),
+            cull: Mat4::identity(),
+        }
+    }
+}
+
+/// Luz y cámara con que se ilumina un objeto.
+#[derive(Debug, Clone, Copy)]
+pub struct Lighting {
+    /// Dirección (normalizada, en el mundo) desde el objeto hacia la luz principal.
+    pub light_direction: Vec3,
+    /// Posición en el mundo de la luz, si es una estrella de la escena; con ella
+    /// `render_instanced` calcula la dirección de la luz de cada copia.
+    pub light_position: Option<Vec3>,
+    /// Posición de la cámara en el mundo.
+    pub camera_position: Vec3,
+    /// Halo atmosférico que se suma al color de la superficie, si hay.
+    pub atmosphere: Option<Atmosphere>,
+    /// Radio, en espacio del modelo, de un cuerpo centrado en el origen del modelo que
+    /// proyecta sombra sobre el objeto (el planeta de un anillo); 0 si no hay.
+    pub occluder_radius: f32,
+}
+
+impl Default for Lighting {
+    /// Luz frontal, como la que usa el rasterizador para `Fragment::intensity`.
+    fn default() -> Self {
+        Lighting {
+            light_direction: Vec3::new(0.0, 0.0, 1.0),
+            light_position: None,
+            camera_position: Vec3::zeros(),
+            atmosphere: None,
+            occluder_radius: 0.0,
+        }
+    }
+}
+
+/// Ruido de los shaders de superficie y la semilla de la que sale.
+#[derive(Clone)]
+pub struct NoiseConfig {
+    /// Generador de ruido; compartido, así copiar los uniforms no lo copia.
+    pub generator: Arc<FastNoiseLite>,
+    /// Semilla del cuerpo; toda la variación aleatoria de los shaders sale de ella
+    /// (ver `shaders::surface_random`), así que se ve igual en cada fotograma.
+    pub seed: u64,
+}
+
+impl NoiseConfig {
+    /// Crea el ruido de un cuerpo a partir de su semilla en la escena.
+    pub fn new(seed: i32) -> Self {
+        NoiseConfig { generator: Arc::new(create_noise(seed)), seed: seed as u32 as u64 }
+    }
+}
+
+/// Qué shader colorea un objeto y con qué datos.
+#[derive(Clone)]
+pub struct MaterialParams {
+    /// Shader de fragmentos del objeto.
+    pub shader: PlanetShader,
+    /// Características opcionales del shader (`FEATURE_*`) que eligen su variante.
+    pub features: u32,
+    /// Materiales del modelo, indexados por el campo `material` de cada fragmento;
+    /// compartidos con el `Model`, así que asignarlos no copia nada.
+    pub materials: Arc<[Material]>,
+    /// Normales interpoladas (suave) o una por cara (plano).
+    pub shading: Shading,
+    /// Cómo se calcula la elevación de cada vértice.
+    pub elevation: ElevationMode,
+    /// Mapa de alturas que usa `ElevationMode::Texture`.
+    pub heightmap: Option<Arc<Heightmap>>,
+    /// Cráteres del cuerpo, para el shader de la Luna (ver `craters::generate`).
+    pub craters: Vec<Crater>,
+    /// Velocidad y turbulencia de los shaders de fluidos.
+    pub flow: Flow,
+}
+
+impl MaterialParams {
+    /// Crea los parámetros de un shader con todo lo demás en sus valores por defecto.
+    pub fn new(shader: PlanetShader) -> Self {
+        MaterialParams {
+            shader,
+            features: 0,
+            materials: Arc::new([]),
+            shading: Shading::default(),
+            elevation: ElevationMode::default(),
+            heightmap: None,
+            craters: Vec::new(),
+            flow: Flow::default(),
+        }
+    }
+}
+
+/// Datos compartidos por todos los vértices y fragmentos de una llamada a `render`.
+#[derive(Clone)]
+pub struct Uniforms {
+    /// Matrices de transformación.
+    pub matrices: Matrices,
+    /// Luz y cámara.
+    pub lighting: Lighting,
+    /// Ruido de superficie.
+    pub noise: NoiseConfig,
+    /// Shader y parámetros de la superficie.
+    pub material: MaterialParams,
+    /// Número de fotograma actual.
+    pub time: u32,
+    /// Parámetros de los shaders de planeta, ya resueltos (ver `ShaderParams::surface`).
+    pub surface: SurfaceParams,
+    /// Valores con nombre que un shader propio puede leer con `param` sin que haga
+    /// falta agregar un campo a `Uniforms` (umbrales, escalas, intensidades).
+    pub user_params: HashMap<String, f32>,
+    /// Mapa de sombras de los cuerpos que tapan la luz de este objeto, si hay alguno.
+    pub shadow: Option<Arc<ShadowMap>>,
+    /// Imagen del cielo que reflejan las superficies lisas, si hay.
+    pub environment: Option<Arc<EnvironmentMap>>,
+}
+
+impl Uniforms {
+    /// Crea los uniforms de un objeto con matrices identidad, luz frontal y los
+    /// parámetros por defecto del shader.
+    ///
+    /// # Parámetros
+    /// - `shader`: Shader de la superficie.
+    /// - `seed`: Semilla del ruido del cuerpo.
+    pub fn new(shader: PlanetShader, seed: i32) -> Self {
+        Uniforms {
+            matrices: Matrices::default(),
+            lighting: Lighting::default(),
+            noise: NoiseConfig::new(seed),
+            material: MaterialParams::new(shader),
+            time: 0,
+            surface: SurfaceParams::default(),
+            user_params: HashMap::new(),
+            shadow: None,
+            environment: None,
+        }
+    }
+
+    /// Devuelve el parámetro de usuario `name`, o `default` si no está definido.
+    pub fn param(&self, name: &str, default: f32) -> f32 {
+        self.user_params.get(name).copied().unwrap_or(default)
+    }
+}
+
+/// Se ejecuta antes del shader de vértices; si devuelve `false` el objeto no se dibuja.
+pub type PreVertexHook = Box<dyn FnMut(&Uniforms, &[Vertex]) -> bool>;
+/// Recibe los triángulos ya transformados y recortados; puede quitar o modificar triángulos.
+pub type PostVertexHook = Box<dyn FnMut(&Uniforms, &mut Vec<[Vertex; 3]>)>;
+/// Recibe los fragmentos rasterizados antes del shader de fragmentos; puede descartarlos.
+pub type PreFragmentHook = Box<dyn FnMut(&Uniforms, &mut Vec<Fragment>)>;
+/// Se ejecuta con el fotograma terminado, antes de presentarlo.
+pub type PostFrameHook = Box<dyn FnMut(&mut Framebuffer)>;
+
+/// Funciones registradas en puntos fijos del pipeline de renderizado.
+///
+/// Permiten agregar comportamiento (estadísticas, descarte propio, marcas de
+/// agua) sin modificar el renderizador. Los hooks de cada etapa se ejecutan en
+/// el orden en que se registraron.
+#[derive(Default)]
+pub struct PipelineHooks {
+    pre_vertex: Vec<PreVertexHook>,
+    post_vertex: Vec<PostVertexHook>,
+    pre_fragment: Vec<PreFragmentHook>,
+    post_frame: Vec<PostFrameHook>,
+}
+
+impl PipelineHooks {
+    /// Crea un conjunto de hooks vacío.
+    pub fn new() -> Self {
+        PipelineHooks::default()
+    }
+
+    /// Registra un hook que se ejecuta antes de transformar los vértices de cada objeto.
+    pub fn on_pre_vertex<F: FnMut(&Uniforms, &[Vertex]) -> bool + 'static>(&mut self, hook: F) {
+        self.pre_vertex.push(Box::new(hook));
+    }
+
+    /// Registra un hook que recibe los triángulos de cada objeto después del shader de vértices.
+    pub fn on_post_vertex<F: FnMut(&Uniforms, &mut Vec<[Vertex; 3]>) + 'static>(&mut self, hook: F) {
+        self.post_vertex.push(Box::new(hook));
+    }
+
+    /// Registra un hook que recibe los fragmentos de cada objeto antes de sombrearlos.
+    /// Solo llegan los que pasaron la prueba de profundidad contra lo ya dibujado.
+    pub fn on_pre_fragment<F: FnMut(&Uniforms, &mut Vec<Fragment>) + 'static>(&mut self, hook: F) {
+        self.pre_fragment.push(Box::new(hook));
+    }
+
+    /// Registra un hook que se ejecuta sobre el fotograma terminado.
+    pub fn on_post_frame<F: FnMut(&mut Framebuffer) + 'static>(&mut self, hook: F) {
+        self.post_frame.push(Box::new(hook));
+    }
+
+    /// Ejecuta los hooks de fin de fotograma. Debe llamarse una vez por fotograma,
+    /// después del post-procesado.
+    pub fn run_post_frame(&mut self, framebuffer: &mut Framebuffer) {
+        for hook in &mut self.post_frame {
+            hook(framebuffer);
+        }
+    }
+}
+
+/// Crea el ruido de superficie de un cuerpo.
+pub fn create_noise(seed: i32) -> FastNoiseLite {
+    create_cloud_noise(seed)
+}
+
+/// Crea un ruido OpenSimplex2 con la semilla dada.
+pub fn create_cloud_noise(seed: i32) -> FastNoiseLite {
+    let mut noise = FastNoiseLite::with_seed(seed);
+    noise.set_noise_type(Some(NoiseType::OpenSimplex2));
+    noise
+}
+
+/// Construye la matriz de modelo a partir de una traslación, una escala uniforme
+/// y una rotación en ángulos de Euler (aplicada en orden X, Y, Z).
+pub fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
+    let (sin_x, cos_x) = rotation.x.sin_cos();
+    let (sin_y, cos_y) = rotation.y.sin_cos();
+    let (sin_z, cos_z) = rotation.z.sin_cos();
+
+    let rotation_matrix_x = Mat4::new(
+        1.0,  0.0,    0.0,   0.0,
+        0.0,  cos_x, -sin_x, 0.0,
+        0.0,  sin_x,  cos_x, 0.0,
+        0.0,  0.0,    0.0,   1.0,
+    );
+
+    let rotation_matrix_y = Mat4::new(
+        cos_y,  0.0,  sin_y, 0.0,
+        0.0,    1.0,  0.0,   0.0,
+        -sin_y, 0.0,  cos_y, 0.0,
+        0.0,    0.0,  0.0,   1.0,
+    );
+
+    let rotation_matrix_z = Mat4::new(
+        cos_z, -sin_z, 0.0, 0.0,
+        sin_z,  cos_z, 0.0, 0.0,
+        0.0,    0.0,  1.0, 0.0,
+        0.0,    0.0,  0.0, 1.0,
+    );
+
+    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
+
+    let transform_matrix = Mat4::new(
+        scale, 0.0,   0.0,   translation.x,
+        0.0,   scale, 0.0,   translation.y,
+        0.0,   0.0,   scale, translation.z,
+        0.0,   0.0,   0.0,   1.0,
+    );
+
+    transform_matrix * rotation_matrix
+}
+
+/// Construye una proyección ortográfica: los objetos se ven del mismo tamaño a
+/// cualquier distancia, como en un plano o un mapa del sistema.
+///
+/// Como la de `nalgebra_glm::perspective`, lleva la profundidad entre `near` y
+/// `far` al rango -1 a 1, pero en forma lineal.
+///
+/// # Parámetros
+/// - `half_width`: Mitad del ancho visible, en unidades del mundo.
+/// - `half_height`: Mitad del alto visible, en unidades del mundo.
+/// - `near`: Distancia al plano cercano.
+/// - `far`: Distancia al plano lejano.
+pub fn create_orthographic_matrix(half_width: f32, half_height: f32, near: f32, far: f32) -> Mat4 {
+    let depth = far - near;
+    Mat4::new(
+        1.0 / half_width, 0.0, 0.0, 0.0,
+        0.0, 1.0 / half_height, 0.0, 0.0,
+        0.0, 0.0, -2.0 / depth, -(far + near) / depth,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+/// Construye la matriz que lleva coordenadas normalizadas (-1 a 1) a píxeles,
+/// con el eje y hacia abajo.
+pub fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
+    Mat4::new(
+        width / 2.0, 0.0, 0.0, width / 2.0,
+        0.0, -height / 2.0, 0.0, height / 2.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0
+    )
+}
+
+/// Dibuja una malla indexada en el framebuffer.
+///
+/// Cada vértice pasa una sola vez por el shader de vértices aunque lo
+/// compartan varios triángulos.
+///
+/// # Parámetros
+/// - `framebuffer`: El framebuffer de destino.
+/// - `uniforms`: Matrices, ruido y shader del objeto.
+/// - `vertices`: Buffer de vértices de la malla.
+/// - `indices`: Índices dentro de `vertices`, tres por triángulo. Los triángulos
+///   con índices fuera de rango se ignoran.
+/// - `hooks`: Hooks que se ejecutan en cada etapa del pipeline.
+pub fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertices: &[Vertex], indices: &[u32], hooks: &mut PipelineHooks) {
+    render_profiled(framebuffer, uniforms, vertices, indices, hooks, None);
+}
+
+/// Igual que `render`, pero suma a `profiler` el tiempo de las etapas de vértices,
+/// rasterización y fragmentos (los hooks cuentan dentro de la etapa que los rodea)
+/// y los triángulos y fragmentos que se dibujaron.
+pub fn render_profiled(
+    framebuffer: &mut Framebuffer,
+    uniforms: &Uniforms,
+    vertices: &[Vertex],
+    indices: &[u32],
+    hooks: &mut PipelineHooks,
+    profiler: Option<&mut Profiler>,
+) {
+    render_into(&mut Target::Framebuffer(framebuffer), uniforms, vertices, indices, hooks, profiler);
+}
+
+/// Dónde terminan los triángulos armados de un objeto: se dibujan en el
+/// framebuffer o se anotan en los tiles de un `TiledFrame`.
+enum Target<'f, 'a> {
+    Framebuffer(&'f mut Framebuffer),
+    Frame(&'f mut TiledFrame<'a>),
+}
+
+/// El pipeline de `render_profiled` y `TiledFrame::render`.
+fn render_into<'a>(
+    target: &mut Target<'_, 'a>,
+    uniforms: &'a Uniforms,
+    vertices: &[Vertex],
+    indices: &[u32],
+    hooks: &mut PipelineHooks,
+    mut profiler: Option<&mut Profiler>,
+) {
+    for hook in &mut hooks.pre_vertex {
+        if !hook(uniforms, vertices) {
+            return;
+        }
+    }
+
+    // Vertex Shader
+    let start = Instant::now();
+    let mut transformed_vertices = Vec::with_capacity(vertices.len());
+    let per_vertex = uniforms.material.shading.per_vertex();
+    for vertex in vertices {
+        let mut transformed = vertex_shader(vertex, uniforms);
+        if per_vertex {
+            transformed.color = shade_vertex(&transformed, uniforms);
+        }
+        transformed_vertices.push(transformed);
+    }
+    let clip_positions = cull_positions(vertices, uniforms);
+    if let Some(profiler) = profiler.as_deref_mut() {
+        profiler.record(Stage::Vertex, start.elapsed());
+    }
+
+    let triangles = assemble(uniforms, &transformed_vertices, &clip_positions, indices, &mut hooks.post_vertex, profiler.as_deref_mut());
+    match target {
+        Target::Framebuffer(framebuffer) => draw_triangles(framebuffer, uniforms, &triangles, &mut hooks.pre_fragment, profiler),
+        Target::Frame(frame) => frame.add(Cow::Borrowed(uniforms), triangles, profiler),
+    }
+}
+
+/// Dibuja muchas copias de una malla, cada una con su propia matriz de modelo
+/// (ver `instancing::AsteroidBelt`).
+///
+/// La elevación y el color por elevación de cada vértice no dependen de dónde está
+/// la copia, así que el shader de vértices corre una sola vez para todas; cada copia
+/// solo vuelve a transformar posiciones y normales. Las copias cuya esfera envolvente
+/// queda fuera del frustum de recorte se saltan enteras.
+///
+/// # Parámetros
+/// - `framebuffer`: El framebuffer de destino.
+/// - `uniforms`: Uniforms compartidos; `matrices.model` ubica el conjunto en el mundo y
+///   se restaura al terminar.
+/// - `vertices`: Buffer de vértices de la malla.
+/// - `indices`: Índices dentro de `vertices`, tres por triángulo.
+/// - `instances`: Matriz de cada copia, relativa a `matrices.model`.
+/// - `hooks`: Hooks del pipeline; el de antes de los vértices corre una vez y los
+///   demás, una vez por copia.
+/// - `profiler`: Donde se suman las etapas y los triángulos y fragmentos dibujados.
+///
+/// # Retorna
+/// La cantidad de copias descartadas por el frustum.
+pub fn render_instanced(
+    framebuffer: &mut Framebuffer,
+    uniforms: &mut Uniforms,
+    vertices: &[Vertex],
+    indices: &[u32],
+    instances: &[Mat4],
+    hooks: &mut PipelineHooks,
+    profiler: Option<&mut Profiler>,
+) -> usize {
+    render_instanced_into(&mut Target::Framebuffer(framebuffer), uniforms, vertices, indices, instances, hooks, profiler)
+}
+
+/// El pipeline de `render_instanced` y `TiledFrame::render_instanced`.
+fn render_instanced_into(
+    target: &mut Target,
+    uniforms: &mut Uniforms,
+    vertices: &[Vertex],
+    indices: &[u32],
+    instances: &[Mat4],
+    hooks: &mut PipelineHooks,
+    mut profiler: Option<&mut Profiler>,
+) -> usize {
+    for hook in &mut hooks.pre_vertex {
+        if !hook(uniforms, vertices) {
+            return 0;
+        }
+    }
+
+    let start = Instant::now();
+    let shaded: Vec<Vertex> = vertices.iter().map(|vertex| vertex_shader(vertex, uniforms)).collect();
+    let local_sphere = BoundingSphere::from_points(vertices.iter().map(|vertex| &vertex.position));
+    let frustum = Frustum::from_matrix(&uniforms.matrices.cull);
+    let placement = uniforms.matrices.model;
+    let light_direction = uniforms.lighting.light_direction;
+    let per_vertex = uniforms.material.shading.per_vertex();
+    if let Some(profiler) = profiler.as_deref_mut() {
+        profiler.record(Stage::Vertex, start.elapsed());
+    }
+
+    let mut culled = 0;
+    let mut transformed_vertices = Vec::with_capacity(vertices.len());
+    for instance in instances {
+        let model = placement * instance;
+        let visible = match (frustum, local_sphere) {
+            (Some(frustum), Some(sphere)) => frustum.intersects_sphere(&sphere.transformed(&model)),
+            _ => true,
+        };
+        if !visible {
+            culled += 1;
+            continue;
+        }
+
+        let start = Instant::now();
+        uniforms.matrices.model = model;
+        if let Some(light) = uniforms.lighting.light_position {
+            let center = model.column(3).xyz();
+            uniforms.lighting.light_direction = (light - center).try_normalize(1e-4).unwrap_or(light_direction);
+        }
+        let screen_matrix = uniforms.matrices.viewport * uniforms.matrices.projection * uniforms.matrices.view * model;
+        let normal_matrix = mat4_to_mat3(&model).transpose().try_inverse().unwrap_or(Mat3::identity());
+        transformed_vertices.clear();
+        for vertex in &shaded {
+            let position = screen_matrix * Vec4::new(vertex.position.x, vertex.position.y, vertex.position.z, 1.0);
+            let mut transformed = vertex.clone();
+            transformed.transformed_position = position.xyz() / position.w;
+            transformed.transformed_normal = normal_matrix * vertex.normal;
+            transformed.world_position = (model * Vec4::new(vertex.position.x, vertex.position.y, vertex.position.z, 1.0)).xyz();
+            if per_vertex {
+                transformed.color = shade_vertex(&transformed, uniforms);
+            }
+            transformed_vertices.push(transformed);
+        }
+        let clip_positions = cull_positions(vertices, uniforms);
+        if let Some(profiler) = profiler.as_deref_mut() {
+            profiler.record(Stage::Vertex, start.elapsed());
+        }
+
+        let triangles = assemble(uniforms, &transformed_vertices, &clip_positions, indices, &mut hooks.post_vertex, profiler.as_deref_mut());
+        match target {
+            Target::Framebuffer(framebuffer) => {
+                draw_triangles(framebuffer, uniforms, &triangles, &mut hooks.pre_fragment, profiler.as_deref_mut());
+            }
+            // cada copia se sombrea con su propia matriz de modelo y su dirección de la luz
+            Target::Frame(frame) if !triangles.is_empty() => frame.add(Cow::Owned(uniforms.clone()), triangles, profiler.as_deref_mut()),
+            Target::Frame(_) => {}
+        }
+    }
+    uniforms.matrices.model = placement;
+    uniforms.lighting.light_direction = light_direction;
+    culled
+}
+
+/// Posiciones de los vértices en coordenadas de recorte de la cámara que decide qué
+/// triángulos se descartan, calculadas una vez por vértice.
+fn cull_positions(vertices: &[Vertex], uniforms: &Uniforms) -> Vec<Vec4> {
+    let cull_matrix = uniforms.matrices.cull * uniforms.matrices.model;
+    vertices.iter()
+        .map(|vertex| {
+            let position = vertex.position;
+            cull_matrix * Vec4::new(position.x, position.y, position.z, 1.0)
+        })
+        .collect()
+}
+
+/// Arma los triángulos de vértices ya transformados, sin los que quedan fuera del
+/// frustum de recorte, y les pasa los hooks de después de los vértices.
+fn assemble(
+    uniforms: &Uniforms,
+    transformed_vertices: &[Vertex],
+    clip_positions: &[Vec4],
+    indices: &[u32],
+    post_vertex: &mut [PostVertexHook],
+    profiler: Option<&mut Profiler>,
+) -> Vec<[Vertex; 3]> {
+    // Primitive Assembly, dropping triangles outside the culling frustum
+    let start = Instant::now();
+    let mut triangles = Vec::with_capacity(indices.len() / 3);
+    for face in indices.chunks_exact(3) {
+        let [a, b, c] = [face[0] as usize, face[1] as usize, face[2] as usize];
+        if a.max(b).max(c) >= transformed_vertices.len() {
+            continue;
+        }
+        if outside_frustum(&[clip_positions[a], clip_positions[b], clip_positions[c]]) {
+            continue;
+        }
+        triangles.push([
+            transformed_vertices[a].clone(),
+            transformed_vertices[b].clone(),
+            transformed_vertices[c].clone(),
+        ]);
+    }
+
+    for hook in post_vertex {
+        hook(uniforms, &mut triangles);
+    }
+    if let Some(profiler) = profiler {
+        profiler.record(Stage::Vertex, start.elapsed());
+    }
+    triangles
+}
+
+/// Rasteriza y sombrea en el framebuffer los triángulos armados de un objeto: la
+/// parte de `render_profiled` que sigue a los vértices.
+fn draw_triangles(
+    framebuffer: &mut Framebuffer,
+    uniforms: &Uniforms,
+    triangles: &[[Vertex; 3]],
+    pre_fragment: &mut [PreFragmentHook],
+    mut profiler: Option<&mut Profiler>,
+) {
+    let mut record = |stage: Stage, start: Instant| {
+        if let Some(profiler) = profiler.as_deref_mut() {
+            profiler.record(stage, start.elapsed());
+        }
+    };
+
+    // Rasterization with early-Z: pixels behind what is already in the z-buffer
+    // (or off screen) never become fragments, so they are never shaded
+    let start = Instant::now();
+    let mut fragments = Vec::new();
+    let visible = |x: i32, y: i32, depth: f32| framebuffer.passes_depth_test(x, y, depth);
+    for tri in triangles {
+        rasterize(&tri[0], &tri[1], &tri[2], uniforms.material.shading, visible, &mut fragments);
+    }
+
+    for hook in pre_fragment {
+        hook(uniforms, &mut fragments);
+    }
+    record(Stage::Rasterization, start);
+
+    // Fragment Processing; transparent shaders blend instead of overwriting
+    let start = Instant::now();
+    let transparent = uniforms.material.shader.is_transparent();
+    let fragment_count = fragments.len();
+    for mut fragment in fragments {
+        let x = fragment.position.x as usize;
+        let y = fragment.position.y as usize;
+
+        if x < framebuffer.width && y < framebuffer.height {
+            let Some((color, alpha)) = shade_fragment(&mut fragment, uniforms) else { continue };
+            let color = framebuffer.pixel_format().pack(color, x, y);
+            framebuffer.set_current_color(color);
+            if transparent {
+                framebuffer.blend_point(x, y, fragment.depth, alpha);
+            } else {
+                framebuffer.point(x, y, fragment.depth);
+            }
+        }
+    }
+    record(Stage::Fragment, start);
+
+    if let Some(profiler) = profiler {
+        profiler.count(triangles.len(), fragment_count);
+    }
+}
+
+/// Un fotograma (o una `DrawList`) que se dibuja en varios hilos sobre los tiles
+/// del framebuffer (ver `tiles::TileGrid`).
+///
+/// Cada objeto pasa por el shader de vértices en cuanto se agrega, y sus
+/// triángulos se anotan en los tiles que toca su caja. `finish` hace después una
+/// sola pasada por los tiles, repartida entre los hilos: cada tile rasteriza y
+/// sombrea sus triángulos objeto por objeto, en el orden en que se agregaron, así
+/// cada píxel ve los mismos fragmentos en el mismo orden que dibujando en el
+/// framebuffer. Los tiles escriben directo en el color y el z-buffer del
+/// framebuffer, sin copiarlo.
+///
+/// El stencil no se usa: con una prueba de stencil activa hay que dibujar con
+/// `render_profiled`.
+pub struct TiledFrame<'a> {
+    grid: TileGrid,
+    threads: usize,
+    /// Uniforms y triángulos armados de cada objeto (o de cada copia de un
+    /// objeto instanciado, con su propia matriz de modelo).
+    draws: Vec<(Cow<'a, Uniforms>, Vec<[Vertex; 3]>)>,
+    /// Triángulos que toca cada tile, como (objeto, triángulo), en orden de dibujo.
+    bins: Vec<Vec<(u32, u32)>>,
+}
+
+impl<'a> TiledFrame<'a> {
+    /// Crea un fotograma vacío para un framebuffer; el framebuffer no debe cambiar de
+    /// tamaño ni de tijera hasta `finish`.
+    ///
+    /// # Parámetros
+    /// - `framebuffer`: Framebuffer donde se va a dibujar.
+    /// - `threads`: Hilos que reparten los tiles en `finish` (ver `tiles::available_threads`).
+    pub fn new(framebuffer: &Framebuffer, threads: usize) -> Self {
+        let grid = TileGrid::new(framebuffer);
+        TiledFrame { grid, threads: threads.max(1), draws: Vec::new(), bins: vec![Vec::new(); grid.len()] }
+    }
+
+    /// Agrega un objeto, como `render_profiled`; se dibuja en `finish`.
+    pub fn render(&mut self, uniforms: &'a Uniforms, vertices: &[Vertex], indices: &[u32], hooks: &mut PipelineHooks, profiler: Option<&mut Profiler>) {
+        render_into(&mut Target::Frame(self), uniforms, vertices, indices, hooks, profiler);
+    }
+
+    /// Agrega las copias de una malla, como `render_instanced`; se dibujan en `finish`.
+    ///
+    /// # Retorna
+    /// La cantidad de copias descartadas por el frustum.
+    pub fn render_instanced(
+        &mut self,
+        uniforms: &mut Uniforms,
+        vertices: &[Vertex],
+        indices: &[u32],
+        instances: &[Mat4],
+        hooks: &mut PipelineHooks,
+        profiler: Option<&mut Profiler>,
+    ) -> usize {
+        render_instanced_into(&mut Target::Frame(self), uniforms, vertices, indices, instances, hooks, profiler)
+    }
+
+    /// Anota los triángulos de un objeto en los tiles que toca su caja.
+    fn add(&mut self, uniforms: Cow<'a, Uniforms>, triangles: Vec<[Vertex; 3]>, profiler: Option<&mut Profiler>) {
+        // Binning: the triangles whose bounding box touches each tile
+        let start = Instant::now();
+        let draw = self.draws.len() as u32;
+        for (index, [a, b, c]) in triangles.iter().enumerate() {
+            let (a, b, c) = (a.transformed_position, b.transformed_position, c.transformed_position);
+            let bounds = (
+                a.x.min(b.x).min(c.x).floor() as i32,
+                a.y.min(b.y).min(c.y).floor() as i32,
+                a.x.max(b.x).max(c.x).ceil() as i32,
+                a.y.max(b.y).max(c.y).ceil() as i32,
+            );
+            for tile in self.grid.tiles_touching(bounds) {
+                self.bins[tile].push((draw, index as u32));
+            }
+        }
+        if let Some(profiler) = profiler {
+            profiler.record(Stage::Rasterization, start.elapsed());
+            profiler.count(triangles.len(), 0);
+        }
+        self.draws.push((uniforms, triangles));
+    }
+
+    /// Rasteriza y sombrea todo lo agregado, en una sola pasada por los tiles
+    /// repartida entre los hilos. Con hooks de fragmentos los tiles se dibujan en
+    /// un solo hilo, porque los hooks no se pueden compartir entre hilos; cada
+    /// hook recibe los fragmentos de a un objeto y un tile.
+    ///
+    /// # Parámetros
+    /// - `framebuffer`: El framebuffer con el que se creó el fotograma.
+    /// - `hooks`: Hooks del pipeline; aquí solo corren los de antes de los fragmentos.
+    /// - `profiler`: Donde se suman el tiempo de la pasada y los fragmentos sombreados.
+    pub fn finish(self, framebuffer: &mut Framebuffer, hooks: &mut PipelineHooks, profiler: Option<&mut Profiler>) {
+        // Rasterization with early-Z against the tile's part of the z-buffer, then
+        // shading; both run per tile, so they are measured together as the fragment stage
+        let start = Instant::now();
+        let draws = &self.draws;
+        let mut tiles = self.grid.split(framebuffer);
+        let fragment_count = if hooks.pre_fragment.is_empty() {
+            let busy: Vec<usize> = (0..self.bins.len()).filter(|&index| !self.bins[index].is_empty()).collect();
+            let fragment_count = AtomicUsize::new(0);
+            tiles::for_each_tile(&mut tiles, &busy, self.threads, |index, tile| {
+                fragment_count.fetch_add(draw_tile(tile, &self.bins[index], draws, &mut []), Ordering::Relaxed);
+            });
+            fragment_count.into_inner()
+        } else {
+            tiles.iter_mut().zip(&self.bins).map(|(tile, bin)| draw_tile(tile, bin, draws, &mut hooks.pre_fragment)).sum()
+        };
+        if let Some(profiler) = profiler {
+            profiler.record(Stage::Fragment, start.elapsed());
+            profiler.count(0, fragment_count);
+        }
+    }
+}
+
+/// Rasteriza y sombrea los triángulos anotados en un tile, como el final de
+/// `draw_triangles` para cada objeto.
+///
+/// # Retorna
+/// La cantidad de fragmentos que llegaron al shader.
+fn draw_tile(tile: &mut Tile, bin: &[(u32, u32)], draws: &[(Cow<Uniforms>, Vec<[Vertex; 3]>)], pre_fragment: &mut [PreFragmentHook]) -> usize {
+    let mut fragment_count = 0;
+    let mut fragments = Vec::new();
+    for run in bin.chunk_by(|a, b| a.0 == b.0) {
+        let (uniforms, triangles) = &draws[run[0].0 as usize];
+        for &(_, index) in run {
+            let [a, b, c] = &triangles[index as usize];
+            let visible = |x: i32, y: i32, depth: f32| tile.passes_depth_test(x, y, depth);
+            if let Some(covered) = TriangleFragments::new(a, b, c, uniforms.material.shading, visible).within(tile.bounds()) {
+                fragments.extend(covered);
+            }
+        }
+        for hook in pre_fragment.iter_mut() {
+            hook(uniforms, &mut fragments);
+        }
+        fragment_count += fragments.len();
+        shade_tile(tile, fragments.drain(..), uniforms);
+    }
+    fragment_count
+}
+
+/// Sombrea los fragmentos de un objeto en un tile y los escribe en él.
+fn shade_tile(tile: &mut Tile, fragments: impl Iterator<Item = Fragment>, uniforms: &Uniforms) {
+    let transparent = uniforms.material.shader.is_transparent();
+    for mut fragment in fragments {
+        let Some((color, alpha)) = shade_fragment(&mut fragment, uniforms) else { continue };
+        let (x, y) = (fragment.position.x as i32, fragment.position.y as i32);
+        let color = tile.pixel_format().pack(color, x as usize, y as usize);
+        if transparent {
+            tile.blend_point(x, y, fragment.depth, color, alpha);
+        } else {
+            tile.point(x, y, fragment.depth, color);
+        }
+    }
+}
+
+/// Color lineal y opacidad de un fragmento: el de sus vértices con el sombreado
+/// por vértice o, si no, el del shader de fragmentos. Los shaders opacos dan
+/// opacidad 1; devuelve `None` si el fragmento es del todo transparente.
+pub fn shade_fragment(fragment: &mut Fragment, uniforms: &Uniforms) -> Option<(Vec3, f32)> {
+    let alpha = if uniforms.material.shader.is_transparent() { fragment_alpha(fragment, uniforms) } else { 1.0 };
+    if alpha <= 0.0 {
+        return None;
+    }
+    let color = if uniforms.material.shading.per_vertex() {
+        Vec3::from(fragment.color)
+    } else {
+        fragment.view_direction = view_direction(fragment, uniforms);
+        fragment_shader(fragment, uniforms)
+    };
+    Some((color, alpha))
+}
+
+/// Dibuja solo la profundidad de una malla, sin shader de fragmentos: es la
+/// pasada de los mapas de sombras (ver `ShadowMap`). Los objetos semitransparentes
+/// solo escriben donde su opacidad pasa de la mitad, así los huecos de un anillo
+/// dejan pasar la luz.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer cuyo z-buffer recibe la profundidad.
+/// - `uniforms`: Matrices (las de la luz), ruido y shader del objeto.
+/// - `vertices`: Buffer de vértices de la malla.
+/// - `indices`: Índices dentro de `vertices`, tres por triángulo.
+pub fn render_depth(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertices: &[Vertex], indices: &[u32]) {
+    let transformed_vertices: Vec<Vertex> = vertices.iter().map(|vertex| vertex_shader(vertex, uniforms)).collect();
+    let cull_matrix = uniforms.matrices.cull * uniforms.matrices.model;
+    let clip_positions: Vec<Vec4> = vertices.iter()
+        .map(|vertex| cull_matrix * Vec4::new(vertex.position.x, vertex.position.y, vertex.position.z, 1.0))
+        .collect();
+    let transparent = uniforms.material.shader.is_transparent();
+
+    let mut fragments = Vec::new();
+    for face in indices.chunks_exact(3) {
+        let [a, b, c] = [face[0] as usize, face[1] as usize, face[2] as usize];
+        if a.max(b).max(c) >= vertices.len() || outside_frustum(&[clip_positions[a], clip_positions[b], clip_positions[c]]) {
+            continue;
+        }
+        let visible = |x: i32, y: i32, depth: f32| framebuffer.passes_depth_test(x, y, depth);
+        rasterize(&transformed_vertices[a], &transformed_vertices[b], &transformed_vertices[c], uniforms.material.shading, visible, &mut fragments);
+        for fragment in fragments.drain(..) {
+            if transparent && fragment_alpha(&fragment, uniforms) < 0.5 {
+                continue;
+            }
+            framebuffer.point(fragment.position.x as usize, fragment.position.y as usize, fragment.depth);
+        }
+    }
+}
+
+/// Indica si un triángulo queda fuera del frustum: solo se descarta cuando
+/// sus tres vértices están fuera del mismo plano de recorte.
+///
+/// # Parámetros
+/// - `clip`: Los vértices del triángulo en coordenadas de recorte.
+pub fn outside_frustum(clip: &[Vec4; 3]) -> bool {
+    let outside = |test: fn(&Vec4) -> bool| clip.iter().all(test);
+    outside(|v| v.x < -v.w) || outside(|v| v.x > v.w) ||
+    outside(|v| v.y < -v.w) || outside(|v| v.y > v.w) ||
+    outside(|v| v.z < -v.w) || outside(|v| v.z > v.w)
+}
+
+/// Sombrea un vértice ya transformado como si fuera un fragmento en su posición,
+/// para `Shading::Gouraud`: el shader de fragmentos corre una vez por vértice y el
+/// rasterizador interpola los colores resultantes (`Fragment::color`).
+///
+/// # Parámetros
+/// - `vertex`: Vértice que devolvió el shader de vértices.
+/// - `uniforms`: Uniforms del objeto.
+pub fn shade_vertex(vertex: &Vertex, uniforms: &Uniforms) -> Color {
+    let position = vertex.transformed_position;
+    let normal = vertex.transformed_normal.normalize();
+    let mut fragment = Fragment::new(position.x, position.y, vertex.color, position.z, normal, normal.z.max(0.0), vertex.position);
+    fragment.material = vertex.material;
+    fragment.tex_coords = vertex.tex_coords;
+    fragment.elevation = vertex.elevation;
+    fragment.view_direction = view_direction(&fragment, uniforms);
+    Color::from(fragment_shader(&fragment, uniforms))
+}
+
+/// Dirección desde un fragmento hacia la cámara (`Lighting::camera_position`), en el
+/// mundo; con una proyección ortográfica es la misma para todos: hacia atrás de la vista.
+fn view_direction(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    if uniforms.matrices.is_orthographic() {
+        let view = &uniforms.matrices.view;
+        return Vec3::new(view[(2, 0)], view[(2, 1)], view[(2, 2)]);
+    }
+    let position = fragment.vertex_position;
+    let world_position = uniforms.matrices.model * Vec4::new(position.x, position.y, position.z, 1.0);
+    let offset = uniforms.lighting.camera_position - world_position.xyz();
+    if offset.magnitude() > 1e-6 { offset.normalize() } else { Vec3::new(0.0, 0.0, 1.0) }
+}
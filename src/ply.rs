@@ -0,0 +1,481 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use nalgebra_glm::{Vec2, Vec3};
+use crate::color::Color;
+use crate::obj::{face_normals, spherical_texcoords, Model};
+use crate::vertex::Vertex;
+
+/// Error al leer un archivo PLY.
+#[derive(Debug)]
+pub enum PlyError {
+    /// No se pudo leer el archivo.
+    Io(io::Error),
+    /// El contenido no es un PLY válido o usa algo que no se soporta.
+    Format(String),
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlyError::Io(error) => write!(f, "error de E/S: {}", error),
+            PlyError::Format(message) => write!(f, "PLY inválido: {}", message),
+        }
+    }
+}
+
+impl From<io::Error> for PlyError {
+    fn from(error: io::Error) -> Self {
+        PlyError::Io(error)
+    }
+}
+
+/// Crea un error de formato.
+fn format_error(message: impl Into<String>) -> PlyError {
+    PlyError::Format(message.into())
+}
+
+/// Carga una malla PLY (texto o binaria).
+///
+/// Lee las posiciones, y si están, las normales (`nx ny nz`), las coordenadas de
+/// textura (`u v`, `s t` o `texture_u texture_v`) y los colores por vértice
+/// (`red green blue`), que quedan en `Vertex::color` para el shader `VertexColor`.
+/// Las caras de más de tres vértices se dividen en abanico. Si faltan normales o
+/// coordenadas de textura se calculan igual que en los OBJ.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Model, PlyError> {
+    from_bytes(&fs::read(path)?)
+}
+
+/// Interpreta el contenido de un archivo PLY.
+pub fn from_bytes(bytes: &[u8]) -> Result<Model, PlyError> {
+    let (header, body) = parse_header(bytes)?;
+    let mut reader = match header.format {
+        Format::Ascii => Reader::Ascii(std::str::from_utf8(body)
+            .map_err(|_| format_error("el cuerpo de texto no es UTF-8"))?
+            .split_ascii_whitespace()),
+        Format::BinaryLittleEndian => Reader::Binary { bytes: body, position: 0, big_endian: false },
+        Format::BinaryBigEndian => Reader::Binary { bytes: body, position: 0, big_endian: true },
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut texcoords = Vec::new();
+    let mut colors = Vec::new();
+    let mut indices = Vec::new();
+
+    for element in &header.elements {
+        match element.name.as_str() {
+            "vertex" => {
+                let find = |names: &[&str]| element.properties.iter().position(|property| names.contains(&property.name.as_str()));
+                let position = [find(&["x"]), find(&["y"]), find(&["z"])];
+                let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+                let texcoord = [find(&["u", "s", "texture_u"]), find(&["v", "t", "texture_v"])];
+                let color = [find(&["red"]), find(&["green"]), find(&["blue"])];
+                let [Some(x), Some(y), Some(z)] = position else {
+                    return Err(format_error("los vértices no tienen x, y, z"));
+                };
+
+                let mut values = vec![0.0_f32; element.properties.len()];
+                for _ in 0..element.count {
+                    for (value, property) in values.iter_mut().zip(&element.properties) {
+                        *value = match property.kind {
+                            PropertyKind::Scalar(scalar) => reader.read(scalar)? as f32,
+                            // Las listas en los vértices no se usan: se leen y se descartan.
+                            PropertyKind::List { count, item } => {
+                                for _ in 0..list_length(reader.read(count)?)? {
+                                    reader.read(item)?;
+                                }
+                                0.0
+                            }
+                        };
+                    }
+
+                    positions.push(Vec3::new(values[x], values[y], values[z]));
+                    if let [Some(nx), Some(ny), Some(nz)] = normal {
+                        normals.push(Vec3::new(values[nx], values[ny], values[nz]));
+                    }
+                    if let [Some(u), Some(v)] = texcoord {
+                        // Igual que en los OBJ, el eje V se invierte.
+                        texcoords.push(Vec2::new(values[u], 1.0 - values[v]));
+                    }
+                    if let [Some(r), Some(g), Some(b)] = color {
+                        let channel = |index: usize| {
+                            let value = values[index];
+                            // Los colores enteros van de 0 a 255 y los de punto flotante de 0 a 1.
+                            let value = match element.properties[index].kind {
+                                PropertyKind::Scalar(Scalar::Float) | PropertyKind::Scalar(Scalar::Double) => value * 255.0,
+                                _ => value,
+                            };
+                            value.round().clamp(0.0, 255.0) as u8
+                        };
+                        colors.push(Color::new(channel(r), channel(g), channel(b)));
+                    }
+                }
+            }
+            "face" => {
+                let list = element.properties.iter()
+                    .position(|property| matches!(property.name.as_str(), "vertex_indices" | "vertex_index"))
+                    .ok_or_else(|| format_error("las caras no tienen vertex_indices"))?;
+
+                let mut face = Vec::new();
+                for _ in 0..element.count {
+                    for (index, property) in element.properties.iter().enumerate() {
+                        match property.kind {
+                            PropertyKind::Scalar(scalar) => {
+                                reader.read(scalar)?;
+                            }
+                            PropertyKind::List { count, item } => {
+                                face.clear();
+                                for _ in 0..list_length(reader.read(count)?)? {
+                                    face.push(reader.read(item)?);
+                                }
+                                if index == list {
+                                    triangulate(&face, &mut indices)?;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            // Otros elementos (aristas, materiales) se leen y se descartan.
+            _ if element.properties.is_empty() => {}
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        match property.kind {
+                            PropertyKind::Scalar(scalar) => {
+                                reader.read(scalar)?;
+                            }
+                            PropertyKind::List { count, item } => {
+                                for _ in 0..list_length(reader.read(count)?)? {
+                                    reader.read(item)?;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if positions.iter().chain(&normals).any(|v| !v.iter().all(|c| c.is_finite()))
+        || texcoords.iter().any(|t| !t.iter().all(|c| c.is_finite()))
+    {
+        return Err(format_error("hay coordenadas que no son finitas"));
+    }
+    if indices.iter().any(|&index| index as usize >= positions.len()) {
+        return Err(format_error("una cara usa un vértice que no existe"));
+    }
+
+    if normals.len() != positions.len() {
+        normals = face_normals(&positions, &indices);
+    }
+    if texcoords.len() != positions.len() {
+        texcoords = spherical_texcoords(&positions);
+    }
+
+    let vertices = positions.iter().enumerate()
+        .map(|(index, &position)| {
+            let mut vertex = Vertex::new(position, normals[index], texcoords[index], 0.0);
+            if let Some(&color) = colors.get(index) {
+                vertex.color = color;
+            }
+            vertex
+        })
+        .collect();
+
+    Ok(Model::new(vertices, indices, Vec::new()))
+}
+
+/// Codificación del cuerpo del archivo.
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// Tipo numérico de una propiedad.
+#[derive(Clone, Copy)]
+enum Scalar {
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Float,
+    Double,
+}
+
+impl Scalar {
+    /// Interpreta un nombre de tipo, en su forma corta (`uchar`) o con tamaño (`uint8`).
+    fn from_name(name: &str) -> Option<Scalar> {
+        match name {
+            "char" | "int8" => Some(Scalar::Char),
+            "uchar" | "uint8" => Some(Scalar::UChar),
+            "short" | "int16" => Some(Scalar::Short),
+            "ushort" | "uint16" => Some(Scalar::UShort),
+            "int" | "int32" => Some(Scalar::Int),
+            "uint" | "uint32" => Some(Scalar::UInt),
+            "float" | "float32" => Some(Scalar::Float),
+            "double" | "float64" => Some(Scalar::Double),
+            _ => None,
+        }
+    }
+
+    /// Tamaño en bytes en los formatos binarios.
+    fn size(self) -> usize {
+        match self {
+            Scalar::Char | Scalar::UChar => 1,
+            Scalar::Short | Scalar::UShort => 2,
+            Scalar::Int | Scalar::UInt | Scalar::Float => 4,
+            Scalar::Double => 8,
+        }
+    }
+}
+
+/// Una propiedad: un número o una lista de números con su largo al principio.
+enum PropertyKind {
+    Scalar(Scalar),
+    List { count: Scalar, item: Scalar },
+}
+
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+}
+
+/// Lee la cabecera y devuelve el resto del archivo.
+fn parse_header(bytes: &[u8]) -> Result<(Header, &[u8]), PlyError> {
+    const END: &[u8] = b"end_header";
+    let end = bytes.windows(END.len())
+        .position(|window| window == END)
+        .ok_or_else(|| format_error("falta end_header"))?;
+    // Los datos empiezan después del salto de línea que cierra la cabecera.
+    let body_start = bytes[end..].iter()
+        .position(|&byte| byte == b'\n')
+        .map_or(bytes.len(), |offset| end + offset + 1);
+    let text = std::str::from_utf8(&bytes[..end]).map_err(|_| format_error("la cabecera no es UTF-8"))?;
+
+    let mut lines = text.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err(format_error("no empieza con 'ply'"));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", name, _version] => {
+                format = Some(match *name {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(format_error(format!("formato desconocido '{}'", name))),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| format_error(format!("cantidad inválida '{}'", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let scalar = |name: &str| Scalar::from_name(name).ok_or_else(|| format_error(format!("tipo desconocido '{}'", name)));
+                let kind = PropertyKind::List { count: scalar(count)?, item: scalar(item)? };
+                push_property(&mut elements, name, kind)?;
+            }
+            ["property", kind, name] => {
+                let scalar = Scalar::from_name(kind).ok_or_else(|| format_error(format!("tipo desconocido '{}'", kind)))?;
+                push_property(&mut elements, name, PropertyKind::Scalar(scalar))?;
+            }
+            _ => return Err(format_error(format!("línea de cabecera inválida '{}'", line))),
+        }
+    }
+
+    let format = format.ok_or_else(|| format_error("falta la línea 'format'"))?;
+    Ok((Header { format, elements }, &bytes[body_start..]))
+}
+
+/// Agrega una propiedad al último elemento declarado.
+fn push_property(elements: &mut [Element], name: &str, kind: PropertyKind) -> Result<(), PlyError> {
+    let element = elements.last_mut().ok_or_else(|| format_error("propiedad fuera de un elemento"))?;
+    element.properties.push(Property { name: name.to_string(), kind });
+    Ok(())
+}
+
+/// Convierte el largo leído de una lista en un número de elementos.
+fn list_length(value: f64) -> Result<usize, PlyError> {
+    if value >= 0.0 && value.fract() == 0.0 {
+        Ok(value as usize)
+    } else {
+        Err(format_error("largo de lista inválido"))
+    }
+}
+
+/// Divide una cara en triángulos en abanico desde su primer vértice.
+fn triangulate(face: &[f64], indices: &mut Vec<u32>) -> Result<(), PlyError> {
+    let index = |value: f64| {
+        if value >= 0.0 && value <= u32::MAX as f64 && value.fract() == 0.0 {
+            Ok(value as u32)
+        } else {
+            Err(format_error("índice de vértice inválido"))
+        }
+    };
+    for i in 1..face.len().saturating_sub(1) {
+        indices.extend([index(face[0])?, index(face[i])?, index(face[i + 1])?]);
+    }
+    Ok(())
+}
+
+/// Lee los números del cuerpo, en texto o en binario.
+enum Reader<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary { bytes: &'a [u8], position: usize, big_endian: bool },
+}
+
+impl Reader<'_> {
+    /// Lee el siguiente número del tipo dado.
+    fn read(&mut self, scalar: Scalar) -> Result<f64, PlyError> {
+        match self {
+            Reader::Ascii(words) => words.next()
+                .and_then(|word| word.parse().ok())
+                .ok_or_else(|| format_error("faltan datos o hay un número inválido")),
+            Reader::Binary { bytes, position, big_endian } => {
+                let size = scalar.size();
+                let data = bytes.get(*position..*position + size).ok_or_else(|| format_error("faltan datos"))?;
+                *position += size;
+
+                let mut raw = [0u8; 8];
+                raw[..size].copy_from_slice(data);
+                if *big_endian {
+                    raw[..size].reverse();
+                }
+                Ok(match scalar {
+                    Scalar::Char => raw[0] as i8 as f64,
+                    Scalar::UChar => raw[0] as f64,
+                    Scalar::Short => i16::from_le_bytes([raw[0], raw[1]]) as f64,
+                    Scalar::UShort => u16::from_le_bytes([raw[0], raw[1]]) as f64,
+                    Scalar::Int => i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    Scalar::UInt => u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    Scalar::Float => f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    Scalar::Double => f64::from_le_bytes(raw),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cabecera de un triángulo con colores `uchar` en el formato dado.
+    fn colored_triangle_header(format: &str) -> String {
+        format!(
+            "ply\nformat {} 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+             property uchar red\nproperty uchar green\nproperty uchar blue\n\
+             element face 1\nproperty list uchar int vertex_indices\nend_header\n",
+            format,
+        )
+    }
+
+    /// Cuerpo binario del triángulo de `colored_triangle_header`.
+    fn colored_triangle_body(to_bytes: fn(f32) -> [u8; 4], index_bytes: fn(i32) -> [u8; 4]) -> Vec<u8> {
+        let vertices = [([0.0, 0.0, 0.0], [255, 0, 0]), ([1.0, 0.0, 0.0], [0, 255, 0]), ([0.0, 1.0, 0.0], [0, 0, 255])];
+        let mut body = Vec::new();
+        for (position, color) in vertices {
+            for coordinate in position {
+                body.extend(to_bytes(coordinate));
+            }
+            body.extend(color);
+        }
+        body.push(3);
+        for index in 0..3 {
+            body.extend(index_bytes(index));
+        }
+        body
+    }
+
+    fn assert_colored_triangle(model: &Model) {
+        assert_eq!(model.indices, vec![0, 1, 2]);
+        assert_eq!(model.vertices[1].position, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(model.vertices[2].position, Vec3::new(0.0, 1.0, 0.0));
+        let colors: Vec<[u8; 3]> = model.vertices.iter().map(|vertex| vertex.color.into()).collect();
+        assert_eq!(colors, vec![[255, 0, 0], [0, 255, 0], [0, 0, 255]]);
+    }
+
+    #[test]
+    fn reads_an_ascii_triangle() {
+        let text = "ply\nformat ascii 1.0\ncomment un triángulo\nelement vertex 3\n\
+                    property float x\nproperty float y\nproperty float z\n\
+                    element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+                    0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n";
+        let model = from_bytes(text.as_bytes()).unwrap();
+        assert_eq!(model.vertices.len(), 3);
+        assert_eq!(model.indices, vec![0, 1, 2]);
+        assert_eq!(model.vertices[1].position, Vec3::new(1.0, 0.0, 0.0));
+        // sin normales en el archivo se calculan de la cara, que mira hacia +z
+        assert!((model.vertices[0].normal - Vec3::new(0.0, 0.0, 1.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn reads_a_binary_little_endian_triangle_with_colors() {
+        let mut bytes = colored_triangle_header("binary_little_endian").into_bytes();
+        bytes.extend(colored_triangle_body(f32::to_le_bytes, i32::to_le_bytes));
+        assert_colored_triangle(&from_bytes(&bytes).unwrap());
+    }
+
+    #[test]
+    fn reads_a_binary_big_endian_triangle_with_colors() {
+        let mut bytes = colored_triangle_header("binary_big_endian").into_bytes();
+        bytes.extend(colored_triangle_body(f32::to_be_bytes, i32::to_be_bytes));
+        assert_colored_triangle(&from_bytes(&bytes).unwrap());
+    }
+
+    #[test]
+    fn splits_a_quad_into_a_fan() {
+        let text = "ply\nformat ascii 1.0\nelement vertex 4\n\
+                    property float x\nproperty float y\nproperty float z\n\
+                    element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+                    0 0 0\n1 0 0\n1 1 0\n0 1 0\n4 0 1 2 3\n";
+        let model = from_bytes(text.as_bytes()).unwrap();
+        assert_eq!(model.indices, vec![0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn rejects_a_face_index_out_of_range() {
+        let text = "ply\nformat ascii 1.0\nelement vertex 3\n\
+                    property float x\nproperty float y\nproperty float z\n\
+                    element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+                    0 0 0\n1 0 0\n0 1 0\n3 0 1 3\n";
+        assert!(matches!(from_bytes(text.as_bytes()), Err(PlyError::Format(_))));
+    }
+
+    #[test]
+    fn rejects_non_finite_coordinates() {
+        for bad in ["nan", "inf"] {
+            let text = format!(
+                "ply\nformat ascii 1.0\nelement vertex 3\n\
+                 property float x\nproperty float y\nproperty float z\n\
+                 element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+                 0 0 0\n{} 0 0\n0 1 0\n3 0 1 2\n",
+                bad,
+            );
+            assert!(matches!(from_bytes(text.as_bytes()), Err(PlyError::Format(_))), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use nalgebra_glm::Vec3;
+use crate::color::{Color, OutputEncoding, PixelFormat};
+use crate::framebuffer::Framebuffer;
+
+/// Configuración de la etapa de post-procesado que se aplica sobre el
+/// framebuffer antes de presentarlo en la ventana.
+///
+/// Usa el z-buffer para conocer la distancia de cada píxel a la cámara y
+/// aplicar niebla por distancia y un desenfoque de profundidad de campo.
+pub struct PostProcess {
+    /// Plano cercano de la proyección, necesario para linealizar la profundidad.
+    pub near: f32,
+    /// Plano lejano de la proyección, necesario para linealizar la profundidad.
+    pub far: f32,
+    /// Si la proyección es ortográfica, con la profundidad ya lineal; como `near` y
+    /// `far`, lo actualiza quien dibuja la escena.
+    pub orthographic: bool,
+    /// Activa la niebla por distancia.
+    pub fog_enabled: bool,
+    /// Color hacia el que se desvanecen los píxeles lejanos.
+    pub fog_color: Color,
+    /// Distancia a la que empieza la niebla.
+    pub fog_start: f32,
+    /// Distancia a la que la niebla cubre por completo el píxel.
+    pub fog_end: f32,
+    /// Activa el desenfoque de profundidad de campo.
+    pub dof_enabled: bool,
+    /// Distancia que queda perfectamente enfocada.
+    pub focus_distance: f32,
+    /// Distancia alrededor del foco a partir de la cual el desenfoque es máximo.
+    pub focus_range: f32,
+    /// Radio máximo del desenfoque en píxeles.
+    pub max_blur_radius: usize,
+    /// Activa el resplandor (bloom) alrededor de las zonas brillantes.
+    pub bloom_enabled: bool,
+    /// Luminancia mínima (0 a 1) para que un píxel aporte al resplandor.
+    pub bloom_threshold: f32,
+    /// Desviación estándar del desenfoque gaussiano, en píxeles.
+    pub bloom_sigma: f32,
+    /// Factor con el que se suma el resplandor a la imagen.
+    pub bloom_intensity: f32,
+    /// Filtro de daltonismo, para revisar que las paletas de los planetas se distingan.
+    pub color_vision: ColorVisionFilter,
+    /// Codificación de la imagen final. No la aplica `apply`: la aplica el
+    /// framebuffer al escribir cada color (ver `pixel_format`), y los efectos la
+    /// deshacen para trabajar en espacio lineal.
+    pub output_encoding: OutputEncoding,
+    /// Activa el tramado ordenado (Bayer) al cuantizar a 8 bits por canal, que
+    /// reemplaza las franjas de los degradados suaves por un patrón fino. Como
+    /// `output_encoding`, lo aplica el framebuffer al escribir cada color.
+    pub dither: bool,
+    /// Activa el destello de lente cuando el sol se ve en pantalla.
+    pub flare_enabled: bool,
+    /// Brillo del destello cuando el sol se ve entero.
+    pub flare_intensity: f32,
+    /// Dónde está el sol en la imagen de este fotograma, si está delante de la cámara;
+    /// como `near` y `far`, lo actualiza quien dibuja la escena.
+    pub flare_source: Option<FlareSource>,
+}
+
+/// Posición en pantalla de la fuente de luz del destello de lente.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlareSource {
+    /// Columna del centro del sol, en píxeles.
+    pub x: f32,
+    /// Fila del centro del sol, en píxeles.
+    pub y: f32,
+    /// Profundidad (normalizada, como el z-buffer) del punto del sol más cercano a la cámara.
+    pub depth: f32,
+    /// Radio del sol en pantalla, en píxeles.
+    pub radius: f32,
+}
+
+/// Reflejos del destello sobre la recta que va del sol al centro de la imagen:
+/// posición sobre la recta (1 es el sol, 0 el centro y los negativos quedan del
+/// otro lado), radio como fracción del alto de la imagen, color y si es un anillo.
+const FLARE_GHOSTS: [(f32, f32, [f32; 3], bool); 7] = [
+    (1.0, 0.35, [1.0, 0.85, 0.6], true),
+    (0.55, 0.03, [0.9, 0.7, 0.35], false),
+    (0.25, 0.05, [0.4, 0.8, 0.5], false),
+    (-0.2, 0.02, [0.6, 0.6, 1.0], false),
+    (-0.45, 0.08, [0.35, 0.5, 1.0], false),
+    (-0.8, 0.04, [1.0, 0.5, 0.7], false),
+    (-1.2, 0.14, [0.5, 0.7, 1.0], true),
+];
+
+/// Tipos de daltonismo dicromático: falta uno de los tres tipos de cono.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDeficiency {
+    /// Sin conos sensibles al rojo.
+    Protanopia,
+    /// Sin conos sensibles al verde; el más común.
+    Deuteranopia,
+    /// Sin conos sensibles al azul.
+    Tritanopia,
+}
+
+impl ColorDeficiency {
+    /// Todos los tipos, en el orden en que se alternan desde el teclado.
+    pub const ALL: [ColorDeficiency; 3] = [ColorDeficiency::Protanopia, ColorDeficiency::Deuteranopia, ColorDeficiency::Tritanopia];
+
+    /// Devuelve el nombre del tipo.
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorDeficiency::Protanopia => "protanopia",
+            ColorDeficiency::Deuteranopia => "deuteranopia",
+            ColorDeficiency::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// Matriz que transforma un color RGB lineal en el que percibe una persona
+    /// con este tipo de daltonismo (Machado, Oliveira y Fernandes, 2009, severidad 1).
+    pub fn simulation_matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorDeficiency::Protanopia => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+            ColorDeficiency::Deuteranopia => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            ColorDeficiency::Tritanopia => [
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ],
+        }
+    }
+
+    /// Matriz que reparte la información de color que se pierde hacia los canales
+    /// que sí se distinguen: para protanopia y deuteranopia la diferencia en rojo
+    /// se suma al verde y al azul, y para tritanopia la del azul al rojo y al verde.
+    fn compensation_matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorDeficiency::Protanopia | ColorDeficiency::Deuteranopia => [
+                [0.0, 0.0, 0.0],
+                [0.7, 1.0, 0.0],
+                [0.7, 0.0, 1.0],
+            ],
+            ColorDeficiency::Tritanopia => [
+                [1.0, 0.0, 0.7],
+                [0.0, 1.0, 0.7],
+                [0.0, 0.0, 0.0],
+            ],
+        }
+    }
+}
+
+/// Filtro de accesibilidad para el daltonismo que se aplica al final del post-procesado.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorVisionFilter {
+    /// La imagen no cambia.
+    #[default]
+    Off,
+    /// Muestra la imagen como la vería una persona con este tipo de daltonismo.
+    Simulate(ColorDeficiency),
+    /// Corrige la imagen (daltonización) para que una persona con este tipo de
+    /// daltonismo distinga mejor los colores que confundiría.
+    Compensate(ColorDeficiency),
+}
+
+impl ColorVisionFilter {
+    /// Devuelve una descripción corta del filtro, para mostrarla en pantalla.
+    pub fn label(self) -> String {
+        match self {
+            ColorVisionFilter::Off => "normal".to_string(),
+            ColorVisionFilter::Simulate(deficiency) => format!("simula {}", deficiency.name()),
+            ColorVisionFilter::Compensate(deficiency) => format!("compensa {}", deficiency.name()),
+        }
+    }
+
+    /// Devuelve el siguiente filtro: primero las tres simulaciones, después las
+    /// tres compensaciones y de vuelta a `Off`.
+    pub fn next(self) -> Self {
+        let following = |deficiency: ColorDeficiency| {
+            let index = ColorDeficiency::ALL.iter().position(|&other| other == deficiency).unwrap_or(0);
+            ColorDeficiency::ALL.get(index + 1).copied()
+        };
+        match self {
+            ColorVisionFilter::Off => ColorVisionFilter::Simulate(ColorDeficiency::ALL[0]),
+            ColorVisionFilter::Simulate(deficiency) => match following(deficiency) {
+                Some(next) => ColorVisionFilter::Simulate(next),
+                None => ColorVisionFilter::Compensate(ColorDeficiency::ALL[0]),
+            },
+            ColorVisionFilter::Compensate(deficiency) => {
+                following(deficiency).map_or(ColorVisionFilter::Off, ColorVisionFilter::Compensate)
+            }
+        }
+    }
+}
+
+impl Default for PostProcess {
+    /// Crea una configuración con niebla suave, resplandor y la profundidad de campo desactivada.
+    fn default() -> Self {
+        PostProcess {
+            near: 0.1,
+            far: 1000.0,
+            orthographic: false,
+            fog_enabled: true,
+            fog_color: Color::black(),
+            fog_start: 6.0,
+            fog_end: 30.0,
+            dof_enabled: false,
+            focus_distance: 5.0,
+            focus_range: 2.0,
+            max_blur_radius: 3,
+            bloom_enabled: true,
+            bloom_threshold: 0.8,
+            bloom_sigma: 4.0,
+            bloom_intensity: 0.8,
+            color_vision: ColorVisionFilter::Off,
+            output_encoding: OutputEncoding::Linear,
+            dither: true,
+            flare_enabled: true,
+            flare_intensity: 0.25,
+            flare_source: None,
+        }
+    }
+}
+
+impl PostProcess {
+    /// Aplica los efectos activos sobre el buffer de color del framebuffer.
+    ///
+    /// # Parámetros
+    /// - `framebuffer`: El framebuffer ya rasterizado, con su z-buffer completo.
+    pub fn apply(&self, framebuffer: &mut Framebuffer) {
+        if self.dof_enabled {
+            self.apply_depth_of_field(framebuffer);
+        }
+        if self.fog_enabled {
+            self.apply_fog(framebuffer);
+        }
+        if self.bloom_enabled {
+            self.apply_bloom(framebuffer);
+        }
+        if self.flare_enabled {
+            self.apply_lens_flare(framebuffer);
+        }
+        self.apply_color_vision(framebuffer);
+    }
+
+    /// Formato con el que se tiene que dibujar la escena para que la imagen salga
+    /// con `output_encoding` (ver `Framebuffer::set_pixel_format`).
+    pub fn pixel_format(&self) -> PixelFormat {
+        PixelFormat { encoding: self.output_encoding, dither: self.dither }
+    }
+
+    /// Aplica el filtro de daltonismo sobre los valores lineales, porque las
+    /// matrices están definidas para ellos.
+    fn apply_color_vision(&self, framebuffer: &mut Framebuffer) {
+        let (deficiency, compensate) = match self.color_vision {
+            ColorVisionFilter::Off => return,
+            ColorVisionFilter::Simulate(deficiency) => (deficiency, false),
+            ColorVisionFilter::Compensate(deficiency) => (deficiency, true),
+        };
+        let simulation = deficiency.simulation_matrix();
+        let compensation = deficiency.compensation_matrix();
+        let (width, format) = (framebuffer.width, framebuffer.pixel_format());
+        for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            let rgb = unpack(format, *pixel);
+            let seen = multiply(&simulation, rgb);
+            let (x, y) = (index % width, index / width);
+            *pixel = if compensate {
+                // lo que no se percibe se redistribuye sobre el color original
+                let lost = [rgb[0] - seen[0], rgb[1] - seen[1], rgb[2] - seen[2]];
+                let shift = multiply(&compensation, lost);
+                pack(format, [rgb[0] + shift[0], rgb[1] + shift[1], rgb[2] + shift[2]], x, y)
+            } else {
+                pack(format, seen, x, y)
+            };
+        }
+    }
+
+    /// Suma el destello de lente: una cadena de reflejos sobre la recta que va del
+    /// sol al centro de la imagen, más tenue cuanto más tapado está el sol.
+    fn apply_lens_flare(&self, framebuffer: &mut Framebuffer) {
+        let Some(source) = self.flare_source else { return };
+        let visibility = flare_visibility(framebuffer, &source);
+        if visibility <= 0.0 {
+            return;
+        }
+
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+        let offset = (source.x - center_x, source.y - center_y);
+        let strength = self.flare_intensity * visibility;
+        let format = framebuffer.pixel_format();
+
+        for &(position, size, tint, ring) in &FLARE_GHOSTS {
+            let ghost_x = center_x + offset.0 * position;
+            let ghost_y = center_y + offset.1 * position;
+            let radius = size * height as f32;
+            let (x0, x1) = ((ghost_x - radius).floor().max(0.0) as usize, ((ghost_x + radius).ceil().max(0.0) as usize).min(width));
+            let (y0, y1) = ((ghost_y - radius).floor().max(0.0) as usize, ((ghost_y + radius).ceil().max(0.0) as usize).min(height));
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let distance = ((x as f32 + 0.5 - ghost_x).powi(2) + (y as f32 + 0.5 - ghost_y).powi(2)).sqrt() / radius;
+                    if distance >= 1.0 {
+                        continue;
+                    }
+                    // Los discos se desvanecen hacia el borde; los anillos brillan cerca de él.
+                    let shape = if ring {
+                        (1.0 - ((distance - 0.9) / 0.1).abs()).max(0.0).powi(2) * 0.3
+                    } else {
+                        (1.0 - distance * distance).powi(2)
+                    };
+                    let index = y * width + x;
+                    let rgb = unpack(format, framebuffer.buffer[index]);
+                    let amount = strength * shape;
+                    framebuffer.buffer[index] = pack(format, [
+                        rgb[0] + tint[0] * amount,
+                        rgb[1] + tint[1] * amount,
+                        rgb[2] + tint[2] * amount,
+                    ], x, y);
+                }
+            }
+        }
+    }
+
+    /// Convierte la profundidad del z-buffer (en coordenadas normalizadas, de -1 a 1)
+    /// a la distancia real a la cámara.
+    ///
+    /// # Retorna
+    /// La distancia lineal, o infinito si el píxel no tiene geometría.
+    pub fn linear_depth(&self, depth: f32) -> f32 {
+        if !depth.is_finite() {
+            return f32::INFINITY;
+        }
+        let (n, f) = (self.near, self.far);
+        if self.orthographic {
+            return n + (depth + 1.0) / 2.0 * (f - n);
+        }
+        (2.0 * n * f) / (f + n - depth * (f - n))
+    }
+
+    /// Mezcla cada píxel con el color de niebla según su distancia a la cámara.
+    /// Los píxeles de fondo (sin profundidad) no se modifican.
+    fn apply_fog(&self, framebuffer: &mut Framebuffer) {
+        let range = (self.fog_end - self.fog_start).max(f32::EPSILON);
+        let format = framebuffer.pixel_format();
+        let fog_color = Vec3::from(self.fog_color);
+
+        let width = framebuffer.width;
+        for (index, (pixel, &depth)) in framebuffer.buffer.iter_mut().zip(framebuffer.zbuffer.iter()).enumerate() {
+            let distance = self.linear_depth(depth);
+            if !distance.is_finite() {
+                continue;
+            }
+
+            let fog_factor = ((distance - self.fog_start) / range).clamp(0.0, 1.0);
+            if fog_factor > 0.0 {
+                *pixel = format.pack(format.unpack(*pixel).lerp(&fog_color, fog_factor), index % width, index / width);
+            }
+        }
+    }
+
+    /// Desenfoca cada píxel con un radio proporcional a su distancia al plano de foco.
+    fn apply_depth_of_field(&self, framebuffer: &mut Framebuffer) {
+        if self.max_blur_radius == 0 {
+            return;
+        }
+
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let format = framebuffer.pixel_format();
+        let source: Vec<Vec3> = framebuffer.buffer.iter().map(|&pixel| format.unpack(pixel)).collect();
+        let focus_range = self.focus_range.max(f32::EPSILON);
+
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                let distance = self.linear_depth(framebuffer.zbuffer[index]);
+
+                // Cuánto se aleja el píxel del plano de foco, entre 0 y 1.
+                let blur = ((distance - self.focus_distance).abs() / focus_range).min(1.0);
+                let radius = (blur * self.max_blur_radius as f32).round() as usize;
+                if radius == 0 {
+                    continue;
+                }
+
+                let (x0, x1) = (x.saturating_sub(radius), (x + radius).min(width - 1));
+                let (y0, y1) = (y.saturating_sub(radius), (y + radius).min(height - 1));
+
+                let (mut sum, mut count) = (Vec3::zeros(), 0);
+                for sy in y0..=y1 {
+                    for pixel in &source[sy * width + x0..=sy * width + x1] {
+                        sum += pixel;
+                        count += 1;
+                    }
+                }
+
+                framebuffer.buffer[index] = format.pack(sum / count as f32, x, y);
+            }
+        }
+    }
+
+    /// Extrae los píxeles brillantes, los desenfoca con un filtro gaussiano
+    /// separable y los suma de vuelta a la imagen.
+    fn apply_bloom(&self, framebuffer: &mut Framebuffer) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let format = framebuffer.pixel_format();
+
+        // Paso de brillo: solo se conserva la parte de cada píxel que supera el umbral.
+        let mut bright: Vec<[f32; 3]> = framebuffer.buffer.iter().map(|&pixel| {
+            let rgb = unpack(format, pixel);
+            let luminance = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+            if luminance > self.bloom_threshold {
+                let factor = (luminance - self.bloom_threshold) / luminance;
+                [rgb[0] * factor, rgb[1] * factor, rgb[2] * factor]
+            } else {
+                [0.0; 3]
+            }
+        }).collect();
+
+        // Desenfoque horizontal seguido de uno vertical.
+        let kernel = gaussian_kernel(self.bloom_sigma);
+        let mut temp = vec![[0.0f32; 3]; width * height];
+        blur_pass(&bright, &mut temp, width, height, &kernel, true);
+        blur_pass(&temp, &mut bright, width, height, &kernel, false);
+
+        // Composición aditiva del resplandor sobre la imagen original; el tramado
+        // evita anillos en el halo, que se desvanece muy despacio.
+        for (index, (pixel, glow)) in framebuffer.buffer.iter_mut().zip(bright.iter()).enumerate() {
+            let rgb = unpack(format, *pixel);
+            *pixel = pack(format, [
+                rgb[0] + glow[0] * self.bloom_intensity,
+                rgb[1] + glow[1] * self.bloom_intensity,
+                rgb[2] + glow[2] * self.bloom_intensity,
+            ], index % width, index / width);
+        }
+    }
+}
+
+/// Fracción del sol que se ve: prueba una rejilla de puntos sobre su disco y cuenta
+/// los que están dentro de la imagen y sin nada delante en el z-buffer.
+fn flare_visibility(framebuffer: &Framebuffer, source: &FlareSource) -> f32 {
+    const SAMPLES: i32 = 4;
+    let mut visible = 0;
+    let mut total = 0;
+    for row in -SAMPLES..=SAMPLES {
+        for column in -SAMPLES..=SAMPLES {
+            let (u, v) = (column as f32 / SAMPLES as f32, row as f32 / SAMPLES as f32);
+            if u * u + v * v > 1.0 {
+                continue;
+            }
+            total += 1;
+            let x = (source.x + u * source.radius * 0.8).floor();
+            let y = (source.y + v * source.radius * 0.8).floor();
+            if x < 0.0 || y < 0.0 || x >= framebuffer.width as f32 || y >= framebuffer.height as f32 {
+                continue;
+            }
+            // El propio sol deja en el z-buffer una profundidad igual o mayor que la de su frente.
+            let depth = framebuffer.zbuffer[y as usize * framebuffer.width + x as usize];
+            if depth >= source.depth - 1e-4 {
+                visible += 1;
+            }
+        }
+    }
+    visible as f32 / total as f32
+}
+
+/// Separa un píxel en sus componentes RGB lineales entre 0 y 1.
+fn unpack(format: PixelFormat, pixel: u32) -> [f32; 3] {
+    format.unpack(pixel).into()
+}
+
+/// Empaqueta componentes RGB lineales en el píxel (x, y), saturando los valores
+/// fuera de rango (ver `PixelFormat::pack`).
+fn pack(format: PixelFormat, rgb: [f32; 3], x: usize, y: usize) -> u32 {
+    format.pack(Vec3::from(rgb), x, y)
+}
+
+/// Multiplica una matriz de 3x3 por un color RGB.
+fn multiply(matrix: &[[f32; 3]; 3], rgb: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2])
+}
+
+/// Aplica un núcleo de convolución 1D en horizontal o en vertical,
+/// repitiendo los píxeles del borde.
+fn blur_pass(source: &[[f32; 3]], target: &mut [[f32; 3]], width: usize, height: usize, kernel: &[f32], horizontal: bool) {
+    let radius = (kernel.len() / 2) as isize;
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0.0f32; 3];
+            for (k, weight) in kernel.iter().enumerate() {
+                let offset = k as isize - radius;
+                let (sx, sy) = if horizontal {
+                    ((x as isize + offset).clamp(0, width as isize - 1) as usize, y)
+                } else {
+                    (x, (y as isize + offset).clamp(0, height as isize - 1) as usize)
+                };
+                let sample = source[sy * width + sx];
+                sum[0] += sample[0] * weight;
+                sum[1] += sample[1] * weight;
+                sum[2] += sample[2] * weight;
+            }
+            target[y * width + x] = sum;
+        }
+    }
+}
+
+/// Construye un núcleo gaussiano normalizado de radio `3 * sigma`.
+fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+    let sigma = sigma.max(0.1);
+    let radius = (sigma * 3.0).ceil() as isize;
+    let mut kernel: Vec<f32> = (-radius..=radius)
+        .map(|i| (-(i * i) as f32 / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|weight| *weight /= total);
+    kernel
+}
@@ -0,0 +1,531 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use crate::export::{ExportFormat, Recorder};
+use crate::framebuffer::Framebuffer;
+
+/// Error al mostrar un fotograma.
+#[derive(Debug)]
+pub enum PresentError {
+    /// La ventana o el canvas no aceptó el fotograma.
+    Surface(String),
+    /// No se pudo escribir el fotograma en disco (`FilePresenter`).
+    Io(io::Error),
+}
+
+impl fmt::Display for PresentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PresentError::Surface(message) => write!(f, "no se pudo mostrar el fotograma: {}", message),
+            PresentError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<io::Error> for PresentError {
+    fn from(error: io::Error) -> Self {
+        PresentError::Io(error)
+    }
+}
+
+/// Tecla, sin depender de la biblioteca de ventanas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// Tecla que escribe un carácter: letras (en minúscula), dígitos, signos y `' '`.
+    Char(char),
+    /// Tecla de función, de `F1` a `F12`.
+    Function(u8),
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Insert,
+    Delete,
+    Backspace,
+    Tab,
+    Enter,
+    Escape,
+    LeftShift,
+    RightShift,
+    LeftCtrl,
+    RightCtrl,
+    LeftAlt,
+    RightAlt,
+}
+
+impl Key {
+    /// Convierte el nombre de una tecla del navegador (`KeyboardEvent.key`, como
+    /// `"a"`, `"ArrowUp"` o `"F9"`); los modificadores cuentan como los de la izquierda.
+    pub fn from_name(name: &str) -> Option<Key> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Some(Key::Char(c.to_ascii_lowercase()));
+        }
+        if let Some(number) = name.strip_prefix('F').and_then(|number| number.parse().ok()) {
+            return (1..=12).contains(&number).then_some(Key::Function(number));
+        }
+        let key = match name {
+            "ArrowUp" => Key::Up,
+            "ArrowDown" => Key::Down,
+            "ArrowLeft" => Key::Left,
+            "ArrowRight" => Key::Right,
+            "PageUp" => Key::PageUp,
+            "PageDown" => Key::PageDown,
+            "Home" => Key::Home,
+            "End" => Key::End,
+            "Insert" => Key::Insert,
+            "Delete" => Key::Delete,
+            "Backspace" => Key::Backspace,
+            "Tab" => Key::Tab,
+            "Enter" => Key::Enter,
+            "Escape" => Key::Escape,
+            "Shift" => Key::LeftShift,
+            "Control" => Key::LeftCtrl,
+            "Alt" => Key::LeftAlt,
+            _ => return None,
+        };
+        Some(key)
+    }
+}
+
+/// Botón del mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+/// Algo que hizo el usuario desde el último fotograma (ver `Presenter::poll_input`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    /// Se apretó una tecla.
+    KeyPressed(Key),
+    /// Se soltó una tecla.
+    KeyReleased(Key),
+    /// El mouse se movió a una posición, en píxeles del área visible.
+    MouseMoved { x: f32, y: f32 },
+    /// Se apretó (`pressed`) o se soltó un botón del mouse.
+    MouseButton { button: MouseButton, pressed: bool },
+    /// La rueda del mouse giró; `y` positivo es hacia arriba.
+    Scroll { x: f32, y: f32 },
+    /// El área visible cambió de tamaño, en píxeles.
+    Resized { width: usize, height: usize },
+    /// El usuario cerró la ventana.
+    Closed,
+}
+
+/// Lo que muestra los fotogramas terminados y recibe la entrada del usuario: la
+/// ventana del visor nativo (`WindowPresenter`), un canvas del navegador
+/// (`web::CanvasPresenter`), archivos en disco (`FilePresenter`) o memoria, para
+/// pruebas (`MemoryPresenter`).
+///
+/// El renderizador solo escribe en un `Framebuffer`; quien lo muestra decide cómo
+/// copiar sus píxeles, así el mismo código de dibujo sirve en todas las plataformas.
+pub trait Presenter {
+    /// Tamaño del área visible, en píxeles, para ajustar el framebuffer.
+    fn size(&self) -> (usize, usize);
+
+    /// Indica si todavía hay dónde mostrar fotogramas (la ventana no se cerró).
+    fn is_open(&self) -> bool;
+
+    /// Muestra un fotograma, escalándolo al área visible si tiene otro tamaño.
+    ///
+    /// Los presentadores de pantalla muestran el búfer frontal y copian solo sus
+    /// regiones cambiadas, así que antes hay que llamar a `Framebuffer::swap_buffers`;
+    /// los que guardan fotogramas enteros (`FilePresenter`, `MemoryPresenter`) usan `buffer`.
+    fn present(&mut self, framebuffer: &Framebuffer) -> Result<(), PresentError>;
+
+    /// Devuelve lo que hizo el usuario desde la última llamada, en orden.
+    fn poll_input(&mut self) -> Vec<InputEvent>;
+}
+
+/// Ventana de minifb, la del visor nativo.
+///
+/// Además de `Presenter`, se comporta como la ventana (`Deref`), para leer el
+/// teclado y el mouse directamente donde conviene preguntar "¿está apretada?" en
+/// lugar de seguir eventos.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct WindowPresenter {
+    window: minifb::Window,
+    size: (usize, usize),
+    mouse: Option<(f32, f32)>,
+    buttons: [bool; 3],
+    closed: bool,
+    // Tamaño del último fotograma copiado a la ventana; `None` antes del primero.
+    shown: Option<(usize, usize)>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl WindowPresenter {
+    /// Envuelve una ventana ya abierta.
+    pub fn new(window: minifb::Window) -> Self {
+        let size = window.get_size();
+        WindowPresenter { window, size, mouse: None, buttons: [false; 3], closed: false, shown: None }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::ops::Deref for WindowPresenter {
+    type Target = minifb::Window;
+
+    fn deref(&self) -> &minifb::Window {
+        &self.window
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::ops::DerefMut for WindowPresenter {
+    fn deref_mut(&mut self) -> &mut minifb::Window {
+        &mut self.window
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Presenter for WindowPresenter {
+    fn size(&self) -> (usize, usize) {
+        self.window.get_size()
+    }
+
+    fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    fn present(&mut self, framebuffer: &Framebuffer) -> Result<(), PresentError> {
+        // minifb no copia rectángulos sueltos: si nada cambió solo se atienden los
+        // eventos de la ventana, que conserva la imagen anterior
+        let size = (framebuffer.width, framebuffer.height);
+        if framebuffer.dirty_regions().is_empty() && self.shown == Some(size) {
+            self.window.update();
+            return Ok(());
+        }
+        self.shown = Some(size);
+        self.window.update_with_buffer(framebuffer.front(), framebuffer.width, framebuffer.height)
+            .map_err(|error| PresentError::Surface(error.to_string()))
+    }
+
+    fn poll_input(&mut self) -> Vec<InputEvent> {
+        use minifb::KeyRepeat;
+
+        let mut events = Vec::new();
+        if !self.window.is_open() {
+            if !self.closed {
+                self.closed = true;
+                events.push(InputEvent::Closed);
+            }
+            return events;
+        }
+        let size = self.window.get_size();
+        if size != self.size && size.0 > 0 && size.1 > 0 {
+            self.size = size;
+            events.push(InputEvent::Resized { width: size.0, height: size.1 });
+        }
+        let pressed = self.window.get_keys_pressed(KeyRepeat::No);
+        events.extend(pressed.into_iter().filter_map(minifb_key).map(InputEvent::KeyPressed));
+        events.extend(self.window.get_keys_released().into_iter().filter_map(minifb_key).map(InputEvent::KeyReleased));
+        let mouse = self.window.get_mouse_pos(minifb::MouseMode::Discard);
+        if let Some((x, y)) = mouse.filter(|&position| Some(position) != self.mouse) {
+            events.push(InputEvent::MouseMoved { x, y });
+        }
+        self.mouse = mouse;
+        let buttons = [
+            (MouseButton::Left, minifb::MouseButton::Left),
+            (MouseButton::Middle, minifb::MouseButton::Middle),
+            (MouseButton::Right, minifb::MouseButton::Right),
+        ];
+        for (state, (button, minifb_button)) in self.buttons.iter_mut().zip(buttons) {
+            let pressed = self.window.get_mouse_down(minifb_button);
+            if pressed != *state {
+                *state = pressed;
+                events.push(InputEvent::MouseButton { button, pressed });
+            }
+        }
+        if let Some((x, y)) = self.window.get_scroll_wheel() {
+            events.push(InputEvent::Scroll { x, y });
+        }
+        events
+    }
+}
+
+/// Tecla de minifb con su equivalente en `Key`, si lo tiene.
+#[cfg(not(target_arch = "wasm32"))]
+fn minifb_key(key: minifb::Key) -> Option<Key> {
+    use minifb::Key as K;
+
+    let key = match key {
+        K::Key0 | K::NumPad0 => Key::Char('0'),
+        K::Key1 | K::NumPad1 => Key::Char('1'),
+        K::Key2 | K::NumPad2 => Key::Char('2'),
+        K::Key3 | K::NumPad3 => Key::Char('3'),
+        K::Key4 | K::NumPad4 => Key::Char('4'),
+        K::Key5 | K::NumPad5 => Key::Char('5'),
+        K::Key6 | K::NumPad6 => Key::Char('6'),
+        K::Key7 | K::NumPad7 => Key::Char('7'),
+        K::Key8 | K::NumPad8 => Key::Char('8'),
+        K::Key9 | K::NumPad9 => Key::Char('9'),
+        K::A => Key::Char('a'),
+        K::B => Key::Char('b'),
+        K::C => Key::Char('c'),
+        K::D => Key::Char('d'),
+        K::E => Key::Char('e'),
+        K::F => Key::Char('f'),
+        K::G => Key::Char('g'),
+        K::H => Key::Char('h'),
+        K::I => Key::Char('i'),
+        K::J => Key::Char('j'),
+        K::K => Key::Char('k'),
+        K::L => Key::Char('l'),
+        K::M => Key::Char('m'),
+        K::N => Key::Char('n'),
+        K::O => Key::Char('o'),
+        K::P => Key::Char('p'),
+        K::Q => Key::Char('q'),
+        K::R => Key::Char('r'),
+        K::S => Key::Char('s'),
+        K::T => Key::Char('t'),
+        K::U => Key::Char('u'),
+        K::V => Key::Char('v'),
+        K::W => Key::Char('w'),
+        K::X => Key::Char('x'),
+        K::Y => Key::Char('y'),
+        K::Z => Key::Char('z'),
+        K::F1 => Key::Function(1),
+        K::F2 => Key::Function(2),
+        K::F3 => Key::Function(3),
+        K::F4 => Key::Function(4),
+        K::F5 => Key::Function(5),
+        K::F6 => Key::Function(6),
+        K::F7 => Key::Function(7),
+        K::F8 => Key::Function(8),
+        K::F9 => Key::Function(9),
+        K::F10 => Key::Function(10),
+        K::F11 => Key::Function(11),
+        K::F12 => Key::Function(12),
+        K::Space => Key::Char(' '),
+        K::Apostrophe => Key::Char('\''),
+        K::Backquote => Key::Char('`'),
+        K::Backslash => Key::Char('\\'),
+        K::Comma => Key::Char(','),
+        K::Equal | K::NumPadPlus => Key::Char('='),
+        K::LeftBracket => Key::Char('['),
+        K::Minus | K::NumPadMinus => Key::Char('-'),
+        K::Period | K::NumPadDot => Key::Char('.'),
+        K::RightBracket => Key::Char(']'),
+        K::Semicolon => Key::Char(';'),
+        K::Slash | K::NumPadSlash => Key::Char('/'),
+        K::NumPadAsterisk => Key::Char('*'),
+        K::Up => Key::Up,
+        K::Down => Key::Down,
+        K::Left => Key::Left,
+        K::Right => Key::Right,
+        K::PageUp => Key::PageUp,
+        K::PageDown => Key::PageDown,
+        K::Home => Key::Home,
+        K::End => Key::End,
+        K::Insert => Key::Insert,
+        K::Delete => Key::Delete,
+        K::Backspace => Key::Backspace,
+        K::Tab => Key::Tab,
+        K::Enter | K::NumPadEnter => Key::Enter,
+        K::Escape => Key::Escape,
+        K::LeftShift => Key::LeftShift,
+        K::RightShift => Key::RightShift,
+        K::LeftCtrl => Key::LeftCtrl,
+        K::RightCtrl => Key::RightCtrl,
+        K::LeftAlt => Key::LeftAlt,
+        K::RightAlt => Key::RightAlt,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Guarda cada fotograma en disco en vez de mostrarlo: un GIF o una secuencia de
+/// PNG (con `export::Recorder`) o, sin formato, una secuencia de PPM numerados.
+/// Es el presentador de los renders sin ventana; no recibe entrada.
+pub struct FilePresenter {
+    recorder: Option<Recorder>,
+    directory: std::path::PathBuf,
+    width: usize,
+    height: usize,
+    presented: u32,
+}
+
+impl FilePresenter {
+    /// Crea la salida.
+    ///
+    /// # Parámetros
+    /// - `path`: Archivo GIF o carpeta de la secuencia (se crea si no existe).
+    /// - `format`: Formato de la grabación, o `None` para guardar PPM.
+    /// - `width`, `height`: Tamaño de los fotogramas.
+    pub fn create<P: AsRef<Path>>(path: P, format: Option<ExportFormat>, width: usize, height: usize) -> io::Result<Self> {
+        let path = path.as_ref();
+        let recorder = match format {
+            Some(format) => Some(Recorder::create(path, format, width, height, 60.0)?),
+            None => {
+                std::fs::create_dir_all(path)?;
+                None
+            }
+        };
+        Ok(FilePresenter { recorder, directory: path.to_path_buf(), width, height, presented: 0 })
+    }
+
+    /// Fotogramas ya guardados.
+    pub fn presented(&self) -> u32 {
+        self.presented
+    }
+
+    /// Termina la salida y cierra el archivo (ver `Recorder::finish`).
+    pub fn finish(self) -> io::Result<()> {
+        self.recorder.map_or(Ok(()), Recorder::finish)
+    }
+}
+
+impl Presenter for FilePresenter {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn is_open(&self) -> bool {
+        true
+    }
+
+    fn present(&mut self, framebuffer: &Framebuffer) -> Result<(), PresentError> {
+        match &mut self.recorder {
+            Some(recorder) => recorder.record(framebuffer)?,
+            None => framebuffer.save_ppm(self.directory.join(format!("frame_{:05}.ppm", self.presented)))?,
+        }
+        self.presented += 1;
+        Ok(())
+    }
+
+    fn poll_input(&mut self) -> Vec<InputEvent> {
+        Vec::new()
+    }
+}
+
+/// Presentador sin pantalla para pruebas: guarda una copia de cada fotograma y
+/// entrega la entrada que se le haya preparado, un grupo de eventos por llamada a
+/// `poll_input`. Se cierra al entregar un `InputEvent::Closed`.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryPresenter {
+    width: usize,
+    height: usize,
+    /// Píxeles de los fotogramas mostrados, en orden.
+    pub frames: Vec<Vec<u32>>,
+    input: VecDeque<Vec<InputEvent>>,
+    closed: bool,
+}
+
+impl MemoryPresenter {
+    /// Crea un presentador con un área visible de `width` x `height` píxeles.
+    pub fn new(width: usize, height: usize) -> Self {
+        MemoryPresenter { width, height, ..MemoryPresenter::default() }
+    }
+
+    /// Agrega los eventos que devolverá una próxima llamada a `poll_input`.
+    pub fn push_input(&mut self, events: Vec<InputEvent>) {
+        self.input.push_back(events);
+    }
+}
+
+impl Presenter for MemoryPresenter {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn is_open(&self) -> bool {
+        !self.closed
+    }
+
+    fn present(&mut self, framebuffer: &Framebuffer) -> Result<(), PresentError> {
+        self.frames.push(framebuffer.buffer.clone());
+        Ok(())
+    }
+
+    fn poll_input(&mut self) -> Vec<InputEvent> {
+        let events = self.input.pop_front().unwrap_or_default();
+        for event in &events {
+            match *event {
+                InputEvent::Closed => self.closed = true,
+                InputEvent::Resized { width, height } => (self.width, self.height) = (width, height),
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Framebuffer de 2 x 2 pintado de un solo color.
+    fn filled(color: u32) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(2, 2);
+        framebuffer.set_background_color(color);
+        framebuffer.clear();
+        framebuffer
+    }
+
+    #[test]
+    fn memory_presenter_keeps_frames_in_order() {
+        let mut presenter = MemoryPresenter::new(2, 2);
+        for color in [0xFF0000, 0x00FF00, 0x0000FF] {
+            presenter.present(&filled(color)).unwrap();
+        }
+        assert_eq!(presenter.frames, vec![vec![0xFF0000; 4], vec![0x00FF00; 4], vec![0x0000FF; 4]]);
+    }
+
+    #[test]
+    fn memory_presenter_returns_one_group_per_poll() {
+        let mut presenter = MemoryPresenter::new(2, 2);
+        let first = vec![InputEvent::KeyPressed(Key::Char('w')), InputEvent::MouseMoved { x: 1.0, y: 0.5 }];
+        let second = vec![InputEvent::KeyReleased(Key::Char('w'))];
+        presenter.push_input(first.clone());
+        presenter.push_input(second.clone());
+
+        assert_eq!(presenter.poll_input(), first);
+        assert_eq!(presenter.poll_input(), second);
+        // sin eventos preparados no llega nada
+        assert!(presenter.poll_input().is_empty());
+        assert!(presenter.is_open());
+    }
+
+    #[test]
+    fn memory_presenter_applies_resize_and_close() {
+        let mut presenter = MemoryPresenter::new(2, 2);
+        presenter.push_input(vec![InputEvent::Resized { width: 8, height: 6 }]);
+        presenter.push_input(vec![InputEvent::Scroll { x: 0.0, y: 1.0 }, InputEvent::Closed]);
+
+        presenter.poll_input();
+        assert_eq!(presenter.size(), (8, 6));
+        assert!(presenter.is_open());
+        assert_eq!(presenter.poll_input(), vec![InputEvent::Scroll { x: 0.0, y: 1.0 }, InputEvent::Closed]);
+        assert!(!presenter.is_open());
+    }
+
+    #[test]
+    fn file_presenter_writes_numbered_frames() {
+        let directory = std::env::temp_dir().join(format!("present_test_{}", std::process::id()));
+        let mut presenter = FilePresenter::create(&directory, None, 2, 2).unwrap();
+        presenter.present(&filled(0xFF0000)).unwrap();
+        presenter.present(&filled(0x0000FF)).unwrap();
+        assert_eq!(presenter.presented(), 2);
+        assert!(presenter.poll_input().is_empty());
+        presenter.finish().unwrap();
+
+        // cada fotograma en su PPM, con la cabecera y los píxeles en RGB
+        let first = std::fs::read(directory.join("frame_00000.ppm")).unwrap();
+        let second = std::fs::read(directory.join("frame_00001.ppm")).unwrap();
+        assert!(!directory.join("frame_00002.ppm").exists());
+        let header = b"P6\n2 2\n255\n";
+        assert_eq!(first, [&header[..], &[255, 0, 0].repeat(4)].concat());
+        assert_eq!(second, [&header[..], &[0, 0, 255].repeat(4)].concat());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use nalgebra_glm::{Vec2, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::obj::{face_normals, Model};
+use crate::vertex::Vertex;
+
+/// Prefijo de los nombres de malla que se generan en lugar de leerse de un archivo.
+pub const PRIMITIVE_PREFIX: &str = "primitive:";
+
+/// Genera una malla a partir de su nombre en la escena.
+///
+/// Los nombres reconocidos son `primitive:uv_sphere`, `primitive:icosphere`,
+/// `primitive:torus`, `primitive:ring` y `primitive:asteroid`. Todas caben en una
+/// esfera de radio 0.5, como `sphere.obj`, para que el radio del nodo signifique lo mismo.
+///
+/// # Retorna
+/// La malla generada, o `None` si el nombre no es una primitiva conocida.
+pub fn from_name(name: &str) -> Option<Model> {
+    from_name_with_lod(name, 0)
+}
+
+/// Igual que `from_name`, pero con más o menos detalle que el normal.
+///
+/// # Parámetros
+/// - `name`: Nombre de la malla en la escena.
+/// - `lod_bias`: Cada unidad duplica (si es positiva) o divide a la mitad (si es
+///   negativa) las divisiones de la malla; en la icoesfera suma o resta una subdivisión.
+///
+/// # Retorna
+/// La malla generada, o `None` si el nombre no es una primitiva conocida.
+pub fn from_name_with_lod(name: &str, lod_bias: i32) -> Option<Model> {
+    let detail = |count: u32| (count as f32 * 2.0_f32.powi(lod_bias)).round() as u32;
+    match name.strip_prefix(PRIMITIVE_PREFIX)? {
+        "uv_sphere" => Some(uv_sphere(0.5, detail(32), detail(16))),
+        "icosphere" => Some(icosphere(0.5, (3 + lod_bias).clamp(0, 5) as u32)),
+        "torus" => Some(torus(0.35, 0.15, detail(32), detail(16))),
+        "ring" => Some(ring(0.3, 0.5, detail(64))),
+        "asteroid" => Some(asteroid(0.5, (2 + lod_bias).clamp(0, 4) as u32, ASTEROID_SEED)),
+        _ => None,
+    }
+}
+
+/// Genera una esfera por paralelos y meridianos.
+///
+/// # Parámetros
+/// - `radius`: Radio de la esfera.
+/// - `segments`: Número de meridianos (al menos 3).
+/// - `rings`: Número de franjas entre los polos (al menos 2).
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Model {
+    let (segments, rings) = (segments.max(3), rings.max(2));
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    // La columna u = 1 repite la u = 0 para que la textura no se estire en la costura.
+    for ring in 0..=rings {
+        let v = ring as f32 / rings as f32;
+        let (sin_theta, cos_theta) = (v * PI).sin_cos();
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            let (sin_phi, cos_phi) = (u * 2.0 * PI).sin_cos();
+            let normal = Vec3::new(sin_theta * cos_phi, cos_theta, sin_theta * sin_phi);
+            vertices.push(vertex(normal * radius, normal, Vec2::new(u, v)));
+        }
+    }
+
+    let columns = segments + 1;
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let a = ring * columns + segment;
+            let b = a + columns;
+            indices.extend([a, b, a + 1, a + 1, b, b + 1]);
+        }
+    }
+
+    Model::new(vertices, indices, Vec::new())
+}
+
+/// Genera una esfera subdividiendo un icosaedro, con triángulos de tamaño casi uniforme.
+///
+/// # Parámetros
+/// - `radius`: Radio de la esfera.
+/// - `subdivisions`: Veces que se divide cada triángulo en cuatro (0 da el icosaedro).
+pub fn icosphere(radius: f32, subdivisions: u32) -> Model {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let mut positions: Vec<Vec3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+    ].iter().map(|&(x, y, z)| Vec3::new(x, y, z).normalize()).collect();
+
+    let mut faces: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // Cada arista se parte una sola vez aunque la compartan dos triángulos.
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32, positions: &mut Vec<Vec3>| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                positions.push(((positions[a as usize] + positions[b as usize]) / 2.0).normalize());
+                positions.len() as u32 - 1
+            })
+        };
+
+        let mut subdivided = Vec::with_capacity(faces.len() * 4);
+        for [a, b, c] in faces {
+            let ab = midpoint(a, b, &mut positions);
+            let bc = midpoint(b, c, &mut positions);
+            let ca = midpoint(c, a, &mut positions);
+            subdivided.extend([[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+        }
+        faces = subdivided;
+    }
+
+    let vertices = positions.into_iter()
+        .map(|normal| {
+            let u = 0.5 + normal.z.atan2(normal.x) / (2.0 * PI);
+            let v = normal.y.clamp(-1.0, 1.0).acos() / PI;
+            vertex(normal * radius, normal, Vec2::new(u, v))
+        })
+        .collect();
+
+    Model::new(vertices, faces.concat(), Vec::new())
+}
+
+/// Genera un toro acostado sobre el plano XZ.
+///
+/// # Parámetros
+/// - `major_radius`: Distancia del centro al centro del tubo.
+/// - `minor_radius`: Radio del tubo.
+/// - `major_segments`: Divisiones alrededor del eje Y (al menos 3).
+/// - `minor_segments`: Divisiones alrededor del tubo (al menos 3).
+pub fn torus(major_radius: f32, minor_radius: f32, major_segments: u32, minor_segments: u32) -> Model {
+    let (major_segments, minor_segments) = (major_segments.max(3), minor_segments.max(3));
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for i in 0..=major_segments {
+        let u = i as f32 / major_segments as f32;
+        let (sin_u, cos_u) = (u * 2.0 * PI).sin_cos();
+        let tube_center = Vec3::new(cos_u, 0.0, sin_u) * major_radius;
+        for j in 0..=minor_segments {
+            let v = j as f32 / minor_segments as f32;
+            let (sin_v, cos_v) = (v * 2.0 * PI).sin_cos();
+            let normal = Vec3::new(cos_u * cos_v, sin_v, sin_u * cos_v);
+            vertices.push(vertex(tube_center + normal * minor_radius, normal, Vec2::new(u, v)));
+        }
+    }
+
+    let columns = minor_segments + 1;
+    for i in 0..major_segments {
+        for j in 0..minor_segments {
+            let a = i * columns + j;
+            let b = a + columns;
+            indices.extend([a, a + 1, b, b, a + 1, b + 1]);
+        }
+    }
+
+    Model::new(vertices, indices, Vec::new())
+}
+
+/// Genera un anillo plano (corona circular) sobre el plano XZ, como los de Saturno.
+///
+/// La coordenada de textura `v` va de 0 en el borde interior a 1 en el exterior,
+/// para que un shader pueda dibujar bandas concéntricas.
+///
+/// # Parámetros
+/// - `inner_radius`: Radio del borde interior.
+/// - `outer_radius`: Radio del borde exterior.
+/// - `segments`: Divisiones alrededor del anillo (al menos 3).
+pub fn ring(inner_radius: f32, outer_radius: f32, segments: u32) -> Model {
+    let segments = segments.max(3);
+    let normal = Vec3::new(0.0, 1.0, 0.0);
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for i in 0..=segments {
+        let u = i as f32 / segments as f32;
+        let (sin_a, cos_a) = (u * 2.0 * PI).sin_cos();
+        let direction = Vec3::new(cos_a, 0.0, sin_a);
+        vertices.push(vertex(direction * inner_radius, normal, Vec2::new(u, 0.0)));
+        vertices.push(vertex(direction * outer_radius, normal, Vec2::new(u, 1.0)));
+    }
+
+    for i in 0..segments {
+        let a = i * 2;
+        indices.extend([a, a + 1, a + 2, a + 2, a + 1, a + 3]);
+    }
+
+    Model::new(vertices, indices, Vec::new())
+}
+
+/// Semilla de la forma de `primitive:asteroid`.
+const ASTEROID_SEED: u64 = 7;
+
+/// Genera una roca irregular: una icoesfera con bultos y abolladuras grandes,
+/// pensada para dibujarse muchas veces con escalas y giros distintos (ver
+/// `instancing::AsteroidBelt`).
+///
+/// # Parámetros
+/// - `radius`: Radio de la esfera que envuelve la roca.
+/// - `subdivisions`: Subdivisiones de la icoesfera de partida.
+/// - `seed`: Semilla de la forma; la misma semilla da la misma roca.
+pub fn asteroid(radius: f32, subdivisions: u32, seed: u64) -> Model {
+    let sphere = icosphere(1.0, subdivisions);
+    let mut rng = StdRng::seed_from_u64(seed);
+    // Cada bulto empuja hacia afuera o hacia adentro un casquete alrededor de su dirección.
+    let lumps: Vec<(Vec3, f32)> = (0..8)
+        .map(|_| {
+            let direction = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+            (direction.try_normalize(f32::EPSILON).unwrap_or(Vec3::new(0.0, 1.0, 0.0)), rng.gen_range(-0.35..0.35))
+        })
+        .collect();
+    let mut positions: Vec<Vec3> = sphere.vertices.iter()
+        .map(|vertex| {
+            let direction = vertex.normal;
+            let height: f32 = lumps.iter()
+                .map(|(center, amount)| amount * ((direction.dot(center) - 0.4) / 0.6).max(0.0).powi(2))
+                .sum();
+            direction * (1.0 + height).max(0.3)
+        })
+        .collect();
+
+    // La roca se escala para que su punto más lejano quede justo en `radius`.
+    let farthest = positions.iter().map(|position| position.magnitude()).fold(0.0, f32::max);
+    for position in &mut positions {
+        *position *= radius / farthest;
+    }
+    let normals = face_normals(&positions, &sphere.indices);
+    let vertices = sphere.vertices.iter().zip(positions.iter().zip(normals))
+        .map(|(original, (&position, normal))| vertex(position, normal, original.tex_coords))
+        .collect();
+
+    Model::new(vertices, sphere.indices, Vec::new())
+}
+
+/// Crea un vértice; la elevación la calcula el shader de vértices según el
+/// `ElevationMode` del cuerpo, igual que en los modelos cargados.
+fn vertex(position: Vec3, normal: Vec3, tex_coords: Vec2) -> Vertex {
+    Vertex::new(position, normal, tex_coords, 0.0)
+}
@@ -0,0 +1,176 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+// En el navegador `std::time::Instant::now` entra en pánico.
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Etapa del fotograma que se mide por separado.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Shader de vértices y ensamblado de triángulos (con el descarte por frustum).
+    Vertex,
+    /// Rasterización de los triángulos en fragmentos.
+    Rasterization,
+    /// Shader de fragmentos y escritura en el framebuffer.
+    Fragment,
+    /// Posprocesado y envío del framebuffer a la ventana.
+    Present,
+}
+
+impl Stage {
+    /// Todas las etapas, en el orden en que ocurren.
+    pub const ALL: [Stage; 4] = [Stage::Vertex, Stage::Rasterization, Stage::Fragment, Stage::Present];
+
+    /// Devuelve el nombre corto de la etapa.
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Vertex => "vertex",
+            Stage::Rasterization => "raster",
+            Stage::Fragment => "fragment",
+            Stage::Present => "present",
+        }
+    }
+}
+
+/// Mediciones de un fotograma: un tiempo por etapa, el total entre fotogramas y
+/// cuánta geometría se dibujó.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameTiming {
+    /// Tiempo de cada etapa, en el orden de `Stage::ALL`.
+    pub stages: [Duration; 4],
+    /// Tiempo desde el fotograma anterior (cero en el primero).
+    pub frame: Duration,
+    /// Triángulos que llegaron a rasterizarse, después del descarte por frustum.
+    pub triangles: usize,
+    /// Fragmentos que llegaron al shader de fragmentos, después de la prueba de profundidad.
+    pub fragments: usize,
+    /// Objetos que pasaron por el pipeline.
+    pub objects: usize,
+    /// Objetos descartados enteros por quedar fuera del frustum, sin pasar por el shader de vértices.
+    pub culled_objects: usize,
+}
+
+impl FrameTiming {
+    /// Tiempo de una etapa.
+    pub fn stage(&self, stage: Stage) -> Duration {
+        self.stages[stage as usize]
+    }
+}
+
+/// Mide cuánto tarda cada etapa del fotograma y promedia los últimos fotogramas.
+///
+/// Las etapas se acumulan con `record` o `time` (varias veces por fotograma si
+/// se dibujan varios objetos) y `end_frame` cierra el fotograma. Los promedios
+/// usan una ventana móvil, así que una subida de tiempos se nota en pocos fotogramas.
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    /// Tiempos acumulados del fotograma en curso.
+    current: FrameTiming,
+    /// Últimos fotogramas terminados, del más viejo al más nuevo.
+    history: VecDeque<FrameTiming>,
+    /// Cantidad de fotogramas que se promedian.
+    window: usize,
+    /// Momento en que terminó el fotograma anterior.
+    last_frame: Option<Instant>,
+}
+
+impl Profiler {
+    /// Crea un perfilador que promedia los últimos `window` fotogramas (al menos uno).
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Profiler {
+            current: FrameTiming::default(),
+            history: VecDeque::with_capacity(window),
+            window,
+            last_frame: None,
+        }
+    }
+
+    /// Suma un tiempo a una etapa del fotograma en curso.
+    pub fn record(&mut self, stage: Stage, duration: Duration) {
+        self.current.stages[stage as usize] += duration;
+    }
+
+    /// Suma triángulos y fragmentos dibujados al fotograma en curso.
+    pub fn count(&mut self, triangles: usize, fragments: usize) {
+        self.current.triangles += triangles;
+        self.current.fragments += fragments;
+    }
+
+    /// Suma objetos dibujados y descartados por frustum al fotograma en curso.
+    pub fn count_objects(&mut self, drawn: usize, culled: usize) {
+        self.current.objects += drawn;
+        self.current.culled_objects += culled;
+    }
+
+    /// Ejecuta `work` y suma lo que tardó a una etapa.
+    ///
+    /// # Retorna
+    /// Lo que devuelve `work`.
+    pub fn time<T, F: FnOnce() -> T>(&mut self, stage: Stage, work: F) -> T {
+        let start = Instant::now();
+        let result = work();
+        self.record(stage, start.elapsed());
+        result
+    }
+
+    /// Cierra el fotograma en curso: lo guarda en la ventana y empieza uno nuevo.
+    /// El tiempo total del fotograma se mide desde la llamada anterior.
+    pub fn end_frame(&mut self) {
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            self.current.frame = now - last_frame;
+        }
+        self.last_frame = Some(now);
+
+        if self.history.len() == self.window {
+            self.history.pop_front();
+        }
+        self.history.push_back(std::mem::take(&mut self.current));
+    }
+
+    /// Mediciones del último fotograma terminado.
+    pub fn last(&self) -> Option<&FrameTiming> {
+        self.history.back()
+    }
+
+    /// Promedia un valor de los fotogramas de la ventana.
+    fn average_by(&self, value: impl Fn(&FrameTiming) -> Duration) -> Duration {
+        if self.history.is_empty() {
+            return Duration::ZERO;
+        }
+        self.history.iter().map(value).sum::<Duration>() / self.history.len() as u32
+    }
+
+    /// Tiempo promedio de una etapa por fotograma.
+    pub fn average(&self, stage: Stage) -> Duration {
+        self.average_by(|timing| timing.stages[stage as usize])
+    }
+
+    /// Tiempo promedio entre fotogramas, incluyendo lo que no se mide por etapas.
+    pub fn average_frame(&self) -> Duration {
+        self.average_by(|timing| timing.frame)
+    }
+
+    /// Fotogramas por segundo según el tiempo promedio entre fotogramas; 0 al principio.
+    pub fn fps(&self) -> f32 {
+        let frame = self.average_frame().as_secs_f32();
+        if frame > 0.0 { 1.0 / frame } else { 0.0 }
+    }
+
+    /// Resumen de una línea con los promedios, por ejemplo para la consola.
+    pub fn report(&self) -> String {
+        let stages: Vec<String> = Stage::ALL.iter()
+            .map(|&stage| format!("{} {:.2} ms", stage.name(), self.average(stage).as_secs_f32() * 1000.0))
+            .collect();
+        format!("{} | {:.0} fps", stages.join(" | "), self.fps())
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new(60)
+    }
+}
@@ -0,0 +1,166 @@
+use nalgebra_glm::{Mat4, Vec3};
+use crate::framebuffer::Framebuffer;
+
+/// Divisor de la resolución en cada pasada inicial: un cuarto, la mitad y la completa.
+const SCALES: [usize; 3] = [4, 2, 1];
+
+/// Vista previa progresiva: mientras nada cambia, cada fotograma mejora la imagen anterior.
+///
+/// Las dos primeras pasadas dibujan a un cuarto y a la mitad de la resolución y
+/// se amplían, así que la imagen responde enseguida aunque los efectos sean
+/// caros. Desde la tercera se dibuja a resolución completa y cada pasada mueve
+/// la proyección una fracción de píxel distinta; el promedio de esas muestras
+/// suaviza los bordes como un supermuestreo. Al llegar a `max_samples` la imagen
+/// queda terminada y no hace falta volver a dibujarla hasta que algo cambie
+/// (`restart`).
+pub struct Progressive {
+    width: usize,
+    height: usize,
+    /// Muestras a resolución completa que se promedian antes de dar la imagen por terminada.
+    pub max_samples: u32,
+    /// Pasadas hechas desde el último `restart`.
+    pass: u32,
+    /// Framebuffer donde se dibuja la pasada en curso, del tamaño que le corresponde.
+    target: Framebuffer,
+    /// Suma de las muestras a resolución completa, por canal.
+    accumulation: Vec<Vec3>,
+    /// Imagen resultante, del tamaño completo.
+    image: Vec<u32>,
+}
+
+impl Progressive {
+    /// Crea una vista previa para una imagen de `width` x `height`.
+    ///
+    /// # Parámetros
+    /// - `width`, `height`: Tamaño de la imagen final.
+    /// - `max_samples`: Muestras a resolución completa que se promedian (al menos una).
+    pub fn new(width: usize, height: usize, max_samples: u32) -> Self {
+        Progressive {
+            width,
+            height,
+            max_samples: max_samples.max(1),
+            pass: 0,
+            target: Framebuffer::new(width, height),
+            accumulation: vec![Vec3::zeros(); width * height],
+            image: vec![0; width * height],
+        }
+    }
+
+    /// Vuelve a empezar desde la pasada de menor resolución; se llama cuando
+    /// cambia la cámara, la escena o algún efecto.
+    pub fn restart(&mut self) {
+        self.pass = 0;
+    }
+
+    /// Divisor de la resolución de la pasada en curso (4, 2 o 1).
+    pub fn scale(&self) -> usize {
+        SCALES[(self.pass as usize).min(SCALES.len() - 1)]
+    }
+
+    /// Muestras a resolución completa ya promediadas.
+    pub fn samples(&self) -> u32 {
+        self.pass.saturating_sub(SCALES.len() as u32 - 1)
+    }
+
+    /// Indica si la imagen ya está terminada.
+    pub fn is_converged(&self) -> bool {
+        self.samples() >= self.max_samples
+    }
+
+    /// Desplazamiento de la pasada en curso, en fracciones de píxel entre -0.5 y 0.5.
+    ///
+    /// Las pasadas de baja resolución y la primera muestra completa no se
+    /// desplazan; las demás siguen la secuencia de Halton en bases 2 y 3, que
+    /// reparte bien los puntos dentro del píxel con pocas muestras.
+    pub fn jitter(&self) -> (f32, f32) {
+        if self.scale() > 1 || self.samples() == 0 {
+            return (0.0, 0.0);
+        }
+        let index = self.samples();
+        (halton(index, 2) - 0.5, halton(index, 3) - 0.5)
+    }
+
+    /// Aplica el desplazamiento de la pasada en curso a una matriz de proyección
+    /// que cubre la imagen entera.
+    pub fn jitter_projection(&self, projection: &Mat4) -> Mat4 {
+        jitter_projection(projection, self.jitter(), self.width, self.height)
+    }
+
+    /// Prepara el framebuffer de la pasada en curso, limpio y del tamaño que le corresponde.
+    pub fn begin_pass(&mut self) -> &mut Framebuffer {
+        let scale = self.scale();
+        let (width, height) = (self.width.div_ceil(scale), self.height.div_ceil(scale));
+        if self.target.width != width || self.target.height != height {
+            self.target = Framebuffer::new(width, height);
+        }
+        self.target.set_background_color(0x000000);
+        self.target.clear();
+        &mut self.target
+    }
+
+    /// Incorpora la pasada dibujada en el framebuffer de `begin_pass`.
+    ///
+    /// # Retorna
+    /// La imagen resultante, de tamaño completo.
+    pub fn end_pass(&mut self) -> &[u32] {
+        let scale = self.scale();
+        if scale > 1 {
+            // ampliación por vecino más cercano: cada píxel cubre un cuadrado de scale x scale
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    self.image[y * self.width + x] = self.target.buffer[(y / scale) * self.target.width + x / scale];
+                }
+            }
+        } else {
+            if self.samples() == 0 {
+                self.accumulation.fill(Vec3::zeros());
+            }
+            let count = (self.samples() + 1) as f32;
+            let format = self.target.pixel_format();
+            let width = self.width;
+            for (index, ((sum, pixel), &sample)) in self.accumulation.iter_mut().zip(self.image.iter_mut()).zip(&self.target.buffer).enumerate() {
+                *sum += format.unpack(sample);
+                *pixel = format.pack(*sum / count, index % width, index / width);
+            }
+        }
+        self.pass += 1;
+        &self.image
+    }
+
+    /// Imagen resultante de la última pasada.
+    pub fn image(&self) -> &[u32] {
+        &self.image
+    }
+}
+
+/// Desplaza una proyección una fracción de píxel.
+///
+/// # Parámetros
+/// - `projection`: Proyección a desplazar.
+/// - `jitter`: Desplazamiento en píxeles (ver `Progressive::jitter`).
+/// - `width`, `height`: Tamaño en píxeles del viewport que cubre la proyección.
+pub fn jitter_projection(projection: &Mat4, jitter: (f32, f32), width: usize, height: usize) -> Mat4 {
+    let (jitter_x, jitter_y) = jitter;
+    // un píxel mide 2 / ancho en coordenadas normalizadas; la y de pantalla crece hacia abajo
+    let offset_x = 2.0 * jitter_x / width as f32;
+    let offset_y = -2.0 * jitter_y / height as f32;
+    let translation = Mat4::new(
+        1.0, 0.0, 0.0, offset_x,
+        0.0, 1.0, 0.0, offset_y,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    );
+    translation * projection
+}
+
+/// Elemento `index` de la secuencia de Halton en la base dada, entre 0 y 1.
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
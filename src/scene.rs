@@ -0,0 +1,543 @@
+use nalgebra_glm::Vec3;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use crate::shaders::{Flow, PlanetShader, FEATURE_NAMES};
+use crate::elevation::ElevationMode;
+use crate::triangle::Shading;
+use crate::instancing::AsteroidBelt;
+use crate::particles::ParticleEffect;
+use crate::bounds::BoundingSphere;
+use crate::geometry::intersect::{ray_sphere, Ray};
+
+/// Malla que se usa por defecto para los cuerpos de la escena: una esfera
+/// generada (ver `primitives::from_name`), así la demo no depende de archivos OBJ.
+pub const DEFAULT_MESH: &str = "primitive:uv_sphere";
+
+/// Escala de la esfera de nubes respecto al cuerpo que la lleva.
+pub const CLOUD_LAYER_SCALE: f32 = 1.02;
+
+/// Capa de los cuerpos celestes.
+pub const LAYER_BODIES: u32 = 1 << 0;
+/// Capa de las trayectorias orbitales.
+pub const LAYER_ORBITS: u32 = 1 << 1;
+/// Capa de las ayudas de depuración.
+pub const LAYER_DEBUG: u32 = 1 << 2;
+/// Capa de la interfaz superpuesta.
+pub const LAYER_UI: u32 = 1 << 3;
+
+/// Nombres de las capas tal como se escriben en el archivo de escena.
+const LAYER_NAMES: [(&str, u32); 4] = [
+    ("bodies", LAYER_BODIES),
+    ("orbits", LAYER_ORBITS),
+    ("debug", LAYER_DEBUG),
+    ("ui", LAYER_UI),
+];
+
+/// Convierte una máscara de bits a texto con los nombres de los bits activos,
+/// separados por `|` (por ejemplo `bodies|debug`).
+pub fn flags_to_string(flags: u32, names: &[(&str, u32)]) -> String {
+    names.iter()
+        .filter(|(_, bit)| flags & bit != 0)
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Interpreta una lista de nombres separados por `|` como máscara de bits.
+pub fn flags_from_str(text: &str, names: &[(&str, u32)]) -> Result<u32, String> {
+    text.split('|')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .try_fold(0, |mask, name| {
+            names.iter()
+                .find(|(flag, _)| *flag == name)
+                .map(|(_, bit)| mask | bit)
+                .ok_or_else(|| format!("valor desconocido '{}'", name))
+        })
+}
+
+/// Convierte una máscara de capas a texto, por ejemplo `bodies|debug`.
+pub fn layers_to_string(layers: u32) -> String {
+    flags_to_string(layers, &LAYER_NAMES)
+}
+
+/// Interpreta una lista de capas separadas por `|`.
+pub fn layers_from_str(text: &str) -> Result<u32, String> {
+    flags_from_str(text, &LAYER_NAMES)
+}
+
+/// Órbita circular de un nodo alrededor de su padre.
+#[derive(Debug, Clone, Copy)]
+pub struct Orbit {
+    /// Distancia al centro del padre.
+    pub distance: f32,
+    /// Inclinación del plano orbital respecto al plano XZ, en radianes.
+    pub inclination: f32,
+    /// Velocidad angular en radianes por fotograma.
+    pub speed: f32,
+    /// Ángulo inicial sobre la órbita, en radianes.
+    pub phase: f32,
+}
+
+impl Orbit {
+    /// Calcula la posición relativa al padre en el instante dado.
+    ///
+    /// # Parámetros
+    /// - `time`: Tiempo de simulación en fotogramas.
+    ///
+    /// # Retorna
+    /// El desplazamiento desde el centro del padre.
+    pub fn offset(&self, time: f32) -> Vec3 {
+        self.point_at(self.phase + self.speed * time)
+    }
+
+    /// Calcula el punto de la órbita (relativo al padre) para un ángulo dado.
+    ///
+    /// # Parámetros
+    /// - `angle`: Ángulo sobre la órbita, en radianes.
+    pub fn point_at(&self, angle: f32) -> Vec3 {
+        let (sin_a, cos_a) = angle.sin_cos();
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+
+        // Punto sobre el plano XZ inclinado alrededor del eje X.
+        let z = self.distance * sin_a;
+        Vec3::new(self.distance * cos_a, -z * sin_i, z * cos_i)
+    }
+
+    /// Normal del plano orbital (el eje `+Y` inclinado como la órbita).
+    pub fn normal(&self) -> Vec3 {
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+        Vec3::new(0.0, cos_i, sin_i)
+    }
+}
+
+/// Nodo de la escena: un cuerpo con su malla, shader y órbita opcional.
+#[derive(Debug, Clone)]
+pub struct SceneNode {
+    /// Nombre del cuerpo.
+    pub name: String,
+    /// Índice del nodo padre, o `None` si está en el origen de la escena.
+    pub parent: Option<usize>,
+    /// Ruta de la malla OBJ que se dibuja para este nodo.
+    pub mesh: String,
+    /// Shader de fragmentos que colorea la superficie.
+    pub shader: PlanetShader,
+    /// Radio del cuerpo (escala uniforme de la malla).
+    pub radius: f32,
+    /// Semilla del ruido usado por el shader.
+    pub seed: i32,
+    /// Órbita alrededor del padre.
+    pub orbit: Option<Orbit>,
+    /// Capas a las que pertenece el nodo.
+    pub layers: u32,
+    /// Si es `false`, el nodo no se dibuja en ninguna cámara.
+    pub visible: bool,
+    /// Características opcionales del shader (`FEATURE_*`), por ejemplo nubes.
+    pub features: u32,
+    /// Sombreado suave o plano (por cara).
+    pub shading: Shading,
+    /// Cómo se calcula la elevación de los vértices de la malla.
+    pub elevation: ElevationMode,
+    /// Ruta del mapa de alturas (PGM) que usa `ElevationMode::Texture`.
+    pub heightmap: Option<String>,
+    /// Velocidad y turbulencia de los shaders de fluidos (`lava`, `ocean`).
+    pub flow: Flow,
+    /// Multiplicador de la cantidad de cráteres del shader `moon` (0 no dibuja ninguno).
+    pub crater_density: f32,
+    /// Si hay, el nodo es un cinturón: su malla se repite alrededor de su posición y
+    /// `radius` es el tamaño medio de cada copia.
+    pub belt: Option<AsteroidBelt>,
+    /// Efecto de partículas que emite el cuerpo, como la cola de un cometa.
+    pub particles: Option<ParticleEffect>,
+}
+
+impl SceneNode {
+    /// Crea un nodo sin padre ni órbita, usando la malla por defecto.
+    ///
+    /// # Parámetros
+    /// - `name`: Nombre del cuerpo.
+    /// - `shader`: Shader de la superficie.
+    /// - `radius`: Radio del cuerpo.
+    pub fn new(name: &str, shader: PlanetShader, radius: f32) -> Self {
+        SceneNode {
+            name: name.to_string(),
+            parent: None,
+            mesh: DEFAULT_MESH.to_string(),
+            shader,
+            radius,
+            seed: 1337,
+            orbit: None,
+            layers: LAYER_BODIES,
+            visible: true,
+            features: 0,
+            shading: Shading::default(),
+            elevation: ElevationMode::default(),
+            heightmap: None,
+            flow: Flow::default(),
+            crater_density: 1.0,
+            belt: None,
+            particles: None,
+        }
+    }
+
+    /// Devuelve los campos del nodo (salvo el padre) como pares clave/valor,
+    /// en el mismo orden en que se escriben en el archivo de escena.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("name", self.name.clone()),
+            ("mesh", self.mesh.clone()),
+            ("shader", self.shader.name().to_string()),
+            ("radius", self.radius.to_string()),
+            ("seed", self.seed.to_string()),
+            ("layers", layers_to_string(self.layers)),
+            ("visible", self.visible.to_string()),
+            ("features", flags_to_string(self.features, &FEATURE_NAMES)),
+            ("shading", self.shading.name().to_string()),
+            ("elevation", self.elevation.name().to_string()),
+        ];
+        if let Some(heightmap) = &self.heightmap {
+            fields.push(("heightmap", heightmap.clone()));
+        }
+        if self.flow != Flow::default() {
+            fields.push(("flow_speed", self.flow.speed.to_string()));
+            fields.push(("flow_turbulence", self.flow.turbulence.to_string()));
+        }
+        if self.crater_density != 1.0 {
+            fields.push(("crater_density", self.crater_density.to_string()));
+        }
+        if let Some(effect) = self.particles {
+            fields.push(("particles", effect.name().to_string()));
+        }
+        if let Some(belt) = self.belt {
+            fields.push(("belt_count", belt.count.to_string()));
+            fields.push(("belt_inner_radius", belt.inner_radius.to_string()));
+            fields.push(("belt_outer_radius", belt.outer_radius.to_string()));
+            fields.push(("belt_thickness", belt.thickness.to_string()));
+        }
+        if let Some(orbit) = self.orbit {
+            fields.push(("orbit_distance", orbit.distance.to_string()));
+            fields.push(("orbit_inclination", orbit.inclination.to_string()));
+            fields.push(("orbit_speed", orbit.speed.to_string()));
+            fields.push(("orbit_phase", orbit.phase.to_string()));
+        }
+        fields
+    }
+
+    /// Asigna un campo a partir de su representación en texto.
+    ///
+    /// # Parámetros
+    /// - `key`: Nombre del campo, tal como aparece en el archivo de escena.
+    /// - `value`: Valor en texto.
+    ///
+    /// # Retorna
+    /// Un mensaje de error si la clave no existe o el valor no es válido.
+    pub fn set_field(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "name" => self.name = value.to_string(),
+            "mesh" => self.mesh = value.to_string(),
+            "shader" => {
+                self.shader = PlanetShader::from_name(value)
+                    .ok_or_else(|| format!("shader desconocido '{}'", value))?;
+            }
+            "radius" => self.radius = parse_value(key, value)?,
+            "seed" => self.seed = parse_value(key, value)?,
+            "layers" => self.layers = layers_from_str(value)?,
+            "visible" => self.visible = parse_value(key, value)?,
+            "features" => self.features = flags_from_str(value, &FEATURE_NAMES)?,
+            "shading" => {
+                self.shading = Shading::from_name(value)
+                    .ok_or_else(|| format!("sombreado desconocido '{}'", value))?;
+            }
+            "elevation" => {
+                self.elevation = ElevationMode::from_name(value)
+                    .ok_or_else(|| format!("elevación desconocida '{}'", value))?;
+            }
+            "heightmap" => self.heightmap = Some(value.to_string()),
+            "flow_speed" => self.flow.speed = parse_value(key, value)?,
+            "flow_turbulence" => self.flow.turbulence = parse_value(key, value)?,
+            "crater_density" => self.crater_density = parse_value(key, value)?,
+            "particles" => {
+                self.particles = Some(ParticleEffect::from_name(value)
+                    .ok_or_else(|| format!("efecto de partículas desconocido '{}'", value))?);
+            }
+            "belt_count" => self.belt.get_or_insert_with(AsteroidBelt::default).count = parse_value(key, value)?,
+            "belt_inner_radius" | "belt_outer_radius" | "belt_thickness" => {
+                let number: f32 = parse_value(key, value)?;
+                let belt = self.belt.get_or_insert_with(AsteroidBelt::default);
+                match key {
+                    "belt_inner_radius" => belt.inner_radius = number,
+                    "belt_outer_radius" => belt.outer_radius = number,
+                    _ => belt.thickness = number,
+                }
+            }
+            "orbit_distance" | "orbit_inclination" | "orbit_speed" | "orbit_phase" => {
+                let number: f32 = parse_value(key, value)?;
+                let orbit = self.orbit.get_or_insert(Orbit {
+                    distance: 0.0,
+                    inclination: 0.0,
+                    speed: 0.0,
+                    phase: 0.0,
+                });
+                match key {
+                    "orbit_distance" => orbit.distance = number,
+                    "orbit_inclination" => orbit.inclination = number,
+                    "orbit_speed" => orbit.speed = number,
+                    _ => orbit.phase = number,
+                }
+            }
+            _ => return Err(format!("campo desconocido '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Quita un campo opcional, como si no figurara en el archivo de escena: el
+    /// mapa de alturas, las partículas, el cinturón y la órbita desaparecen, y el
+    /// flujo y la densidad de cráteres vuelven a su valor por defecto.
+    ///
+    /// # Retorna
+    /// Un mensaje de error si la clave no existe o el campo es obligatorio.
+    pub fn clear_field(&mut self, key: &str) -> Result<(), String> {
+        match key {
+            "heightmap" => self.heightmap = None,
+            "flow_speed" => self.flow.speed = Flow::default().speed,
+            "flow_turbulence" => self.flow.turbulence = Flow::default().turbulence,
+            "crater_density" => self.crater_density = 1.0,
+            "particles" => self.particles = None,
+            "belt_count" | "belt_inner_radius" | "belt_outer_radius" | "belt_thickness" => self.belt = None,
+            "orbit_distance" | "orbit_inclination" | "orbit_speed" | "orbit_phase" => self.orbit = None,
+            _ => return Err(format!("el campo '{}' no se puede quitar", key)),
+        }
+        Ok(())
+    }
+}
+
+/// Interpreta el valor de un campo numérico.
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("valor inválido para '{}': '{}'", key, value))
+}
+
+/// Error al leer o interpretar un archivo de escena.
+#[derive(Debug)]
+pub enum SceneError {
+    /// No se pudo leer o escribir el archivo.
+    Io(io::Error),
+    /// El contenido del archivo no es válido.
+    Parse {
+        /// Número de línea (empezando en 1) donde se encontró el error.
+        line: usize,
+        /// Descripción del problema.
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(error) => write!(f, "error de E/S: {}", error),
+            SceneError::Parse { line, message } => write!(f, "línea {}: {}", line, message),
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(error: io::Error) -> Self {
+        SceneError::Io(error)
+    }
+}
+
+/// Grafo de escena plano: los nodos guardan el índice de su padre.
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    /// Nodos de la escena; un padre siempre aparece antes que sus hijos.
+    pub nodes: Vec<SceneNode>,
+}
+
+impl Scene {
+    /// Crea una escena vacía.
+    pub fn new() -> Self {
+        Scene { nodes: Vec::new() }
+    }
+
+    /// Agrega un nodo a la escena.
+    ///
+    /// # Retorna
+    /// El índice del nodo agregado.
+    pub fn add_node(&mut self, node: SceneNode) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// Agrega una capa de nubes a un cuerpo: una esfera hija, sin órbita y
+    /// `CLOUD_LAYER_SCALE` veces más grande, con el shader semitransparente `Clouds`.
+    ///
+    /// # Parámetros
+    /// - `body`: Índice del cuerpo que lleva las nubes.
+    ///
+    /// # Retorna
+    /// El índice del nodo de nubes.
+    pub fn add_cloud_layer(&mut self, body: usize) -> usize {
+        let parent = &self.nodes[body];
+        let mut clouds = SceneNode::new(
+            &format!("{} nubes", parent.name),
+            PlanetShader::Clouds,
+            parent.radius * CLOUD_LAYER_SCALE,
+        );
+        clouds.parent = Some(body);
+        clouds.mesh = parent.mesh.clone();
+        clouds.seed = parent.seed.wrapping_add(1);
+        self.add_node(clouds)
+    }
+
+    /// Calcula la posición en el mundo de un nodo, acumulando las órbitas de sus padres.
+    ///
+    /// # Parámetros
+    /// - `index`: Índice del nodo.
+    /// - `time`: Tiempo de simulación en fotogramas.
+    pub fn world_position(&self, index: usize, time: f32) -> Vec3 {
+        let node = &self.nodes[index];
+        let parent_position = node.parent
+            .map(|parent| self.world_position(parent, time))
+            .unwrap_or_else(Vec3::zeros);
+        let offset = node.orbit
+            .map(|orbit| orbit.offset(time))
+            .unwrap_or_else(Vec3::zeros);
+
+        parent_position + offset
+    }
+
+    /// Busca el cuerpo más cercano que corta un rayo, por ejemplo el que está bajo el
+    /// cursor (ver `Camera::ray_through`). Cada cuerpo se prueba con su esfera; las
+    /// nubes, los anillos y los cinturones no se pueden elegir, así un clic sobre
+    /// ellos elige lo que hay detrás.
+    ///
+    /// # Parámetros
+    /// - `ray`: Rayo en el mundo.
+    /// - `time`: Tiempo de simulación en fotogramas.
+    /// - `layers`: Capas visibles; los nodos que no están en ninguna se ignoran.
+    ///
+    /// # Retorna
+    /// El índice del nodo, o `None` si el rayo no toca ningún cuerpo.
+    pub fn pick(&self, ray: &Ray, time: f32, layers: u32) -> Option<usize> {
+        self.solid_spheres(time, layers)
+            .into_iter()
+            .filter_map(|(index, sphere)| Some((index, ray_sphere(ray, &sphere)?)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+
+    /// Esferas de los cuerpos sólidos visibles: los que se pueden elegir con `pick` y
+    /// contra los que choca la cámara. Quedan afuera las nubes, los anillos y los cinturones.
+    ///
+    /// # Parámetros
+    /// - `time`: Tiempo de simulación en fotogramas.
+    /// - `layers`: Capas visibles; los nodos que no están en ninguna se ignoran.
+    ///
+    /// # Retorna
+    /// El índice de cada nodo con su esfera en el mundo.
+    pub fn solid_spheres(&self, time: f32, layers: u32) -> Vec<(usize, BoundingSphere)> {
+        self.nodes.iter()
+            .enumerate()
+            .filter(|(_, node)| {
+                node.visible && node.layers & layers != 0 && !node.shader.is_transparent() && node.belt.is_none()
+            })
+            .map(|(index, node)| {
+                // Las mallas miden 0.5 de radio, así que el cuerpo ocupa la mitad de `radius`.
+                (index, BoundingSphere { center: self.world_position(index, time), radius: node.radius * 0.5 })
+            })
+            .collect()
+    }
+
+    /// Busca la fuente de luz de la escena: el primer nodo con shader de sol o de estrella.
+    pub fn light_source(&self) -> Option<usize> {
+        self.nodes.iter().position(|node| node.shader.is_emissive())
+    }
+
+    /// Carga una escena desde un archivo.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Guarda la escena en un archivo, creando las carpetas que falten.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SceneError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+}
+
+/// Escribe la escena en el formato declarativo de archivo de escena:
+///
+/// ```text
+/// [node]
+/// name = Tierra
+/// parent = Sol
+/// shader = earth
+/// radius = 1
+/// orbit_distance = 8
+/// ```
+///
+/// Los padres se referencian por nombre y siempre se escriben antes que sus hijos.
+impl fmt::Display for Scene {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# Escena de SHADERS")?;
+        for node in &self.nodes {
+            writeln!(f)?;
+            writeln!(f, "[node]")?;
+            for (key, value) in node.fields() {
+                writeln!(f, "{} = {}", key, value)?;
+                if key == "name" {
+                    if let Some(parent) = node.parent {
+                        writeln!(f, "parent = {}", self.nodes[parent].name)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Scene {
+    type Err = SceneError;
+
+    /// Interpreta una escena escrita en el formato de archivo de escena.
+    /// Las líneas vacías y las que empiezan con `#` se ignoran.
+    fn from_str(text: &str) -> Result<Scene, SceneError> {
+        let mut scene = Scene::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: String| SceneError::Parse { line: number + 1, message };
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line == "[node]" {
+                scene.add_node(SceneNode::new("", PlanetShader::Rocky, 1.0));
+                continue;
+            }
+
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| error(format!("se esperaba 'clave = valor': '{}'", line)))?;
+            let (key, value) = (key.trim(), value.trim());
+            let index = scene.nodes.len().checked_sub(1)
+                .ok_or_else(|| error("campo fuera de un bloque [node]".to_string()))?;
+
+            if key == "parent" {
+                // El padre es el último nodo anterior con ese nombre.
+                let parent = scene.nodes[..index].iter().rposition(|node| node.name == value)
+                    .ok_or_else(|| error(format!("padre desconocido '{}'", value)))?;
+                scene.nodes[index].parent = Some(parent);
+            } else {
+                scene.nodes[index].set_field(key, value).map_err(error)?;
+            }
+        }
+
+        Ok(scene)
+    }
+}
@@ -0,0 +1,287 @@
+use std::fmt;
+use crate::scene::{Scene, SceneNode};
+use crate::shaders::PlanetShader;
+
+/// Un cambio entre dos escenas. Los nodos se identifican por su nombre.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SceneChange {
+    /// Nodo que solo existe en la escena nueva, con todos sus campos
+    /// (incluido `parent`, vacío si no tiene).
+    Added {
+        name: String,
+        fields: Vec<(String, String)>,
+    },
+    /// Nodo que solo existe en la escena base.
+    Removed {
+        name: String,
+    },
+    /// Campo con distinto valor en ambas escenas.
+    Changed {
+        name: String,
+        key: String,
+        old: String,
+        new: String,
+    },
+    /// Campo opcional que solo tiene el nodo de la escena base (un mapa de
+    /// alturas, una órbita); al aplicarlo vuelve a su valor por defecto.
+    FieldRemoved {
+        name: String,
+        key: String,
+        old: String,
+    },
+}
+
+/// Conjunto de diferencias entre dos escenas, en orden de aplicación.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SceneDiff {
+    pub changes: Vec<SceneChange>,
+}
+
+/// Cambio que no se pudo aplicar porque la escena ya no tenía el valor esperado.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    /// Nombre del nodo afectado.
+    pub name: String,
+    /// Campo en conflicto (vacío si el conflicto es sobre el nodo entero).
+    pub key: String,
+    /// Descripción del conflicto.
+    pub message: String,
+}
+
+impl SceneDiff {
+    /// Indica si las dos escenas comparadas eran iguales.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Nombres de los nodos agregados o modificados por este diff.
+    pub fn touched_nodes(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.changes.iter()
+            .filter_map(|change| match change {
+                SceneChange::Added { name, .. }
+                | SceneChange::Changed { name, .. }
+                | SceneChange::FieldRemoved { name, .. } => Some(name.as_str()),
+                SceneChange::Removed { .. } => None,
+            })
+            .collect();
+        names.dedup();
+        names
+    }
+}
+
+impl fmt::Display for SceneDiff {
+    /// Escribe un cambio por línea: `+` agregado, `-` eliminado (un nodo o un
+    /// campo), `~` modificado.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in &self.changes {
+            match change {
+                SceneChange::Added { name, .. } => writeln!(f, "+ {}", name)?,
+                SceneChange::Removed { name } => writeln!(f, "- {}", name)?,
+                SceneChange::Changed { name, key, old, new } => {
+                    writeln!(f, "~ {}.{}: {} -> {}", name, key, old, new)?
+                }
+                SceneChange::FieldRemoved { name, key, old } => writeln!(f, "- {}.{}: {}", name, key, old)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Campos de un nodo incluyendo el nombre de su padre (vacío si no tiene).
+fn node_fields(scene: &Scene, node: &SceneNode) -> Vec<(String, String)> {
+    let parent = node.parent.map_or(String::new(), |parent| scene.nodes[parent].name.clone());
+    let mut fields: Vec<(String, String)> = node.fields().into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+    fields.insert(1, ("parent".to_string(), parent));
+    fields
+}
+
+/// Calcula las diferencias para pasar de la escena `base` a la escena `other`.
+///
+/// # Retorna
+/// Los nodos agregados, eliminados y los campos modificados o quitados de los nodos comunes.
+pub fn diff(base: &Scene, other: &Scene) -> SceneDiff {
+    let mut changes = Vec::new();
+
+    for node in &other.nodes {
+        let new_fields = node_fields(other, node);
+        match base.find(&node.name) {
+            None => changes.push(SceneChange::Added { name: node.name.clone(), fields: new_fields }),
+            Some(index) => {
+                let old_fields = node_fields(base, &base.nodes[index]);
+                for (key, new) in &new_fields {
+                    let old = old_fields.iter()
+                        .find(|(old_key, _)| old_key == key)
+                        .map_or(String::new(), |(_, value)| value.clone());
+                    if &old != new {
+                        changes.push(SceneChange::Changed {
+                            name: node.name.clone(),
+                            key: key.clone(),
+                            old,
+                            new: new.clone(),
+                        });
+                    }
+                }
+                // los campos opcionales que el nodo nuevo ya no escribe
+                for (key, old) in old_fields {
+                    if !new_fields.iter().any(|(new_key, _)| *new_key == key) {
+                        changes.push(SceneChange::FieldRemoved { name: node.name.clone(), key, old });
+                    }
+                }
+            }
+        }
+    }
+
+    for node in &base.nodes {
+        if other.find(&node.name).is_none() {
+            changes.push(SceneChange::Removed { name: node.name.clone() });
+        }
+    }
+
+    SceneDiff { changes }
+}
+
+impl Scene {
+    /// Busca un nodo por nombre.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node.name == name)
+    }
+
+    /// Aplica un diff (o un conjunto de ajustes compartido por otra persona) sobre la escena.
+    ///
+    /// Un campo solo se sobrescribe si su valor actual coincide con el valor
+    /// `old` del diff; si no, se conserva el valor actual y se informa el conflicto.
+    ///
+    /// # Retorna
+    /// Los conflictos encontrados; una lista vacía significa que se aplicó todo.
+    pub fn merge(&mut self, diff: &SceneDiff) -> Vec<MergeConflict> {
+        let mut conflicts = Vec::new();
+        let conflict = |name: &str, key: &str, message: String| MergeConflict {
+            name: name.to_string(),
+            key: key.to_string(),
+            message,
+        };
+
+        for change in &diff.changes {
+            match change {
+                SceneChange::Added { name, fields } => {
+                    if self.find(name).is_some() {
+                        conflicts.push(conflict(name, "", "el nodo ya existe".to_string()));
+                        continue;
+                    }
+                    let index = self.add_node(SceneNode::new(name, PlanetShader::Rocky, 1.0));
+                    for (key, value) in fields {
+                        if let Err(message) = self.set_node_field(index, key, value) {
+                            conflicts.push(conflict(name, key, message));
+                        }
+                    }
+                }
+                SceneChange::Changed { name, key, old, new } => {
+                    let Some(index) = self.find(name) else {
+                        conflicts.push(conflict(name, key, "el nodo no existe".to_string()));
+                        continue;
+                    };
+                    let current = self.node_field(index, key);
+                    if &current != old {
+                        conflicts.push(conflict(name, key, format!("se esperaba '{}' pero vale '{}'", old, current)));
+                        continue;
+                    }
+                    if let Err(message) = self.set_node_field(index, key, new) {
+                        conflicts.push(conflict(name, key, message));
+                    }
+                }
+                SceneChange::FieldRemoved { name, key, old } => {
+                    let Some(index) = self.find(name) else {
+                        conflicts.push(conflict(name, key, "el nodo no existe".to_string()));
+                        continue;
+                    };
+                    // los campos de un grupo (la órbita, el cinturón) se quitan juntos,
+                    // así que uno que ya no está cuenta como aplicado
+                    let current = self.node_field(index, key);
+                    if current.is_empty() {
+                        continue;
+                    }
+                    if &current != old {
+                        conflicts.push(conflict(name, key, format!("se esperaba '{}' pero vale '{}'", old, current)));
+                        continue;
+                    }
+                    if let Err(message) = self.nodes[index].clear_field(key) {
+                        conflicts.push(conflict(name, key, message));
+                    }
+                }
+                SceneChange::Removed { name } => match self.find(name) {
+                    Some(index) => self.remove_node(index),
+                    None => conflicts.push(conflict(name, "", "el nodo no existe".to_string())),
+                },
+            }
+        }
+
+        self.sort_parents_first();
+        conflicts
+    }
+
+    /// Lee un campo de un nodo, incluido `parent` (nombre del padre o cadena vacía).
+    pub fn node_field(&self, index: usize, key: &str) -> String {
+        node_fields(self, &self.nodes[index]).into_iter()
+            .find(|(field, _)| field == key)
+            .map_or(String::new(), |(_, value)| value)
+    }
+
+    /// Asigna un campo de un nodo, resolviendo `parent` por nombre.
+    pub fn set_node_field(&mut self, index: usize, key: &str, value: &str) -> Result<(), String> {
+        if key != "parent" {
+            return self.nodes[index].set_field(key, value);
+        }
+        if value.is_empty() {
+            self.nodes[index].parent = None;
+            return Ok(());
+        }
+        let parent = self.find(value)
+            .filter(|&parent| parent != index)
+            .ok_or_else(|| format!("padre desconocido '{}'", value))?;
+        self.nodes[index].parent = Some(parent);
+        Ok(())
+    }
+
+    /// Elimina un nodo; sus hijos pasan a depender del padre del nodo eliminado.
+    pub fn remove_node(&mut self, index: usize) {
+        let removed = self.nodes.remove(index);
+        for node in &mut self.nodes {
+            node.parent = match node.parent {
+                Some(parent) if parent == index => removed.parent,
+                other => other,
+            };
+            // Los índices posteriores al eliminado se desplazan una posición.
+            node.parent = node.parent.map(|parent| if parent > index { parent - 1 } else { parent });
+        }
+    }
+
+    /// Reordena los nodos para que cada padre aparezca antes que sus hijos,
+    /// conservando el orden relativo del resto.
+    fn sort_parents_first(&mut self) {
+        let depth = |mut index: usize| {
+            let mut depth = 0;
+            while let Some(parent) = self.nodes[index].parent {
+                index = parent;
+                depth += 1;
+                if depth > self.nodes.len() {
+                    break; // ciclo de padres: se deja como está
+                }
+            }
+            depth
+        };
+        let mut order: Vec<usize> = (0..self.nodes.len()).collect();
+        order.sort_by_key(|&index| depth(index));
+
+        let mut new_index = vec![0; order.len()];
+        for (position, &old) in order.iter().enumerate() {
+            new_index[old] = position;
+        }
+        let mut nodes: Vec<SceneNode> = order.iter().map(|&old| self.nodes[old].clone()).collect();
+        for node in &mut nodes {
+            node.parent = node.parent.map(|parent| new_index[parent]);
+        }
+        self.nodes = nodes;
+    }
+}
@@ -0,0 +1,135 @@
+use crate::postprocess::PostProcess;
+
+/// Suavizado de bordes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Antialiasing {
+    /// Sin suavizado: una muestra por píxel.
+    Off,
+    /// Supermuestreo: se dibuja al doble de resolución en cada eje y se promedian
+    /// las cuatro muestras de cada píxel. Cuesta unas cuatro veces más.
+    Supersample2x,
+}
+
+impl Antialiasing {
+    /// Devuelve el nombre corto del modo.
+    pub fn name(self) -> &'static str {
+        match self {
+            Antialiasing::Off => "off",
+            Antialiasing::Supersample2x => "ssaa2x",
+        }
+    }
+
+    /// Veces que se multiplica la resolución en cada eje al dibujar.
+    pub fn factor(self) -> usize {
+        match self {
+            Antialiasing::Off => 1,
+            Antialiasing::Supersample2x => 2,
+        }
+    }
+}
+
+/// Ajustes de calidad del renderizado que cambian el costo de cada fotograma.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderSettings {
+    /// Fracción de la resolución de la ventana a la que se dibuja (la ventana amplía el resultado).
+    pub resolution_scale: f32,
+    /// Suavizado de bordes.
+    pub antialiasing: Antialiasing,
+    /// Activa la niebla por distancia.
+    pub fog: bool,
+    /// Activa el resplandor alrededor de las zonas brillantes.
+    pub bloom: bool,
+    /// Detalle de las mallas generadas (ver `primitives::from_name_with_lod`).
+    pub lod_bias: i32,
+    /// Lado de los mapas de sombras en píxeles (ver `shadow::ShadowMap`); 0 desactiva
+    /// las sombras entre cuerpos.
+    pub shadow_map_size: usize,
+}
+
+impl RenderSettings {
+    /// Calcula el tamaño de la imagen que se presenta, antes de que la ventana la amplíe.
+    ///
+    /// # Parámetros
+    /// - `window_width`, `window_height`: Tamaño de la ventana.
+    pub fn output_size(&self, window_width: usize, window_height: usize) -> (usize, usize) {
+        let scale = |size: usize| ((size as f32 * self.resolution_scale).round() as usize).max(1);
+        (scale(window_width), scale(window_height))
+    }
+
+    /// Calcula el tamaño al que se dibuja la escena: el de la imagen presentada
+    /// multiplicado por el factor del supermuestreo.
+    ///
+    /// # Parámetros
+    /// - `window_width`, `window_height`: Tamaño de la ventana.
+    pub fn render_size(&self, window_width: usize, window_height: usize) -> (usize, usize) {
+        let (width, height) = self.output_size(window_width, window_height);
+        let factor = self.antialiasing.factor();
+        (width * factor, height * factor)
+    }
+
+    /// Activa o desactiva los efectos de post-procesado según estos ajustes.
+    ///
+    /// La profundidad de campo no cambia: es una decisión de encuadre, no de calidad.
+    pub fn apply_post_process(&self, post_process: &mut PostProcess) {
+        post_process.fog_enabled = self.fog;
+        post_process.bloom_enabled = self.bloom;
+    }
+}
+
+impl Default for RenderSettings {
+    /// Ajustes del nivel `High`.
+    fn default() -> Self {
+        QualityPreset::High.settings()
+    }
+}
+
+/// Niveles de calidad predefinidos, de una laptop modesta a una computadora de escritorio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QualityPreset {
+    /// Mitad de resolución, sin resplandor ni sombras y mallas con la mitad de divisiones.
+    Low,
+    /// Tres cuartos de resolución, con niebla, resplandor y sombras de poca resolución.
+    Medium,
+    /// Resolución completa, con niebla, resplandor y sombras.
+    #[default]
+    High,
+    /// Resolución completa con supermuestreo, sombras más nítidas y mallas con el doble de divisiones.
+    Ultra,
+}
+
+impl QualityPreset {
+    /// Todos los niveles, de menor a mayor calidad.
+    pub const ALL: [QualityPreset; 4] = [QualityPreset::Low, QualityPreset::Medium, QualityPreset::High, QualityPreset::Ultra];
+
+    /// Devuelve el nombre corto del nivel.
+    pub fn name(self) -> &'static str {
+        match self {
+            QualityPreset::Low => "low",
+            QualityPreset::Medium => "medium",
+            QualityPreset::High => "high",
+            QualityPreset::Ultra => "ultra",
+        }
+    }
+
+    /// Busca un nivel por su nombre corto, sin distinguir mayúsculas.
+    pub fn from_name(name: &str) -> Option<QualityPreset> {
+        QualityPreset::ALL.into_iter().find(|preset| preset.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Devuelve el siguiente nivel; después de `Ultra` vuelve a `Low`.
+    pub fn next(self) -> QualityPreset {
+        let index = QualityPreset::ALL.iter().position(|&preset| preset == self).unwrap_or(0);
+        QualityPreset::ALL[(index + 1) % QualityPreset::ALL.len()]
+    }
+
+    /// Devuelve los ajustes del nivel.
+    pub fn settings(self) -> RenderSettings {
+        let (resolution_scale, antialiasing, bloom, lod_bias, shadow_map_size) = match self {
+            QualityPreset::Low => (0.5, Antialiasing::Off, false, -1, 0),
+            QualityPreset::Medium => (0.75, Antialiasing::Off, true, 0, 256),
+            QualityPreset::High => (1.0, Antialiasing::Off, true, 0, 512),
+            QualityPreset::Ultra => (1.0, Antialiasing::Supersample2x, true, 1, 1024),
+        };
+        RenderSettings { resolution_scale, antialiasing, fog: true, bloom, lod_bias, shadow_map_size }
+    }
+}
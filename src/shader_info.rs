@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::color::Color;
+use crate::shader_params::ParamValue;
+
+/// Tipo, valor de fábrica y rango de un parámetro ajustable.
+#[derive(Debug, Clone, Copy)]
+pub enum ParamKind {
+    /// Un número; fuera de `min..=max` el shader sigue funcionando, pero el resultado deja de tener sentido.
+    Number { default: f32, min: f32, max: f32 },
+    /// Un color, que en `assets/shader_params.toml` se escribe `"#rrggbb"` o `[r, g, b]`.
+    Color { default: Color },
+    /// Una rampa de colores por elevación, `[[elevación, color], ...]`; si falta, el
+    /// shader la arma con sus umbrales y colores.
+    Ramp,
+}
+
+impl ParamKind {
+    /// Valor de fábrica, o `None` para las rampas, que el shader arma con sus umbrales.
+    pub fn default_value(&self) -> Option<ParamValue> {
+        match *self {
+            ParamKind::Number { default, .. } => Some(ParamValue::Number(default)),
+            ParamKind::Color { default } => Some(ParamValue::Color(default)),
+            ParamKind::Ramp => None,
+        }
+    }
+}
+
+impl fmt::Display for ParamKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParamKind::Number { default, min, max } => write!(f, "{} (de {} a {})", default, min, max),
+            ParamKind::Color { default } => write!(f, "{} (color)", ParamValue::Color(default)),
+            ParamKind::Ramp => write!(f, "según los umbrales (rampa de colores)"),
+        }
+    }
+}
+
+/// Un parámetro ajustable de un shader (ver `shader_params::ShaderParams`).
+#[derive(Debug, Clone, Copy)]
+pub struct ParamInfo {
+    /// Clave en la sección del shader del archivo de parámetros.
+    pub name: &'static str,
+    /// Qué cambia en la imagen.
+    pub description: &'static str,
+    /// Tipo, valor de fábrica y rango.
+    pub kind: ParamKind,
+}
+
+/// Documentación de un shader: qué dibuja y qué se puede ajustar.
+#[derive(Debug, Clone, Copy)]
+pub struct ShaderInfo {
+    /// Nombre corto, el mismo de `PlanetShader::name` para los shaders del renderizador.
+    pub name: &'static str,
+    /// Descripción de una línea.
+    pub description: &'static str,
+    /// Parámetros ajustables, en el orden del archivo de parámetros.
+    pub params: &'static [ParamInfo],
+}
+
+impl ShaderInfo {
+    /// Busca un parámetro por su clave.
+    pub fn param(&self, name: &str) -> Option<&ParamInfo> {
+        self.params.iter().find(|param| param.name == name)
+    }
+
+    /// Describe cada parámetro con su valor actual, una línea por parámetro.
+    ///
+    /// # Parámetros
+    /// - `values`: Valores cargados para el shader (`ShaderParams::for_shader`); los
+    ///   que faltan se muestran con su valor de fábrica.
+    pub fn describe_params(&self, values: &HashMap<String, ParamValue>) -> Vec<String> {
+        self.params.iter()
+            .map(|param| match values.get(param.name).copied().or(param.kind.default_value()) {
+                Some(value) => format!("{} = {}", param.name, value),
+                None => format!("{} = (umbrales)", param.name),
+            })
+            .collect()
+    }
+}
+
+/// Catálogo de shaders con su documentación, para descubrir desde la biblioteca, la
+/// línea de comandos (`--list-shaders`) o el visor qué shaders hay y qué se ajusta.
+///
+/// Empieza con los shaders del renderizador; quien agrega un shader propio (por
+/// ejemplo con un hook de fragmentos que lee `Uniforms::user_params`) lo documenta con
+/// `register` para que aparezca junto a los demás.
+#[derive(Debug, Clone)]
+pub struct ShaderRegistry {
+    shaders: Vec<ShaderInfo>,
+}
+
+impl ShaderRegistry {
+    /// Crea el catálogo con los shaders del renderizador.
+    pub fn new() -> Self {
+        ShaderRegistry { shaders: BUILTIN_SHADERS.to_vec() }
+    }
+
+    /// Agrega un shader, o reemplaza la documentación de uno con el mismo nombre.
+    pub fn register(&mut self, info: ShaderInfo) {
+        match self.shaders.iter_mut().find(|shader| shader.name == info.name) {
+            Some(existing) => *existing = info,
+            None => self.shaders.push(info),
+        }
+    }
+
+    /// Busca un shader por su nombre corto.
+    pub fn get(&self, name: &str) -> Option<&ShaderInfo> {
+        self.shaders.iter().find(|shader| shader.name == name)
+    }
+
+    /// Todos los shaders, en el orden en que se registraron.
+    pub fn shaders(&self) -> &[ShaderInfo] {
+        &self.shaders
+    }
+}
+
+impl Default for ShaderRegistry {
+    fn default() -> Self {
+        ShaderRegistry::new()
+    }
+}
+
+/// Devuelve la documentación de los shaders del renderizador, en el orden de `PlanetShader::ALL`.
+pub fn list_shaders() -> Vec<ShaderInfo> {
+    ShaderRegistry::new().shaders
+}
+
+/// Atajo para declarar un parámetro numérico.
+const fn number(name: &'static str, description: &'static str, default: f32, min: f32, max: f32) -> ParamInfo {
+    ParamInfo { name, description, kind: ParamKind::Number { default, min, max } }
+}
+
+/// Atajo para declarar un parámetro de color.
+const fn color(name: &'static str, description: &'static str, r: u8, g: u8, b: u8) -> ParamInfo {
+    ParamInfo { name, description, kind: ParamKind::Color { default: Color::new(r, g, b) } }
+}
+
+/// Atajo para declarar una rampa de colores.
+const fn ramp(name: &'static str, description: &'static str) -> ParamInfo {
+    ParamInfo { name, description, kind: ParamKind::Ramp }
+}
+
+/// Escala del ruido de la superficie, que comparten casi todos los shaders.
+const fn noise_scale(default: f32) -> ParamInfo {
+    number("noise_scale", "Escala del ruido: valores altos dan detalles más pequeños.", default, 0.5, 50.0)
+}
+
+/// Documentación de los shaders de `PlanetShader::ALL`. Los valores de fábrica son
+/// los que usa cada shader cuando falta la clave en el archivo de parámetros.
+const BUILTIN_SHADERS: [ShaderInfo; 17] = [
+    ShaderInfo {
+        name: "earth",
+        description: "Planeta tipo Tierra: océanos, costas, tierra y montañas nevadas, con reflejo del sol en el agua.",
+        params: &[
+            noise_scale(10.0),
+            number("ocean_threshold", "Elevación hasta la que hay océano profundo.", 0.0, -1.0, 1.0),
+            number("land_threshold", "Elevación hasta la que hay agua poco profunda.", 0.2, -1.0, 1.0),
+            number("mountain_threshold", "Elevación desde la que empiezan las montañas.", 0.5, -1.0, 1.0),
+            color("ocean_color", "Océano profundo.", 0, 105, 148),
+            color("shallow_water_color", "Agua poco profunda.", 0, 191, 255),
+            color("land_color", "Tierra baja.", 34, 139, 34),
+            color("mountain_color", "Montañas.", 139, 69, 19),
+            color("snow_color", "Nieve de las cumbres.", 255, 255, 255),
+            number("land_roughness", "Rugosidad de la tierra: 1 es completamente mate.", 0.9, 0.0, 1.0),
+            number("ocean_roughness", "Rugosidad del agua: más baja, reflejo del sol más pequeño y nítido.", 0.25, 0.0, 1.0),
+            ramp("terrain_ramp", "Colores por elevación; reemplaza los umbrales y colores de arriba."),
+        ],
+    },
+    ShaderInfo {
+        name: "moon",
+        description: "Luna gris con cráteres (ver `crater_density` en el archivo de escena).",
+        params: &[
+            noise_scale(20.0),
+            number("low_threshold", "Elevación hasta la que la superficie es oscura.", -0.1, -1.0, 1.0),
+            number("medium_threshold", "Elevación hasta la que la superficie es clara.", 0.1, -1.0, 1.0),
+            number("high_threshold", "Elevación desde la que se ven los picos.", 0.3, -1.0, 1.0),
+            color("dark_surface_color", "Mares oscuros.", 169, 169, 169),
+            color("light_surface_color", "Tierras altas.", 211, 211, 211),
+            color("crater_color", "Bordes de los cráteres.", 255, 255, 255),
+            color("peak_color", "Picos.", 240, 240, 240),
+            ramp("terrain_ramp", "Colores por elevación; reemplaza los umbrales y colores de arriba."),
+        ],
+    },
+    ShaderInfo {
+        name: "sun",
+        description: "Sol con manchas en movimiento; ilumina la escena.",
+        params: &[
+            noise_scale(10.0),
+            color("deep_color", "Zonas más calientes.", 255, 140, 0),
+            color("light_color", "Zonas más frías.", 255, 165, 80),
+        ],
+    },
+    ShaderInfo {
+        name: "gas",
+        description: "Gigante gaseoso con bandas de nubes y halo atmosférico.",
+        params: &[
+            noise_scale(5.0),
+            color("base_color", "Color de fondo.", 70, 130, 180),
+            color("cloud_color", "Bandas claras.", 255, 255, 255),
+            color("shadow_color", "Bandas oscuras.", 50, 50, 100),
+        ],
+    },
+    ShaderInfo {
+        name: "rocky",
+        description: "Planeta rocoso con vetas claras y oscuras.",
+        params: &[
+            noise_scale(3.0),
+            color("base_color", "Roca.", 139, 69, 19),
+            color("highlight_color", "Vetas claras.", 255, 255, 255),
+            color("shadow_color", "Vetas oscuras.", 80, 50, 0),
+        ],
+    },
+    ShaderInfo {
+        name: "star",
+        description: "Estrella lejana: puntos brillantes sobre un fondo oscuro; ilumina la escena.",
+        params: &[
+            noise_scale(5.0),
+            color("base_color", "Fondo.", 30, 30, 60),
+            color("star_color", "Puntos brillantes.", 255, 255, 255),
+        ],
+    },
+    ShaderInfo {
+        name: "fantasy",
+        description: "Planeta de fantasía con remolinos de colores y nubes.",
+        params: &[
+            noise_scale(2.0),
+            color("base_color", "Color de fondo.", 50, 50, 200),
+            color("accent_color", "Remolinos.", 180, 80, 200),
+            color("cloud_color", "Nubes.", 255, 255, 255),
+        ],
+    },
+    ShaderInfo {
+        name: "lava",
+        description: "Ríos de lava que fluyen sobre una corteza oscura (`flow_speed`, `flow_turbulence`).",
+        params: &[
+            noise_scale(3.0),
+            number("river_width", "Ancho de los ríos de lava.", 0.2, 0.01, 1.0),
+            color("crust_color", "Corteza fría.", 40, 25, 20),
+            color("hot_color", "Borde de los ríos.", 255, 80, 0),
+            color("core_color", "Centro de los ríos.", 255, 220, 90),
+        ],
+    },
+    ShaderInfo {
+        name: "ocean",
+        description: "Océano con olas, espuma en las crestas y reflejo del sol (`flow_speed`, `flow_turbulence`).",
+        params: &[
+            noise_scale(6.0),
+            number("foam_threshold", "Altura de las olas desde la que aparece espuma.", 0.55, -1.0, 1.0),
+            color("deep_color", "Agua profunda.", 5, 40, 90),
+            color("shallow_color", "Crestas de las olas.", 20, 110, 160),
+            color("foam_color", "Espuma.", 230, 240, 255),
+            number("roughness", "Rugosidad del agua: más baja, reflejo del sol más pequeño y nítido.", 0.3, 0.0, 1.0),
+        ],
+    },
+    ShaderInfo {
+        name: "material",
+        description: "Material (.mtl) de cada cara del modelo importado, con `Pr` y `Pm` si los trae.",
+        params: &[],
+    },
+    ShaderInfo {
+        name: "vertex_color",
+        description: "Colores por vértice del modelo (OBJ con `v x y z r g b` o PLY con `red green blue`).",
+        params: &[],
+    },
+    ShaderInfo {
+        name: "ring",
+        description: "Anillo semitransparente con bandas y la sombra del planeta, para `primitive:ring`.",
+        params: &[],
+    },
+    ShaderInfo {
+        name: "clouds",
+        description: "Capa de nubes animada y semitransparente sobre el planeta (`Scene::add_cloud_layer`).",
+        params: &[],
+    },
+    ShaderInfo {
+        name: "debug_normal",
+        description: "Depuración: la normal como color (x, y, z en rojo, verde y azul).",
+        params: &[],
+    },
+    ShaderInfo {
+        name: "debug_uv",
+        description: "Depuración: las coordenadas de textura, u en rojo y v en verde.",
+        params: &[],
+    },
+    ShaderInfo {
+        name: "debug_intensity",
+        description: "Depuración: la intensidad de la luz en grises.",
+        params: &[],
+    },
+    ShaderInfo {
+        name: "debug_elevation",
+        description: "Depuración: los colores por elevación de los vértices, interpolados en cada triángulo.",
+        params: &[],
+    },
+];
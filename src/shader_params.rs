@@ -0,0 +1,534 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use crate::color::{Color, ColorRamp};
+use crate::shaders::PlanetShader;
+
+/// Archivo de parámetros que el visor lee al arrancar y vuelve a leer al modificarse.
+pub const SHADER_PARAMS_PATH: &str = "assets/shader_params.toml";
+
+/// Parámetros de los shaders de planeta leídos de un archivo de configuración.
+///
+/// El archivo usa un subconjunto de TOML: una sección por shader con su nombre
+/// corto (`[earth]`, `[moon]`, ...) y líneas `clave = valor` debajo. Un valor puede ser
+/// un número, un color `"#rrggbb"` o un color `[r, g, b]` con componentes de 0 a 255.
+/// Las líneas vacías y lo que sigue a `#` fuera de una cadena se ignoran.
+///
+/// Un valor también puede ser una rampa de colores por elevación, una lista de
+/// paradas `[elevación, color]` en una sola línea:
+///
+/// ```toml
+/// [earth]
+/// terrain_ramp = [[-0.2, "#00284d"], [0.0, [0, 105, 148]], [0.05, "#c2b280"], [0.6, "#ffffff"]]
+/// ```
+///
+/// Cada vez que cambia un valor se vuelven a resolver los `SurfaceParams`, con los
+/// valores de fábrica para las claves que faltan; cada objeto los copia a
+/// `Uniforms::surface` y los shaders leen campos, sin buscar claves por fragmento.
+#[derive(Debug, Clone)]
+pub struct ShaderParams {
+    /// Valores leídos de cada shader, por nombre de parámetro.
+    values: HashMap<PlanetShader, HashMap<String, ParamValue>>,
+    /// Valores de `values` ya resueltos, con los de fábrica donde faltan.
+    surface: SurfaceParams,
+}
+
+/// Un valor del archivo de parámetros.
+#[derive(Debug, Clone, Copy)]
+pub enum ParamValue {
+    /// Un número: umbrales, escalas, rugosidades.
+    Number(f32),
+    /// Un color, escrito `"#rrggbb"` o `[r, g, b]`.
+    Color(Color),
+    /// Una rampa de colores por elevación, `[[elevación, color], ...]`.
+    Ramp(ColorRamp),
+}
+
+impl fmt::Display for ParamValue {
+    /// Escribe el valor como se escribiría en el archivo; de una rampa, solo cuántas paradas tiene.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamValue::Number(value) => write!(f, "{}", value),
+            ParamValue::Color(color) => {
+                let [r, g, b] = <[u8; 3]>::from(*color);
+                write!(f, "[{}, {}, {}]", r, g, b)
+            }
+            ParamValue::Ramp(ramp) => write!(f, "{} paradas", ramp.stops().len()),
+        }
+    }
+}
+
+/// Error al leer o interpretar un archivo de parámetros.
+#[derive(Debug)]
+pub enum ShaderParamsError {
+    /// No se pudo leer el archivo.
+    Io(io::Error),
+    /// El contenido del archivo no es válido.
+    Parse {
+        /// Número de línea (empezando en 1) donde se encontró el error.
+        line: usize,
+        /// Descripción del problema.
+        message: String,
+    },
+}
+
+impl fmt::Display for ShaderParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderParamsError::Io(error) => write!(f, "error de E/S: {}", error),
+            ShaderParamsError::Parse { line, message } => write!(f, "línea {}: {}", line, message),
+        }
+    }
+}
+
+impl From<io::Error> for ShaderParamsError {
+    fn from(error: io::Error) -> Self {
+        ShaderParamsError::Io(error)
+    }
+}
+
+impl Default for ShaderParams {
+    fn default() -> Self {
+        ShaderParams::new()
+    }
+}
+
+impl ShaderParams {
+    /// Crea un conjunto de parámetros vacío: todos los shaders usan sus valores de fábrica.
+    pub fn new() -> Self {
+        let values = PlanetShader::ALL.iter().map(|&shader| (shader, HashMap::new())).collect();
+        ShaderParams { values, surface: SurfaceParams::default() }
+    }
+
+    /// Lee los parámetros de un archivo.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ShaderParams, ShaderParamsError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Devuelve los valores leídos para un shader, sin los de fábrica.
+    pub fn for_shader(&self, shader: PlanetShader) -> &HashMap<String, ParamValue> {
+        &self.values[&shader]
+    }
+
+    /// Devuelve los parámetros resueltos de todos los shaders, listos para `Uniforms::surface`.
+    pub fn surface(&self) -> &SurfaceParams {
+        &self.surface
+    }
+
+    /// Cambia un parámetro de un shader y vuelve a resolver los de ese shader.
+    pub fn set(&mut self, shader: PlanetShader, name: &str, value: ParamValue) {
+        let values = self.values.entry(shader).or_default();
+        values.insert(name.to_string(), value);
+        self.surface.resolve(shader, values);
+    }
+}
+
+/// Parámetros de todos los shaders de planeta, ya resueltos: cada shader lee los
+/// suyos como campos de `Uniforms::surface`, que tienen el nombre de su clave en el archivo.
+///
+/// `Default` da los valores de fábrica, los mismos que documenta `shader_info`.
+#[derive(Debug, Clone, Copy)]
+pub struct SurfaceParams {
+    pub earth: EarthParams,
+    pub moon: MoonParams,
+    pub sun: SunParams,
+    pub gas: GasParams,
+    pub rocky: RockyParams,
+    pub star: StarParams,
+    pub fantasy: FantasyParams,
+    pub lava: LavaParams,
+    pub ocean: OceanParams,
+}
+
+impl Default for SurfaceParams {
+    fn default() -> Self {
+        let none = Lookup(None);
+        SurfaceParams {
+            earth: EarthParams::resolve(none),
+            moon: MoonParams::resolve(none),
+            sun: SunParams::resolve(none),
+            gas: GasParams::resolve(none),
+            rocky: RockyParams::resolve(none),
+            star: StarParams::resolve(none),
+            fantasy: FantasyParams::resolve(none),
+            lava: LavaParams::resolve(none),
+            ocean: OceanParams::resolve(none),
+        }
+    }
+}
+
+impl SurfaceParams {
+    /// Vuelve a resolver los parámetros de un shader a partir de sus valores leídos.
+    fn resolve(&mut self, shader: PlanetShader, values: &HashMap<String, ParamValue>) {
+        let values = Lookup(Some(values));
+        match shader {
+            PlanetShader::Earth => self.earth = EarthParams::resolve(values),
+            PlanetShader::Moon => self.moon = MoonParams::resolve(values),
+            PlanetShader::Sun => self.sun = SunParams::resolve(values),
+            PlanetShader::Gas => self.gas = GasParams::resolve(values),
+            PlanetShader::Rocky => self.rocky = RockyParams::resolve(values),
+            PlanetShader::Star => self.star = StarParams::resolve(values),
+            PlanetShader::Fantasy => self.fantasy = FantasyParams::resolve(values),
+            PlanetShader::Lava => self.lava = LavaParams::resolve(values),
+            PlanetShader::Ocean => self.ocean = OceanParams::resolve(values),
+            _ => {}
+        }
+    }
+}
+
+/// Búsqueda de valores leídos con su valor de fábrica; un valor del tipo equivocado
+/// (un número donde va un color) se ignora.
+#[derive(Clone, Copy)]
+struct Lookup<'a>(Option<&'a HashMap<String, ParamValue>>);
+
+impl Lookup<'_> {
+    fn get(&self, name: &str) -> Option<ParamValue> {
+        self.0.and_then(|values| values.get(name)).copied()
+    }
+
+    fn number(&self, name: &str, default: f32) -> f32 {
+        match self.get(name) {
+            Some(ParamValue::Number(value)) => value,
+            _ => default,
+        }
+    }
+
+    fn color(&self, name: &str, default: Color) -> Color {
+        match self.get(name) {
+            Some(ParamValue::Color(color)) => color,
+            _ => default,
+        }
+    }
+
+    fn ramp(&self, name: &str) -> Option<ColorRamp> {
+        match self.get(name) {
+            Some(ParamValue::Ramp(ramp)) => Some(ramp),
+            _ => None,
+        }
+    }
+}
+
+/// Parámetros de la Tierra (`[earth]`), también con nubes y luces de ciudades.
+#[derive(Debug, Clone, Copy)]
+pub struct EarthParams {
+    pub noise_scale: f32,
+    /// Elevación desde la que empieza la tierra; debajo el agua refleja el sol.
+    pub land_threshold: f32,
+    /// Colores por elevación: `terrain_ramp` o, si falta, las bandas de océano, agua
+    /// poco profunda, tierra, montaña y nieve que arman los umbrales.
+    pub terrain_ramp: ColorRamp,
+    pub land_roughness: f32,
+    pub ocean_roughness: f32,
+}
+
+impl EarthParams {
+    fn resolve(values: Lookup) -> Self {
+        let ocean_threshold = values.number("ocean_threshold", 0.0);
+        let land_threshold = values.number("land_threshold", 0.2);
+        let mountain_threshold = values.number("mountain_threshold", 0.5);
+        let ocean_color = values.color("ocean_color", Color::new(0, 105, 148));
+        let shallow_water_color = values.color("shallow_water_color", Color::new(0, 191, 255));
+        let land_color = values.color("land_color", Color::new(34, 139, 34));
+        let mountain_color = values.color("mountain_color", Color::new(139, 69, 19));
+        let snow_color = values.color("snow_color", Color::new(255, 255, 255));
+
+        // Bandas con cortes duros en los umbrales, y nieve en las cumbres.
+        let terrain_ramp = values.ramp("terrain_ramp").unwrap_or(ColorRamp::from_sorted([
+            (ocean_threshold, ocean_color),
+            (ocean_threshold, shallow_water_color),
+            (land_threshold, shallow_water_color),
+            (land_threshold, land_color),
+            (mountain_threshold, land_color),
+            (mountain_threshold, mountain_color),
+            (mountain_threshold + 0.3, mountain_color),
+            (mountain_threshold + 0.3, mountain_color + snow_color * 0.5),
+        ]));
+        EarthParams {
+            noise_scale: values.number("noise_scale", 10.0),
+            land_threshold,
+            terrain_ramp,
+            land_roughness: values.number("land_roughness", 0.9),
+            ocean_roughness: values.number("ocean_roughness", 0.25),
+        }
+    }
+}
+
+/// Parámetros de la Luna (`[moon]`).
+#[derive(Debug, Clone, Copy)]
+pub struct MoonParams {
+    pub noise_scale: f32,
+    /// Colores por elevación: `terrain_ramp` o, si falta, el degradado de mares
+    /// oscuros, tierras altas, cráteres y picos que arman los umbrales.
+    pub terrain_ramp: ColorRamp,
+}
+
+impl MoonParams {
+    fn resolve(values: Lookup) -> Self {
+        let low_threshold = values.number("low_threshold", -0.1);
+        let medium_threshold = values.number("medium_threshold", 0.1);
+        let high_threshold = values.number("high_threshold", 0.3);
+        let terrain_ramp = values.ramp("terrain_ramp").unwrap_or_else(|| ColorRamp::new(&[
+            (low_threshold, values.color("dark_surface_color", Color::new(169, 169, 169))),
+            (medium_threshold, values.color("light_surface_color", Color::new(211, 211, 211))),
+            (high_threshold, values.color("crater_color", Color::new(255, 255, 255))),
+            (high_threshold * 2.0 - medium_threshold, values.color("peak_color", Color::new(240, 240, 240))),
+        ]).expect("cuatro paradas caben en una rampa"));
+        MoonParams { noise_scale: values.number("noise_scale", 20.0), terrain_ramp }
+    }
+}
+
+/// Parámetros del Sol (`[sun]`).
+#[derive(Debug, Clone, Copy)]
+pub struct SunParams {
+    pub noise_scale: f32,
+    pub deep_color: Color,
+    pub light_color: Color,
+}
+
+impl SunParams {
+    fn resolve(values: Lookup) -> Self {
+        SunParams {
+            noise_scale: values.number("noise_scale", 10.0),
+            deep_color: values.color("deep_color", Color::new(255, 140, 0)),
+            light_color: values.color("light_color", Color::new(255, 165, 80)),
+        }
+    }
+}
+
+/// Parámetros del planeta gaseoso (`[gas]`).
+#[derive(Debug, Clone, Copy)]
+pub struct GasParams {
+    pub noise_scale: f32,
+    pub base_color: Color,
+    pub cloud_color: Color,
+    pub shadow_color: Color,
+}
+
+impl GasParams {
+    fn resolve(values: Lookup) -> Self {
+        GasParams {
+            noise_scale: values.number("noise_scale", 5.0),
+            base_color: values.color("base_color", Color::new(70, 130, 180)),
+            cloud_color: values.color("cloud_color", Color::new(255, 255, 255)),
+            shadow_color: values.color("shadow_color", Color::new(50, 50, 100)),
+        }
+    }
+}
+
+/// Parámetros del planeta rocoso (`[rocky]`).
+#[derive(Debug, Clone, Copy)]
+pub struct RockyParams {
+    pub noise_scale: f32,
+    pub base_color: Color,
+    pub highlight_color: Color,
+    pub shadow_color: Color,
+}
+
+impl RockyParams {
+    fn resolve(values: Lookup) -> Self {
+        RockyParams {
+            noise_scale: values.number("noise_scale", 3.0),
+            base_color: values.color("base_color", Color::new(139, 69, 19)),
+            highlight_color: values.color("highlight_color", Color::new(255, 255, 255)),
+            shadow_color: values.color("shadow_color", Color::new(80, 50, 0)),
+        }
+    }
+}
+
+/// Parámetros del planeta estrellado (`[star]`).
+#[derive(Debug, Clone, Copy)]
+pub struct StarParams {
+    pub noise_scale: f32,
+    pub base_color: Color,
+    pub star_color: Color,
+}
+
+impl StarParams {
+    fn resolve(values: Lookup) -> Self {
+        StarParams {
+            noise_scale: values.number("noise_scale", 5.0),
+            base_color: values.color("base_color", Color::new(30, 30, 60)),
+            star_color: values.color("star_color", Color::new(255, 255, 255)),
+        }
+    }
+}
+
+/// Parámetros del planeta fantástico (`[fantasy]`).
+#[derive(Debug, Clone, Copy)]
+pub struct FantasyParams {
+    pub noise_scale: f32,
+    pub base_color: Color,
+    pub accent_color: Color,
+    pub cloud_color: Color,
+}
+
+impl FantasyParams {
+    fn resolve(values: Lookup) -> Self {
+        FantasyParams {
+            noise_scale: values.number("noise_scale", 2.0),
+            base_color: values.color("base_color", Color::new(50, 50, 200)),
+            accent_color: values.color("accent_color", Color::new(180, 80, 200)),
+            cloud_color: values.color("cloud_color", Color::new(255, 255, 255)),
+        }
+    }
+}
+
+/// Parámetros del planeta de lava (`[lava]`).
+#[derive(Debug, Clone, Copy)]
+pub struct LavaParams {
+    pub noise_scale: f32,
+    /// Ancho de los ríos, nunca menor que 0.001.
+    pub river_width: f32,
+    pub crust_color: Color,
+    pub hot_color: Color,
+    pub core_color: Color,
+}
+
+impl LavaParams {
+    fn resolve(values: Lookup) -> Self {
+        LavaParams {
+            noise_scale: values.number("noise_scale", 3.0),
+            river_width: values.number("river_width", 0.2).max(1e-3),
+            crust_color: values.color("crust_color", Color::new(40, 25, 20)),
+            hot_color: values.color("hot_color", Color::new(255, 80, 0)),
+            core_color: values.color("core_color", Color::new(255, 220, 90)),
+        }
+    }
+}
+
+/// Parámetros del planeta océano (`[ocean]`).
+#[derive(Debug, Clone, Copy)]
+pub struct OceanParams {
+    pub noise_scale: f32,
+    pub foam_threshold: f32,
+    pub roughness: f32,
+    pub deep_color: Color,
+    pub shallow_color: Color,
+    pub foam_color: Color,
+}
+
+impl OceanParams {
+    fn resolve(values: Lookup) -> Self {
+        OceanParams {
+            noise_scale: values.number("noise_scale", 6.0),
+            foam_threshold: values.number("foam_threshold", 0.55),
+            roughness: values.number("roughness", 0.3),
+            deep_color: values.color("deep_color", Color::new(5, 40, 90)),
+            shallow_color: values.color("shallow_color", Color::new(20, 110, 160)),
+            foam_color: values.color("foam_color", Color::new(230, 240, 255)),
+        }
+    }
+}
+
+impl FromStr for ShaderParams {
+    type Err = ShaderParamsError;
+
+    fn from_str(text: &str) -> Result<ShaderParams, ShaderParamsError> {
+        let mut params = ShaderParams::new();
+        let mut section: Option<PlanetShader> = None;
+
+        for (number, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            let error = |message: String| ShaderParamsError::Parse { line: number + 1, message };
+
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                let name = name.trim();
+                section = Some(PlanetShader::from_name(name)
+                    .ok_or_else(|| error(format!("shader desconocido '{}'", name)))?);
+                continue;
+            }
+
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| error(format!("se esperaba 'clave = valor': '{}'", line)))?;
+            let (key, value) = (key.trim(), value.trim());
+            let shader = section
+                .ok_or_else(|| error("parámetro fuera de una sección [shader]".to_string()))?;
+            if value.starts_with("[[") {
+                let ramp = parse_ramp(value)
+                    .ok_or_else(|| error(format!("rampa inválida para '{}': '{}'", key, value)))?;
+                params.set(shader, key, ParamValue::Ramp(ramp));
+                continue;
+            }
+            let value = parse_value(value)
+                .ok_or_else(|| error(format!("valor inválido para '{}': '{}'", key, value)))?;
+            params.set(shader, key, value);
+        }
+
+        Ok(params)
+    }
+}
+
+/// Quita el comentario de una línea, sin cortar un `#` dentro de una cadena (`"#ff8800"`).
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Interpreta un valor: un número, un color `"#rrggbb"` o un color `[r, g, b]`.
+fn parse_value(value: &str) -> Option<ParamValue> {
+    if let Some(hex) = value.strip_prefix("\"#").and_then(|rest| rest.strip_suffix('"')) {
+        if hex.len() != 6 {
+            return None;
+        }
+        let hex = u32::from_str_radix(hex, 16).ok()?;
+        return Some(ParamValue::Color(Color::from_hex(hex)));
+    }
+    if let Some(list) = value.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+        let components = list.split(',')
+            .map(|component| component.trim().parse::<u8>().ok())
+            .collect::<Option<Vec<u8>>>()?;
+        let [r, g, b] = components[..] else { return None };
+        return Some(ParamValue::Color(Color::new(r, g, b)));
+    }
+    value.parse::<f32>().ok().filter(|value| value.is_finite()).map(ParamValue::Number)
+}
+
+/// Interpreta una rampa `[[elevación, color], ...]`, con cada color en cualquiera de
+/// las formas de `parse_value`. Las paradas pueden estar en cualquier orden.
+fn parse_ramp(value: &str) -> Option<ColorRamp> {
+    let list = value.strip_prefix('[')?.strip_suffix(']')?;
+    let mut stops = Vec::new();
+    let mut rest = list.trim();
+    while !rest.is_empty() {
+        // Cada parada termina en el `]` que cierra su corchete.
+        let stop = rest.strip_prefix('[')?;
+        let mut depth = 1;
+        let end = stop.find(|character| {
+            match character {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        })?;
+        let (elevation, color) = stop[..end].split_once(',')?;
+        let elevation = elevation.trim().parse::<f32>().ok().filter(|elevation| elevation.is_finite())?;
+        let color = color.trim();
+        if !color.starts_with('[') && !color.starts_with('"') {
+            return None;
+        }
+        let ParamValue::Color(color) = parse_value(color)? else { return None };
+        stops.push((elevation, color));
+
+        rest = stop[end + 1..].trim_start();
+        if let Some(after_comma) = rest.strip_prefix(',') {
+            rest = after_comma.trim_start();
+        } else if !rest.is_empty() {
+            return None;
+        }
+    }
+    ColorRamp::new(&stops)
+}
@@ -1,12 +1,13 @@
 // Importaciones necesarias para el shader, incluyendo vectores y matrices de nalgebra.
 use nalgebra_glm::{Vec3, Vec4, Mat3, mat4_to_mat3};
-use crate::vertex::Vertex; // Importa la estructura Vertex del módulo correspondiente.
+use crate::vertex::{Vertex, ELEVATION_RAMP}; // Vértices y su rampa de colores por defecto.
 use crate::Uniforms; // Importa la estructura Uniforms del módulo correspondiente.
 use crate::fragment::Fragment; // Importa la estructura Fragment del módulo correspondiente.
 use crate::color::Color; // Importa la estructura Color del módulo correspondiente.
-use rand::Rng; // Mantener solo si se usa
-use rand::SeedableRng; // Mantener solo si se usa
-use rand::rngs::StdRng; // Mantener solo si se usa
+use crate::obj::Material; // Importa la estructura Material del módulo correspondiente.
+use crate::craters; // Cráteres de la superficie lunar.
+use crate::pbr; // Modelo de iluminación físico común a todos los shaders iluminados.
+use crate::shadow::SHADOW_AMBIENT; // Brillo que queda dentro de una sombra.
 
 /// Función del shader de vértices que transforma la posición del vértice y calcula el color.
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
@@ -19,7 +20,7 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     );
 
     // Transforma la posición usando las matrices de proyección, vista y modelo.
-    let transformed = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * position;
+    let transformed = uniforms.matrices.projection * uniforms.matrices.view * uniforms.matrices.model * position;
 
     // Calcula la componente w para la perspectiva.
     let w = transformed.w;
@@ -33,10 +34,10 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     );
 
     // Calcula la posición en la pantalla.
-    let screen_position = uniforms.viewport_matrix * transformed_position;
+    let screen_position = uniforms.matrices.viewport * transformed_position;
 
     // Convierte la matriz de modelo 4x4 a 3x3 para calcular las normales.
-    let model_mat3 = mat4_to_mat3(&uniforms.model_matrix);
+    let model_mat3 = mat4_to_mat3(&uniforms.matrices.model);
     let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or(Mat3::identity());
 
     // Transforma la normal del vértice.
@@ -50,109 +51,478 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
         color: vertex.color, // Color inicial
         transformed_position: Vec3::new(screen_position.x, screen_position.y, screen_position.z),
         transformed_normal: transformed_normal,
-        elevation: vertex.elevation, // Mantiene la elevación original
+        world_position: (uniforms.matrices.model * position).xyz(), // Posición en el mundo, para la normal de la cara
+        elevation: uniforms.material.elevation.elevation(vertex, uniforms.material.heightmap.as_deref()), // Elevación según la estrategia del objeto
+        material: vertex.material, // Mantiene el material de la cara
     };
 
-    // Actualiza el color del vértice basado en la elevación.
-    new_vertex.update_color_based_on_elevation();
+    // Actualiza el color del vértice basado en la elevación, salvo que el shader use el del modelo.
+    if uniforms.material.shader != PlanetShader::VertexColor {
+        new_vertex.update_color_based_on_elevation(&ELEVATION_RAMP);
+    }
 
     // Retorna el nuevo vértice.
     new_vertex
 }
 
-/// Función del shader de fragmentos que calcula el color del fragmento.
-pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    // Devuelve el color de la luna o de la tierra, según se desee.
-    //earth_color(fragment, uniforms)
-    //moon_color(fragment, uniforms)
-   // sun_gradient(fragment, uniforms)
-   //gas_planet_color(fragment, uniforms)
-   //rocky_planet_color(fragment, uniforms)
-   //star_planet_color(fragment, uniforms)
-   fantasy_planet_color(fragment, uniforms)
+/// Capa de nubes animada sobre la superficie.
+pub const FEATURE_CLOUDS: u32 = 1 << 0;
+/// Luces de ciudades en el lado nocturno.
+pub const FEATURE_CITY_LIGHTS: u32 = 1 << 1;
+
+/// Nombres de las características tal como se escriben en el archivo de escena.
+pub const FEATURE_NAMES: [(&str, u32); 2] = [
+    ("clouds", FEATURE_CLOUDS),
+    ("city_lights", FEATURE_CITY_LIGHTS),
+];
+
+/// Firma común de todas las variantes de un shader de fragmentos.
+type ShaderFn = fn(&Fragment, &Uniforms) -> Vec3;
+
+/// Variantes del shader de la Tierra, indexadas por `features & (FEATURE_CLOUDS | FEATURE_CITY_LIGHTS)`.
+///
+/// Cada variante se compila por separado con parámetros const, así que un
+/// objeto sin nubes ni luces no paga ni siquiera la comprobación de esas ramas.
+const EARTH_VARIANTS: [ShaderFn; 4] = [
+    earth_color::<false, false>,
+    earth_color::<true, false>,
+    earth_color::<false, true>,
+    earth_color::<true, true>,
+];
+
+/// Parámetros del halo atmosférico que se suma al color de la superficie.
+///
+/// Aproxima la dispersión de la luz con un término tipo Fresnel: el halo crece
+/// donde la superficie se ve de canto (borde del disco) y solo aparece del lado
+/// iluminado, tiñéndose de `sunset_color` cerca de la línea entre el día y la noche.
+#[derive(Debug, Clone, Copy)]
+pub struct Atmosphere {
+    /// Color del halo del lado de día.
+    pub color: Color,
+    /// Color del halo cerca del terminador.
+    pub sunset_color: Color,
+    /// Exponente del borde: valores altos concentran el halo en el contorno.
+    pub power: f32,
+    /// Intensidad máxima del halo.
+    pub strength: f32,
+}
+
+/// Parámetros del movimiento de los shaders de fluidos (`Lava` y `Ocean`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flow {
+    /// Velocidad a la que se deforma el ruido con el tiempo (1 es la normal).
+    pub speed: f32,
+    /// Cuánto se desplaza el ruido al deformarlo: valores altos dan remolinos más marcados.
+    pub turbulence: f32,
+}
+
+impl Default for Flow {
+    fn default() -> Self {
+        Flow { speed: 1.0, turbulence: 1.0 }
+    }
+}
+
+/// Shaders de planeta disponibles para colorear un cuerpo de la escena.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlanetShader {
+    Earth,
+    Moon,
+    Sun,
+    Gas,
+    Rocky,
+    Star,
+    Fantasy,
+    /// Ríos de lava que fluyen sobre una corteza oscura.
+    Lava,
+    /// Océano con olas y espuma en movimiento.
+    Ocean,
+    /// Usa los colores del material (.mtl) de cada cara del modelo importado.
+    Material,
+    /// Anillo semitransparente tipo Saturno, para la malla `primitive:ring`.
+    Ring,
+    /// Usa los colores por vértice del modelo (OBJ con `v x y z r g b` o PLY con `red green blue`).
+    VertexColor,
+    /// Capa de nubes semitransparente y animada, para una esfera un poco mayor
+    /// que el planeta (ver `Scene::add_cloud_layer`).
+    Clouds,
+    /// Depuración: la normal interpolada como color (x, y, z de -1 a 1 en rojo, verde y azul).
+    DebugNormal,
+    /// Depuración: las coordenadas de textura, `u` en rojo y `v` en verde.
+    DebugUv,
+    /// Depuración: la intensidad de la luz en escala de grises.
+    DebugIntensity,
+    /// Depuración: los colores por elevación que el shader de vértices asigna a cada
+    /// vértice (`vertex::ELEVATION_RAMP`), interpolados en el triángulo y sin iluminar.
+    DebugElevation,
+}
+
+impl PlanetShader {
+    /// Todos los shaders, en el orden en que se presentan al usuario.
+    pub const ALL: [PlanetShader; 17] = [
+        PlanetShader::Earth,
+        PlanetShader::Moon,
+        PlanetShader::Sun,
+        PlanetShader::Gas,
+        PlanetShader::Rocky,
+        PlanetShader::Star,
+        PlanetShader::Fantasy,
+        PlanetShader::Lava,
+        PlanetShader::Ocean,
+        PlanetShader::Material,
+        PlanetShader::VertexColor,
+        PlanetShader::Ring,
+        PlanetShader::Clouds,
+        PlanetShader::DebugNormal,
+        PlanetShader::DebugUv,
+        PlanetShader::DebugIntensity,
+        PlanetShader::DebugElevation,
+    ];
+
+    /// Devuelve el nombre corto del shader.
+    pub fn name(self) -> &'static str {
+        match self {
+            PlanetShader::Earth => "earth",
+            PlanetShader::Moon => "moon",
+            PlanetShader::Sun => "sun",
+            PlanetShader::Gas => "gas",
+            PlanetShader::Rocky => "rocky",
+            PlanetShader::Star => "star",
+            PlanetShader::Fantasy => "fantasy",
+            PlanetShader::Lava => "lava",
+            PlanetShader::Ocean => "ocean",
+            PlanetShader::Material => "material",
+            PlanetShader::VertexColor => "vertex_color",
+            PlanetShader::Ring => "ring",
+            PlanetShader::Clouds => "clouds",
+            PlanetShader::DebugNormal => "debug_normal",
+            PlanetShader::DebugUv => "debug_uv",
+            PlanetShader::DebugIntensity => "debug_intensity",
+            PlanetShader::DebugElevation => "debug_elevation",
+        }
+    }
+
+    /// Atmósfera por defecto del shader, o `None` si el cuerpo no tiene.
+    pub fn atmosphere(self) -> Option<Atmosphere> {
+        match self {
+            PlanetShader::Earth => Some(Atmosphere {
+                color: Color::new(90, 150, 255),
+                sunset_color: Color::new(255, 140, 60),
+                power: 3.0,
+                strength: 0.9,
+            }),
+            PlanetShader::Gas => Some(Atmosphere {
+                color: Color::new(150, 210, 230),
+                sunset_color: Color::new(230, 170, 110),
+                power: 2.5,
+                strength: 0.6,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Indica si el shader produce fragmentos semitransparentes, que se dibujan
+    /// después de los opacos y se mezclan con lo que hay detrás.
+    pub fn is_transparent(self) -> bool {
+        matches!(self, PlanetShader::Ring | PlanetShader::Clouds)
+    }
+
+    /// Indica si el shader es de un cuerpo con luz propia (sol o estrella), que no
+    /// recibe sombras ni las proyecta.
+    pub fn is_emissive(self) -> bool {
+        matches!(self, PlanetShader::Sun | PlanetShader::Star)
+    }
+
+    /// Indica si los cuerpos con este shader tapan la luz de los demás (ver
+    /// `shadow::ShadowMap`). Las capas de nubes no: su sombra apenas se notaría y
+    /// obligaría a calcular un mapa para cada planeta con nubes en cada fotograma.
+    pub fn casts_shadow(self) -> bool {
+        !self.is_emissive() && self != PlanetShader::Clouds
+    }
+
+    /// Busca un shader por su nombre corto.
+    pub fn from_name(name: &str) -> Option<PlanetShader> {
+        PlanetShader::ALL.into_iter().find(|shader| shader.name() == name)
+    }
+}
+
+/// Función del shader de fragmentos que calcula el color del fragmento
+/// usando el shader de planeta indicado en los uniforms, con el halo
+/// atmosférico encima si los uniforms tienen uno y oscurecido donde otro
+/// cuerpo le tapa la luz (ver `Uniforms::shadow`).
+///
+/// # Retorna
+/// El color en espacio lineal y sin saturar; se codifica y se redondea a 8 bits al
+/// escribirlo en el framebuffer (ver `color::PixelFormat`).
+pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let surface = surface_color(fragment, uniforms);
+    let color = match uniforms.lighting.atmosphere {
+        Some(atmosphere) => surface + atmosphere_glow(fragment, uniforms, &atmosphere),
+        None => surface,
+    };
+    match &uniforms.shadow {
+        Some(shadow) => {
+            let position = fragment.vertex_position;
+            let world_position = uniforms.matrices.model * Vec4::new(position.x, position.y, position.z, 1.0);
+            let visibility = shadow.visibility(world_position.xyz());
+            color * (SHADOW_AMBIENT + (1.0 - SHADOW_AMBIENT) * visibility)
+        }
+        None => color,
+    }
+}
+
+/// Calcula el halo atmosférico de un fragmento.
+fn atmosphere_glow(fragment: &Fragment, uniforms: &Uniforms, atmosphere: &Atmosphere) -> Vec3 {
+    let normal = fragment.normal.normalize();
+    let view = fragment.view_direction;
+
+    // Término de borde: 0 mirando de frente, 1 de canto.
+    let rim = (1.0 - normal.dot(&view).max(0.0)).powf(atmosphere.power);
+
+    // Solo del lado iluminado, con un margen para que el halo envuelva el terminador.
+    let sun = normal.dot(&uniforms.lighting.light_direction);
+    let daylight = ((sun + 0.25) / 1.25).clamp(0.0, 1.0);
+    let sunset = (1.0 - sun.abs() * 3.0).clamp(0.0, 1.0);
+
+    let tint = atmosphere.color.lerp(&atmosphere.sunset_color, sunset);
+    Vec3::from(tint) * (rim * daylight * atmosphere.strength)
+}
+
+/// Color de la superficie según el shader de planeta, sin efectos encima.
+fn surface_color(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    match uniforms.material.shader {
+        PlanetShader::Earth => {
+            let variant = (uniforms.material.features & (FEATURE_CLOUDS | FEATURE_CITY_LIGHTS)) as usize;
+            EARTH_VARIANTS[variant](fragment, uniforms)
+        }
+        PlanetShader::Moon => moon_color(fragment, uniforms),
+        PlanetShader::Sun => Vec3::from(sun_gradient(fragment, uniforms)),
+        PlanetShader::Gas => Vec3::from(gas_planet_color(fragment, uniforms)),
+        PlanetShader::Rocky => Vec3::from(rocky_planet_color(fragment, uniforms)),
+        PlanetShader::Star => Vec3::from(star_planet_color(fragment, uniforms)),
+        PlanetShader::Fantasy => Vec3::from(fantasy_planet_color(fragment, uniforms)),
+        PlanetShader::Lava => lava_planet_color(fragment, uniforms),
+        PlanetShader::Ocean => ocean_planet_color(fragment, uniforms),
+        PlanetShader::Material => material_color(fragment, uniforms),
+        PlanetShader::VertexColor => lit(fragment, uniforms, fragment.color, 0.0, 1.0),
+        PlanetShader::Ring => ring_color(fragment, uniforms).0,
+        PlanetShader::Clouds => cloud_color(fragment, uniforms).0,
+        PlanetShader::DebugNormal => {
+            let normal = fragment.normal.normalize();
+            debug_color(normal.x * 0.5 + 0.5, normal.y * 0.5 + 0.5, normal.z * 0.5 + 0.5)
+        }
+        PlanetShader::DebugUv => debug_color(fragment.tex_coords.x, fragment.tex_coords.y, 0.0),
+        PlanetShader::DebugIntensity => {
+            let light = diffuse_light(fragment, uniforms);
+            debug_color(light, light, light)
+        }
+        PlanetShader::DebugElevation => Vec3::from(fragment.color),
+    }
+}
+
+/// Ilumina el albedo de un fragmento con la luz principal usando el modelo físico
+/// (ver `pbr::shade`).
+///
+/// # Parámetros
+/// - `albedo`: Color de la superficie sin iluminar.
+/// - `metallic`: Entre 0 (dieléctrico) y 1 (metal).
+/// - `roughness`: Entre 0 (espejo, con un reflejo pequeño y nítido) y 1 (mate).
+fn lit(fragment: &Fragment, uniforms: &Uniforms, albedo: Color, metallic: f32, roughness: f32) -> Vec3 {
+    shade_material(fragment, uniforms, &pbr::Material::new(albedo, metallic, roughness))
+}
+
+/// Ilumina un fragmento con un material físico y le suma el reflejo del cielo
+/// (`Uniforms::environment`), que solo se nota en las superficies lisas como el agua.
+fn shade_material(fragment: &Fragment, uniforms: &Uniforms, material: &pbr::Material) -> Vec3 {
+    let color = pbr::shade(material, fragment.normal, fragment.view_direction, uniforms.lighting.light_direction);
+    match &uniforms.environment {
+        Some(environment) => color + pbr::environment_reflection(material, fragment.normal, fragment.view_direction, environment),
+        None => color,
+    }
+}
+
+/// Luz directa que llega a un fragmento (N·L), entre 0 y 1, para los efectos que
+/// no son una superficie (nubes, luces nocturnas).
+fn diffuse_light(fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+    fragment.normal.normalize().dot(&uniforms.lighting.light_direction).max(0.0)
+}
+
+/// Convierte valores entre 0 y 1 en un color, para los shaders de depuración.
+/// Los valores fuera de rango se saturan al escribirlos: una UV mayor que 1 se ve
+/// amarilla.
+///
+/// El color pasa después por la codificación de salida; con la salida lineal
+/// (tecla `O`) cada canal es exactamente el valor por 255.
+fn debug_color(r: f32, g: f32, b: f32) -> Vec3 {
+    Vec3::new(r, g, b)
+}
+
+/// Opacidad del fragmento, entre 0 y 1. Es 1 para todos los shaders opacos.
+pub fn fragment_alpha(fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+    match uniforms.material.shader {
+        PlanetShader::Ring => ring_color(fragment, uniforms).1,
+        PlanetShader::Clouds => cloud_color(fragment, uniforms).1,
+        _ => 1.0,
+    }
+}
+
+/// Calcula el color y la opacidad de un anillo planetario.
+///
+/// Usa la coordenada de textura `v` (0 en el borde interior, 1 en el exterior)
+/// para dibujar bandas concéntricas que se desvanecen hacia los bordes, y
+/// oscurece la franja donde el planeta (una esfera de radio
+/// `uniforms.lighting.occluder_radius` en el origen del modelo) tapa la luz.
+fn ring_color(fragment: &Fragment, uniforms: &Uniforms) -> (Vec3, f32) {
+    let v = fragment.tex_coords.y;
+
+    // Bandas: varias frecuencias de ruido sobre el radio, con huecos oscuros.
+    let bands = uniforms.noise.generator.get_noise_2d(v * 40.0, 0.0) * 0.5
+        + uniforms.noise.generator.get_noise_2d(v * 160.0, 10.0) * 0.25
+        + 0.5;
+    let density = bands.clamp(0.0, 1.0);
+
+    // Desvanecimiento suave hacia ambos bordes.
+    let edge = |value: f32, width: f32| (value / width).clamp(0.0, 1.0);
+    let falloff = edge(v, 0.15) * edge(1.0 - v, 0.25);
+    let alpha = density * falloff * 0.85;
+
+    let inner_color = Color::new(150, 130, 110);
+    let outer_color = Color::new(225, 205, 170);
+    let color = inner_color.lerp(&outer_color, density);
+
+    // El anillo está en el plano XZ: se ilumina según qué tan de canto le llega la luz.
+    let light = uniforms.lighting.light_direction;
+    let brightness = 0.35 + 0.65 * light.y.abs().max(0.3);
+
+    // Sombra del planeta: el rayo hacia la luz pasa por la esfera del planeta.
+    let position = fragment.vertex_position;
+    let along = -position.dot(&light);
+    let closest = (position + light * along).magnitude();
+    let in_shadow = along > 0.0 && closest < uniforms.lighting.occluder_radius;
+    let shadow = if in_shadow { 0.2 } else { 1.0 };
+
+    (Vec3::from(color) * (brightness * shadow), alpha)
+}
+
+/// Calcula el color y la opacidad de una capa de nubes.
+///
+/// Las nubes giran lentamente alrededor del eje Y y cambian de forma con
+/// `uniforms.time`; donde no hay nubes el fragmento es transparente y deja ver
+/// la superficie del planeta.
+fn cloud_color(fragment: &Fragment, uniforms: &Uniforms) -> (Vec3, f32) {
+    let time = uniforms.time as f32;
+
+    // Rotación de la capa respecto a la superficie.
+    let (sin_a, cos_a) = (time * 0.002).sin_cos();
+    let position = fragment.vertex_position;
+    let x = position.x * cos_a - position.z * sin_a;
+    let z = position.x * sin_a + position.z * cos_a;
+
+    // Dos octavas de ruido; la tercera coordenada se desplaza para que las nubes evolucionen.
+    let evolve = time * 0.004;
+    let cover = uniforms.noise.generator.get_noise_3d(x * 4.0, position.y * 4.0 + evolve, z * 4.0) * 0.7
+        + uniforms.noise.generator.get_noise_3d(x * 12.0, position.y * 12.0 - evolve, z * 12.0) * 0.3;
+    let alpha = ((cover - 0.05) * 2.5).clamp(0.0, 0.85);
+
+    // Las nubes densas son más grises por debajo.
+    let color = Color::new(255, 255, 255).lerp(&Color::new(200, 205, 215), alpha);
+
+    (Vec3::from(color) * (0.08 + 0.92 * diffuse_light(fragment, uniforms)), alpha)
+}
+
+/// Calcula el color de un fragmento con el material de su cara, convertido al
+/// modelo físico (`pbr::Material::from_mtl`). Las caras sin material usan `Material::default()`.
+fn material_color(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let default_material = Material::default();
+    let material = fragment.material
+        .and_then(|index| uniforms.material.materials.get(index))
+        .unwrap_or(&default_material);
+
+    shade_material(fragment, uniforms, &pbr::Material::from_mtl(material))
 }
 
 /// Calcula el color para un fragmento en la superficie de la Tierra.
-fn earth_color(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+///
+/// - `CLOUDS`: agrega una capa de nubes que se desplaza con el tiempo.
+/// - `CITY_LIGHTS`: agrega luces de ciudades sobre la tierra del lado nocturno.
+///
+/// El agua es lisa y refleja el sol (`ocean_roughness`) y la tierra es mate
+/// (`land_roughness`); las nubes apagan el reflejo.
+fn earth_color<const CLOUDS: bool, const CITY_LIGHTS: bool>(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let params = &uniforms.surface.earth;
+
     // Obtener un valor de ruido para la posición del fragmento.
-    let noise_value = uniforms.noise.get_noise_2d(fragment.vertex_position.x * 10.0, fragment.vertex_position.z * 10.0);
+    let scale = params.noise_scale;
+    let noise_value = uniforms.noise.generator.get_noise_2d(fragment.vertex_position.x * scale, fragment.vertex_position.z * scale);
     
-    // Aplica el ruido a la elevación.
-    let elevation = noise_value; // Aquí puedes escalar el ruido si es necesario.
-
-    // Define umbrales para diferentes tipos de terreno.
-    let ocean_threshold = 0.0;        // Umbral para el océano.
-    let land_threshold = 0.2;         // Umbral para la tierra baja.
-    let mountain_threshold = 0.5;     // Umbral para las montañas.
-
-    // Define colores representativos.
-    let ocean_color = Color::new(0, 105, 148);
-    let shallow_water_color = Color::new(0, 191, 255);
-    let land_color = Color::new(34, 139, 34);
-    let mountain_color = Color::new(139, 69, 19);
-    let snow_color = Color::new(255, 255, 255);
-
-    // Determina el color basado en la elevación.
-    let color = if elevation <= ocean_threshold {
-        ocean_color
-    } else if elevation > ocean_threshold && elevation <= land_threshold {
-        shallow_water_color
-    } else if elevation > land_threshold && elevation <= mountain_threshold {
-        land_color
+    // Aplica el ruido a la elevación de la geometría (ver `ElevationMode`).
+    let elevation = noise_value + fragment.elevation;
+
+    // Rampa de colores por elevación: bandas de océano, agua poco profunda, tierra,
+    // montaña y nieve, o la `terrain_ramp` del archivo de parámetros.
+    let color = params.terrain_ramp.sample(elevation);
+    let is_land = elevation > params.land_threshold;
+
+    // Nubes: ruido a otra escala, desplazado lentamente con el tiempo.
+    let (color, cloud_cover) = if CLOUDS {
+        let position = fragment.vertex_position;
+        let drift = uniforms.time as f32 * 0.01;
+        let cloud_noise = uniforms.noise.generator.get_noise_3d(position.x * 4.0 + drift, position.y * 4.0, position.z * 4.0 + 100.0);
+        let cover = ((cloud_noise - 0.1) * 2.0).clamp(0.0, 0.9);
+        (color.lerp(&Color::new(255, 255, 255), cover), cover)
     } else {
-        let base_color = mountain_color;
+        (color, 0.0)
+    };
 
-        // Agregar blanco para las cumbres nevadas.
-        if elevation > mountain_threshold + 0.3 {
-            base_color + snow_color * 0.5
-        } else {
-            base_color
+    let roughness = if is_land { params.land_roughness } else { params.ocean_roughness };
+    let roughness = roughness + (1.0 - roughness) * cloud_cover;
+    let lit_color = lit(fragment, uniforms, color, 0.0, roughness);
+
+    // Luces de ciudades: solo en tierra, del lado oscuro y donde no hay nubes.
+    if CITY_LIGHTS && is_land {
+        let position = fragment.vertex_position;
+        let darkness = (1.0 - diffuse_light(fragment, uniforms) * 4.0).clamp(0.0, 1.0);
+        let cities = uniforms.noise.generator.get_noise_3d(position.x * 60.0, position.y * 60.0, position.z * 60.0);
+        if cities > 0.4 {
+            let glow = darkness * (1.0 - cloud_cover) * (cities - 0.4) / 0.6;
+            return lit_color + Vec3::from(Color::new(255, 200, 120)) * glow;
         }
-    };
+    }
 
-    // Devuelve el color multiplicado por la intensidad del fragmento.
-    color * fragment.intensity
+    lit_color
 }
 
-/// Calcula el color para un fragmento en la superficie de la Luna.
-fn moon_color(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+/// Calcula el color para un fragmento en la superficie de la Luna, con los
+/// cráteres de `uniforms.material.craters` encima del terreno de ruido.
+fn moon_color(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
     // Aumenta la escala del ruido para más detalles.
-    let noise_value = uniforms.noise.get_noise_2d(fragment.vertex_position.x * 20.0, fragment.vertex_position.z * 20.0);
-    
-    let elevation = noise_value; // Puedes combinar varios niveles de ruido si lo deseas.
-
-    // Define umbrales para diferentes tipos de terreno lunar.
-    let low_threshold = -0.1; 
-    let medium_threshold = 0.1;
-    let high_threshold = 0.3; // Agregar un nuevo umbral para cráteres.
-
-    // Define colores representativos para la luna.
-    let dark_surface_color = Color::new(169, 169, 169); // Gris oscuro.
-    let light_surface_color = Color::new(211, 211, 211); // Gris claro.
-    let crater_color = Color::new(255, 255, 255);       // Blanco para los cráteres.
-
-    // Determina el color basado en la elevación lunar.
-    let color = if elevation < low_threshold {
-        dark_surface_color
-    } else if elevation < medium_threshold {
-        light_surface_color
-    } else if elevation < high_threshold {
-        crater_color // Área de cráteres.
-    } else {
-        Color::new(240, 240, 240) // Color para áreas muy altas.
-    };
+    let scale = uniforms.surface.moon.noise_scale;
+    let noise_value = uniforms.noise.generator.get_noise_2d(fragment.vertex_position.x * scale, fragment.vertex_position.z * scale);
+
+    // Cuencos hundidos, bordes elevados y material eyectado alrededor.
+    let craters = craters::sample(&uniforms.material.craters, fragment.vertex_position);
+
+    let elevation = noise_value + fragment.elevation + craters.elevation * 4.0; // Suma la elevación de la geometría y los cráteres.
+
+    // Degradado según la elevación lunar: mares oscuros, tierras altas, cráteres y
+    // picos, o la `terrain_ramp` del archivo de parámetros.
+    let color = uniforms.surface.moon.terrain_ramp.sample(elevation);
 
-    // Devuelve el color multiplicado por la intensidad del fragmento.
-    color * fragment.intensity
+    // Los cuencos quedan en sombra, los bordes reciben más luz y el eyectado oscurece el suelo.
+    let relief = (1.0 + craters.elevation * 10.0).clamp(0.4, 1.3);
+    let ejecta = 1.0 - 0.35 * craters.ejecta;
+
+    // El polvo lunar es completamente mate.
+    lit(fragment, uniforms, color * (relief * ejecta), 0.0, 1.0)
 }
 
 fn sun_gradient(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     // Obtiene un valor de ruido para efectos adicionales (opcional).
-    let noise_value = uniforms.noise.get_noise_2d(fragment.vertex_position.x * 10.0, fragment.vertex_position.z * 10.0);
+    let scale = uniforms.surface.sun.noise_scale;
+    let noise_value = uniforms.noise.generator.get_noise_2d(fragment.vertex_position.x * scale, fragment.vertex_position.z * scale);
     
     // Define colores representativos para el sol en tonos naranjas.
-    let deep_orange_color = Color::new(255, 140, 0); // Naranja profundo.
-    let light_orange_color = Color::new(255, 165, 80); // Naranja claro.
+    let deep_orange_color = uniforms.surface.sun.deep_color; // Naranja profundo.
+    let light_orange_color = uniforms.surface.sun.light_color; // Naranja claro.
     let white_color = Color::new(255, 255, 255); // Blanco para el brillo.
     let warm_orange_color = Color::new(255, 200, 100); // Naranja cálido para el resplandor.
 
@@ -185,23 +555,46 @@ fn sun_gradient(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 }
 
 
+/// Tamaño de las celdas (en espacio del modelo) en que se divide la superficie
+/// para `surface_random`: todos los fragmentos de una celda reciben el mismo valor.
+const SURFACE_CELL: f32 = 1.0 / 256.0;
+
+/// Devuelve un número pseudoaleatorio entre 0 y 1 que depende solo de la semilla
+/// del cuerpo y de la celda de la superficie donde cae `position`.
+///
+/// A diferencia de un generador sembrado con el tiempo, el resultado es el mismo
+/// en cada fotograma y en cada ejecución, así que la superficie no parpadea.
+pub fn surface_random(position: Vec3, seed: u64) -> f32 {
+    let cell = |value: f32| (value / SURFACE_CELL).floor() as i32 as u32 as u64;
+    let mut hash = seed;
+    for value in [cell(position.x), cell(position.y), cell(position.z)] {
+        hash = splitmix64(hash ^ value);
+    }
+    // Los 24 bits altos caben exactos en un f32.
+    (hash >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Paso del generador SplitMix64, usado como función de mezcla.
+pub fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
 fn gas_planet_color(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    // Utiliza la posición del fragmento y el tiempo para generar un "seed" para el ruido.
-    let seed = uniforms.time as f32 * fragment.vertex_position.y * fragment.vertex_position.x;
-    
-    // Crea un generador de números aleatorios basado en el seed.
-    let mut rng = StdRng::seed_from_u64(seed.abs() as u64);
-    
-    // Genera un número aleatorio para la variación en el color.
-    let random_number = rng.gen_range(0..=100);
+    // Número estable (de 0 a 100) para la variación en el color, según la semilla y la posición.
+    let random_number = (surface_random(fragment.vertex_position, uniforms.noise.seed) * 101.0) as u32;
 
     // Define colores base para el planeta gaseoso.
-    let base_color = Color::new(70, 130, 180); // Azul
-    let cloud_color = Color::new(255, 255, 255); // Blanco para nubes
-    let shadow_color = Color::new(50, 50, 100); // Color oscuro para sombras
+    let params = &uniforms.surface.gas;
+    let base_color = params.base_color; // Azul
+    let cloud_color = params.cloud_color; // Blanco para nubes
+    let shadow_color = params.shadow_color; // Color oscuro para sombras
 
     // Calcular el factor de nubes usando el ruido
-    let noise_value = uniforms.noise.get_noise_2d(fragment.vertex_position.x * 5.0, fragment.vertex_position.z * 5.0);
+    let scale = params.noise_scale;
+    let noise_value = uniforms.noise.generator.get_noise_2d(fragment.vertex_position.x * scale, fragment.vertex_position.z * scale);
     let cloud_factor = (noise_value * 0.5 + 0.5).powi(2); // Escala el ruido entre 0 y 1.
 
     // Selección de color basado en el número aleatorio para agregar variación.
@@ -228,19 +621,15 @@ fn gas_planet_color(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 }
 
 fn rocky_planet_color(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    // Utiliza la posición del fragmento y el tiempo para generar un "seed" para el ruido.
-    let seed = uniforms.time as f32 * fragment.vertex_position.y * fragment.vertex_position.x;
-
-    // Crea un generador de números aleatorios basado en el seed.
-    let mut rng = StdRng::seed_from_u64(seed.abs() as u64);
-    
     // Define colores base para el planeta rocoso.
-    let base_color = Color::new(139, 69, 19); // Marrón (color de tierra)
-    let highlight_color = Color::new(255, 255, 255); // Blanco para resaltar
-    let shadow_color = Color::new(80, 50, 0); // Sombra más oscura
+    let params = &uniforms.surface.rocky;
+    let base_color = params.base_color; // Marrón (color de tierra)
+    let highlight_color = params.highlight_color; // Blanco para resaltar
+    let shadow_color = params.shadow_color; // Sombra más oscura
 
     // Calcular el factor de ruido para la textura del planeta
-    let noise_value = uniforms.noise.get_noise_2d(fragment.vertex_position.x * 3.0, fragment.vertex_position.z * 3.0);
+    let scale = params.noise_scale;
+    let noise_value = uniforms.noise.generator.get_noise_2d(fragment.vertex_position.x * scale, fragment.vertex_position.z * scale);
     let texture_factor = (noise_value * 0.5 + 0.5).powi(2); // Escala el ruido entre 0 y 1.
 
     // Crear líneas utilizando el ruido en la textura
@@ -263,22 +652,18 @@ fn rocky_planet_color(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 }
 
 fn star_planet_color(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    // Utiliza la posición del fragmento y el tiempo para generar un "seed" para el ruido.
-    let seed = uniforms.time as f32 * fragment.vertex_position.y * fragment.vertex_position.x;
-
-    // Crea un generador de números aleatorios basado en el seed.
-    let mut rng = StdRng::seed_from_u64(seed.abs() as u64);
-    
     // Define colores base para el planeta estrellado.
-    let base_color = Color::new(30, 30, 60); // Azul oscuro para el fondo del planeta
-    let star_color = Color::new(255, 255, 255); // Blanco para las estrellas
+    let params = &uniforms.surface.star;
+    let base_color = params.base_color; // Azul oscuro para el fondo del planeta
+    let star_color = params.star_color; // Blanco para las estrellas
 
     // Calcular el factor de ruido para las estrellas
-    let noise_value = uniforms.noise.get_noise_2d(fragment.vertex_position.x * 5.0, fragment.vertex_position.z * 5.0);
+    let scale = params.noise_scale;
+    let noise_value = uniforms.noise.generator.get_noise_2d(fragment.vertex_position.x * scale, fragment.vertex_position.z * scale);
     let star_factor = (noise_value * 0.5 + 0.5).powi(2); // Escala el ruido entre 0 y 1.
 
     // Determinar si hay una estrella en este fragmento
-    let random_number = rng.gen_range(0..=100);
+    let random_number = (surface_random(fragment.vertex_position, uniforms.noise.seed) * 101.0) as u32;
     let is_star = random_number < (star_factor * 100.0) as u32; // Convertir el star_factor a un rango de 0 a 100.
 
     // Calcular el color del fragmento
@@ -298,12 +683,14 @@ fn star_planet_color(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
 fn fantasy_planet_color(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     // Define colores base para el planeta fantástico.
-    let base_color = Color::new(50, 50, 200); // Azul profundo.
-    let accent_color = Color::new(180, 80, 200); // Púrpura vibrante para los detalles.
-    let cloud_color = Color::new(255, 255, 255); // Blanco para las nubes.
+    let params = &uniforms.surface.fantasy;
+    let base_color = params.base_color; // Azul profundo.
+    let accent_color = params.accent_color; // Púrpura vibrante para los detalles.
+    let cloud_color = params.cloud_color; // Blanco para las nubes.
 
     // Cálculo del ruido para la textura del planeta
-    let noise_value = uniforms.noise.get_noise_2d(fragment.vertex_position.x * 2.0, fragment.vertex_position.z * 2.0);
+    let scale = params.noise_scale;
+    let noise_value = uniforms.noise.generator.get_noise_2d(fragment.vertex_position.x * scale, fragment.vertex_position.z * scale);
     let cloud_factor = (noise_value * 0.5 + 0.5).powi(3); // Escala el ruido para suavizar la textura.
 
     // Calcula un desplazamiento basado en la posición Y del fragmento para crear capas.
@@ -325,3 +712,50 @@ fn fantasy_planet_color(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     // Devuelve el color final combinado
     planet_color + light_effect
 }
+
+/// Ruido con el dominio deformado: antes de muestrear, la posición se desplaza
+/// con otras dos muestras de ruido que se mueven con el tiempo, lo que produce
+/// formas que fluyen y se retuercen en lugar de deslizarse en bloque.
+///
+/// # Retorna
+/// Un valor de ruido aproximadamente entre -1 y 1.
+fn warped_noise(position: Vec3, scale: f32, uniforms: &Uniforms) -> f32 {
+    let time = uniforms.time as f32 * 0.01 * uniforms.material.flow.speed;
+    let p = position * scale;
+
+    let warp_x = uniforms.noise.generator.get_noise_3d(p.x + time, p.y, p.z);
+    let warp_y = uniforms.noise.generator.get_noise_3d(p.x + 5.2, p.y - time * 0.7, p.z + 1.3);
+    let warp_z = uniforms.noise.generator.get_noise_3d(p.x - 3.1, p.y + 2.8, p.z + time * 0.5);
+    let warped = p + Vec3::new(warp_x, warp_y, warp_z) * uniforms.material.flow.turbulence;
+
+    uniforms.noise.generator.get_noise_3d(warped.x, warped.y, warped.z)
+}
+
+/// Calcula el color de un planeta de lava: ríos incandescentes que fluyen entre
+/// placas de corteza. La lava emite luz propia, así que brilla también del lado oscuro.
+fn lava_planet_color(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let params = &uniforms.surface.lava;
+    let value = warped_noise(fragment.vertex_position, params.noise_scale, uniforms);
+
+    // Los ríos están donde el ruido cruza por cero; su centro es el más caliente.
+    let river = (1.0 - value.abs() / params.river_width).clamp(0.0, 1.0);
+    let lava = params.hot_color.lerp(&params.core_color, river * river);
+
+    let crust = Vec3::from(params.crust_color) * 0.2 + lit(fragment, uniforms, params.crust_color * 0.8, 0.0, 0.8);
+    crust.lerp(&Vec3::from(lava), river)
+}
+
+/// Calcula el color de un planeta océano: olas que se mueven con el tiempo, con
+/// espuma en las crestas y el reflejo del sol (`roughness`), que la espuma apaga.
+fn ocean_planet_color(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let params = &uniforms.surface.ocean;
+    let value = warped_noise(fragment.vertex_position, params.noise_scale, uniforms);
+
+    let waves = (value * 0.5 + 0.5).clamp(0.0, 1.0);
+    let water = params.deep_color.lerp(&params.shallow_color, waves);
+    let foam = ((value - params.foam_threshold) * 4.0).clamp(0.0, 1.0);
+    let color = water.lerp(&params.foam_color, foam);
+
+    let roughness = params.roughness;
+    lit(fragment, uniforms, color, 0.0, roughness + (1.0 - roughness) * foam)
+}
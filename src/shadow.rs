@@ -0,0 +1,122 @@
+use nalgebra_glm::{Vec3, Vec4, Mat4, look_at, perspective};
+use crate::framebuffer::Framebuffer;
+use crate::bounds::BoundingSphere;
+use crate::geometry::plane::Frustum;
+use crate::pipeline::{create_viewport_matrix, render_depth, Matrices, Uniforms};
+use crate::vertex::Vertex;
+
+/// Brillo que conserva la superficie en la sombra total, para que el lado
+/// eclipsado no quede negro del todo.
+pub const SHADOW_AMBIENT: f32 = 0.15;
+
+/// Mapa de sombras de un cuerpo: la profundidad de los demás cuerpos vista desde
+/// la luz, en un encuadre que cubre justo la esfera del cuerpo que recibe la sombra.
+///
+/// Para dibujar una escena, se crea un mapa por cada cuerpo que tiene algo entre
+/// él y la luz (`covers`), se dibujan en él esos cuerpos con `render` y se pasa en
+/// `Uniforms::shadow` al dibujar el cuerpo; el shader de fragmentos oscurece los
+/// puntos que el mapa ve tapados (`visibility`).
+///
+/// El cuerpo no se dibuja en su propio mapa: su lado nocturno ya lo oscurece su
+/// shader, y así no aparece el acné de sombra de una superficie que se tapa a sí misma.
+pub struct ShadowMap {
+    /// Profundidad vista desde la luz (`Framebuffer::zbuffer`); el color no se usa.
+    depth: Framebuffer,
+    /// Vista de la luz.
+    view: Mat4,
+    /// Proyección en perspectiva de la luz.
+    projection: Mat4,
+    /// `projection * view`, que se usa en cada fragmento.
+    view_projection: Mat4,
+}
+
+impl ShadowMap {
+    /// Prepara un mapa vacío desde una luz puntual hacia la esfera del cuerpo que recibe la sombra.
+    ///
+    /// # Parámetros
+    /// - `size`: Lado del mapa en píxeles.
+    /// - `light`: Posición de la luz en el mundo.
+    /// - `receiver`: Esfera envolvente del cuerpo en el mundo.
+    ///
+    /// # Retorna
+    /// `None` si la luz está dentro de la esfera, donde no hay un encuadre posible.
+    pub fn new(size: usize, light: Vec3, receiver: &BoundingSphere) -> Option<Self> {
+        let offset = receiver.center - light;
+        let distance = offset.magnitude();
+        if distance <= receiver.radius * 1.01 {
+            return None;
+        }
+        // el cono que toca la esfera, con un margen para el filtrado de los bordes
+        let half_angle = (receiver.radius / distance).asin() * 1.1;
+        let direction = offset / distance;
+        let up = if direction.y.abs() > 0.99 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+        let view = look_at(&light, &receiver.center, &up);
+        // lo que está detrás del centro del cuerpo no puede taparle la luz
+        let projection = perspective(1.0, half_angle * 2.0, distance * 0.01, distance);
+        Some(ShadowMap { depth: Framebuffer::new(size, size), view, projection, view_projection: projection * view })
+    }
+
+    /// Proyección por vista de la luz.
+    pub fn view_projection(&self) -> Mat4 {
+        self.view_projection
+    }
+
+    /// Indica si un cuerpo con esta esfera envolvente puede tapar la luz del
+    /// cuerpo del mapa; los demás no hace falta dibujarlos.
+    pub fn covers(&self, sphere: &BoundingSphere) -> bool {
+        Frustum::from_matrix(&self.view_projection).is_some_and(|frustum| frustum.intersects_sphere(sphere))
+    }
+
+    /// Dibuja la profundidad de un cuerpo que tapa la luz. Usa las matrices de la
+    /// luz en lugar de las de la cámara y las deja como estaban al terminar.
+    ///
+    /// # Parámetros
+    /// - `uniforms`: Uniforms con que se dibuja el cuerpo en la escena (la matriz de
+    ///   modelo, el shader y su ruido, que deciden los huecos de los semitransparentes).
+    /// - `vertices`: Buffer de vértices de la malla.
+    /// - `indices`: Índices de los triángulos.
+    pub fn render(&mut self, uniforms: &mut Uniforms, vertices: &[Vertex], indices: &[u32]) {
+        let size = self.depth.width as f32;
+        let camera_matrices = uniforms.matrices;
+        uniforms.matrices = Matrices {
+            model: camera_matrices.model,
+            view: self.view,
+            projection: self.projection,
+            viewport: create_viewport_matrix(size, size),
+            cull: self.view_projection,
+        };
+        render_depth(&mut self.depth, uniforms, vertices, indices);
+        uniforms.matrices = camera_matrices;
+    }
+
+    /// Calcula cuánta luz llega a un punto, promediando 3x3 muestras del mapa
+    /// alrededor de él para suavizar el borde de la sombra.
+    ///
+    /// # Parámetros
+    /// - `world_position`: Punto de la superficie en el mundo.
+    ///
+    /// # Retorna
+    /// Un valor entre 0 (en sombra total) y 1 (iluminado). Los puntos fuera del
+    /// encuadre de la luz están iluminados.
+    pub fn visibility(&self, world_position: Vec3) -> f32 {
+        let clip = self.view_projection * Vec4::new(world_position.x, world_position.y, world_position.z, 1.0);
+        if clip.w <= 0.0 {
+            return 1.0;
+        }
+        let size = self.depth.width;
+        let x = ((clip.x / clip.w + 1.0) * 0.5 * size as f32) as isize;
+        let y = ((1.0 - clip.y / clip.w) * 0.5 * size as f32) as isize;
+        let depth = clip.z / clip.w;
+
+        let mut lit = 0;
+        for sample_y in y - 1..=y + 1 {
+            for sample_x in x - 1..=x + 1 {
+                let inside = (0..size as isize).contains(&sample_x) && (0..size as isize).contains(&sample_y);
+                if !inside || self.depth.zbuffer[sample_y as usize * size + sample_x as usize] >= depth {
+                    lit += 1;
+                }
+            }
+        }
+        lit as f32 / 9.0
+    }
+}
@@ -0,0 +1,130 @@
+//! Figuras 2D dibujadas directo sobre el framebuffer, en píxeles: paneles del HUD,
+//! rectángulos de selección, el minimapa.
+//!
+//! Como las guías (`guides::draw_guides`), se mezclan con `blend_point` delante de
+//! toda la geometría y sin tocar el z-buffer, así que conviene dibujarlas al final.
+//! Respetan la tijera del framebuffer, y ningún píxel se mezcla dos veces dentro de
+//! una misma figura, para que las semitransparentes queden parejas.
+
+use nalgebra_glm::Vec2;
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, Viewport};
+
+/// Mezcla un píxel con coordenadas que pueden caer fuera del framebuffer.
+fn blend(framebuffer: &mut Framebuffer, x: i32, y: i32, alpha: f32) {
+    if x >= 0 && y >= 0 {
+        framebuffer.blend_point(x as usize, y as usize, f32::NEG_INFINITY, alpha);
+    }
+}
+
+/// Mezcla el color actual sobre la fila `y`, de `start` a `end` sin incluirlo.
+fn span(framebuffer: &mut Framebuffer, y: i32, start: i32, end: i32, alpha: f32) {
+    let end = end.min(framebuffer.width as i32);
+    for x in start.max(0)..end {
+        blend(framebuffer, x, y, alpha);
+    }
+}
+
+/// Dibuja el borde de un rectángulo, de un píxel de ancho.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer de destino.
+/// - `rect`: Rectángulo en píxeles; el borde queda dentro de él.
+/// - `color`: Color del borde.
+/// - `alpha`: Opacidad entre 0 (invisible) y 1 (opaco).
+pub fn draw_rect(framebuffer: &mut Framebuffer, rect: Viewport, color: Color, alpha: f32) {
+    if rect.width == 0 || rect.height == 0 {
+        return;
+    }
+    framebuffer.set_current_color(color.to_u32());
+    let (left, top) = (rect.x as i32, rect.y as i32);
+    let (right, bottom) = (left + rect.width as i32 - 1, top + rect.height as i32 - 1);
+    span(framebuffer, top, left, right + 1, alpha);
+    if bottom > top {
+        span(framebuffer, bottom, left, right + 1, alpha);
+    }
+    // los lados sin las esquinas, que ya dibujaron las filas de arriba y de abajo
+    for y in top + 1..bottom {
+        blend(framebuffer, left, y, alpha);
+        if right > left {
+            blend(framebuffer, right, y, alpha);
+        }
+    }
+}
+
+/// Rellena un rectángulo.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer de destino.
+/// - `rect`: Rectángulo en píxeles.
+/// - `color`: Color del relleno.
+/// - `alpha`: Opacidad entre 0 (invisible) y 1 (opaco).
+pub fn fill_rect(framebuffer: &mut Framebuffer, rect: Viewport, color: Color, alpha: f32) {
+    framebuffer.set_current_color(color.to_u32());
+    let bottom = (rect.y + rect.height).min(framebuffer.height);
+    for y in rect.y..bottom {
+        span(framebuffer, y as i32, rect.x as i32, (rect.x + rect.width) as i32, alpha);
+    }
+}
+
+/// Dibuja el contorno de un círculo, de un píxel de ancho: los píxeles cuyo centro
+/// queda a menos de medio píxel de la circunferencia.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer de destino.
+/// - `center`: Centro en píxeles (el centro del píxel (x, y) es `(x + 0.5, y + 0.5)`).
+/// - `radius`: Radio en píxeles.
+/// - `color`: Color del contorno.
+/// - `alpha`: Opacidad entre 0 (invisible) y 1 (opaco).
+pub fn draw_circle(framebuffer: &mut Framebuffer, center: Vec2, radius: f32, color: Color, alpha: f32) {
+    if radius <= 0.0 {
+        return;
+    }
+    framebuffer.set_current_color(color.to_u32());
+    let reach = radius + 0.5;
+    let (top, bottom) = ((center.y - reach).floor() as i32, (center.y + reach).ceil() as i32);
+    let (left, right) = ((center.x - reach).floor() as i32, (center.x + reach).ceil() as i32);
+    for y in top.max(0)..bottom.min(framebuffer.height as i32) {
+        for x in left.max(0)..right.min(framebuffer.width as i32) {
+            let distance = (Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - center).norm();
+            if (distance - radius).abs() < 0.5 {
+                blend(framebuffer, x, y, alpha);
+            }
+        }
+    }
+}
+
+/// Rellena un polígono con la regla par-impar, así también sirven los cóncavos y
+/// los que se cruzan a sí mismos. Se pinta cada píxel cuyo centro queda dentro.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer de destino.
+/// - `points`: Vértices en píxeles, en orden; el último se une con el primero.
+/// - `color`: Color del relleno.
+/// - `alpha`: Opacidad entre 0 (invisible) y 1 (opaco).
+pub fn fill_polygon(framebuffer: &mut Framebuffer, points: &[Vec2], color: Color, alpha: f32) {
+    if points.len() < 3 {
+        return;
+    }
+    framebuffer.set_current_color(color.to_u32());
+    let top = points.iter().map(|point| point.y).fold(f32::INFINITY, f32::min);
+    let bottom = points.iter().map(|point| point.y).fold(f32::NEG_INFINITY, f32::max);
+    let rows = (top - 0.5).ceil().max(0.0) as i32..((bottom - 0.5).ceil() as i32).min(framebuffer.height as i32);
+
+    let mut crossings = Vec::new();
+    for y in rows {
+        // dónde cruzan la fila los lados, sobre la línea que pasa por el centro de los píxeles
+        let center = y as f32 + 0.5;
+        crossings.clear();
+        for (index, a) in points.iter().enumerate() {
+            let b = points[(index + 1) % points.len()];
+            if (a.y <= center) != (b.y <= center) {
+                crossings.push(a.x + (center - a.y) / (b.y - a.y) * (b.x - a.x));
+            }
+        }
+        crossings.sort_by(f32::total_cmp);
+        for pair in crossings.chunks_exact(2) {
+            span(framebuffer, y, (pair[0] - 0.5).ceil() as i32, (pair[1] - 0.5).ceil() as i32, alpha);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use nalgebra_glm::{Vec2, Vec3, Vec4, Mat4};
+use crate::framebuffer::Framebuffer;
+use crate::vertex::Vertex;
+use crate::triangle::TriangleFragments;
+use crate::shaders::vertex_shader;
+use crate::pipeline::{outside_frustum, shade_fragment, shade_vertex, Uniforms};
+
+/// Malla diminuta con un número de vértices (`V`) e índices (`I`) fijo en compilación,
+/// para billboards e impostores de uno o dos triángulos.
+///
+/// Se dibuja con `render_small`, que evita el trabajo por objeto del pipeline
+/// general: no reserva memoria, no calcula esferas envolventes, no ejecuta hooks
+/// ni pasa por la lista de dibujo (que ordena los objetos).
+#[derive(Debug, Clone)]
+pub struct SmallMesh<const V: usize, const I: usize> {
+    /// Vértices de la malla.
+    pub vertices: [Vertex; V],
+    /// Índices de los triángulos, tres por triángulo.
+    pub indices: [u32; I],
+}
+
+/// Un cuadrado de dos triángulos.
+pub type Quad = SmallMesh<4, 6>;
+
+impl Quad {
+    /// Crea un cuadrado centrado en el origen sobre el plano XY, mirando hacia +Z.
+    ///
+    /// # Parámetros
+    /// - `size`: Largo del lado.
+    pub fn quad(size: f32) -> Self {
+        let half = size / 2.0;
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let corner = |x: f32, y: f32, u: f32, v: f32| {
+            Vertex::new(Vec3::new(x * half, y * half, 0.0), normal, Vec2::new(u, v), 0.0)
+        };
+        SmallMesh {
+            vertices: [
+                corner(-1.0, -1.0, 0.0, 1.0),
+                corner(1.0, -1.0, 1.0, 1.0),
+                corner(1.0, 1.0, 1.0, 0.0),
+                corner(-1.0, 1.0, 0.0, 0.0),
+            ],
+            indices: [0, 1, 2, 0, 2, 3],
+        }
+    }
+}
+
+/// Matriz de modelo que pone un `Quad` de lado 1 de frente a la cámara: sus ejes X
+/// e Y son los de la pantalla y su normal apunta hacia la cámara.
+///
+/// # Parámetros
+/// - `center`: Centro del billboard en el mundo.
+/// - `size`: Largo del lado en el mundo.
+/// - `view`: Matriz de vista de la cámara.
+pub fn billboard(center: Vec3, size: f32, view: &Mat4) -> Mat4 {
+    // las filas de la rotación de la vista son los ejes de la cámara en el mundo
+    let axis = |row: usize| view.fixed_view::<1, 3>(row, 0).transpose() * size;
+    let (right, up, back) = (axis(0), axis(1), axis(2));
+    Mat4::new(
+        right.x, up.x, back.x, center.x,
+        right.y, up.y, back.y, center.y,
+        right.z, up.z, back.z, center.z,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+/// Dibuja una malla diminuta de uno o dos triángulos.
+///
+/// Los vértices transformados se guardan en un arreglo en la pila y cada
+/// fragmento se sombrea en cuanto sale del rasterizador, sin juntarlos en una
+/// lista; los que tapa el z-buffer se descartan antes del shader.
+///
+/// # Parámetros
+/// - `framebuffer`: El framebuffer de destino.
+/// - `uniforms`: Matrices, ruido y shader del objeto.
+/// - `mesh`: La malla a dibujar.
+pub fn render_small<const V: usize, const I: usize>(framebuffer: &mut Framebuffer, uniforms: &Uniforms, mesh: &SmallMesh<V, I>) {
+    const { assert!(I <= 6 && I.is_multiple_of(3), "una malla diminuta tiene uno o dos triángulos") };
+    let per_vertex = uniforms.material.shading.per_vertex();
+    let transformed: [Vertex; V] = std::array::from_fn(|i| {
+        let mut vertex = vertex_shader(&mesh.vertices[i], uniforms);
+        if per_vertex {
+            vertex.color = shade_vertex(&vertex, uniforms);
+        }
+        vertex
+    });
+
+    let cull_matrix = uniforms.matrices.cull * uniforms.matrices.model;
+    let clip: [Vec4; V] = std::array::from_fn(|i| {
+        let position = mesh.vertices[i].position;
+        cull_matrix * Vec4::new(position.x, position.y, position.z, 1.0)
+    });
+
+    let transparent = uniforms.material.shader.is_transparent();
+    for face in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [face[0] as usize, face[1] as usize, face[2] as usize];
+        if a.max(b).max(c) >= V || outside_frustum(&[clip[a], clip[b], clip[c]]) {
+            continue;
+        }
+        // la prueba de profundidad va aparte del rasterizador, que no puede
+        // mirar el framebuffer mientras se escribe en él
+        let fragments = TriangleFragments::new(&transformed[a], &transformed[b], &transformed[c], uniforms.material.shading, |_, _, _| true);
+        for mut fragment in fragments {
+            let (x, y) = (fragment.position.x as i32, fragment.position.y as i32);
+            if !framebuffer.passes_depth_test(x, y, fragment.depth) {
+                continue;
+            }
+            let Some((color, alpha)) = shade_fragment(&mut fragment, uniforms) else { continue };
+            let (x, y) = (x as usize, y as usize);
+            let color = framebuffer.pixel_format().pack(color, x, y);
+            framebuffer.set_current_color(color);
+            if transparent {
+                framebuffer.blend_point(x, y, fragment.depth, alpha);
+            } else {
+                framebuffer.point(x, y, fragment.depth);
+            }
+        }
+    }
+}
+
+/// Dibuja muchas copias de una malla diminuta que solo difieren en su matriz de
+/// modelo, reutilizando los mismos uniforms (ruido, shader, materiales) para todas.
+/// Como en `pipeline::render_instanced`, cada copia recibe la luz desde su centro.
+///
+/// # Parámetros
+/// - `framebuffer`: El framebuffer de destino.
+/// - `uniforms`: Uniforms compartidos; al terminar quedan como estaban.
+/// - `mesh`: La malla a dibujar.
+/// - `model_matrices`: Matriz de modelo de cada copia.
+pub fn render_small_instances<const V: usize, const I: usize>(
+    framebuffer: &mut Framebuffer,
+    uniforms: &mut Uniforms,
+    mesh: &SmallMesh<V, I>,
+    model_matrices: &[Mat4],
+) {
+    let (placement, light_direction) = (uniforms.matrices.model, uniforms.lighting.light_direction);
+    for &model_matrix in model_matrices {
+        uniforms.matrices.model = model_matrix;
+        if let Some(light) = uniforms.lighting.light_position {
+            let center = model_matrix.column(3).xyz();
+            uniforms.lighting.light_direction = (light - center).try_normalize(1e-4).unwrap_or(light_direction);
+        }
+        render_small(framebuffer, uniforms, mesh);
+    }
+    uniforms.matrices.model = placement;
+    uniforms.lighting.light_direction = light_direction;
+}
@@ -0,0 +1,211 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::color::{Color, Palette};
+use crate::framebuffer::Framebuffer;
+
+/// Catálogo de estrellas que el visor usa si existe; si no, genera uno.
+pub const STAR_CATALOG_PATH: &str = "assets/stars.txt";
+
+/// Magnitud que se dibuja con el brillo máximo; cada 5 magnitudes más el brillo
+/// se divide por 100, como en el cielo.
+const REFERENCE_MAGNITUDE: f32 = 0.0;
+
+/// Una estrella de fondo, infinitamente lejos.
+#[derive(Debug, Clone, Copy)]
+pub struct Star {
+    /// Dirección normalizada hacia la estrella en el mundo.
+    pub direction: Vec3,
+    /// Magnitud aparente: cuanto menor, más brillante (la más brillante del cielo ronda -1.5).
+    pub magnitude: f32,
+    /// Color de la estrella.
+    pub color: Color,
+}
+
+impl Star {
+    /// Crea una estrella a partir de sus coordenadas celestes.
+    ///
+    /// # Parámetros
+    /// - `right_ascension`: Ascensión recta en grados; crece hacia `+X` desde `-Z`.
+    /// - `declination`: Declinación en grados; 90 es el polo norte (`+Y`).
+    /// - `magnitude`: Magnitud aparente.
+    /// - `color_index`: Índice de color B-V: negativo para las azules, cerca de 0.65 para las
+    ///   como el Sol y más de 1.4 para las rojas.
+    pub fn from_celestial(right_ascension: f32, declination: f32, magnitude: f32, color_index: f32) -> Self {
+        let (sin_ra, cos_ra) = right_ascension.to_radians().sin_cos();
+        let (sin_dec, cos_dec) = declination.to_radians().sin_cos();
+        Star {
+            direction: Vec3::new(cos_dec * sin_ra, sin_dec, -cos_dec * cos_ra),
+            magnitude,
+            color: color_from_index(color_index),
+        }
+    }
+
+    /// Brillo lineal de la estrella, entre 0 y 1.
+    pub fn brightness(&self) -> f32 {
+        10.0_f32.powf(-0.4 * (self.magnitude - REFERENCE_MAGNITUDE)).min(1.0)
+    }
+}
+
+/// Color aproximado de una estrella según su índice B-V.
+fn color_from_index(color_index: f32) -> Color {
+    Palette::new([
+        (-0.3, Color::new(155, 176, 255)),
+        (0.0, Color::new(202, 215, 255)),
+        (0.3, Color::new(248, 247, 255)),
+        (0.65, Color::new(255, 244, 234)),
+        (1.0, Color::new(255, 210, 161)),
+        (1.5, Color::new(255, 170, 110)),
+    ]).sample(color_index)
+}
+
+/// Error al leer un catálogo de estrellas.
+#[derive(Debug)]
+pub enum StarCatalogError {
+    /// No se pudo leer el archivo.
+    Io(io::Error),
+    /// Una línea del catálogo no es válida.
+    Parse {
+        /// Número de línea (empezando en 1).
+        line: usize,
+        /// Descripción del problema.
+        message: String,
+    },
+}
+
+impl fmt::Display for StarCatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StarCatalogError::Io(error) => write!(f, "error de E/S: {}", error),
+            StarCatalogError::Parse { line, message } => write!(f, "línea {}: {}", line, message),
+        }
+    }
+}
+
+impl From<io::Error> for StarCatalogError {
+    fn from(error: io::Error) -> Self {
+        StarCatalogError::Io(error)
+    }
+}
+
+/// Estrellas del fondo, que se dibujan como puntos detrás de toda la geometría.
+#[derive(Debug, Clone, Default)]
+pub struct StarCatalog {
+    stars: Vec<Star>,
+}
+
+impl StarCatalog {
+    /// Crea un catálogo con las estrellas dadas.
+    pub fn new(stars: Vec<Star>) -> Self {
+        StarCatalog { stars }
+    }
+
+    /// Genera un cielo al azar con estrellas repartidas de forma pareja sobre la esfera.
+    ///
+    /// Como en el cielo real, por cada magnitud más débil hay unas cuatro veces más
+    /// estrellas, así que la mayoría apenas se ve y unas pocas brillan mucho.
+    ///
+    /// # Parámetros
+    /// - `count`: Cantidad de estrellas.
+    /// - `seed`: Semilla; la misma semilla da el mismo cielo.
+    pub fn generate(count: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let stars = (0..count)
+            .map(|_| {
+                // Coseno de la latitud uniforme: misma densidad en toda la esfera.
+                let declination = rng.gen_range(-1.0_f32..1.0).asin().to_degrees();
+                let right_ascension = rng.gen_range(0.0..360.0);
+                // Magnitudes entre -1 y 6.5, con la cantidad creciendo como 10^(0.6 m).
+                let magnitude = 6.5 + rng.gen_range(1e-4_f32..1.0).log10() / 0.6;
+                let color_index = rng.gen_range(-0.3..1.6);
+                Star::from_celestial(right_ascension, declination, magnitude.max(-1.0), color_index)
+            })
+            .collect();
+        StarCatalog { stars }
+    }
+
+    /// Carga un catálogo en texto (ver `FromStr`).
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, StarCatalogError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Estrellas del catálogo.
+    pub fn stars(&self) -> &[Star] {
+        &self.stars
+    }
+
+    /// Dibuja las estrellas en los píxeles de fondo, los que no tienen geometría en
+    /// el z-buffer. Cada estrella se reparte entre los cuatro píxeles que rodean su
+    /// posición, así se mueve con suavidad al girar la cámara.
+    ///
+    /// # Parámetros
+    /// - `framebuffer`: Framebuffer de destino; se usa su viewport.
+    /// - `view`: Matriz de vista de la cámara; solo se usa su rotación.
+    /// - `projection`: Proyección de la cámara; con una ortográfica no se dibuja nada.
+    pub fn draw(&self, framebuffer: &mut Framebuffer, view: &Mat4, projection: &Mat4) {
+        let screen_matrix = framebuffer.viewport().matrix() * projection;
+        for star in &self.stars {
+            let direction = star.direction;
+            // Con w = 0 la traslación de la cámara no mueve las estrellas.
+            let clip = screen_matrix * view * Vec4::new(direction.x, direction.y, direction.z, 0.0);
+            if clip.w <= 1e-6 {
+                continue;
+            }
+            let (x, y) = (clip.x / clip.w - 0.5, clip.y / clip.w - 0.5);
+            let (column, row) = (x.floor(), y.floor());
+            let (tx, ty) = (x - column, y - row);
+            framebuffer.set_current_color(star.color.to_u32());
+            let brightness = star.brightness();
+            for (dx, dy, weight) in [(0, 0, (1.0 - tx) * (1.0 - ty)), (1, 0, tx * (1.0 - ty)), (0, 1, (1.0 - tx) * ty), (1, 1, tx * ty)] {
+                let (px, py) = (column as i32 + dx, row as i32 + dy);
+                if px >= 0 && py >= 0 {
+                    // `f32::MAX` solo pasa la prueba donde el z-buffer sigue vacío.
+                    framebuffer.add_point(px as usize, py as usize, f32::MAX, brightness * weight);
+                }
+            }
+        }
+    }
+}
+
+/// Interpreta un catálogo de texto con una estrella por línea:
+///
+/// ```text
+/// # ascensión recta (grados), declinación (grados), magnitud, índice B-V (opcional)
+/// 101.287 -16.716 -1.46 0.00
+/// 88.793 7.407 0.42 1.85
+/// ```
+///
+/// Las líneas vacías y las que empiezan con `#` se ignoran; sin índice B-V la
+/// estrella es blanca como el Sol.
+impl FromStr for StarCatalog {
+    type Err = StarCatalogError;
+
+    fn from_str(text: &str) -> Result<StarCatalog, StarCatalogError> {
+        let mut stars = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| StarCatalogError::Parse { line: number + 1, message };
+            let values = line.split_whitespace()
+                .map(|value| value.parse::<f32>().map_err(|_| error(format!("número inválido '{}'", value))))
+                .collect::<Result<Vec<_>, _>>()?;
+            let (right_ascension, declination, magnitude, color_index) = match values[..] {
+                [right_ascension, declination, magnitude] => (right_ascension, declination, magnitude, 0.65),
+                [right_ascension, declination, magnitude, color_index] => (right_ascension, declination, magnitude, color_index),
+                _ => return Err(error(format!("se esperaban 3 o 4 valores y hay {}", values.len()))),
+            };
+            if !(-90.0..=90.0).contains(&declination) {
+                return Err(error(format!("declinación fuera de rango: {}", declination)));
+            }
+            stars.push(Star::from_celestial(right_ascension, declination, magnitude, color_index));
+        }
+        Ok(StarCatalog { stars })
+    }
+}
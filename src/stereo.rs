@@ -0,0 +1,73 @@
+use crate::camera::{Camera, Projection};
+use crate::framebuffer::Framebuffer;
+
+/// Estéreo anaglifo: la escena se dibuja dos veces, desde dos ojos separados a lo
+/// largo del eje derecho de la cámara, y las dos imágenes se combinan en una sola
+/// que se ve en relieve con lentes rojo (izquierdo) y cian (derecho).
+///
+/// Los ojos miran en paralelo y cada proyección se corre hacia un costado
+/// (`Camera::lens_shift`) para que las dos imágenes coincidan a la distancia de
+/// convergencia; así no aparece la diferencia vertical que deja girar los ojos
+/// hacia el punto central.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stereo {
+    /// Distancia entre los dos ojos, en unidades del mundo. Cuanto mayor, más relieve;
+    /// una treintava parte de la convergencia suele ser cómoda.
+    pub eye_separation: f32,
+    /// Distancia a la que las dos imágenes coinciden: lo que está más cerca sale de la
+    /// pantalla y lo que está más lejos queda detrás. `None` usa la distancia al punto
+    /// central de la cámara.
+    pub convergence: Option<f32>,
+}
+
+impl Default for Stereo {
+    fn default() -> Self {
+        Stereo { eye_separation: 0.15, convergence: None }
+    }
+}
+
+impl Stereo {
+    /// Crea las cámaras del ojo izquierdo y del derecho a partir de la cámara de la vista.
+    ///
+    /// # Retorna
+    /// `[izquierdo, derecho]`. Con una cámara ortográfica, o sin dirección de vista,
+    /// las dos son copias de `camera`: sin perspectiva no hay relieve que mostrar.
+    pub fn eye_cameras(&self, camera: &Camera) -> [Camera; 2] {
+        let forward = camera.center - camera.eye;
+        let right = forward.cross(&camera.up).try_normalize(1e-6);
+        let (Some(right), Projection::Perspective) = (right, camera.projection) else {
+            return [camera.clone(), camera.clone()];
+        };
+        let convergence = self.convergence.unwrap_or_else(|| forward.magnitude()).max(camera.near);
+        // Ancho de media imagen, en unidades del mundo, a la distancia de convergencia.
+        let half_width = convergence * (camera.fov / 2.0).tan() * camera.aspect;
+
+        [-0.5, 0.5].map(|side| {
+            let offset = side * self.eye_separation;
+            let mut eye = camera.clone();
+            eye.eye += right * offset;
+            eye.center += right * offset;
+            // Lo que está sobre el eje de la vista original a la distancia de convergencia
+            // vuelve al medio de la imagen.
+            eye.lens_shift = camera.lens_shift + offset / half_width;
+            eye
+        })
+    }
+}
+
+/// Combina las dos imágenes en un anaglifo rojo-cian dentro del viewport del
+/// framebuffer: el canal rojo sale del ojo izquierdo y el verde y el azul del derecho.
+///
+/// # Parámetros
+/// - `framebuffer`: Imagen del ojo derecho, que recibe el resultado.
+/// - `left`: Imagen del ojo izquierdo, del mismo tamaño que el framebuffer.
+pub fn composite_anaglyph(framebuffer: &mut Framebuffer, left: &[u32]) {
+    let area = framebuffer.viewport();
+    let width = framebuffer.width;
+    for y in area.y..(area.y + area.height).min(framebuffer.height) {
+        for x in area.x..(area.x + area.width).min(width) {
+            let index = y * width + x;
+            framebuffer.buffer[index] = (left[index] & 0xFF0000) | (framebuffer.buffer[index] & 0x00FFFF);
+        }
+    }
+}
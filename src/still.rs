@@ -0,0 +1,94 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use nalgebra_glm::Mat4;
+use crate::framebuffer::Framebuffer;
+use crate::color::Color;
+
+/// Un rectángulo de la imagen final, en píxeles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    /// Columna de la esquina superior izquierda.
+    pub x: usize,
+    /// Fila de la esquina superior izquierda.
+    pub y: usize,
+    /// Ancho del rectángulo.
+    pub width: usize,
+    /// Alto del rectángulo.
+    pub height: usize,
+}
+
+impl Tile {
+    /// Recorta una proyección a este rectángulo.
+    ///
+    /// La matriz resultante lleva la parte de la imagen que cubre el rectángulo a
+    /// todo el rango de coordenadas normalizadas, así que con un viewport del
+    /// tamaño del rectángulo cada píxel cae donde caería en la imagen completa.
+    ///
+    /// # Parámetros
+    /// - `projection`: Proyección de la imagen completa.
+    /// - `image_width`, `image_height`: Tamaño de la imagen completa.
+    pub fn projection(&self, projection: &Mat4, image_width: usize, image_height: usize) -> Mat4 {
+        let (image_width, image_height) = (image_width as f32, image_height as f32);
+        let scale_x = image_width / self.width as f32;
+        let scale_y = image_height / self.height as f32;
+        // centro del rectángulo en coordenadas normalizadas; la y de pantalla crece hacia abajo
+        let center_x = (self.x as f32 + self.width as f32 / 2.0) / image_width * 2.0 - 1.0;
+        let center_y = 1.0 - (self.y as f32 + self.height as f32 / 2.0) / image_height * 2.0;
+        let crop = Mat4::new(
+            scale_x, 0.0, 0.0, -center_x * scale_x,
+            0.0, scale_y, 0.0, -center_y * scale_y,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        );
+        crop * projection
+    }
+}
+
+/// Dibuja una imagen de cualquier tamaño por partes y la guarda como PPM binaria (P6).
+///
+/// Cada rectángulo se dibuja en su propio framebuffer y se escribe en el archivo
+/// en cuanto se completa su fila de rectángulos, así que en memoria solo hay una
+/// fila a la vez: una imagen de 8192x8192 con rectángulos de 512 ocupa unos 16 MB
+/// en lugar de más de 500. Los efectos que miran a los píxeles vecinos (bloom,
+/// profundidad de campo) no deben aplicarse dentro de `draw`, porque dejarían
+/// costuras entre los rectángulos.
+///
+/// # Parámetros
+/// - `path`: Archivo de destino.
+/// - `width`, `height`: Tamaño de la imagen completa.
+/// - `tile_size`: Lado de los rectángulos.
+/// - `draw`: Dibuja un rectángulo en el framebuffer recibido, nuevo y del tamaño
+///   del rectángulo; normalmente con la proyección de `Tile::projection`.
+pub fn render_tiled<P, F>(path: P, width: usize, height: usize, tile_size: usize, mut draw: F) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    F: FnMut(&mut Framebuffer, &Tile),
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(writer, "P6\n{} {}\n255\n", width, height)?;
+
+    let tile_size = tile_size.max(1);
+    let mut row = Vec::new();
+    for row_y in (0..height).step_by(tile_size) {
+        let row_height = tile_size.min(height - row_y);
+        row.clear();
+        row.resize(width * row_height, 0u32);
+
+        for x in (0..width).step_by(tile_size) {
+            let tile = Tile { x, y: row_y, width: tile_size.min(width - x), height: row_height };
+            let mut framebuffer = Framebuffer::new(tile.width, tile.height);
+            draw(&mut framebuffer, &tile);
+            for line in 0..tile.height {
+                let source = &framebuffer.buffer[line * tile.width..(line + 1) * tile.width];
+                let start = line * width + tile.x;
+                row[start..start + tile.width].copy_from_slice(source);
+            }
+        }
+
+        for &pixel in &row {
+            writer.write_all(&<[u8; 3]>::from(Color::from_u32(pixel)))?;
+        }
+    }
+    writer.flush()
+}
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use nalgebra_glm::Vec3;
+use crate::obj::{spherical_texcoords, Model};
+use crate::vertex::Vertex;
+
+/// Error al leer un archivo STL.
+#[derive(Debug)]
+pub enum StlError {
+    /// No se pudo leer el archivo.
+    Io(io::Error),
+    /// El contenido no es un STL válido.
+    Format(String),
+}
+
+impl fmt::Display for StlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StlError::Io(error) => write!(f, "error de E/S: {}", error),
+            StlError::Format(message) => write!(f, "STL inválido: {}", message),
+        }
+    }
+}
+
+impl From<io::Error> for StlError {
+    fn from(error: io::Error) -> Self {
+        StlError::Io(error)
+    }
+}
+
+/// Crea un error de formato.
+fn format_error(message: impl Into<String>) -> StlError {
+    StlError::Format(message.into())
+}
+
+/// Un triángulo tal como viene en el archivo: su normal y sus tres esquinas.
+struct Facet {
+    normal: Vec3,
+    corners: [Vec3; 3],
+}
+
+/// Carga una malla STL, binaria o de texto.
+///
+/// STL guarda cada triángulo por separado; los vértices con la misma posición
+/// y la misma normal se sueldan en uno solo para obtener una malla indexada.
+/// Cada vértice conserva la normal de su cara (si una cara no trae normal se
+/// calcula con su orientación), así las aristas vivas de una pieza mecánica no
+/// se suavizan, y las coordenadas de textura se generan con una proyección esférica.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Model, StlError> {
+    from_bytes(&fs::read(path)?)
+}
+
+/// Interpreta el contenido de un archivo STL.
+pub fn from_bytes(bytes: &[u8]) -> Result<Model, StlError> {
+    let facets = if is_binary(bytes) {
+        parse_binary(bytes)?
+    } else {
+        parse_ascii(bytes)?
+    };
+
+    let finite = |v: &Vec3| v.iter().all(|c| c.is_finite());
+    if facets.iter().any(|facet| !facet.corners.iter().all(finite)) {
+        return Err(format_error("hay coordenadas que no son finitas"));
+    }
+
+    Ok(weld(&facets))
+}
+
+/// Un STL binario tiene 80 bytes de cabecera, la cantidad de triángulos y 50
+/// bytes por triángulo. Muchos binarios también empiezan con `solid`, así que
+/// se decide por el tamaño y no por el texto.
+fn is_binary(bytes: &[u8]) -> bool {
+    match bytes.get(80..84) {
+        Some(count) => {
+            let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as u64;
+            bytes.len() as u64 == 84 + count * 50 || !bytes.trim_ascii_start().starts_with(b"solid")
+        }
+        None => false,
+    }
+}
+
+/// Lee un STL binario.
+fn parse_binary(bytes: &[u8]) -> Result<Vec<Facet>, StlError> {
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    let data = &bytes[84..];
+    if data.len() / 50 < count {
+        return Err(format_error("faltan triángulos"));
+    }
+
+    let vector = |chunk: &[u8]| {
+        let float = |offset: usize| f32::from_le_bytes([chunk[offset], chunk[offset + 1], chunk[offset + 2], chunk[offset + 3]]);
+        Vec3::new(float(0), float(4), float(8))
+    };
+
+    // Cada triángulo: normal, tres esquinas (12 bytes cada una) y 2 bytes de atributos.
+    Ok(data.chunks_exact(50)
+        .take(count)
+        .map(|chunk| Facet {
+            normal: vector(&chunk[0..12]),
+            corners: [vector(&chunk[12..24]), vector(&chunk[24..36]), vector(&chunk[36..48])],
+        })
+        .collect())
+}
+
+/// Lee un STL de texto (`solid` / `facet normal` / `vertex` / `endfacet`).
+fn parse_ascii(bytes: &[u8]) -> Result<Vec<Facet>, StlError> {
+    let text = std::str::from_utf8(bytes).map_err(|_| format_error("el archivo de texto no es UTF-8"))?;
+    let mut words = text.split_ascii_whitespace();
+    if words.next() != Some("solid") {
+        return Err(format_error("no empieza con 'solid'"));
+    }
+
+    let vector = |words: &mut std::str::SplitAsciiWhitespace| -> Result<Vec3, StlError> {
+        let mut component = || words.next()
+            .and_then(|word| word.parse::<f32>().ok())
+            .ok_or_else(|| format_error("se esperaba un número"));
+        Ok(Vec3::new(component()?, component()?, component()?))
+    };
+
+    let mut facets = Vec::new();
+    let mut normal = Vec3::zeros();
+    let mut corners = Vec::with_capacity(3);
+    while let Some(word) = words.next() {
+        match word {
+            "facet" => {
+                if words.next() != Some("normal") {
+                    return Err(format_error("se esperaba 'facet normal'"));
+                }
+                normal = vector(&mut words)?;
+                corners.clear();
+            }
+            "vertex" => corners.push(vector(&mut words)?),
+            "endfacet" => {
+                let [a, b, c] = corners[..] else {
+                    return Err(format_error("una cara no tiene tres vértices"));
+                };
+                facets.push(Facet { normal, corners: [a, b, c] });
+            }
+            // `outer loop`, `endloop`, `endsolid` y el nombre del sólido no aportan datos.
+            _ => {}
+        }
+    }
+
+    Ok(facets)
+}
+
+/// Suelda los vértices con la misma posición y normal y arma la malla indexada.
+fn weld(facets: &[Facet]) -> Model {
+    let mut welded: HashMap<[u32; 6], u32> = HashMap::new();
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut indices = Vec::with_capacity(facets.len() * 3);
+
+    for facet in facets {
+        let [a, b, c] = facet.corners;
+        // La normal del archivo se ignora si es nula o inválida; entonces se usa la orientación de la cara.
+        let normal = facet.normal.try_normalize(f32::EPSILON)
+            .filter(|normal| normal.iter().all(|c| c.is_finite()))
+            .or_else(|| (b - a).cross(&(c - a)).try_normalize(f32::EPSILON))
+            .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+
+        for corner in facet.corners {
+            // 0.0 y -0.0 son el mismo valor.
+            let key = [corner.x, corner.y, corner.z, normal.x, normal.y, normal.z].map(|c| (c + 0.0).to_bits());
+            let index = *welded.entry(key).or_insert_with(|| {
+                positions.push(corner);
+                normals.push(normal);
+                positions.len() as u32 - 1
+            });
+            indices.push(index);
+        }
+    }
+
+    let texcoords = spherical_texcoords(&positions);
+    let vertices = positions.iter().zip(normals).zip(texcoords)
+        .map(|((&position, normal), tex_coords)| Vertex::new(position, normal, tex_coords, 0.0))
+        .collect();
+
+    Model::new(vertices, indices, Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// STL de texto con una cara por triángulo: su normal y sus tres esquinas.
+    fn ascii_stl(facets: &[([f32; 3], [[f32; 3]; 3])]) -> String {
+        let mut text = String::from("solid prueba\n");
+        for (normal, corners) in facets {
+            text += &format!("facet normal {} {} {}\nouter loop\n", normal[0], normal[1], normal[2]);
+            for [x, y, z] in corners {
+                text += &format!("vertex {} {} {}\n", x, y, z);
+            }
+            text += "endloop\nendfacet\n";
+        }
+        text + "endsolid prueba\n"
+    }
+
+    /// Cubo de lado 2 centrado en el origen, dos triángulos por cara.
+    fn cube() -> Vec<([f32; 3], [[f32; 3]; 3])> {
+        let mut facets = Vec::new();
+        for axis in 0..3 {
+            for side in [-1.0, 1.0] {
+                let mut normal = [0.0; 3];
+                normal[axis] = side;
+                // las esquinas de la cara, en el orden que la hace mirar hacia afuera
+                let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                let corner = |a: f32, b: f32| {
+                    let mut point = [0.0; 3];
+                    point[axis] = side;
+                    point[u] = a * side;
+                    point[v] = b;
+                    point
+                };
+                let [p0, p1, p2, p3] = [corner(-1.0, -1.0), corner(1.0, -1.0), corner(1.0, 1.0), corner(-1.0, 1.0)];
+                facets.push((normal, [p0, p1, p2]));
+                facets.push((normal, [p0, p2, p3]));
+            }
+        }
+        facets
+    }
+
+    #[test]
+    fn cube_keeps_hard_edge_normals() {
+        let model = from_bytes(ascii_stl(&cube()).as_bytes()).unwrap();
+        // cada esquina se repite una vez por cara que la toca, con la normal de esa cara
+        assert_eq!(model.vertices.len(), 24);
+        assert_eq!(model.indices.len(), 36);
+        for vertex in &model.vertices {
+            let normal = vertex.normal;
+            assert_eq!(normal.iter().filter(|c| c.abs() == 1.0).count(), 1, "{:?}", normal);
+            assert!(normal.dot(&vertex.position) > 0.0, "la normal {:?} mira hacia adentro", normal);
+        }
+    }
+
+    #[test]
+    fn welds_positive_and_negative_zero() {
+        let normal = [0.0, 0.0, 1.0];
+        let text = ascii_stl(&[
+            (normal, [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]]),
+            (normal, [[-0.0, -0.0, 0.0], [1.0, 1.0, -0.0], [0.0, 1.0, 0.0]]),
+        ]);
+        assert!(text.contains("vertex -0 -0 0"));
+        let model = from_bytes(text.as_bytes()).unwrap();
+        assert_eq!(model.vertices.len(), 4);
+        assert_eq!(model.indices, vec![0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn zero_facet_normal_uses_the_winding() {
+        let text = ascii_stl(&[([0.0, 0.0, 0.0], [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]])]);
+        let model = from_bytes(text.as_bytes()).unwrap();
+        // recorrida en sentido horario vista desde +z, así que mira hacia -z
+        for vertex in &model.vertices {
+            assert_eq!(vertex.normal, Vec3::new(0.0, 0.0, -1.0));
+        }
+    }
+
+    #[test]
+    fn ascii_facet_with_two_vertices_is_an_error() {
+        let text = "solid prueba\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nendloop\nendfacet\nendsolid prueba\n";
+        assert!(matches!(from_bytes(text.as_bytes()), Err(StlError::Format(_))));
+    }
+}
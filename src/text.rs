@@ -0,0 +1,156 @@
+use crate::framebuffer::Framebuffer;
+use crate::color::Color;
+
+/// Ancho de un carácter de la fuente, en píxeles (sin escalar).
+pub const GLYPH_WIDTH: usize = 5;
+/// Filas libres sobre cada carácter para los acentos, la diéresis y la virgulilla.
+const MARK_HEIGHT: usize = 3;
+/// Alto del cuerpo de un carácter, sin el espacio de las marcas.
+const BODY_HEIGHT: usize = 7;
+/// Alto de un carácter de la fuente, con el espacio de las marcas, en píxeles (sin escalar).
+pub const GLYPH_HEIGHT: usize = MARK_HEIGHT + BODY_HEIGHT;
+/// Espacio entre caracteres, en píxeles (sin escalar).
+const GLYPH_SPACING: usize = 1;
+
+/// Cantidad de glifos del atlas.
+const GLYPH_COUNT: usize = 55;
+
+/// Caracteres con glifo propio, en el mismo orden que `ATLAS`.
+const CHARSET: [char; GLYPH_COUNT] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
+    'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ' ', '.',
+    ',', ':', '-', '_', '/', '(', ')', '[', ']', '!', '¡', '?', '¿', '\'', '%', '+', '=',
+];
+
+/// Atlas de la fuente: los mapas de bits de todos los glifos empaquetados en una
+/// sola tabla, una fila por byte con el bit 4 como la columna de la izquierda.
+const ATLAS: [[u8; BODY_HEIGHT]; GLYPH_COUNT] = [
+    [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11], // A
+    [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E], // B
+    [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E], // C
+    [0x1E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1E], // D
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F], // E
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10], // F
+    [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F], // G
+    [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11], // H
+    [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E], // I
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C], // J
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // K
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F], // L
+    [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11], // M
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // N
+    [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // O
+    [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10], // P
+    [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D], // Q
+    [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11], // R
+    [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E], // S
+    [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // T
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // U
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04], // V
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A], // W
+    [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11], // X
+    [0x11, 0x11, 0x0A, 0x04, 0x04, 0x04, 0x04], // Y
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F], // Z
+    [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E], // 0
+    [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E], // 1
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F], // 2
+    [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E], // 3
+    [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02], // 4
+    [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E], // 5
+    [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E], // 6
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // 7
+    [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E], // 8
+    [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C], // 9
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // espacio
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C], // .
+    [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08], // ,
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00], // :
+    [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F], // _
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // /
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // (
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // )
+    [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E], // [
+    [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E], // ]
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // !
+    [0x04, 0x00, 0x04, 0x04, 0x04, 0x04, 0x04], // ¡
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // ?
+    [0x04, 0x00, 0x04, 0x08, 0x10, 0x11, 0x0E], // ¿
+    [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00], // '
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // %
+    [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00], // +
+    [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00], // =
+];
+
+/// Glifo de los caracteres que no están en el atlas: un bloque hueco.
+const MISSING: [u8; BODY_HEIGHT] = [0x1F, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1F];
+
+/// Marcas diacríticas de las letras latinas acentuadas: los dos primeros renglones
+/// del espacio de marcas, las letras que la llevan y sus letras base, en el mismo orden.
+const MARKS: [([u8; 2], &str, &str); 5] = [
+    ([0x02, 0x04], "ÁÉÍÓÚÝ", "AEIOUY"), // aguda
+    ([0x08, 0x04], "ÀÈÌÒÙ", "AEIOU"),   // grave
+    ([0x04, 0x0A], "ÂÊÎÔÛ", "AEIOU"),   // circunfleja
+    ([0x00, 0x0A], "ÄËÏÖÜ", "AEIOU"),   // diéresis
+    ([0x05, 0x0A], "ÃÑÕ", "ANO"),       // virgulilla
+];
+
+/// Devuelve el mapa de bits de un carácter, con el espacio de las marcas arriba.
+/// Las minúsculas se dibujan como mayúsculas, las letras acentuadas como su letra
+/// base con la marca encima y los caracteres sin glifo como un bloque hueco.
+fn glyph(character: char) -> [u8; GLYPH_HEIGHT] {
+    let upper = character.to_uppercase().next().unwrap_or(character);
+    let (base, mark) = MARKS.iter()
+        .find_map(|(bits, marked, bases)| {
+            let index = marked.chars().position(|other| other == upper)?;
+            Some((bases.chars().nth(index)?, *bits))
+        })
+        .unwrap_or((upper, [0x00; 2]));
+
+    let body = CHARSET.iter().position(|&other| other == base).map_or(&MISSING, |index| &ATLAS[index]);
+    let mut bitmap = [0x00; GLYPH_HEIGHT];
+    bitmap[..2].copy_from_slice(&mark);
+    bitmap[MARK_HEIGHT..].copy_from_slice(body);
+    bitmap
+}
+
+/// Calcula el ancho en píxeles que ocupa un texto.
+///
+/// # Parámetros
+/// - `text`: Texto de una sola línea.
+/// - `scale`: Tamaño de cada píxel de la fuente (1 es el tamaño original).
+pub fn text_width(text: &str, scale: usize) -> usize {
+    let count = text.chars().count();
+    (count * (GLYPH_WIDTH + GLYPH_SPACING)).saturating_sub(GLYPH_SPACING) * scale
+}
+
+/// Dibuja un texto con la fuente de mapa de bits.
+///
+/// El texto se dibuja delante de toda la geometría; conviene llamarlo después del
+/// posprocesado para que la niebla y el resplandor no lo afecten. Lo que queda
+/// fuera del framebuffer se recorta.
+///
+/// # Parámetros
+/// - `framebuffer`: Framebuffer donde se dibuja.
+/// - `x`, `y`: Esquina superior izquierda del texto, en píxeles.
+/// - `text`: Texto de una sola línea.
+/// - `color`: Color del texto.
+/// - `scale`: Tamaño de cada píxel de la fuente (1 es el tamaño original).
+pub fn draw_text(framebuffer: &mut Framebuffer, x: usize, y: usize, text: &str, color: Color, scale: usize) {
+    framebuffer.set_current_color(color.to_u32());
+    for (index, character) in text.chars().enumerate() {
+        let left = x + index * (GLYPH_WIDTH + GLYPH_SPACING) * scale;
+        for (row, bits) in glyph(character).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        framebuffer.point(left + column * scale + dx, y + row * scale + dy, f32::NEG_INFINITY);
+                    }
+                }
+            }
+        }
+    }
+}
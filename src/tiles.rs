@@ -0,0 +1,194 @@
+use std::ops::Range;
+use std::thread;
+use crate::color::PixelFormat;
+use crate::framebuffer::{Framebuffer, Viewport};
+
+/// Alto de los tiles en filas de la imagen. Cada tile es una franja de filas
+/// enteras, así su color y su profundidad son un trozo contiguo de los buffers
+/// del framebuffer y los hilos escriben directo en ellos, sin copiar la imagen;
+/// una imagen de 1920x1080 da 68 franjas para repartir.
+pub const TILE_ROWS: usize = 16;
+
+/// Tiles que tiene que tener cada hilo para que valga la pena crearlo (ver
+/// `for_each_tile`).
+pub const MIN_TILES_PER_THREAD: usize = 4;
+
+/// Franja de filas del framebuffer, con su parte del color y del z-buffer.
+///
+/// Un hilo que tiene el tile escribe en él sin compartir memoria con los demás,
+/// así los triángulos se rasterizan en paralelo sin bloqueos.
+#[derive(Debug)]
+pub struct Tile<'a> {
+    /// Parte de la franja donde se puede escribir: sus filas dentro de la tijera
+    /// del framebuffer, si tiene.
+    area: Viewport,
+    /// Primera fila de la imagen que guardan `color` y `depth`.
+    top: usize,
+    /// Ancho del framebuffer.
+    width: usize,
+    /// Color de las filas de la franja, del framebuffer.
+    color: &'a mut [u32],
+    /// Profundidad de las filas de la franja, del z-buffer del framebuffer.
+    depth: &'a mut [f32],
+    /// El `PixelFormat` del framebuffer.
+    pixel_format: PixelFormat,
+}
+
+impl Tile<'_> {
+    /// Índice en `color` y `depth` del píxel (x, y) de la imagen, si está dentro del tile.
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || !self.area.contains(x as usize, y as usize) {
+            return None;
+        }
+        Some((y as usize - self.top) * self.width + x as usize)
+    }
+
+    /// Cómo se codifican los colores del tile, el mismo que en su framebuffer.
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.pixel_format
+    }
+
+    /// Caja del tile `(min_x, min_y, max_x, max_y)` con los extremos incluidos, como
+    /// la que recibe `TriangleFragments::within`.
+    pub fn bounds(&self) -> (i32, i32, i32, i32) {
+        let Viewport { x, y, width, height } = self.area;
+        (x as i32, y as i32, (x + width) as i32 - 1, (y + height) as i32 - 1)
+    }
+
+    /// Igual que `Framebuffer::passes_depth_test`, con las coordenadas de la imagen:
+    /// los píxeles de otros tiles no pasan.
+    pub fn passes_depth_test(&self, x: i32, y: i32, depth: f32) -> bool {
+        self.index(x, y).is_some_and(|index| self.depth[index] > depth)
+    }
+
+    /// Igual que `Framebuffer::point`, con el color explícito.
+    pub fn point(&mut self, x: i32, y: i32, depth: f32, color: u32) {
+        if let Some(index) = self.index(x, y).filter(|&index| self.depth[index] > depth) {
+            self.color[index] = color;
+            self.depth[index] = depth;
+        }
+    }
+
+    /// Igual que `Framebuffer::blend_point`, con el color explícito: mezcla sin
+    /// actualizar la profundidad.
+    pub fn blend_point(&mut self, x: i32, y: i32, depth: f32, color: u32, alpha: f32) {
+        if let Some(index) = self.index(x, y).filter(|&index| self.depth[index] > depth) {
+            let destination = self.pixel_format.unpack(self.color[index]);
+            let source = self.pixel_format.unpack(color);
+            self.color[index] = self.pixel_format.pack(destination.lerp(&source, alpha.clamp(0.0, 1.0)), x as usize, y as usize);
+        }
+    }
+}
+
+/// Cómo se parte un framebuffer en tiles: franjas de `TILE_ROWS` filas, numeradas
+/// de arriba hacia abajo. Sirve para anotar los triángulos en los tiles que tocan
+/// antes de tener los tiles (ver `tiles_touching` y `split`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileGrid {
+    /// Ancho y alto del framebuffer.
+    size: (usize, usize),
+    /// Rectángulo donde se puede escribir: la tijera del framebuffer o todo él.
+    area: Viewport,
+}
+
+impl TileGrid {
+    /// Grilla de un framebuffer; si tiene tijera, los tiles solo cubren ese rectángulo.
+    pub fn new(framebuffer: &Framebuffer) -> Self {
+        let full = Viewport::full(framebuffer.width, framebuffer.height);
+        let area = framebuffer.scissor().map_or(full, |scissor| {
+            let x = scissor.x.min(full.width);
+            let y = scissor.y.min(full.height);
+            Viewport::new(x, y, scissor.width.min(full.width - x), scissor.height.min(full.height - y))
+        });
+        TileGrid { size: (framebuffer.width, framebuffer.height), area }
+    }
+
+    /// Cantidad de tiles, contando los que quedan fuera de la tijera.
+    pub fn len(&self) -> usize {
+        self.size.1.div_ceil(TILE_ROWS)
+    }
+
+    /// Indica si la grilla no tiene tiles (un framebuffer sin filas).
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Índices de los tiles que toca una caja de la imagen, con los extremos
+    /// incluidos (como `Tile::bounds`); vacío si la caja queda fuera de la tijera.
+    pub fn tiles_touching(&self, (min_x, min_y, max_x, max_y): (i32, i32, i32, i32)) -> Range<usize> {
+        let Viewport { x, y, width, height } = self.area;
+        let outside = max_x < x as i32
+            || max_y < y as i32
+            || min_x >= (x + width) as i32
+            || min_y >= (y + height) as i32;
+        if outside {
+            return 0..0;
+        }
+        let first = min_y.max(y as i32) as usize / TILE_ROWS;
+        let last = (max_y as usize).min(y + height - 1) / TILE_ROWS;
+        first..last + 1
+    }
+
+    /// Parte el color y el z-buffer de un framebuffer en tiles que escriben en él
+    /// directamente; mientras existen, el framebuffer queda prestado.
+    ///
+    /// # Parámetros
+    /// - `framebuffer`: El framebuffer con el que se creó la grilla.
+    pub fn split<'a>(&self, framebuffer: &'a mut Framebuffer) -> Vec<Tile<'a>> {
+        let (width, area, pixel_format) = (framebuffer.width, self.area, framebuffer.pixel_format());
+        let rows = width * TILE_ROWS;
+        framebuffer.buffer.chunks_mut(rows.max(1))
+            .zip(framebuffer.zbuffer.chunks_mut(rows.max(1)))
+            .enumerate()
+            .map(|(index, (color, depth))| {
+                let top = index * TILE_ROWS;
+                let start = top.max(area.y);
+                let end = (top + TILE_ROWS).min(area.y + area.height).max(start);
+                Tile { area: Viewport::new(area.x, start, area.width, end - start), top, width, color, depth, pixel_format }
+            })
+            .collect()
+    }
+}
+
+/// Ejecuta `draw` sobre algunos tiles, repartiéndolos entre los hilos: el hilo
+/// `i` se queda con los tiles `i`, `i + hilos`, `i + 2 * hilos`... de la lista,
+/// así las zonas con mucho trabajo (un planeta en el centro) quedan repartidas.
+/// Cada hilo recibe al menos `MIN_TILES_PER_THREAD` tiles, porque crear un hilo
+/// cuesta más que dibujar un tile casi vacío.
+///
+/// # Parámetros
+/// - `tiles`: Los tiles del framebuffer (ver `TileGrid::split`).
+/// - `selected`: Índices de los tiles a visitar, de menor a mayor.
+/// - `threads`: Hilos entre los que se reparten (al menos uno; ver `available_threads`).
+/// - `draw`: Recibe el índice del tile y el tile.
+pub fn for_each_tile<F: Fn(usize, &mut Tile) + Sync>(tiles: &mut [Tile], selected: &[usize], threads: usize, draw: F) {
+    let mut wanted = selected.iter().copied().peekable();
+    let chosen = tiles.iter_mut().enumerate().filter(|(index, _)| wanted.next_if_eq(index).is_some());
+    let threads = threads.min(selected.len() / MIN_TILES_PER_THREAD);
+    if threads <= 1 {
+        for (index, tile) in chosen {
+            draw(index, tile);
+        }
+        return;
+    }
+    let mut shares: Vec<Vec<(usize, &mut Tile)>> = (0..threads).map(|_| Vec::new()).collect();
+    for (position, tile) in chosen.enumerate() {
+        shares[position % threads].push(tile);
+    }
+    let draw = &draw;
+    thread::scope(|scope| {
+        for share in shares {
+            scope.spawn(move || {
+                for (index, tile) in share {
+                    draw(index, tile);
+                }
+            });
+        }
+    });
+}
+
+/// Cantidad de hilos que conviene usar: los núcleos de la máquina, o uno si no se
+/// sabe (en el navegador no se pueden crear hilos).
+pub fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
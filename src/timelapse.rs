@@ -0,0 +1,94 @@
+use std::f32::consts::TAU;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use crate::framebuffer::Framebuffer;
+use crate::scene::Scene;
+
+/// Carpeta donde el visor guarda los fotogramas de un time-lapse.
+pub const TIMELAPSE_DIR: &str = "assets/timelapse";
+
+/// Exportación de un time-lapse: cada fotograma guardado avanza la simulación
+/// `step` fotogramas de una vez.
+///
+/// Solo las órbitas usan el tiempo de simulación. Las animaciones de los shaders
+/// (nubes, lava, olas) siguen avanzando un fotograma por fotograma exportado, así
+/// que en el video se mueven a su velocidad normal en vez de saltar de una
+/// posición a otra sin relación entre fotogramas.
+#[derive(Debug, Clone)]
+pub struct TimeLapse {
+    /// Tiempo de simulación que avanza cada fotograma exportado, en fotogramas de simulación.
+    pub step: f32,
+    /// Cantidad de fotogramas a exportar.
+    pub frames: u32,
+    /// Carpeta donde se escriben los fotogramas.
+    pub directory: PathBuf,
+    /// Fotogramas ya exportados.
+    exported: u32,
+}
+
+impl TimeLapse {
+    /// Crea una exportación.
+    ///
+    /// # Parámetros
+    /// - `step`: Fotogramas de simulación por fotograma exportado.
+    /// - `frames`: Cantidad de fotogramas a exportar.
+    /// - `directory`: Carpeta de destino; se crea al exportar el primer fotograma.
+    pub fn new<P: Into<PathBuf>>(step: f32, frames: u32, directory: P) -> Self {
+        TimeLapse { step, frames, directory: directory.into(), exported: 0 }
+    }
+
+    /// Crea una exportación que cubre un período orbital completo.
+    ///
+    /// # Parámetros
+    /// - `period`: Duración del período en fotogramas de simulación (ver `orbital_period`).
+    /// - `frames`: Cantidad de fotogramas en que se reparte el período (al menos uno).
+    /// - `directory`: Carpeta de destino.
+    pub fn full_period<P: Into<PathBuf>>(period: f32, frames: u32, directory: P) -> Self {
+        let frames = frames.max(1);
+        TimeLapse::new(period / frames as f32, frames, directory)
+    }
+
+    /// Fotogramas ya exportados.
+    pub fn exported(&self) -> u32 {
+        self.exported
+    }
+
+    /// Indica si ya se exportaron todos los fotogramas.
+    pub fn is_finished(&self) -> bool {
+        self.exported >= self.frames
+    }
+
+    /// Guarda el fotograma actual como `frame_00000.ppm`, `frame_00001.ppm`, ...
+    ///
+    /// # Retorna
+    /// La ruta del archivo escrito.
+    pub fn record(&mut self, framebuffer: &Framebuffer) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.directory)?;
+        let path = self.directory.join(format!("frame_{:05}.ppm", self.exported));
+        framebuffer.save_ppm(&path)?;
+        self.exported += 1;
+        Ok(path)
+    }
+}
+
+/// Calcula el período orbital de un nodo: lo que tarda en dar una vuelta completa.
+///
+/// Los nodos sin órbita propia (como los anillos o las nubes) usan la de su padre.
+///
+/// # Parámetros
+/// - `scene`: Escena que contiene el nodo.
+/// - `index`: Índice del nodo.
+///
+/// # Retorna
+/// El período en fotogramas de simulación, o `None` si ni el nodo ni sus padres orbitan.
+pub fn orbital_period(scene: &Scene, index: usize) -> Option<f32> {
+    let mut current = Some(index);
+    while let Some(node) = current.map(|index| &scene.nodes[index]) {
+        if let Some(orbit) = node.orbit.filter(|orbit| orbit.speed.abs() > f32::EPSILON) {
+            return Some(TAU / orbit.speed.abs());
+        }
+        current = node.parent;
+    }
+    None
+}
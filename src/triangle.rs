@@ -1,67 +1,425 @@
 use nalgebra_glm::{Vec3, dot};
+use crate::color::Color;
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
-use crate::color::Color;
+
+/// Forma de calcular la normal de cada fragmento de un triángulo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Shading {
+    /// Interpola las normales de los vértices y sombrea cada fragmento (Phong):
+    /// superficies suaves.
+    #[default]
+    Smooth,
+    /// Usa la misma normal en todo el triángulo (la de la cara), con facetas visibles;
+    /// útil para asteroides y modelos de pocos polígonos.
+    Flat,
+    /// Sombrea solo los vértices e interpola el color (Gouraud): mucho más barato en
+    /// cuerpos chicos o lejanos, pero los detalles del ruido y los brillos más
+    /// pequeños que un triángulo se pierden.
+    Gouraud,
+}
+
+impl Shading {
+    /// Todos los modos, en el orden en que se alternan desde el teclado.
+    pub const ALL: [Shading; 3] = [Shading::Smooth, Shading::Gouraud, Shading::Flat];
+
+    /// Devuelve el nombre corto del modo.
+    pub fn name(self) -> &'static str {
+        match self {
+            Shading::Smooth => "smooth",
+            Shading::Flat => "flat",
+            Shading::Gouraud => "gouraud",
+        }
+    }
+
+    /// Busca un modo por su nombre corto.
+    pub fn from_name(name: &str) -> Option<Shading> {
+        Shading::ALL.into_iter().find(|shading| shading.name() == name)
+    }
+
+    /// Devuelve el modo siguiente de `ALL`, volviendo al primero después del último.
+    pub fn next(self) -> Shading {
+        let index = Shading::ALL.iter().position(|&shading| shading == self).unwrap_or(0);
+        Shading::ALL[(index + 1) % Shading::ALL.len()]
+    }
+
+    /// Indica si el shader de fragmentos corre una vez por vértice en lugar de por
+    /// fragmento (ver `pipeline::shade_vertex`).
+    pub fn per_vertex(self) -> bool {
+        self == Shading::Gouraud
+    }
+}
+
+/// Lado de los bloques de píxeles que el rasterizador descarta de una sola vez.
+#[cfg(not(feature = "bbox_raster"))]
+const BLOCK_SIZE: i32 = 8;
 
 /// Genera un conjunto de fragmentos a partir de un triángulo definido por tres vértices.
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
-    let mut fragments = Vec::new(); // Vector para almacenar los fragmentos generados.
-    let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position); // Posiciones transformadas de los vértices.
+///
+/// Equivale a recoger `triangle_fragments` en un vector.
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, shading: Shading) -> Vec<Fragment> {
+    triangle_fragments(v1, v2, v3, shading).collect() // Devuelve el vector de fragmentos generados.
+}
+
+/// Recorre los fragmentos de un triángulo sin guardarlos: cada uno se calcula
+/// cuando se pide, así que no se reserva memoria por triángulo.
+///
+/// # Parámetros
+/// - `v1`, `v2`, `v3`: Vértices ya transformados a coordenadas de pantalla.
+/// - `shading`: Cómo se calcula la normal de los fragmentos.
+pub fn triangle_fragments<'a>(v1: &'a Vertex, v2: &'a Vertex, v3: &'a Vertex, shading: Shading) -> impl Iterator<Item = Fragment> + 'a {
+    TriangleFragments::new(v1, v2, v3, shading, |_, _, _| true)
+}
+
+/// Rasteriza un triángulo y agrega sus fragmentos a `fragments`.
+///
+/// Igual que `TriangleFragments::new`, pero en un vector que se puede reutilizar
+/// entre triángulos.
+///
+/// # Parámetros
+/// - `v1`, `v2`, `v3`: Vértices ya transformados a coordenadas de pantalla.
+/// - `shading`: Cómo se calcula la normal de los fragmentos.
+/// - `visible`: Recibe `x`, `y` y la profundidad de cada píxel cubierto y decide si se conserva,
+///   por ejemplo con `Framebuffer::passes_depth_test`.
+/// - `fragments`: Vector donde se agregan los fragmentos.
+pub fn rasterize<F: Fn(i32, i32, f32) -> bool>(
+    v1: &Vertex,
+    v2: &Vertex,
+    v3: &Vertex,
+    shading: Shading,
+    visible: F,
+    fragments: &mut Vec<Fragment>,
+) {
+    fragments.extend(TriangleFragments::new(v1, v2, v3, shading, visible));
+}
+
+/// Iterador sobre los fragmentos de un triángulo.
+///
+/// La caja delimitadora se recorre en bloques de 8x8 píxeles y se descartan sin
+/// mirar sus píxeles los bloques que quedan enteros fuera de alguna arista, lo que
+/// ahorra casi todo el trabajo en los triángulos delgados o en diagonal. Con la
+/// característica `bbox_raster` se usa el recorrido original, píxel por píxel de
+/// toda la caja, para comparar.
+///
+/// La profundidad de cada píxel cubierto se calcula antes que el resto del
+/// fragmento y se le pasa a `visible`; si devuelve `false` el píxel se descarta
+/// sin interpolar normales, colores ni coordenadas (early-Z).
+///
+/// Con `Shading::Flat` la normal de la cara es el producto cruz de sus lados en el
+/// mundo (`Vertex::world_position`), que ya está en el espacio de iluminación,
+/// orientada hacia el mismo lado que las normales de los vértices. Si el triángulo
+/// es degenerado y no tiene normal, se usa la interpolada como en el modo suave.
+pub struct TriangleFragments<'a, F> {
+    vertices: [&'a Vertex; 3],
+    shading: Shading,
+    visible: F,
+    /// Normal de la cara, la misma para todos los fragmentos en el modo plano;
+    /// `None` si el triángulo es degenerado.
+    face_normal: Option<Vec3>,
+    /// Área del triángulo con signo, según la función de borde.
+    area: f32,
+    /// Caja delimitadora `(min_x, min_y, max_x, max_y)`.
+    bounds: (i32, i32, i32, i32),
+    /// Bloque en curso `(x0, y0, x1, y1)`, con los extremos incluidos.
+    block: (i32, i32, i32, i32),
+    /// Próximo píxel a revisar dentro del bloque.
+    x: i32,
+    y: i32,
+    /// Tramo de píxeles de una fila ya evaluado, con los cubiertos que faltan entregar.
+    #[cfg(feature = "simd")]
+    span: Span,
+}
+
+impl<'a, F: Fn(i32, i32, f32) -> bool> TriangleFragments<'a, F> {
+    /// Prepara el recorrido de un triángulo.
+    ///
+    /// # Parámetros
+    /// - `v1`, `v2`, `v3`: Vértices ya transformados a coordenadas de pantalla.
+    /// - `shading`: Cómo se calcula la normal de los fragmentos.
+    /// - `visible`: Recibe `x`, `y` y la profundidad de cada píxel cubierto y decide si se conserva.
+    pub fn new(v1: &'a Vertex, v2: &'a Vertex, v3: &'a Vertex, shading: Shading, visible: F) -> Self {
+        let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position); // Posiciones transformadas de los vértices.
+
+        // Calcula la caja delimitadora del triángulo.
+        let bounds = calculate_bounding_box(&a, &b, &c);
+
+        let mut fragments = TriangleFragments {
+            vertices: [v1, v2, v3],
+            shading,
+            visible,
+            face_normal: face_normal(v1, v2, v3),
+            // Calcula el área del triángulo usando la función de borde.
+            area: edge_function(&a, &b, &c),
+            bounds,
+            block: (0, 0, 0, 0),
+            x: 0,
+            y: 0,
+            #[cfg(feature = "simd")]
+            span: Span::default(),
+        };
+        fragments.enter_block(bounds.0, bounds.1);
+        fragments
+    }
 
-    // Calcula la caja delimitadora del triángulo.
-    let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
+    /// Limita el recorrido a un rectángulo de la pantalla, por ejemplo el de un
+    /// `tiles::Tile`: los píxeles de afuera ni se visitan.
+    ///
+    /// # Parámetros
+    /// - `area`: Caja `(min_x, min_y, max_x, max_y)`, con los extremos incluidos.
+    ///
+    /// # Retorna
+    /// El mismo recorrido, o `None` si el triángulo no toca el rectángulo.
+    pub fn within(mut self, area: (i32, i32, i32, i32)) -> Option<Self> {
+        let bounds = (
+            self.bounds.0.max(area.0),
+            self.bounds.1.max(area.1),
+            self.bounds.2.min(area.2),
+            self.bounds.3.min(area.3),
+        );
+        if bounds.0 > bounds.2 || bounds.1 > bounds.3 {
+            return None;
+        }
+        self.bounds = bounds;
+        self.enter_block(bounds.0, bounds.1);
+        Some(self)
+    }
 
-    let light_dir = Vec3::new(0.0, 0.0, 1.0); // Dirección de la luz para el cálculo de iluminación.
+    /// Posiciones transformadas de los tres vértices.
+    fn positions(&self) -> (Vec3, Vec3, Vec3) {
+        let [v1, v2, v3] = self.vertices;
+        (v1.transformed_position, v2.transformed_position, v3.transformed_position)
+    }
 
-    // Calcula el área del triángulo usando la función de borde.
-    let triangle_area = edge_function(&a, &b, &c);
+    /// Empieza el bloque cuya esquina superior izquierda es `(x0, y0)`; si queda
+    /// entero fuera del triángulo se marca como terminado sin visitar sus píxeles.
+    fn enter_block(&mut self, x0: i32, y0: i32) {
+        let (_, _, max_x, max_y) = self.bounds;
+
+        // Itera sobre cada pixel dentro de la caja delimitadora.
+        #[cfg(feature = "bbox_raster")]
+        {
+            self.block = (x0, y0, max_x, max_y);
+        }
 
-    // Itera sobre cada pixel dentro de la caja delimitadora.
-    for y in min_y..=max_y {
-        for x in min_x..=max_x {
-            let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0); // Punto en el espacio de píxeles.
+        // Recorre la caja por bloques y solo visita los píxeles de los que tocan el triángulo.
+        #[cfg(not(feature = "bbox_raster"))]
+        {
+            self.block = (x0, y0, (x0 + BLOCK_SIZE - 1).min(max_x), (y0 + BLOCK_SIZE - 1).min(max_y));
+            let (a, b, c) = self.positions();
+            if block_outside(self.block, &a, &b, &c, self.area) {
+                self.x = x0;
+                self.y = self.block.3 + 1;
+                return;
+            }
+        }
+
+        self.x = x0;
+        self.y = y0;
+    }
 
-            // Calcula las coordenadas baricéntricas del punto en relación al triángulo.
-            let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
+    /// Crea el fragmento de un píxel cubierto a partir de sus coordenadas baricéntricas,
+    /// si pasa la prueba de profundidad.
+    fn fragment(&self, x: i32, y: i32, (w1, w2, w3): (f32, f32, f32)) -> Option<Fragment> {
+        let [v1, v2, v3] = self.vertices;
+        let (a, b, c) = self.positions();
 
-            // Verifica si el punto está dentro del triángulo usando las coordenadas baricéntricas.
-            if w1 >= 0.0 && w1 <= 1.0 && 
-               w2 >= 0.0 && w2 <= 1.0 &&
-               w3 >= 0.0 && w3 <= 1.0 {
+        // Calcula la profundidad del fragmento.
+        let depth = a.z * w1 + b.z * w2 + c.z * w3;
+        if !(self.visible)(x, y, depth) {
+            return None;
+        }
 
-                // Calcula la normal del triángulo en el punto utilizando las coordenadas baricéntricas.
+        // Calcula la normal del triángulo en el punto utilizando las coordenadas baricéntricas,
+        // o usa la de la cara en el modo plano.
+        let normal = match (self.shading, self.face_normal) {
+            (Shading::Flat, Some(face_normal)) => face_normal,
+            _ => {
                 let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
-                let normal = normal.normalize(); // Normaliza la normal.
-
-                // Calcula la intensidad de iluminación usando el producto punto.
-                let intensity = dot(&normal, &light_dir).max(0.0);
-
-                let base_color = Color::new(100, 100, 100); // Color base del triángulo.
-                let lit_color = base_color * intensity; // Color iluminado.
-
-                // Calcula la profundidad del fragmento.
-                let depth = a.z * w1 + b.z * w2 + c.z * w3;
-
-                // Calcula la posición del vértice usando las coordenadas baricéntricas.
-                let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
-
-                // Crea un nuevo fragmento y lo añade al vector de fragmentos.
-                fragments.push(
-                    Fragment::new(
-                        x as f32,
-                        y as f32,
-                        lit_color,
-                        depth,
-                        normal,
-                        intensity,
-                        vertex_position,
-                    )
-                );
+                normal.normalize() // Normaliza la normal.
+            }
+        };
+
+        let light_dir = Vec3::new(0.0, 0.0, 1.0); // Dirección de la luz para el cálculo de iluminación.
+
+        // Calcula la intensidad de iluminación usando el producto punto.
+        let intensity = dot(&normal, &light_dir).max(0.0);
+
+        // Color interpolado de los vértices, sin iluminar; en punto flotante para
+        // no perder precisión al sumar las tres partes.
+        let color = Color::from(Vec3::from(v1.color) * w1 + Vec3::from(v2.color) * w2 + Vec3::from(v3.color) * w3);
+
+        // Calcula la posición del vértice usando las coordenadas baricéntricas.
+        let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
+
+        // Crea un nuevo fragmento con el material de la cara.
+        let mut fragment = Fragment::new(
+            x as f32,
+            y as f32,
+            color,
+            depth,
+            normal,
+            intensity,
+            vertex_position,
+        );
+        fragment.material = v1.material;
+        fragment.tex_coords = v1.tex_coords * w1 + v2.tex_coords * w2 + v3.tex_coords * w3;
+        fragment.elevation = v1.elevation * w1 + v2.elevation * w2 + v3.elevation * w3;
+        Some(fragment)
+    }
+}
+
+impl<F: Fn(i32, i32, f32) -> bool> Iterator for TriangleFragments<'_, F> {
+    type Item = Fragment;
+
+    fn next(&mut self) -> Option<Fragment> {
+        let (min_x, _, max_x, max_y) = self.bounds;
+        loop {
+            // Entrega los píxeles cubiertos del tramo ya evaluado.
+            #[cfg(feature = "simd")]
+            while self.span.covered != 0 {
+                let lane = self.span.covered.trailing_zeros() as usize;
+                self.span.covered &= self.span.covered - 1;
+                let (x, y) = (self.span.x + lane as i32, self.span.y);
+                let weights = (self.span.weights[0][lane], self.span.weights[1][lane], self.span.weights[2][lane]);
+                if let Some(fragment) = self.fragment(x, y, weights) {
+                    return Some(fragment);
+                }
+            }
+
+            let (x0, y0, x1, y1) = self.block;
+
+            // Terminado el bloque, sigue con el de la derecha o con la siguiente fila de bloques.
+            if self.y > y1 {
+                let (next_x, next_y) = if x1 < max_x { (x1 + 1, y0) } else { (min_x, y1 + 1) };
+                if next_y > max_y {
+                    return None;
+                }
+                self.enter_block(next_x, next_y);
+                continue;
+            }
+
+            // Evalúa el siguiente tramo de la fila, de hasta `LANES` píxeles.
+            #[cfg(feature = "simd")]
+            {
+                let len = ((x1 - self.x + 1) as usize).min(LANES);
+                let (a, b, c) = self.positions();
+                self.span = coverage_span(self.x, self.y, len, &a, &b, &c, self.area);
+                if self.x + (len as i32) <= x1 {
+                    self.x += len as i32;
+                } else {
+                    self.x = x0;
+                    self.y += 1;
+                }
+            }
+
+            #[cfg(not(feature = "simd"))]
+            {
+                let (x, y) = (self.x, self.y);
+                if self.x < x1 {
+                    self.x += 1;
+                } else {
+                    self.x = x0;
+                    self.y += 1;
+                }
+
+                let (a, b, c) = self.positions();
+                if let Some(fragment) = coverage(x, y, &a, &b, &c, self.area).and_then(|weights| self.fragment(x, y, weights)) {
+                    return Some(fragment);
+                }
             }
         }
     }
+}
+
+/// Píxeles seguidos de una fila que se evalúan juntos con la característica `simd`.
+#[cfg(feature = "simd")]
+const LANES: usize = 8;
+
+/// Tramo de hasta `LANES` píxeles seguidos de una fila.
+#[cfg(feature = "simd")]
+#[derive(Default)]
+struct Span {
+    /// Columna y fila del primer píxel.
+    x: i32,
+    y: i32,
+    /// Coordenadas baricéntricas de cada píxel, una fila por vértice.
+    weights: [[f32; LANES]; 3],
+    /// Un bit por píxel cubierto, el menos significativo para el primero.
+    covered: u32,
+}
+
+/// Evalúa `len` píxeles seguidos de una fila, empezando en `(x, y)`, de a `LANES`.
+///
+/// Las tres funciones de borde se calculan sobre arreglos de `LANES` valores con
+/// las mismas operaciones que `edge_function`, así que el compilador las lleva a
+/// instrucciones vectoriales y los resultados son idénticos a los del camino escalar.
+#[cfg(feature = "simd")]
+fn coverage_span(x: i32, y: i32, len: usize, a: &Vec3, b: &Vec3, c: &Vec3, area: f32) -> Span {
+    let mut px = [0.0; LANES];
+    for (lane, value) in px.iter_mut().enumerate() {
+        *value = (x + lane as i32) as f32 + 0.5;
+    }
+    let py = y as f32 + 0.5;
+
+    let mut weights = [[0.0; LANES]; 3];
+    for (w, (from, to)) in weights.iter_mut().zip([(b, c), (c, a), (a, b)]) {
+        let (step_x, step_y) = (to.y - from.y, to.x - from.x);
+        let row = (py - from.y) * step_y;
+        for (weight, px) in w.iter_mut().zip(px) {
+            *weight = ((px - from.x) * step_x - row) / area;
+        }
+    }
+
+    let inside = |w: f32| (0.0..=1.0).contains(&w);
+    let [w1, w2, w3] = &weights;
+    let covered = w1.iter().zip(w2).zip(w3).take(len).enumerate()
+        .filter(|&(_, ((&w1, &w2), &w3))| inside(w1) && inside(w2) && inside(w3))
+        .fold(0, |mask, (lane, _)| mask | 1 << lane);
+    Span { x, y, weights, covered }
+}
+
+/// Indica si el centro de un píxel cae dentro del triángulo.
+///
+/// # Retorna
+/// Las coordenadas baricéntricas del centro del píxel, o `None` si queda fuera.
+#[cfg(not(feature = "simd"))]
+fn coverage(x: i32, y: i32, a: &Vec3, b: &Vec3, c: &Vec3, area: f32) -> Option<(f32, f32, f32)> {
+    let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0); // Punto en el espacio de píxeles.
+
+    // Calcula las coordenadas baricéntricas del punto en relación al triángulo.
+    let (w1, w2, w3) = barycentric_coordinates(&point, a, b, c, area);
+
+    // Verifica si el punto está dentro del triángulo usando las coordenadas baricéntricas.
+    let inside = |w: f32| (0.0..=1.0).contains(&w);
+    (inside(w1) && inside(w2) && inside(w3)).then_some((w1, w2, w3))
+}
+
+/// Indica si un bloque de píxeles `(x0, y0, x1, y1)`, con los extremos incluidos,
+/// queda entero fuera del triángulo.
+///
+/// Cada coordenada baricéntrica varía linealmente sobre la pantalla, así que si es
+/// negativa en los centros de las cuatro esquinas del bloque lo es en todos sus
+/// píxeles. El resultado es conservador: un bloque que no se descarta puede no
+/// tener ningún píxel cubierto.
+#[cfg(not(feature = "bbox_raster"))]
+fn block_outside(block: (i32, i32, i32, i32), a: &Vec3, b: &Vec3, c: &Vec3, area: f32) -> bool {
+    let (x0, y0, x1, y1) = block;
+    let corners = [(x0, y0), (x1, y0), (x0, y1), (x1, y1)].map(|(x, y)| {
+        let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+        barycentric_coordinates(&point, a, b, c, area)
+    });
+    corners.iter().all(|weights| weights.0 < 0.0)
+        || corners.iter().all(|weights| weights.1 < 0.0)
+        || corners.iter().all(|weights| weights.2 < 0.0)
+}
 
-    fragments // Devuelve el vector de fragmentos generados.
+/// Normal de la cara en el mundo, del producto cruz de sus lados, del mismo lado
+/// que las normales de los vértices. `None` si el triángulo no tiene área.
+fn face_normal(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Option<Vec3> {
+    let (a, b, c) = (v1.world_position, v2.world_position, v3.world_position);
+    let normal = (b - a).cross(&(c - a)).try_normalize(f32::MIN_POSITIVE)?;
+    let vertex_normals = v1.transformed_normal + v2.transformed_normal + v3.transformed_normal;
+    Some(if normal.dot(&vertex_normals) < 0.0 { -normal } else { normal })
 }
 
 /// Calcula la caja delimitadora de un triángulo dado por tres vértices.
@@ -75,6 +433,7 @@ fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i3
 }
 
 /// Calcula las coordenadas baricéntricas de un punto respecto a un triángulo.
+#[cfg(not(all(feature = "simd", feature = "bbox_raster")))]
 fn barycentric_coordinates(p: &Vec3, a: &Vec3, b: &Vec3, c: &Vec3, area: f32) -> (f32, f32, f32) {
     let w1 = edge_function(b, c, p) / area; // Calcula w1.
     let w2 = edge_function(c, a, p) / area; // Calcula w2.
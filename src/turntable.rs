@@ -0,0 +1,75 @@
+use std::f32::consts::TAU;
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+use crate::scene::{Scene, SceneNode};
+use crate::shaders::PlanetShader;
+
+/// Archivo donde se guarda un giro si no se indica otro con `--out`.
+pub const TURNTABLE_PATH: &str = "assets/turntable.gif";
+
+/// Fracción del lado más corto de la imagen que ocupa el objeto.
+const FILL: f32 = 0.7;
+
+/// Giro completo de un objeto sobre su eje vertical frente a una cámara y una
+/// luz fijas, la forma clásica de mostrar un shader o una malla nueva.
+///
+/// El último fotograma queda un paso antes de la vuelta completa, así que la
+/// secuencia se puede repetir sin un salto.
+#[derive(Debug, Clone)]
+pub struct Turntable {
+    /// Cantidad de fotogramas de la vuelta.
+    pub frames: u32,
+    /// Dirección hacia la luz, fija en el mundo: arriba a la izquierda y por
+    /// delante, para que se vean el relieve y el terminador.
+    pub light_direction: Vec3,
+    /// Ángulo de la cámara sobre el ecuador del objeto, en radianes.
+    pub pitch: f32,
+}
+
+impl Turntable {
+    /// Crea un giro con la luz y la cámara por defecto.
+    ///
+    /// # Parámetros
+    /// - `frames`: Cantidad de fotogramas de la vuelta (al menos uno).
+    pub fn new(frames: u32) -> Self {
+        Turntable {
+            frames: frames.max(1),
+            light_direction: Vec3::new(-0.6, 0.5, 0.8).normalize(),
+            pitch: 15.0_f32.to_radians(),
+        }
+    }
+
+    /// Ángulo de giro del objeto en un fotograma, en radianes alrededor del eje Y.
+    pub fn angle(&self, frame: u32) -> f32 {
+        TAU * (frame % self.frames) as f32 / self.frames as f32
+    }
+
+    /// Crea la escena del giro: un único cuerpo en el origen.
+    ///
+    /// # Parámetros
+    /// - `mesh`: Ruta de la malla o nombre de una malla generada (`primitive:...`).
+    /// - `shader`: Shader de la superficie.
+    pub fn scene(mesh: &str, shader: PlanetShader) -> Scene {
+        let mut scene = Scene::new();
+        let mut node = SceneNode::new(mesh, shader, 1.0);
+        node.mesh = mesh.to_string();
+        scene.add_node(node);
+        scene
+    }
+
+    /// Crea la cámara del giro, a la distancia justa para que el cuerpo de la
+    /// escena (de radio 0.5) ocupe buena parte de la imagen.
+    ///
+    /// # Parámetros
+    /// - `aspect`: Relación de aspecto de la imagen (ancho / alto).
+    pub fn camera(&self, aspect: f32) -> Camera {
+        let mut camera = Camera::new(Vec3::new(0.0, 0.0, 1.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+        camera.set_aspect(aspect);
+        // en imágenes verticales manda el campo de visión horizontal
+        let half_fov = (camera.fov / 2.0).tan() * aspect.min(1.0);
+        let distance = 0.5 / (FILL * half_fov);
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        camera.eye = Vec3::new(0.0, sin_pitch, cos_pitch) * distance;
+        camera
+    }
+}
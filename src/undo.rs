@@ -0,0 +1,108 @@
+use crate::scene::Scene;
+use crate::scene_diff::{diff, SceneDiff};
+
+/// Una edición reversible de la escena.
+///
+/// Se guarda como un par de diffs: `forward` rehace la edición y `backward`
+/// la deshace, así cualquier modificación de la escena se puede registrar sin
+/// escribir un comando específico para cada tipo de cambio.
+#[derive(Debug, Clone)]
+pub struct Edit {
+    /// Descripción corta para mostrar al usuario.
+    pub description: String,
+    /// Cambios que aplican la edición.
+    pub forward: SceneDiff,
+    /// Cambios que revierten la edición.
+    pub backward: SceneDiff,
+}
+
+/// Pila de deshacer/rehacer para las ediciones interactivas de la escena.
+#[derive(Debug)]
+pub struct UndoStack {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// Número máximo de ediciones que se recuerdan.
+    limit: usize,
+    /// Cambios de la última edición aplicada, deshecha o rehecha, en el sentido en que se aplicaron.
+    last_change: SceneDiff,
+}
+
+impl UndoStack {
+    /// Crea una pila vacía que recuerda como máximo `limit` ediciones.
+    pub fn new(limit: usize) -> Self {
+        UndoStack { undo: Vec::new(), redo: Vec::new(), limit, last_change: SceneDiff::default() }
+    }
+
+    /// Ejecuta una edición sobre la escena y la registra para poder deshacerla.
+    /// Si la edición no cambia nada, no se registra.
+    ///
+    /// # Parámetros
+    /// - `scene`: La escena a editar.
+    /// - `description`: Descripción de la edición.
+    /// - `edit`: Función que modifica la escena.
+    ///
+    /// # Retorna
+    /// `true` si la escena cambió.
+    pub fn apply<F: FnOnce(&mut Scene)>(&mut self, scene: &mut Scene, description: &str, edit: F) -> bool {
+        let before = scene.clone();
+        edit(scene);
+
+        let forward = diff(&before, scene);
+        if forward.is_empty() {
+            return false;
+        }
+
+        let backward = diff(scene, &before);
+        self.last_change = forward.clone();
+        self.undo.push(Edit { description: description.to_string(), forward, backward });
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        true
+    }
+
+    /// Deshace la última edición.
+    ///
+    /// # Retorna
+    /// La descripción de la edición deshecha, o `None` si no había ninguna.
+    pub fn undo(&mut self, scene: &mut Scene) -> Option<&str> {
+        let edit = self.undo.pop()?;
+        report_conflicts(scene.merge(&edit.backward).len(), &edit.description);
+        self.last_change = edit.backward.clone();
+        self.redo.push(edit);
+        self.redo.last().map(|edit| edit.description.as_str())
+    }
+
+    /// Rehace la última edición deshecha.
+    ///
+    /// # Retorna
+    /// La descripción de la edición rehecha, o `None` si no había ninguna.
+    pub fn redo(&mut self, scene: &mut Scene) -> Option<&str> {
+        let edit = self.redo.pop()?;
+        report_conflicts(scene.merge(&edit.forward).len(), &edit.description);
+        self.last_change = edit.forward.clone();
+        self.undo.push(edit);
+        self.undo.last().map(|edit| edit.description.as_str())
+    }
+
+    /// Olvida todas las ediciones, por ejemplo al cambiar de escena.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.last_change = SceneDiff::default();
+    }
+
+    /// Cambios que hizo en la escena la última llamada a `apply`, `undo` o `redo` que
+    /// la modificó; sirven para avisar qué cambió sin comparar la escena entera.
+    pub fn last_change(&self) -> &SceneDiff {
+        &self.last_change
+    }
+}
+
+/// Avisa si la escena cambió por fuera de la pila y algún cambio no se pudo aplicar.
+fn report_conflicts(conflicts: usize, description: &str) {
+    if conflicts > 0 {
+        eprintln!("'{}': {} cambios no se pudieron aplicar", description, conflicts);
+    }
+}
@@ -1,5 +1,14 @@
 use nalgebra_glm::{Vec2, Vec3};
-use crate::color::Color;
+use crate::color::{Color, ColorRamp};
+
+/// Rampa de colores de los vértices según su elevación: océano hasta 0,
+/// tierra hasta 0.5 y montaña desde ahí, con cortes duros entre las bandas.
+pub const ELEVATION_RAMP: ColorRamp = ColorRamp::from_sorted([
+    (0.0, Color::new(0, 105, 148)),  // Color de océano
+    (0.0, Color::new(34, 139, 34)),  // Color de tierra
+    (0.5, Color::new(34, 139, 34)),
+    (0.5, Color::new(139, 69, 19)),  // Color de montaña
+]);
 
 /// Estructura que representa un vértice en un espacio 3D.
 #[derive(Clone, Debug)]
@@ -10,7 +19,9 @@ pub struct Vertex {
     pub color: Color,               // Color del vértice
     pub transformed_position: Vec3, // Posición transformada del vértice
     pub transformed_normal: Vec3,   // Normal transformada del vértice
+    pub world_position: Vec3,       // Posición en el mundo (la calcula el shader de vértices)
     pub elevation: f32,             // Elevación del vértice (nueva propiedad)
+    pub material: Option<usize>,    // Índice del material de la cara (ver `Model::materials`)
 }
 
 impl Vertex {
@@ -24,7 +35,9 @@ impl Vertex {
             color: Color::black(), // Color predeterminado
             transformed_position: position, // Posición transformada inicializada a la original
             transformed_normal: normal,      // Normal transformada inicializada a la original
+            world_position: position,        // Posición en el mundo inicializada a la original
             elevation,                       // Inicializa la elevación
+            material: None,                  // Sin material asignado
         }
     }
 
@@ -38,7 +51,9 @@ impl Vertex {
             color,
             transformed_position: Vec3::new(0.0, 0.0, 0.0), // Posición transformada inicializada a cero
             transformed_normal: Vec3::new(0.0, 0.0, 0.0),   // Normal transformada inicializada a cero
+            world_position: position, // Posición en el mundo inicializada a la original
             elevation: 0.0, // Inicializa la elevación a cero
+            material: None, // Sin material asignado
         }
     }
 
@@ -49,17 +64,11 @@ impl Vertex {
     }
 
     /// Actualiza el color del vértice en función de su elevación.
-    /// - Color de océano (azul) si la elevación es menor que 0.
-    /// - Color de tierra (verde) si la elevación es entre 0 y 0.5.
-    /// - Color de montaña (marrón) si la elevación es mayor o igual a 0.5.
-    pub fn update_color_based_on_elevation(&mut self) {
-        if self.elevation < 0.0 {
-            self.color = Color::new(0, 105, 148); // Color de océano
-        } else if self.elevation < 0.5 {
-            self.color = Color::new(34, 139, 34); // Color de tierra
-        } else {
-            self.color = Color::new(139, 69, 19); // Color de montaña
-        }
+    ///
+    /// # Parámetros
+    /// - `ramp`: Rampa de colores por elevación; `ELEVATION_RAMP` da océano, tierra y montaña.
+    pub fn update_color_based_on_elevation(&mut self, ramp: &ColorRamp) {
+        self.color = ramp.sample(self.elevation);
     }
 }
 
@@ -74,7 +83,9 @@ impl Default for Vertex {
             color: Color::black(),               // Color predeterminado a negro
             transformed_position: Vec3::new(0.0, 0.0, 0.0), // Posición transformada inicializada a cero
             transformed_normal: Vec3::new(0.0, 1.0, 0.0),   // Normal transformada inicializada hacia arriba
+            world_position: Vec3::new(0.0, 0.0, 0.0),       // Posición en el mundo inicializada a cero
             elevation: 0.0,                      // Inicializa la elevación a cero
+            material: None,                      // Sin material asignado
         }
     }
 }
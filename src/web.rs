@@ -0,0 +1,211 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use nalgebra_glm::Vec3;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData, KeyboardEvent};
+use crate::framebuffer::{Framebuffer, Viewport};
+use crate::obj::Model;
+use crate::pipeline::{create_model_matrix, create_viewport_matrix, render, Lighting, Matrices, PipelineHooks};
+use crate::present::{InputEvent, Key, PresentError, Presenter};
+use crate::primitives;
+use crate::shaders::PlanetShader;
+use crate::turntable::Turntable;
+use crate::Uniforms;
+
+/// Fotogramas de una vuelta completa del planeta en `PlanetDemo`.
+const DEMO_FRAMES: u32 = 600;
+
+/// Color de fondo de `PlanetDemo`, el mismo del visor nativo.
+const DEMO_BACKGROUND: u32 = 0x333355;
+
+/// Muestra los fotogramas en un `<canvas>` de la página, con `putImageData`; solo
+/// copia las regiones que cambiaron en el último `Framebuffer::swap_buffers`.
+///
+/// El canvas toma el tamaño del framebuffer en cada fotograma; para verlo más
+/// grande basta con escalarlo por CSS. Las teclas llegan mientras el canvas
+/// tiene el foco (tiene `tabindex` para poder tomarlo).
+pub struct CanvasPresenter {
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+    // Píxeles en el formato de `ImageData`: rojo, verde, azul y alfa, un byte cada uno.
+    rgba: Vec<u8>,
+    // Eventos que dejan los listeners del canvas hasta el próximo `poll_input`.
+    events: Rc<RefCell<Vec<InputEvent>>>,
+    size: (usize, usize),
+    // Tamaño del último fotograma copiado al canvas; `None` antes del primero.
+    shown: Option<(u32, u32)>,
+    // Se guardan para que los listeners sigan vivos mientras exista el presentador.
+    _listeners: Vec<Closure<dyn FnMut(KeyboardEvent)>>,
+}
+
+impl CanvasPresenter {
+    /// Busca el canvas en la página y prepara su contexto 2D.
+    ///
+    /// # Parámetros
+    /// - `canvas_id`: Atributo `id` del elemento `<canvas>`.
+    pub fn new(canvas_id: &str) -> Result<Self, PresentError> {
+        let canvas = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id(canvas_id))
+            .ok_or_else(|| PresentError::Surface(format!("no hay ningún elemento con id '{}'", canvas_id)))?
+            .dyn_into::<HtmlCanvasElement>()
+            .map_err(|_| PresentError::Surface(format!("'{}' no es un canvas", canvas_id)))?;
+        let context = canvas.get_context("2d")
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+            .ok_or_else(|| PresentError::Surface("el canvas no tiene contexto 2D".to_string()))?;
+        canvas.set_tab_index(0);
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut listeners = Vec::new();
+        for (name, pressed) in [("keydown", true), ("keyup", false)] {
+            let events = Rc::clone(&events);
+            let listener = Closure::<dyn FnMut(KeyboardEvent)>::new(move |event: KeyboardEvent| {
+                if event.repeat() {
+                    return;
+                }
+                if let Some(key) = Key::from_name(&event.key()) {
+                    events.borrow_mut().push(if pressed { InputEvent::KeyPressed(key) } else { InputEvent::KeyReleased(key) });
+                }
+            });
+            canvas.add_event_listener_with_callback(name, listener.as_ref().unchecked_ref())
+                .map_err(|_| PresentError::Surface(format!("no se pudo escuchar {}", name)))?;
+            listeners.push(listener);
+        }
+        let mut presenter = CanvasPresenter { canvas, context, rgba: Vec::new(), events, size: (0, 0), shown: None, _listeners: listeners };
+        presenter.size = presenter.size();
+        Ok(presenter)
+    }
+}
+
+impl Presenter for CanvasPresenter {
+    fn size(&self) -> (usize, usize) {
+        (self.canvas.client_width().max(1) as usize, self.canvas.client_height().max(1) as usize)
+    }
+
+    fn is_open(&self) -> bool {
+        self.canvas.is_connected()
+    }
+
+    fn present(&mut self, framebuffer: &Framebuffer) -> Result<(), PresentError> {
+        let (width, height) = (framebuffer.width as u32, framebuffer.height as u32);
+        if self.canvas.width() != width || self.canvas.height() != height {
+            self.canvas.set_width(width);
+            self.canvas.set_height(height);
+        }
+        // cambiar el tamaño del canvas lo borra, así que entonces va la imagen entera
+        let full = [Viewport::full(framebuffer.width, framebuffer.height)];
+        let regions = if self.shown == Some((width, height)) { framebuffer.dirty_regions() } else { &full };
+        self.shown = Some((width, height));
+
+        let to_error = |error: JsValue| PresentError::Surface(error.as_string().unwrap_or_else(|| format!("{:?}", error)));
+        let front = framebuffer.front();
+        for region in regions {
+            self.rgba.clear();
+            for y in region.y..region.y + region.height {
+                let start = y * framebuffer.width + region.x;
+                self.rgba.extend(front[start..start + region.width].iter().flat_map(|&pixel| {
+                    [(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8, 255]
+                }));
+            }
+            let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&self.rgba), region.width as u32, region.height as u32)
+                .map_err(to_error)?;
+            self.context.put_image_data(&image, region.x as f64, region.y as f64).map_err(to_error)?;
+        }
+        Ok(())
+    }
+
+    fn poll_input(&mut self) -> Vec<InputEvent> {
+        let mut events = std::mem::take(&mut *self.events.borrow_mut());
+        // el navegador no avisa al canvas cuando el CSS lo cambia de tamaño
+        let size = self.size();
+        if size != self.size {
+            self.size = size;
+            events.push(InputEvent::Resized { width: size.0, height: size.1 });
+        }
+        events
+    }
+}
+
+/// Planeta girando en un canvas, para mostrar los shaders en el navegador.
+///
+/// Desde JavaScript se crea con `new PlanetDemo("canvas", "earth", 320, 320)` y se
+/// llama a `frame()` en cada `requestAnimationFrame` (ver `web/index.html`); la
+/// barra espaciadora pausa el giro. La cámara y la luz son las de `Turntable`; la
+/// malla es una esfera generada, porque en el navegador no hay archivos que cargar.
+#[wasm_bindgen]
+pub struct PlanetDemo {
+    presenter: CanvasPresenter,
+    framebuffer: Framebuffer,
+    model: Model,
+    shader: PlanetShader,
+    turntable: Turntable,
+    frame: u32,
+    paused: bool,
+}
+
+#[wasm_bindgen]
+impl PlanetDemo {
+    /// Prepara la demostración.
+    ///
+    /// # Parámetros
+    /// - `canvas_id`: Atributo `id` del `<canvas>` donde se dibuja.
+    /// - `shader`: Nombre del shader, como en `--shader` (por ejemplo `earth`).
+    /// - `width`, `height`: Tamaño del framebuffer, en píxeles.
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas_id: &str, shader: &str, width: usize, height: usize) -> Result<PlanetDemo, JsValue> {
+        let shader = PlanetShader::from_name(shader)
+            .ok_or_else(|| JsValue::from_str(&format!("shader desconocido: {}", shader)))?;
+        let presenter = CanvasPresenter::new(canvas_id).map_err(|error| JsValue::from_str(&error.to_string()))?;
+        let mut framebuffer = Framebuffer::new(width.max(1), height.max(1));
+        framebuffer.set_background_color(DEMO_BACKGROUND);
+        let model = primitives::from_name_with_lod("primitive:uv_sphere", 1).unwrap_or_else(|| primitives::uv_sphere(0.5, 64, 32));
+        Ok(PlanetDemo { presenter, framebuffer, model, shader, turntable: Turntable::new(DEMO_FRAMES), frame: 0, paused: false })
+    }
+
+    /// Cambia el shader del planeta; devuelve `false` si el nombre no existe.
+    pub fn set_shader(&mut self, shader: &str) -> bool {
+        let Some(shader) = PlanetShader::from_name(shader) else { return false };
+        self.shader = shader;
+        true
+    }
+
+    /// Dibuja el siguiente fotograma y lo muestra en el canvas.
+    pub fn frame(&mut self) -> Result<(), JsValue> {
+        for event in self.presenter.poll_input() {
+            if event == InputEvent::KeyPressed(Key::Char(' ')) {
+                self.paused = !self.paused;
+            }
+        }
+        let (width, height) = (self.framebuffer.width, self.framebuffer.height);
+        let camera = self.turntable.camera(width as f32 / height as f32);
+        let view_matrix = camera.view_matrix();
+        let projection_matrix = camera.projection_matrix();
+
+        let mut uniforms = Uniforms::new(self.shader, 0);
+        uniforms.matrices = Matrices {
+            model: create_model_matrix(Vec3::zeros(), 1.0, Vec3::new(0.0, self.turntable.angle(self.frame), 0.0)),
+            view: view_matrix,
+            projection: projection_matrix,
+            viewport: create_viewport_matrix(width as f32, height as f32),
+            cull: projection_matrix * view_matrix,
+        };
+        uniforms.lighting = Lighting {
+            light_direction: self.turntable.light_direction,
+            camera_position: camera.eye,
+            ..Lighting::default()
+        };
+        uniforms.time = self.frame;
+
+        self.framebuffer.clear();
+        render(&mut self.framebuffer, &uniforms, &self.model.vertices, &self.model.indices, &mut PipelineHooks::new());
+        self.framebuffer.swap_buffers();
+        if !self.paused {
+            self.frame = self.frame.wrapping_add(1);
+        }
+        self.presenter.present(&self.framebuffer).map_err(|error| JsValue::from_str(&error.to_string()))
+    }
+}